@@ -18,6 +18,9 @@ fn main() {
 
     fs::write(&dest_path, generated_code).expect("Failed to write generated code");
 
+    // Instruction categories, for proposal diagnostics
+    generate_grammar_instructions(Path::new(&out_dir));
+
     // Tree-sitter grammar compilation - only for native targets (not WASM)
     let target = env::var("TARGET").unwrap_or_default();
     if !target.contains("wasm") {
@@ -67,6 +70,83 @@ fn compile_tree_sitter_grammar() {
     build.compile("tree-sitter-wat");
 }
 
+/// Write the instruction names spelled out in each `_instruction_*` rule of
+/// the grammar, with the rule's category, to `grammar_instructions.rs`
+fn generate_grammar_instructions(out_dir: &Path) {
+    let grammar_path = "grammars/tree-sitter-wat/grammar.js";
+    println!("cargo:rerun-if-changed={}", grammar_path);
+
+    let grammar = fs::read_to_string(grammar_path).expect("Failed to read grammar.js");
+    let mut instructions = parse_grammar_instructions(&grammar);
+    instructions.sort();
+    instructions.dedup();
+
+    let mut code = String::from(
+        "// This file is automatically generated by build.rs\n\
+         // Do not edit manually - edit grammars/tree-sitter-wat/grammar.js instead\n\n\
+         /// `(instruction, category)` for every instruction named in an\n\
+         /// `_instruction_<category>` rule of the grammar, sorted by name\n\
+         pub static GRAMMAR_INSTRUCTIONS: &[(&str, &str)] = &[\n",
+    );
+    for (name, category) in &instructions {
+        code.push_str(&format!("    (\"{}\", \"{}\"),\n", name, category));
+    }
+    code.push_str("];\n");
+
+    fs::write(out_dir.join("grammar_instructions.rs"), code)
+        .expect("Failed to write generated code");
+}
+
+/// Collect the string literals that are instruction names from the
+/// `_instruction_*` rules. Instructions the grammar matches with regular
+/// expressions (most MVP and SIMD ones) have no literal and are not listed.
+fn parse_grammar_instructions(grammar: &str) -> Vec<(String, String)> {
+    let mut instructions = Vec::new();
+    let mut category: Option<String> = None;
+
+    for line in grammar.lines() {
+        // Rules start at a fixed indent: "    name: $ =>"
+        if let Some(rule) = line
+            .strip_prefix("    ")
+            .filter(|rest| !rest.starts_with(' '))
+            .and_then(|rest| rest.split_once(": $ =>"))
+            .map(|(name, _)| name)
+        {
+            category = rule.strip_prefix("_instruction_").map(str::to_string);
+        }
+        let Some(category) = &category else {
+            continue;
+        };
+
+        let code = line.split("//").next().unwrap_or("");
+        for (i, literal) in code.split('"').enumerate() {
+            if i % 2 == 1 && is_instruction_name(literal) {
+                instructions.push((literal.to_string(), category.clone()));
+            }
+        }
+    }
+    instructions
+}
+
+/// Whether a grammar literal is a whole instruction name rather than a
+/// keyword fragment or a SIMD lane shape like `i8x16`
+fn is_instruction_name(literal: &str) -> bool {
+    if literal.len() < 2 {
+        return false;
+    }
+    let is_lane_shape = literal[1..].split_once('x').is_some_and(|(bits, lanes)| {
+        !bits.is_empty()
+            && bits.bytes().all(|b| b.is_ascii_digit())
+            && !lanes.is_empty()
+            && lanes.bytes().all(|b| b.is_ascii_digit())
+    });
+    literal.starts_with(|c: char| c.is_ascii_lowercase())
+        && literal
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'.' || b == b'_')
+        && !is_lane_shape
+}
+
 fn parse_instruction_docs(content: &str) -> HashMap<String, String> {
     let mut docs = HashMap::new();
     // Normalize line endings to handle both Unix (\n) and Windows (\r\n)
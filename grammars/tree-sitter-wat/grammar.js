@@ -19,7 +19,11 @@ module.exports = grammar({
   conflicts: $ => [[$.op_let], [$.op_select]],
 
   rules: {
-    ROOT: $ => choice($.module, repeat($.module_field)),
+    // A module, its fields without the module wrapper, or a .wast script
+    ROOT: $ => choice(repeat1($.module_field), repeat($._script_command)),
+
+    // proposal: memory64
+    address_type: $ => choice("i32", "i64"),
 
     align_value: $ => seq("align", imm("="), $.align_offset_value),
 
@@ -709,7 +713,7 @@ module.exports = grammar({
 
     memory_fields_type: $ => seq(optional($.import), $.memory_type),
 
-    memory_type: $ => $.limits,
+    memory_type: $ => seq(optional($.address_type), $.limits),
 
     memory_use: $ => seq("(", "memory", $.index, ")"),
 
@@ -888,6 +892,44 @@ module.exports = grammar({
     // proposal: threads
     share: $ => /(un)?shared/,
 
+    // Spec-test scripts (.wast): modules plus the commands that exercise them
+    _script_command: $ =>
+      choice($.module, $.script_module, $.script_register, $.script_action, $.script_assertion),
+
+    // A module given as binary or quoted text: (module $m binary "\00asm" ...)
+    script_module: $ =>
+      seq("(", "module", optional(field("identifier", $.identifier)), choice("binary", "quote"), repeat($.string), ")"),
+
+    // (register "name" $module?)
+    script_register: $ => seq("(", "register", $.name, optional(field("module", $.identifier)), ")"),
+
+    script_action: $ => choice($.script_invoke, $.script_get),
+
+    // (invoke $module? "export" argument*)
+    script_invoke: $ => seq("(", "invoke", optional(field("module", $.identifier)), $.name, repeat($.expr), ")"),
+
+    // (get $module? "export")
+    script_get: $ => seq("(", "get", optional(field("module", $.identifier)), $.name, ")"),
+
+    script_assertion: $ =>
+      seq(
+        "(",
+        choice(
+          seq(field("kind", "assert_return"), $.script_action, repeat($.script_result)),
+          seq(field("kind", choice("assert_trap", "assert_exhaustion")), $.script_action, $.string),
+          seq(field("kind", "assert_exception"), $.script_action),
+          seq(
+            field("kind", choice("assert_trap", "assert_invalid", "assert_malformed", "assert_unlinkable")),
+            choice($.module, $.script_module),
+            $.string,
+          ),
+        ),
+        ")",
+      ),
+
+    // Expected value of an assert_return; `either` accepts any of several
+    script_result: $ => choice($.expr, seq("(", "either", repeat1($.expr), ")")),
+
     sign: $ => /[+-]/,
 
     string: $ => seq('"', repeat(choice(imm(prec(PREC.STRING, /[^"\\\n]+|\\\r?\n/)), $.escape_sequence)), '"'),
@@ -897,7 +939,7 @@ module.exports = grammar({
 
     table_fields_type: $ => seq(optional($.import), $.table_type),
 
-    table_type: $ => seq($.limits, $.ref_type),
+    table_type: $ => seq(optional($.address_type), $.limits, $.ref_type),
 
     table_use: $ => seq("(", "table", $.index, ")"),
 
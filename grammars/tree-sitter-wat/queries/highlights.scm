@@ -89,6 +89,23 @@
   "final"
 ] @keyword
 
+; Spec-test script commands (.wast)
+[
+  "register"
+  "invoke"
+  "get"
+  "binary"
+  "quote"
+  "either"
+  "assert_return"
+  "assert_trap"
+  "assert_exhaustion"
+  "assert_exception"
+  "assert_invalid"
+  "assert_malformed"
+  "assert_unlinkable"
+] @keyword
+
 ; Instructions - these contain the actual instruction text like "local.get", "i32.add"
 ; The semantic tokens provider will split these into namespace.action
 (op_nullary) @function.instruction
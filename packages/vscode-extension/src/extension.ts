@@ -1,7 +1,17 @@
 import * as path from 'path';
 import * as fs from 'fs';
-import { workspace, ExtensionContext, window, commands } from 'vscode';
 import {
+  workspace,
+  ExtensionContext,
+  window,
+  commands,
+  Uri,
+  TextDocumentContentProvider,
+  Selection,
+  TextEditorRevealType
+} from 'vscode';
+import {
+  ExecuteCommandRequest,
   LanguageClient,
   LanguageClientOptions,
   ServerOptions,
@@ -65,6 +75,7 @@ async function startClient(context: ExtensionContext): Promise<void> {
       { scheme: 'file', pattern: '**/*.wast' },
       { scheme: 'file', language: 'wat' },
       { scheme: 'file', language: 'wasm' },
+      { scheme: 'wat-disassembly' },
     ],
     synchronize: {
       fileEvents: workspace.createFileSystemWatcher('**/*.{wat,wast}')
@@ -116,6 +127,175 @@ export function activate(context: ExtensionContext) {
   context.subscriptions.push(toggleCommand);
   console.log('WAT LSP toggle command registered');
 
+  // Register the command behind the reference-count code lenses.
+  // The server sends LSP-shaped arguments, which VS Code's built-in command can't take directly.
+  const showReferencesCommand = commands.registerCommand(
+    'watLsp.showReferences',
+    async (uri: string, position: any, locations: any[]) => {
+      if (!client) {
+        return;
+      }
+      const converter = client.protocol2CodeConverter;
+      await commands.executeCommand(
+        'editor.action.showReferences',
+        converter.asUri(uri),
+        converter.asPosition(position),
+        await converter.asLocations(locations)
+      );
+    }
+  );
+  context.subscriptions.push(showReferencesCommand);
+
+  // Register the command behind the "▶ Run" lenses of functions with parameters.
+  // Asks for each argument, then has the server run the export with them.
+  const promptRunExportCommand = commands.registerCommand(
+    'watLsp.promptRunExport',
+    async (uri: string, name: string, params: { name: string | null; type: string }[]) => {
+      if (!client) {
+        return;
+      }
+      const args: string[] = [];
+      for (const [index, param] of params.entries()) {
+        const value = await window.showInputBox({
+          title: `Run "${name}"`,
+          prompt: `${param.name ?? `Parameter ${index}`}: ${param.type}`,
+          placeHolder: param.type.startsWith('f') ? '1.5, -inf, nan' : '42, -1, 0xff',
+        });
+        if (value === undefined) {
+          return;
+        }
+        args.push(value);
+      }
+      try {
+        await client.sendRequest(ExecuteCommandRequest.type, {
+          command: 'watLsp.runExport',
+          arguments: [uri, name, args],
+        });
+      } catch (err: any) {
+        window.showErrorMessage(`Couldn't run "${name}": ${err.message}`);
+      }
+    }
+  );
+  context.subscriptions.push(promptRunExportCommand);
+
+  // Serve the read-only WAT documents of .wasm files from the server's disassembly.
+  const disassemblyProvider: TextDocumentContentProvider = {
+    async provideTextDocumentContent(uri: Uri): Promise<string> {
+      if (!client) {
+        return '';
+      }
+      const result = await client.sendRequest<{ uri: string; text: string }>(
+        'wat/disassemble',
+        { uri: uri.toString(true) }
+      );
+      return result.text;
+    }
+  };
+  context.subscriptions.push(
+    workspace.registerTextDocumentContentProvider('wat-disassembly', disassemblyProvider)
+  );
+
+  // Open the disassembly of a .wasm file, from the explorer or the active editor.
+  const disassembleCommand = commands.registerCommand('watLsp.disassemble', async (uri?: Uri) => {
+    const target = uri ?? window.activeTextEditor?.document.uri;
+    if (!client || !target) {
+      return;
+    }
+    try {
+      const result = await client.sendRequest<{ uri: string; text: string }>(
+        'wat/disassemble',
+        { uri: target.toString(true) }
+      );
+      const document = await workspace.openTextDocument(Uri.parse(result.uri));
+      await window.showTextDocument(document, { preview: true });
+    } catch (err: any) {
+      window.showErrorMessage(`Couldn't disassemble ${target.fsPath}: ${err.message}`);
+    }
+  });
+  context.subscriptions.push(disassembleCommand);
+
+  // Encode the active WAT document to a .wasm file next to it, or wherever the user picks.
+  const compileCommand = commands.registerCommand('watLsp.compile', async () => {
+    const document = window.activeTextEditor?.document;
+    if (!client || !document) {
+      return;
+    }
+    const target = await window.showSaveDialog({
+      defaultUri: document.uri.with({ path: document.uri.path.replace(/\.wat$/, '') + '.wasm' }),
+      filters: { WebAssembly: ['wasm'] },
+    });
+    if (!target) {
+      return;
+    }
+    try {
+      const result = await client.sendRequest(ExecuteCommandRequest.type, {
+        command: 'watLsp.compileBinary',
+        arguments: [document.uri.toString(), target.fsPath],
+      });
+      window.showInformationMessage(`Wrote ${target.fsPath} (${result.size} bytes)`);
+    } catch (err: any) {
+      window.showErrorMessage(`Couldn't compile: ${err.message}`);
+    }
+  });
+  context.subscriptions.push(compileCommand);
+
+  // Jump from a code offset in a trap or profile, e.g. wasm-function[12]:0x3a4, to its instruction.
+  const gotoBinaryOffsetCommand = commands.registerCommand('watLsp.gotoBinaryOffset', async () => {
+    const editor = window.activeTextEditor;
+    if (!client || !editor) {
+      return;
+    }
+    const offset = await window.showInputBox({
+      title: 'Go to Binary Offset',
+      prompt: 'Code offset in the compiled binary',
+      placeHolder: '0x3a4, wasm-function[12]:0x3a4',
+    });
+    if (!offset) {
+      return;
+    }
+    try {
+      const location = await client.sendRequest<any>('wat/gotoBinaryOffset', {
+        textDocument: { uri: editor.document.uri.toString() },
+        offset,
+      });
+      if (!location) {
+        window.showInformationMessage(`No instruction at ${offset}`);
+        return;
+      }
+      const range = client.protocol2CodeConverter.asRange(location.range);
+      editor.selection = new Selection(range.start, range.end);
+      editor.revealRange(range, TextEditorRevealType.InCenterIfOutsideViewport);
+    } catch (err: any) {
+      window.showErrorMessage(`Couldn't resolve ${offset}: ${err.message}`);
+    }
+  });
+  context.subscriptions.push(gotoBinaryOffsetCommand);
+
+  // Write a .d.ts for the active WAT document's exports and imports next to it, or wherever the user picks.
+  const generateTypesCommand = commands.registerCommand('watLsp.generateTypes', async () => {
+    const document = window.activeTextEditor?.document;
+    if (!client || !document) {
+      return;
+    }
+    const target = await window.showSaveDialog({
+      defaultUri: document.uri.with({ path: document.uri.path.replace(/\.wat$/, '') + '.d.ts' }),
+      filters: { TypeScript: ['d.ts'] },
+    });
+    if (!target) {
+      return;
+    }
+    try {
+      await client.sendRequest(ExecuteCommandRequest.type, {
+        command: 'watLsp.generateDeclarations',
+        arguments: [document.uri.toString(), target.fsPath],
+      });
+      window.showInformationMessage(`Wrote ${target.fsPath}`);
+    } catch (err: any) {
+      window.showErrorMessage(`Couldn't generate declarations: ${err.message}`);
+    }
+  });
+  context.subscriptions.push(generateTypesCommand);
+
   try {
     serverPath = getServerPath(context);
     console.log(`WAT LSP server path: ${serverPath}`);
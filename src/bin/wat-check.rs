@@ -5,18 +5,22 @@
 
 use std::fs;
 use std::io::{self, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
 
 use clap::{Parser, ValueEnum};
 use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
 
+use wat_lsp_rust::compile::{compile_with_source_map, size_report, source_map_path, SizeReport};
 use wat_lsp_rust::diagnostics::{
-    merge_all_diagnostics, provide_semantic_diagnostics, provide_tree_sitter_diagnostics,
-    validate_wat,
+    merge_all_diagnostics, provide_assertion_diagnostics, provide_proposal_diagnostics,
+    provide_semantic_diagnostics, provide_tree_sitter_diagnostics, validate_wat,
 };
-use wat_lsp_rust::parser::parse_document;
-use wat_lsp_rust::tree_sitter_bindings::create_parser;
+use wat_lsp_rust::interpreter::run_script;
+use wat_lsp_rust::parity::{compare_parsers, is_wast_unsupported, SymbolDiff};
+use wat_lsp_rust::parser::{parse_document, parse_tree};
+use wat_lsp_rust::settings::{DiagnosticLevel, Target, TargetProfile};
+use wat_lsp_rust::typescript::generate_declarations;
 
 #[derive(Debug, Clone, Copy, ValueEnum, Default)]
 enum OutputFormat {
@@ -29,17 +33,6 @@ enum OutputFormat {
     Compact,
 }
 
-#[derive(Debug, Clone, Copy, Default, ValueEnum)]
-enum DiagnosticLevel {
-    /// Only syntax errors (fastest)
-    Syntax,
-    /// Syntax + semantic validation (default)
-    #[default]
-    Semantic,
-    /// Full validation including wast deep checks (slowest but most thorough)
-    Full,
-}
-
 /// A standalone WAT file checker using the wat-lsp-rust diagnostic engine
 #[derive(Parser, Debug)]
 #[command(name = "wat-check")]
@@ -57,13 +50,38 @@ struct Args {
     #[arg(short, long, value_enum, default_value_t = DiagnosticLevel::Semantic)]
     level: DiagnosticLevel,
 
+    /// Warn about proposals this spec release doesn't include (semantic level and up)
+    #[arg(short, long, value_enum)]
+    target: Option<TargetProfile>,
+
     /// Only show errors (hide warnings and hints)
     #[arg(short = 'e', long)]
     errors_only: bool,
 
+    /// Run the assertions of .wast scripts with the embedded interpreter and
+    /// report the ones that fail
+    #[arg(short, long)]
+    run: bool,
+
+    /// Encode the file to a binary at this path, with a name section and a
+    /// source map at OUT.wasm.map, and print its size by section and by
+    /// function. Takes a single file.
+    #[arg(long, value_name = "OUT.wasm")]
+    emit: Option<PathBuf>,
+
+    /// Write TypeScript declarations for the file's exports and imports to
+    /// this path. Takes a single file.
+    #[arg(long, value_name = "OUT.d.ts")]
+    emit_types: Option<PathBuf>,
+
     /// Suppress all output except errors (for scripting)
     #[arg(short, long)]
     quiet: bool,
+
+    /// Instead of checking for issues, parse each file with both the
+    /// tree-sitter and wast parsers and report where their symbols differ
+    #[arg(long)]
+    compare_parsers: bool,
 }
 
 #[derive(serde::Serialize)]
@@ -86,6 +104,33 @@ struct DiagnosticOutput {
     source: Option<String>,
 }
 
+#[derive(serde::Serialize)]
+struct ComparisonResult {
+    file: String,
+    differences: Vec<DifferenceOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    skipped: bool,
+}
+
+#[derive(serde::Serialize)]
+struct DifferenceOutput {
+    path: String,
+    tree_sitter: Option<String>,
+    wast: Option<String>,
+}
+
+impl From<&SymbolDiff> for DifferenceOutput {
+    fn from(d: &SymbolDiff) -> Self {
+        DifferenceOutput {
+            path: d.path.clone(),
+            tree_sitter: d.tree_sitter.clone(),
+            wast: d.wast.clone(),
+        }
+    }
+}
+
 impl From<&Diagnostic> for DiagnosticOutput {
     fn from(d: &Diagnostic) -> Self {
         DiagnosticOutput {
@@ -110,10 +155,11 @@ fn check_file(
     filename: &str,
     source: &str,
     level: DiagnosticLevel,
+    target: Option<TargetProfile>,
     errors_only: bool,
+    run: bool,
 ) -> Vec<Diagnostic> {
-    let mut parser = create_parser();
-    let tree = match parser.parse(source, None) {
+    let tree = match parse_tree(source) {
         Some(t) => t,
         None => {
             eprintln!("{}: Failed to parse file", filename);
@@ -124,14 +170,21 @@ fn check_file(
     // Layer 1: Tree-sitter syntax diagnostics (always run)
     let syntax_diags = provide_tree_sitter_diagnostics(&tree, source);
 
-    let all_diags = match level {
+    let mut all_diags = match level {
         DiagnosticLevel::Syntax => syntax_diags,
         DiagnosticLevel::Semantic | DiagnosticLevel::Full => {
             // Layer 2: Semantic diagnostics
-            let semantic_diags = match parse_document(source) {
+            let mut semantic_diags = match parse_document(source) {
                 Ok(symbols) => provide_semantic_diagnostics(&tree, source, &symbols),
                 Err(_) => vec![],
             };
+            if let Some(profile) = target {
+                semantic_diags.extend(provide_proposal_diagnostics(
+                    &tree,
+                    source,
+                    &Target::Profile(profile),
+                ));
+            }
 
             if matches!(level, DiagnosticLevel::Full) {
                 // Layer 3: WAST deep validation
@@ -143,6 +196,13 @@ fn check_file(
         }
     };
 
+    // Scripts that don't parse are already reported above
+    if run {
+        if let Ok(results) = run_script(source) {
+            all_diags.extend(provide_assertion_diagnostics(&results));
+        }
+    }
+
     if errors_only {
         all_diags
             .into_iter()
@@ -187,36 +247,193 @@ fn print_diagnostics_compact(filename: &str, diagnostics: &[Diagnostic]) {
     }
 }
 
+/// Encode a file to a binary at `out` and its source map next to it (--emit)
+fn emit_binary(filename: &str, source: &str, out: &Path) -> Result<SizeReport, String> {
+    let (bytes, map) = compile_with_source_map(source)?;
+    let report = size_report(&bytes)?;
+    let write = |path: &Path, contents: &[u8]| {
+        fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    };
+    write(out, &bytes)?;
+    let source_name = Path::new(filename)
+        .file_name()
+        .map_or(filename.into(), |name| name.to_string_lossy());
+    if !map.unmapped().is_empty() {
+        let unmapped: Vec<_> = map.unmapped().iter().map(u32::to_string).collect();
+        eprintln!(
+            "{}: warning: source map leads only to the start of functions {}",
+            filename,
+            unmapped.join(", ")
+        );
+    }
+    let map = map.to_json(source, &source_name);
+    write(&source_map_path(out), map.to_string().as_bytes())?;
+    Ok(report)
+}
+
+/// Write TypeScript declarations for a file to `out` (--emit-types)
+fn emit_types(source: &str, out: &Path) -> Result<(), String> {
+    let symbols = parse_document(source)?;
+    fs::write(out, generate_declarations(&symbols))
+        .map_err(|e| format!("Failed to write {}: {}", out.display(), e))
+}
+
+fn print_size_report(out: &Path, report: &SizeReport) {
+    eprintln!("\nWrote {} ({} bytes)", out.display(), report.size);
+    eprintln!("Sections:");
+    for section in &report.sections {
+        eprintln!("  {:<20} {:>8}", section.name, section.size);
+    }
+    eprintln!("Functions:");
+    for func in &report.functions {
+        let name = match &func.name {
+            Some(name) => format!("${}", name),
+            None => func.index.to_string(),
+        };
+        eprintln!("  {:<20} {:>8}", name, func.size);
+    }
+}
+
+/// Read a file, or stdin for '-', returning its display name and contents
+fn read_input(path: &Path) -> Option<(String, String)> {
+    if path.as_os_str() == "-" {
+        let mut buf = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut buf) {
+            eprintln!("stdin: Failed to read: {}", e);
+            return None;
+        }
+        Some(("<stdin>".to_string(), buf))
+    } else {
+        match fs::read_to_string(path) {
+            Ok(content) => Some((path.display().to_string(), content)),
+            Err(e) => {
+                eprintln!("{}: Failed to read: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// Run the parser comparison over all files (--compare-parsers)
+fn run_parser_comparison(args: &Args) -> ExitCode {
+    let mut all_results: Vec<ComparisonResult> = vec![];
+    let mut total_differences = 0usize;
+    let mut skipped = 0usize;
+    let mut had_read_error = false;
+
+    for path in &args.files {
+        // Files the wast parser rejects are listed rather than dropped silently
+        if is_wast_unsupported(path) {
+            skipped += 1;
+            if matches!(args.format, OutputFormat::Json) {
+                all_results.push(ComparisonResult {
+                    file: path.display().to_string(),
+                    differences: vec![],
+                    error: None,
+                    skipped: true,
+                });
+            } else {
+                println!(
+                    "{}: skipped, uses syntax the wast parser doesn't support",
+                    path.display()
+                );
+            }
+            continue;
+        }
+
+        let Some((filename, source)) = read_input(path) else {
+            had_read_error = true;
+            continue;
+        };
+
+        let (differences, error) = match compare_parsers(&source) {
+            Ok(differences) => (differences, None),
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                had_read_error = true;
+                (vec![], Some(e))
+            }
+        };
+        total_differences += differences.len();
+
+        if matches!(args.format, OutputFormat::Json) {
+            all_results.push(ComparisonResult {
+                file: filename,
+                differences: differences.iter().map(DifferenceOutput::from).collect(),
+                error,
+                skipped: false,
+            });
+        } else {
+            for d in &differences {
+                println!("{}: {}", filename, d);
+            }
+        }
+    }
+
+    if matches!(args.format, OutputFormat::Json) {
+        let output = serde_json::json!({
+            "files": all_results,
+            "summary": {
+                "total_differences": total_differences,
+                "files_checked": args.files.len() - skipped,
+                "files_skipped": skipped,
+            }
+        });
+        println!("{}", serde_json::to_string_pretty(&output).unwrap());
+    } else if !args.quiet {
+        let file_count = args.files.len() - skipped;
+        let file_word = if file_count == 1 { "file" } else { "files" };
+        eprintln!(
+            "\nCompared parsers on {} {}: {} difference(s), {} skipped",
+            file_count, file_word, total_differences, skipped
+        );
+    }
+
+    // Same exit codes as checking: differences count as errors
+    if total_differences > 0 {
+        ExitCode::from(1)
+    } else if had_read_error {
+        ExitCode::from(2)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
 fn main() -> ExitCode {
     let args = Args::parse();
 
+    if args.compare_parsers {
+        return run_parser_comparison(&args);
+    }
+    if args.emit.is_some() && args.files.len() != 1 {
+        eprintln!("--emit takes a single file");
+        return ExitCode::from(2);
+    }
+    if args.emit_types.is_some() && args.files.len() != 1 {
+        eprintln!("--emit-types takes a single file");
+        return ExitCode::from(2);
+    }
+
     let mut all_results: Vec<FileResult> = vec![];
+    let mut emitted: Option<SizeReport> = None;
     let mut total_errors = 0usize;
     let mut total_warnings = 0usize;
     let mut had_read_error = false;
 
     for path in &args.files {
-        let (filename, source) = if path.as_os_str() == "-" {
-            // Read from stdin
-            let mut buf = String::new();
-            if let Err(e) = io::stdin().read_to_string(&mut buf) {
-                eprintln!("stdin: Failed to read: {}", e);
-                had_read_error = true;
-                continue;
-            }
-            ("<stdin>".to_string(), buf)
-        } else {
-            match fs::read_to_string(path) {
-                Ok(content) => (path.display().to_string(), content),
-                Err(e) => {
-                    eprintln!("{}: Failed to read: {}", path.display(), e);
-                    had_read_error = true;
-                    continue;
-                }
-            }
+        let Some((filename, source)) = read_input(path) else {
+            had_read_error = true;
+            continue;
         };
 
-        let diagnostics = check_file(&filename, &source, args.level, args.errors_only);
+        let diagnostics = check_file(
+            &filename,
+            &source,
+            args.level,
+            args.target,
+            args.errors_only,
+            args.run,
+        );
 
         let error_count = diagnostics
             .iter()
@@ -230,6 +447,22 @@ fn main() -> ExitCode {
         total_errors += error_count;
         total_warnings += warning_count;
 
+        if let Some(out) = &args.emit {
+            match emit_binary(&filename, &source, out) {
+                Ok(report) => emitted = Some(report),
+                Err(e) => {
+                    eprintln!("{}: error: {}", filename, e);
+                    total_errors += 1;
+                }
+            }
+        }
+        if let Some(out) = &args.emit_types {
+            if let Err(e) = emit_types(&source, out) {
+                eprintln!("{}: error: {}", filename, e);
+                total_errors += 1;
+            }
+        }
+
         match args.format {
             OutputFormat::Text => {
                 if !diagnostics.is_empty() {
@@ -252,7 +485,7 @@ fn main() -> ExitCode {
 
     // JSON output at the end
     if matches!(args.format, OutputFormat::Json) {
-        let output = serde_json::json!({
+        let mut output = serde_json::json!({
             "files": all_results,
             "summary": {
                 "total_errors": total_errors,
@@ -260,8 +493,19 @@ fn main() -> ExitCode {
                 "files_checked": args.files.len(),
             }
         });
+        if let (Some(out), Some(report)) = (&args.emit, &emitted) {
+            output["emit"] = serde_json::json!({
+                "output": out,
+                "size": report.size,
+                "sections": report.sections,
+                "functions": report.functions,
+            });
+        }
         println!("{}", serde_json::to_string_pretty(&output).unwrap());
     } else if !args.quiet {
+        if let (Some(out), Some(report)) = (&args.emit, &emitted) {
+            print_size_report(out, report);
+        }
         // Summary for text output
         if args.files.len() > 1 || total_errors > 0 || total_warnings > 0 {
             let file_count = args.files.len();
@@ -11,9 +11,9 @@ use std::process::ExitCode;
 use clap::Parser;
 use tower_lsp::lsp_types::Position;
 
+use wat_lsp_rust::compile::{compile_with_source_map, instruction_range, parse_binary_location};
 use wat_lsp_rust::hover::provide_hover;
-use wat_lsp_rust::parser::parse_document;
-use wat_lsp_rust::tree_sitter_bindings::create_parser;
+use wat_lsp_rust::parser::{parse_document, parse_tree};
 use wat_lsp_rust::utils::{
     determine_context_from_line, determine_instruction_context, get_line_at_position,
     get_word_at_position, node_at_position,
@@ -38,12 +38,18 @@ struct Args {
     file: PathBuf,
 
     /// Line number (1-indexed)
-    #[arg(short, long)]
-    line: u32,
+    #[arg(short, long, required_unless_present = "offset")]
+    line: Option<u32>,
 
     /// Column number (1-indexed)
-    #[arg(short, long)]
-    column: u32,
+    #[arg(short, long, required_unless_present = "offset")]
+    column: Option<u32>,
+
+    /// Look up the instruction at a code offset of the compiled binary
+    /// instead of a line and column, e.g. 0x3a4 or wasm-function[12]:0x3a4.
+    /// With a function index, the offset may also count from its body.
+    #[arg(long, conflicts_with_all = ["line", "column"])]
+    offset: Option<String>,
 
     /// Output format
     #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
@@ -72,6 +78,17 @@ struct HoverContent {
     kind: String,
 }
 
+/// Start of the instruction a binary code offset was compiled from (--offset)
+fn position_at_offset(source: &str, offset: &str) -> Result<Position, String> {
+    let (function, offset) =
+        parse_binary_location(offset).ok_or_else(|| format!("invalid offset '{}'", offset))?;
+    let (_, map) = compile_with_source_map(source)?;
+    let text_offset = map
+        .resolve(function, offset)
+        .ok_or_else(|| format!("no instruction at offset 0x{:x}", offset))?;
+    Ok(instruction_range(source, text_offset).start.into())
+}
+
 fn main() -> ExitCode {
     let args = Args::parse();
 
@@ -94,8 +111,7 @@ fn main() -> ExitCode {
     };
 
     // Parse the document
-    let mut parser = create_parser();
-    let tree = match parser.parse(&source, None) {
+    let tree = match parse_tree(&source) {
         Some(t) => t,
         None => {
             eprintln!("{}: Failed to parse file", filename);
@@ -113,10 +129,28 @@ fn main() -> ExitCode {
     };
 
     // Convert to 0-indexed position
-    let position = Position {
-        line: args.line.saturating_sub(1),
-        character: args.column.saturating_sub(1),
+    let position = match &args.offset {
+        Some(offset) => match position_at_offset(&source, offset) {
+            Ok(position) => {
+                println!(
+                    "{}:{}:{}",
+                    filename,
+                    position.line + 1,
+                    position.character + 1
+                );
+                position
+            }
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                return ExitCode::from(1);
+            }
+        },
+        None => Position {
+            line: args.line.unwrap_or(1).saturating_sub(1),
+            character: args.column.unwrap_or(1).saturating_sub(1),
+        },
     };
+    let (line, column) = (position.line + 1, position.character + 1);
 
     // Debug output
     if args.debug {
@@ -197,10 +231,10 @@ fn main() -> ExitCode {
         let lines: Vec<&str> = source.lines().collect();
         if let Some(line_content) = lines.get(position.line as usize) {
             eprintln!();
-            eprintln!("  {}:{}", args.line, line_content);
+            eprintln!("  {}:{}", line, line_content);
             eprintln!(
                 "  {}{}^",
-                " ".repeat(args.line.to_string().len() + 1),
+                " ".repeat(line.to_string().len() + 1),
                 " ".repeat(position.character as usize)
             );
             eprintln!();
@@ -237,17 +271,14 @@ fn main() -> ExitCode {
                     }
                 }
             } else {
-                println!(
-                    "No hover information at {}:{}:{}",
-                    filename, args.line, args.column
-                );
+                println!("No hover information at {}:{}:{}", filename, line, column);
             }
         }
         OutputFormat::Json => {
             let output = HoverOutput {
                 file: filename,
-                line: args.line,
-                column: args.column,
+                line,
+                column,
                 hover: hover.map(|h| match h.contents {
                     tower_lsp::lsp_types::HoverContents::Markup(markup) => HoverContent {
                         content: markup.value,
@@ -0,0 +1,112 @@
+//! wat-run: A CLI tool for calling an exported function of a WAT module
+//!
+//! This tool runs the module in the wat-lsp-rust embedded interpreter, the
+//! same way the "▶ Run" code lens does, with imports replaced by stubs.
+
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::{Parser, ValueEnum};
+
+use wat_lsp_rust::interpreter::{run_export, ImportStubs};
+
+#[derive(Debug, Clone, Copy, ValueEnum, Default)]
+enum OutputFormat {
+    /// Human-readable text output (default)
+    #[default]
+    Text,
+    /// JSON output for machine processing
+    Json,
+}
+
+/// Call an exported function of a WAT or Wasm module with the embedded interpreter
+#[derive(Parser, Debug)]
+#[command(name = "wat-run")]
+#[command(author, version, about, long_about = None)]
+#[command(allow_negative_numbers = true)]
+struct Args {
+    /// WAT or binary Wasm file. Use '-' to read from stdin.
+    file: PathBuf,
+
+    /// Name of the exported function to call
+    export: String,
+
+    /// Arguments, one per parameter, written like WAT constants (42, 0xff, -1.5, nan).
+    /// Put other values starting with '-', such as -0x10 or -inf, after '--'.
+    args: Vec<String>,
+
+    /// What imported functions do: trap, return zero, or log their arguments
+    #[arg(short, long, value_enum, default_value_t = ImportStubs::Trap)]
+    imports: ImportStubs,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let source = if args.file.as_os_str() == "-" {
+        let mut buf = Vec::new();
+        if let Err(e) = io::stdin().read_to_end(&mut buf) {
+            eprintln!("Error reading stdin: {}", e);
+            return ExitCode::from(2);
+        }
+        buf
+    } else {
+        match fs::read(&args.file) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Error reading {}: {}", args.file.display(), e);
+                return ExitCode::from(2);
+            }
+        }
+    };
+
+    let output = match run_export(&source, &args.export, &args.args, args.imports) {
+        Ok(output) => output,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return ExitCode::from(2);
+        }
+    };
+
+    match args.format {
+        OutputFormat::Text => {
+            for line in &output.log {
+                eprintln!("{}", line);
+            }
+            match &output.results {
+                Ok(values) => {
+                    for value in values {
+                        println!("{}", value);
+                    }
+                }
+                Err(trap) => eprintln!("trap: {}", trap),
+            }
+        }
+        OutputFormat::Json => {
+            let (results, trap) = match &output.results {
+                Ok(values) => (values.iter().map(|v| v.to_string()).collect(), None),
+                Err(trap) => (Vec::new(), Some(trap.to_string())),
+            };
+            let json = serde_json::json!({
+                "export": args.export,
+                "results": results,
+                "trap": trap,
+                "log": output.log,
+            });
+            println!("{}", serde_json::to_string_pretty(&json).unwrap());
+        }
+    }
+
+    // Exit code: 1 if the function trapped, 2 if it couldn't run
+    if output.results.is_err() {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
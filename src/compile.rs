@@ -0,0 +1,544 @@
+//! Encoding a module to a binary, and where the bytes of the binary go.
+//!
+//! The binary keeps a name section, so names survive a round trip through
+//! the disassembler. The size report counts whole sections, headers
+//! included, so section sizes add up to the file size less its 8-byte
+//! preamble; function sizes are the size of each body. The source map leads
+//! from code offsets in the binary, such as the `0x3a4` of a
+//! `wasm-function[12]:0x3a4` trap, back to the instruction in the text.
+
+use crate::core::{LineIndex, Position, PositionEncoding, Range};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use wasmparser::{KnownCustom, Name, Payload};
+use wast::core::{FuncKind, ModuleField, ModuleKind};
+
+/// Encode WAT text to a binary module with a name section
+pub fn compile(text: &str) -> Result<Vec<u8>, String> {
+    compile_with_source_map(text).map(|(bytes, _)| bytes)
+}
+
+/// Encode WAT text to a binary module, and map its code back to the text
+pub fn compile_with_source_map(text: &str) -> Result<(Vec<u8>, SourceMap), String> {
+    let mut buf = wast::parser::ParseBuffer::new(text).map_err(|e| e.to_string())?;
+    buf.track_instr_spans(true);
+    let mut wat = wast::parser::parse::<wast::Wat>(&buf).map_err(|e| e.to_string())?;
+    let bytes = wat.encode().map_err(|e| e.message())?;
+
+    // Text offsets of the defined functions and their instructions, in the
+    // order their bodies are encoded
+    let mut funcs = Vec::new();
+    if let wast::Wat::Module(module) = &wat {
+        if let ModuleKind::Text(fields) = &module.kind {
+            for field in fields {
+                if let ModuleField::Func(func) = field {
+                    if let FuncKind::Inline { expression, .. } = &func.kind {
+                        let spans = expression.instr_spans.as_deref().unwrap_or_default();
+                        let spans: Vec<usize> = spans.iter().map(|span| span.offset()).collect();
+                        funcs.push((func.span.offset(), spans));
+                    }
+                }
+            }
+        }
+    }
+
+    let map = SourceMap::build(&bytes, funcs).map_err(|e| e.to_string())?;
+    Ok((bytes, map))
+}
+
+/// Sizes of the parts of a binary module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SizeReport {
+    /// Size of the whole binary
+    pub size: usize,
+    /// Every section, in binary order
+    pub sections: Vec<SectionSize>,
+    /// Every defined function, in index order
+    pub functions: Vec<FunctionSize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionSize {
+    /// `type`, `code` etc., or `custom "name"` for custom sections
+    pub name: String,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionSize {
+    /// Index in the function index space, imports included
+    pub index: u32,
+    /// Name from the name section
+    pub name: Option<String>,
+    pub size: usize,
+}
+
+impl SizeReport {
+    /// Size of the function at `index`, if it is defined in the module
+    pub fn function(&self, index: u32) -> Option<&FunctionSize> {
+        self.functions.iter().find(|func| func.index == index)
+    }
+}
+
+/// Measure the sections and functions of a binary module
+pub fn size_report(bytes: &[u8]) -> Result<SizeReport, String> {
+    let mut report = SizeReport {
+        size: bytes.len(),
+        ..SizeReport::default()
+    };
+    let mut names = HashMap::new();
+    let mut imported_funcs = 0;
+
+    for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+        let payload = payload.map_err(|e| e.to_string())?;
+        if let Some((id, range)) = payload.as_section() {
+            let name = match &payload {
+                Payload::CustomSection(section) => format!("custom \"{}\"", section.name()),
+                _ => section_name(id).to_string(),
+            };
+            report.sections.push(SectionSize {
+                name,
+                size: 1 + leb128_size(range.len()) + range.len(),
+            });
+        }
+        match payload {
+            Payload::ImportSection(reader) => {
+                for import in reader {
+                    let import = import.map_err(|e| e.to_string())?;
+                    if matches!(
+                        import.ty,
+                        wasmparser::TypeRef::Func(_) | wasmparser::TypeRef::FuncExact(_)
+                    ) {
+                        imported_funcs += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let index = imported_funcs + report.functions.len() as u32;
+                report.functions.push(FunctionSize {
+                    index,
+                    name: None,
+                    size: body.range().len(),
+                });
+            }
+            Payload::CustomSection(section) => {
+                if let KnownCustom::Name(reader) = section.as_known() {
+                    for name in reader.into_iter().flatten() {
+                        if let Name::Function(map) = name {
+                            for naming in map.into_iter().flatten() {
+                                names.insert(naming.index, naming.name.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // The name section comes after the code section
+    for func in &mut report.functions {
+        func.name = names.remove(&func.index);
+    }
+    Ok(report)
+}
+
+fn section_name(id: u8) -> &'static str {
+    match id {
+        0 => "custom",
+        1 => "type",
+        2 => "import",
+        3 => "function",
+        4 => "table",
+        5 => "memory",
+        6 => "global",
+        7 => "export",
+        8 => "start",
+        9 => "element",
+        10 => "code",
+        11 => "data",
+        12 => "data count",
+        13 => "tag",
+        _ => "unknown",
+    }
+}
+
+/// Bytes needed to encode `value` as an unsigned LEB128 number
+fn leb128_size(mut value: usize) -> usize {
+    let mut size = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        size += 1;
+    }
+    size
+}
+
+/// Where the code of a binary came from in its text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Every defined function, in index order
+    functions: Vec<FunctionMap>,
+    /// Functions whose instructions didn't line up with the text, so only
+    /// their `func` keyword is mapped
+    unmapped: Vec<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FunctionMap {
+    index: u32,
+    /// Binary range of the body, locals included
+    body: std::ops::Range<usize>,
+    /// Text offset of the `func` keyword
+    text: usize,
+    /// Binary and text offset of each instruction, in binary order
+    instructions: Vec<(usize, usize)>,
+}
+
+impl SourceMap {
+    /// Pair the instructions of each body with the text offsets of
+    /// `funcs`. Folded forms have a text offset for each instruction they
+    /// encode: a folded `if` for its `if` and `else` keywords and for the
+    /// parenthesis closing it, which stands for its `end`. A body whose
+    /// instructions still don't line up with the text is mapped to its
+    /// `func` keyword only, and listed in [`SourceMap::unmapped`].
+    fn build(bytes: &[u8], funcs: Vec<(usize, Vec<usize>)>) -> wasmparser::Result<SourceMap> {
+        let mut map = SourceMap::default();
+        let mut funcs = funcs.into_iter();
+        let mut imported_funcs = 0;
+        for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        if matches!(
+                            import?.ty,
+                            wasmparser::TypeRef::Func(_) | wasmparser::TypeRef::FuncExact(_)
+                        ) {
+                            imported_funcs += 1;
+                        }
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let Some((text, spans)) = funcs.next() else {
+                        break;
+                    };
+                    let mut offsets = Vec::new();
+                    let mut reader = body.get_operators_reader()?;
+                    while !reader.eof() {
+                        offsets.push(reader.read_with_offset()?.1);
+                    }
+                    // The body's final `end` isn't written in the text
+                    offsets.pop();
+                    let index = imported_funcs + map.functions.len() as u32;
+                    let instructions = if offsets.len() == spans.len() {
+                        offsets.into_iter().zip(spans).collect()
+                    } else {
+                        map.unmapped.push(index);
+                        Vec::new()
+                    };
+                    map.functions.push(FunctionMap {
+                        index,
+                        body: body.range(),
+                        text,
+                        instructions,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(map)
+    }
+
+    /// Indices of the functions mapped to their `func` keyword only,
+    /// because their instructions didn't line up with the text
+    pub fn unmapped(&self) -> &[u32] {
+        &self.unmapped
+    }
+
+    /// Text offset of the instruction at a binary offset. Offsets in a
+    /// body's locals lead to its `func` keyword.
+    pub fn text_offset(&self, offset: usize) -> Option<usize> {
+        let func = self
+            .functions
+            .iter()
+            .find(|func| func.body.contains(&offset))?;
+        let before = func
+            .instructions
+            .partition_point(|&(binary, _)| binary <= offset);
+        match before {
+            0 => Some(func.text),
+            n => Some(func.instructions[n - 1].1),
+        }
+    }
+
+    /// Text offset of the instruction at an offset reported with a
+    /// function index, like `wasm-function[12]:0x3a4`. The offset is from
+    /// the start of the binary, or from the start of the function's body
+    /// when it doesn't fall inside the body otherwise.
+    pub fn resolve(&self, function: Option<u32>, offset: usize) -> Option<usize> {
+        let Some(index) = function else {
+            return self.text_offset(offset);
+        };
+        let func = self.functions.iter().find(|func| func.index == index)?;
+        if func.body.contains(&offset) {
+            self.text_offset(offset)
+        } else {
+            self.text_offset(func.body.start.checked_add(offset)?)
+        }
+    }
+
+    /// A [source map](https://sourcemaps.info/spec.html) for `source`, in the
+    /// form browsers use for WebAssembly: one generated line whose columns
+    /// are binary offsets, with UTF-16 source columns
+    pub fn to_json(&self, text: &str, source: &str) -> serde_json::Value {
+        let index = LineIndex::new(text);
+        let mut mappings = String::new();
+        let mut previous = [0i64; 4];
+        let entries = self.functions.iter().flat_map(|func| {
+            std::iter::once((func.body.start, func.text)).chain(func.instructions.iter().copied())
+        });
+        for (binary, offset) in entries {
+            let position = index.position(text, offset, PositionEncoding::Utf16);
+            let segment = [
+                binary as i64,
+                0,
+                position.line as i64,
+                position.character as i64,
+            ];
+            if !mappings.is_empty() {
+                mappings.push(',');
+            }
+            for (value, previous) in segment.iter().zip(previous.iter_mut()) {
+                write_vlq(&mut mappings, value - *previous);
+                *previous = *value;
+            }
+        }
+        serde_json::json!({
+            "version": 3,
+            "sources": [source],
+            "names": [],
+            "mappings": mappings,
+        })
+    }
+}
+
+/// Where the source map of a binary written to `out` goes: `out.wasm.map`
+pub fn source_map_path(out: &Path) -> PathBuf {
+    let mut path = out.as_os_str().to_owned();
+    path.push(".map");
+    PathBuf::from(path)
+}
+
+/// Range of the instruction name at a text offset, e.g. `i32.add`
+pub fn instruction_range(text: &str, offset: usize) -> Range {
+    let end = text[offset..]
+        .find(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .map_or(text.len(), |len| offset + len);
+    let index = LineIndex::new(text);
+    let position = |offset| -> Position { index.position(text, offset, PositionEncoding::Utf8) };
+    Range::new(position(offset), position(end))
+}
+
+/// Append a source map base64 VLQ number
+fn write_vlq(out: &mut String, value: i64) {
+    const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut rest = if value < 0 {
+        ((-value as u64) << 1) | 1
+    } else {
+        (value as u64) << 1
+    };
+    loop {
+        let mut digit = (rest & 0x1f) as u8;
+        rest >>= 5;
+        if rest > 0 {
+            digit |= 0x20;
+        }
+        out.push(BASE64[digit as usize] as char);
+        if rest == 0 {
+            break;
+        }
+    }
+}
+
+/// A binary code location as written in stack traces: `0x3a4`, `932`, or
+/// `wasm-function[12]:0x3a4`, as a function index and an offset
+pub fn parse_binary_location(text: &str) -> Option<(Option<u32>, usize)> {
+    let text = text.trim();
+    let (function, offset) = match text.strip_prefix("wasm-function[") {
+        Some(rest) => {
+            let (index, offset) = rest.split_once("]:")?;
+            (Some(index.parse().ok()?), offset)
+        }
+        None => (None, text),
+    };
+    let offset = match offset.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok()?,
+        None => offset.parse().ok()?,
+    };
+    Some((function, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_report() {
+        let bytes = compile(
+            r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (func $small)
+  (func $add (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))"#,
+        )
+        .unwrap();
+        let report = size_report(&bytes).unwrap();
+        assert_eq!(report.size, bytes.len());
+
+        let sections: Vec<_> = report.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            sections,
+            ["type", "import", "function", "code", "custom \"name\""]
+        );
+        let total: usize = report.sections.iter().map(|s| s.size).sum();
+        assert_eq!(total + 8, bytes.len());
+
+        // Imports have no body, so defined functions start at index 1
+        let small = report.function(1).unwrap();
+        assert_eq!(small.name.as_deref(), Some("small"));
+        // No locals, then `end`
+        assert_eq!(small.size, 2);
+        let add = report.function(2).unwrap();
+        assert_eq!(add.name.as_deref(), Some("add"));
+        // No locals, two `local.get`s, `i32.add` and `end`
+        assert_eq!(add.size, 7);
+        assert_eq!(report.function(0), None);
+    }
+
+    #[test]
+    fn test_compile_reports_errors() {
+        assert!(compile("(module (func (call $missing)))").is_err());
+    }
+
+    #[test]
+    fn test_source_map_resolves_instructions() {
+        let text = r#"(module
+  (func $first (result i32)
+    i32.const 1)
+  (func $add (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))"#;
+        let (bytes, map) = compile_with_source_map(text).unwrap();
+        let at = |offset: usize| &text[offset..offset + 7];
+
+        // Find `i32.add` (0x6a) in the second body
+        let add = bytes.iter().rposition(|&b| b == 0x6a).unwrap();
+        assert_eq!(at(map.text_offset(add).unwrap()), "i32.add");
+        // Its immediate-free encoding is one byte; the next byte is `end`
+        assert_eq!(at(map.text_offset(add + 1).unwrap()), "i32.add");
+        let local_get = map.text_offset(add - 2).unwrap();
+        assert_eq!(&text[local_get..local_get + 11], "local.get 1");
+
+        // Relative to the body of function 1, or absolute with the index
+        let body = map.functions[1].body.clone();
+        assert_eq!(map.resolve(Some(1), add - body.start), map.text_offset(add));
+        assert_eq!(map.resolve(Some(1), add), map.text_offset(add));
+        // The locals of a body lead to its `func` keyword
+        assert_eq!(at(map.resolve(Some(1), 0).unwrap()), "func $a");
+        assert_eq!(map.text_offset(0), None);
+
+        let range = instruction_range(text, map.text_offset(add).unwrap());
+        assert_eq!(range.start, Position::new(4, 5));
+        assert_eq!(range.end, Position::new(4, 12));
+    }
+
+    #[test]
+    fn test_source_map_resolves_folded_forms() {
+        let text = r#"(module
+  (func (param i32) (result i32)
+    (if (result i32) (local.get 0)
+      (then (i32.const 1))
+      (else (block (result i32) (i32.const 2))))))"#;
+        let (bytes, map) = compile_with_source_map(text).unwrap();
+        assert!(map.unmapped().is_empty());
+        let name = |offset: usize| {
+            let offset = map.text_offset(offset).unwrap();
+            let range = instruction_range(text, offset);
+            &text[offset..offset + (range.end.character - range.start.character) as usize]
+        };
+
+        // local.get 0, if, i32.const 1, else, block, i32.const 2, end, end
+        let ops: Vec<_> = map.functions[0]
+            .instructions
+            .iter()
+            .map(|&(b, _)| b)
+            .collect();
+        assert_eq!(ops.len(), 8);
+        assert_eq!(name(ops[0]), "local.get");
+        assert_eq!(bytes[ops[1]], 0x04);
+        assert_eq!(name(ops[1]), "if");
+        assert_eq!(name(ops[2]), "i32.const");
+        assert_eq!(bytes[ops[3]], 0x05);
+        assert_eq!(name(ops[3]), "else");
+        assert_eq!(name(ops[4]), "block");
+        assert_eq!(name(ops[5]), "i32.const");
+        // The `end`s are the parentheses closing the block and the `if`
+        let closing = text.find("const 2)").unwrap() + 8;
+        assert_eq!(map.text_offset(ops[6]), Some(closing));
+        assert_eq!(map.text_offset(ops[7]), Some(closing + 2));
+    }
+
+    #[test]
+    fn test_source_map_reports_unmapped_functions() {
+        let text = "(module\n  (func $a nop)\n  (func $b nop nop))";
+        let (bytes, _) = compile_with_source_map(text).unwrap();
+        // Text offsets for one `nop` of the second function only
+        let funcs = vec![(11, vec![19]), (27, vec![35])];
+        let map = SourceMap::build(&bytes, funcs).unwrap();
+        assert_eq!(map.unmapped(), [1]);
+        // Its instructions lead to its `func` keyword
+        let body = map.functions[1].body.clone();
+        assert_eq!(map.text_offset(body.end - 2), Some(27));
+        assert_eq!(map.text_offset(map.functions[0].body.end - 2), Some(19));
+    }
+
+    #[test]
+    fn test_source_map_json() {
+        let text = "(module\n  (func\n    nop))";
+        let (_, map) = compile_with_source_map(text).unwrap();
+        let json = map.to_json(text, "a.wat");
+        assert_eq!(json["sources"], serde_json::json!(["a.wat"]));
+        // Body start -> line 1 col 3, `nop` one byte later -> line 2 col 4
+        let body = map.functions[0].body.start as i64;
+        let mut expected = String::new();
+        for value in [body, 0, 1, 3] {
+            write_vlq(&mut expected, value);
+        }
+        expected.push(',');
+        for value in [1, 0, 1, 1] {
+            write_vlq(&mut expected, value);
+        }
+        assert_eq!(json["mappings"], serde_json::json!(expected));
+    }
+
+    #[test]
+    fn test_vlq() {
+        let mut out = String::new();
+        for value in [0, 1, -1, 16, 1000] {
+            write_vlq(&mut out, value);
+            out.push(',');
+        }
+        assert_eq!(out, "A,C,D,gB,w+B,");
+    }
+
+    #[test]
+    fn test_parse_binary_location() {
+        assert_eq!(parse_binary_location("0x3a4"), Some((None, 0x3a4)));
+        assert_eq!(parse_binary_location("932"), Some((None, 932)));
+        assert_eq!(
+            parse_binary_location("wasm-function[12]:0x3a4"),
+            Some((Some(12), 0x3a4))
+        );
+        assert_eq!(parse_binary_location("wasm-function[x]:0x3a4"), None);
+        assert_eq!(parse_binary_location("offset"), None);
+    }
+}
@@ -0,0 +1,1066 @@
+//! Components of the component model.
+//!
+//! The tree-sitter grammar only knows core modules, so a `(component ...)`
+//! document is read here with a small s-expression reader: its items, the
+//! index space each one belongs to, and the references between them. Core
+//! modules nested in a component are left to the regular parsers, which
+//! see them through [`core_module_text`]: the document with everything
+//! else blanked out, so that positions in it are positions in the document.
+//!
+//! Unlike core modules, components aren't reparsed incrementally: every edit
+//! reads the whole document again, masks it and parses its core modules
+//! afresh. That is cheap for components of usual sizes; past
+//! [`REPARSE_LIMIT`] the server waits for edits to pause before doing it.
+
+use crate::core::{HoverResult, LineIndex, Position, PositionEncoding, Range};
+use std::fmt;
+
+/// Size in bytes past which a component is reparsed once edits pause for the
+/// debounce period, rather than on every edit
+pub const REPARSE_LIMIT: usize = 256 * 1024;
+
+/// An index space of a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    CoreModule,
+    CoreInstance,
+    CoreFunc,
+    CoreTable,
+    CoreMemory,
+    CoreGlobal,
+    CoreType,
+    Func,
+    Value,
+    Type,
+    Component,
+    Instance,
+}
+
+impl Sort {
+    /// The sort a keyword names, e.g. `func`; `core` for one after `core`
+    /// or inside a core definition
+    fn from_keyword(keyword: &str, core: bool) -> Option<Self> {
+        Some(match (keyword, core) {
+            ("module", _) => Sort::CoreModule,
+            ("table", _) => Sort::CoreTable,
+            ("memory", _) => Sort::CoreMemory,
+            ("global", _) => Sort::CoreGlobal,
+            ("func", true) => Sort::CoreFunc,
+            ("instance", true) => Sort::CoreInstance,
+            ("type", true) => Sort::CoreType,
+            ("func", false) => Sort::Func,
+            ("instance", false) => Sort::Instance,
+            ("type", false) => Sort::Type,
+            ("value", false) => Sort::Value,
+            ("component", false) => Sort::Component,
+            _ => return None,
+        })
+    }
+
+    pub fn is_core(self) -> bool {
+        matches!(
+            self,
+            Sort::CoreModule
+                | Sort::CoreInstance
+                | Sort::CoreFunc
+                | Sort::CoreTable
+                | Sort::CoreMemory
+                | Sort::CoreGlobal
+                | Sort::CoreType
+        )
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Sort::CoreModule => "core module",
+            Sort::CoreInstance => "core instance",
+            Sort::CoreFunc => "core func",
+            Sort::CoreTable => "core table",
+            Sort::CoreMemory => "core memory",
+            Sort::CoreGlobal => "core global",
+            Sort::CoreType => "core type",
+            Sort::Func => "func",
+            Sort::Value => "value",
+            Sort::Type => "type",
+            Sort::Component => "component",
+            Sort::Instance => "instance",
+        })
+    }
+}
+
+/// A component, the outermost or one nested in another
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentScope {
+    pub name: Option<String>,
+    pub range: Range,
+    /// Scope of the component this one is nested in
+    pub parent: Option<usize>,
+}
+
+/// An item in one of a component's index spaces
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentItem {
+    pub sort: Sort,
+    /// Index within its sort in its component
+    pub index: usize,
+    /// The component that defines it
+    pub scope: usize,
+    pub name: Option<String>,
+    pub name_range: Option<Range>,
+    /// Range of the whole definition
+    pub range: Range,
+    /// The name it is imported or exported under
+    pub external: Option<String>,
+    /// How it is defined, e.g. `canon lift $run` or `import "wasi:cli/run"`
+    pub detail: String,
+    /// Scope of a nested component
+    pub nested: Option<usize>,
+}
+
+impl ComponentItem {
+    /// Its `$name`, the name it is imported or exported under, or its sort
+    /// and index
+    pub fn label(&self) -> String {
+        match (&self.name, &self.external) {
+            (Some(name), _) => name.clone(),
+            (None, Some(external)) => format!("\"{}\"", external),
+            (None, None) => format!("{} {}", self.sort, self.index),
+        }
+    }
+}
+
+/// A `$name` or index that refers to an item
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentReference {
+    pub sort: Sort,
+    /// The component it appears in
+    pub scope: usize,
+    pub target: String,
+    pub range: Range,
+}
+
+/// The items of a component document and the references between them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ComponentSymbols {
+    /// Scope 0 is the outermost component
+    pub scopes: Vec<ComponentScope>,
+    pub items: Vec<ComponentItem>,
+    pub references: Vec<ComponentReference>,
+}
+
+impl ComponentSymbols {
+    /// Read a component document; `None` for core modules and scripts
+    pub fn parse(text: &str) -> Option<Self> {
+        let root = read(text).into_iter().next()?;
+        if root.keyword(text, 0) != Some("component") {
+            return None;
+        }
+        let mut builder = Builder {
+            text,
+            index: LineIndex::new(text),
+            symbols: ComponentSymbols::default(),
+        };
+        builder.component(&root, None);
+        Some(builder.symbols)
+    }
+
+    /// Items defined directly in a component, in source order
+    pub fn items_in(&self, scope: usize) -> impl Iterator<Item = &ComponentItem> {
+        self.items.iter().filter(move |item| item.scope == scope)
+    }
+
+    /// The item a reference refers to
+    pub fn resolve(&self, reference: &ComponentReference) -> Option<&ComponentItem> {
+        let mut candidates = self
+            .items_in(reference.scope)
+            .filter(|item| item.sort == reference.sort);
+        match reference.target.parse::<usize>() {
+            Ok(index) => candidates.find(|item| item.index == index),
+            Err(_) => candidates.find(|item| item.name.as_deref() == Some(&reference.target)),
+        }
+    }
+
+    /// The reference at `position`
+    pub fn reference_at(&self, position: Position) -> Option<&ComponentReference> {
+        self.references
+            .iter()
+            .find(|reference| reference.range.contains(position))
+    }
+
+    /// The item whose `$name` is at `position`
+    pub fn item_at(&self, position: Position) -> Option<&ComponentItem> {
+        self.items.iter().find(|item| {
+            item.name_range
+                .is_some_and(|range| range.contains(position))
+        })
+    }
+
+    /// The item named at `position`, by a reference or its own definition
+    pub fn definition_at(&self, position: Position) -> Option<&ComponentItem> {
+        match self.reference_at(position) {
+            Some(reference) => self.resolve(reference),
+            None => self.item_at(position),
+        }
+    }
+
+    /// The core module whose definition contains `position`
+    pub fn core_module_at(&self, position: Position) -> Option<&ComponentItem> {
+        self.items
+            .iter()
+            .find(|item| item.sort == Sort::CoreModule && item.range.contains(position))
+    }
+
+    /// Hover for the item named at `position`, or the canonical ABI keyword
+    /// there
+    pub fn hover(&self, text: &str, position: Position) -> Option<HoverResult> {
+        let range = self
+            .reference_at(position)
+            .map(|reference| reference.range)
+            .or_else(|| self.item_at(position).and_then(|item| item.name_range));
+        if let Some(item) = self.definition_at(position) {
+            let mut contents = format!(
+                "```wat\n{}\n```\n{} {}",
+                head(text, item),
+                item.sort,
+                item.index
+            );
+            if !item.detail.is_empty() {
+                contents.push_str(&format!(" · {}", item.detail));
+            }
+            if let Some(name) = item.scope_name(self) {
+                contents.push_str(&format!(" of component {}", name));
+            }
+            return Some(HoverResult { contents, range });
+        }
+
+        let word = word_at(text, position)?;
+        let doc = KEYWORD_DOCS
+            .iter()
+            .find(|(keyword, _)| *keyword == word)
+            .map(|(_, doc)| doc)?;
+        Some(HoverResult::new(format!("```wat\n{}\n```\n{}", word, doc)))
+    }
+}
+
+impl ComponentItem {
+    /// Name of the nested component defining the item, if it isn't the
+    /// outermost one
+    fn scope_name<'a>(&self, symbols: &'a ComponentSymbols) -> Option<&'a str> {
+        let scope = symbols.scopes.get(self.scope)?;
+        scope.parent?;
+        scope.name.as_deref()
+    }
+}
+
+/// Documentation for component keywords
+const KEYWORD_DOCS: &[(&str, &str)] = &[
+    (
+        "lift",
+        "Wraps a core function as a component function, lifting its arguments and results from core values through the canonical ABI",
+    ),
+    (
+        "lower",
+        "Wraps a component function as a core function, lowering its arguments and results to core values through the canonical ABI",
+    ),
+    (
+        "instantiate",
+        "Instantiates a module or component, supplying its imports with `with` arguments",
+    ),
+    (
+        "alias",
+        "Brings an export of an instance, or an item of an enclosing component, into this component's index space",
+    ),
+    ("resource.new", "Creates a handle to a new resource of the given type from its representation"),
+    ("resource.drop", "Drops a handle to a resource, calling its destructor if it is owned"),
+    ("resource.rep", "Returns the representation of a resource owned by this component"),
+];
+
+/// The text of an item's definition, or its first line when it is long
+fn head(text: &str, item: &ComponentItem) -> String {
+    let index = LineIndex::new(text);
+    let start = index.offset(text, item.range.start, PositionEncoding::Utf8);
+    let end = index.offset(text, item.range.end, PositionEncoding::Utf8);
+    let definition = text.get(start..end).unwrap_or_default();
+    if definition.lines().count() <= 6 && definition.len() <= 400 {
+        return definition.to_string();
+    }
+    let first = definition.lines().next().unwrap_or_default().trim_end();
+    format!("{} ...)", first)
+}
+
+/// The atom at `position`
+fn word_at(text: &str, position: Position) -> Option<&str> {
+    let line = text.lines().nth(position.line as usize)?;
+    let cursor = (position.character as usize).min(line.len());
+    let is_word = |c: char| !c.is_whitespace() && !"()\"".contains(c);
+    let start = line[..cursor].rfind(|c| !is_word(c)).map_or(0, |i| i + 1);
+    let end = line[cursor..]
+        .find(|c| !is_word(c))
+        .map_or(line.len(), |i| cursor + i);
+    (start < end).then(|| &line[start..end])
+}
+
+/// Whether `text` is a component rather than a core module or script
+pub fn is_component(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let start = skip_trivia(bytes, 0);
+    if bytes.get(start) != Some(&b'(') {
+        return false;
+    }
+    let keyword = skip_trivia(bytes, start + 1);
+    text[keyword..].starts_with("component")
+        && bytes
+            .get(keyword + "component".len())
+            .is_none_or(|&b| is_delimiter(b))
+}
+
+/// The core modules defined in a component, with everything else blanked
+/// out and `(core module` read as `(module`; `None` for other documents.
+/// Byte offsets and line breaks are kept, so positions carry over.
+pub fn core_module_text(text: &str) -> Option<String> {
+    if !is_component(text) {
+        return None;
+    }
+    Some(mask(text, &core_modules(text)))
+}
+
+/// Each core module defined in a component, in its own blanked-out copy of
+/// the text as [`core_module_text`] makes, with the range of its definition
+pub fn core_module_texts(text: &str) -> Vec<(Range, String)> {
+    if !is_component(text) {
+        return Vec::new();
+    }
+    let index = LineIndex::new(text);
+    core_modules(text)
+        .into_iter()
+        .map(|module| {
+            let range = Range {
+                start: index.position(text, module.start, PositionEncoding::Utf8),
+                end: index.position(text, module.end, PositionEncoding::Utf8),
+            };
+            (range, mask(text, &[module]))
+        })
+        .collect()
+}
+
+/// Byte span of a `(core module ...)` definition, and of its `core` keyword
+#[derive(Debug, Clone, Copy)]
+struct CoreModule {
+    start: usize,
+    end: usize,
+    keyword: (usize, usize),
+}
+
+/// The core modules defined in the component and in components nested in it
+fn core_modules(text: &str) -> Vec<CoreModule> {
+    fn visit(component: &Sexp, text: &str, modules: &mut Vec<CoreModule>) {
+        for field in component.items() {
+            match (field.keyword(text, 0), field.keyword(text, 1)) {
+                (Some("core"), Some("module")) => {
+                    let (start, end) = field.span();
+                    modules.push(CoreModule {
+                        start,
+                        end,
+                        keyword: field.items()[0].span(),
+                    });
+                }
+                (Some("component"), _) => visit(field, text, modules),
+                _ => {}
+            }
+        }
+    }
+
+    let mut modules = Vec::new();
+    if let Some(root) = read(text).first() {
+        visit(root, text, &mut modules);
+    }
+    modules
+}
+
+/// `text` with only the given core modules kept, less their `core` keyword
+fn mask(text: &str, modules: &[CoreModule]) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let blank = |masked: &mut String, part: &str| {
+        masked.extend(part.bytes().map(|b| match b {
+            b'\n' | b'\r' => b as char,
+            _ => ' ',
+        }));
+    };
+    let mut offset = 0;
+    for module in modules {
+        blank(&mut masked, &text[offset..module.start]);
+        let (keyword_start, keyword_end) = module.keyword;
+        masked.push_str(&text[module.start..keyword_start]);
+        blank(&mut masked, &text[keyword_start..keyword_end]);
+        masked.push_str(&text[keyword_end..module.end]);
+        offset = module.end;
+    }
+    blank(&mut masked, &text[offset..]);
+    masked
+}
+
+/// Collects the items and references of a component document
+struct Builder<'a> {
+    text: &'a str,
+    index: LineIndex,
+    symbols: ComponentSymbols,
+}
+
+impl Builder<'_> {
+    fn range(&self, (start, end): (usize, usize)) -> Range {
+        Range {
+            start: self
+                .index
+                .position(self.text, start, PositionEncoding::Utf8),
+            end: self.index.position(self.text, end, PositionEncoding::Utf8),
+        }
+    }
+
+    /// The `$id` at item `i` of a list, and its range
+    fn id(&self, list: &Sexp, i: usize) -> (Option<String>, Option<Range>) {
+        match list.items().get(i) {
+            Some(atom) if atom.atom(self.text).is_some_and(|a| a.starts_with('$')) => (
+                atom.atom(self.text).map(str::to_string),
+                Some(self.range(atom.span())),
+            ),
+            _ => (None, None),
+        }
+    }
+
+    /// Read a component and its fields, returning its scope
+    fn component(&mut self, list: &Sexp, parent: Option<usize>) -> usize {
+        let scope = self.symbols.scopes.len();
+        let (name, _) = self.id(list, 1);
+        self.symbols.scopes.push(ComponentScope {
+            name,
+            range: self.range(list.span()),
+            parent,
+        });
+        for field in list.items().iter().skip(1) {
+            if field.is_list() {
+                self.field(field, scope);
+            }
+        }
+        scope
+    }
+
+    fn field(&mut self, field: &Sexp, scope: usize) {
+        let text = self.text;
+        let items = field.items();
+        match field.keyword(text, 0) {
+            Some("core") => {
+                let Some(sort) = field
+                    .keyword(text, 1)
+                    .and_then(|k| Sort::from_keyword(k, true))
+                else {
+                    return;
+                };
+                let detail = self.definition_detail(field, 2);
+                self.define(scope, sort, field, field, 2, None, detail, None);
+                // Core modules are read by the regular parsers
+                if sort != Sort::CoreModule {
+                    self.references(&items[2..], scope, true, None);
+                }
+            }
+            Some("component") => {
+                let nested = self.component(field, Some(scope));
+                self.define(
+                    scope,
+                    Sort::Component,
+                    field,
+                    field,
+                    1,
+                    None,
+                    String::new(),
+                    Some(nested),
+                );
+            }
+            Some(keyword @ ("func" | "value" | "type" | "instance")) => {
+                let Some(sort) = Sort::from_keyword(keyword, false) else {
+                    return;
+                };
+                let detail = self.definition_detail(field, 1);
+                self.define(scope, sort, field, field, 1, None, detail, None);
+                self.references(&items[1..], scope, false, None);
+            }
+            Some("import") => {
+                let external = items.get(1).and_then(|name| name.string(text));
+                let Some(desc) = items.get(2).filter(|desc| desc.is_list()) else {
+                    return;
+                };
+                let Some((sort, id)) = self.sort_of(desc, false) else {
+                    return;
+                };
+                let detail = format!("import \"{}\"", external.as_deref().unwrap_or_default());
+                self.define(scope, sort, field, desc, id, external, detail, None);
+                self.references(&items[2..], scope, false, Some(desc.span().0));
+            }
+            Some("export") => {
+                let (has_id, name_at) = match self.id(field, 1) {
+                    (Some(_), _) => (true, 2),
+                    _ => (false, 1),
+                };
+                let external = items.get(name_at).and_then(|name| name.string(text));
+                let Some((sort, _)) = items
+                    .get(name_at + 1)
+                    .and_then(|sortidx| self.sort_of(sortidx, false))
+                else {
+                    return;
+                };
+                let detail = format!("export \"{}\"", external.as_deref().unwrap_or_default());
+                let id = if has_id { 1 } else { usize::MAX };
+                self.define(scope, sort, field, field, id, external, detail, None);
+                self.references(&items[name_at..], scope, false, None);
+            }
+            Some("alias" | "canon") => {
+                // The last list declares the item, e.g. (core func $f)
+                let Some(target) = items.last().filter(|target| target.is_list()) else {
+                    return;
+                };
+                let Some((sort, id)) = self.sort_of(target, false) else {
+                    return;
+                };
+                let detail = self.describe(field);
+                self.define(scope, sort, field, target, id, None, detail, None);
+                self.references(
+                    std::slice::from_ref(field),
+                    scope,
+                    false,
+                    Some(target.span().0),
+                );
+            }
+            Some("start") => self.references(std::slice::from_ref(field), scope, false, None),
+            _ => {}
+        }
+    }
+
+    /// The sort a list such as `(core func $f ...)` declares, and where its
+    /// `$id` would be
+    fn sort_of(&self, list: &Sexp, core: bool) -> Option<(Sort, usize)> {
+        match list.keyword(self.text, 0)? {
+            "core" => Some((Sort::from_keyword(list.keyword(self.text, 1)?, true)?, 2)),
+            keyword => Some((Sort::from_keyword(keyword, core)?, 1)),
+        }
+    }
+
+    /// Add an item declared by `declaration`, whose `$id` is at item `id`
+    #[allow(clippy::too_many_arguments)]
+    fn define(
+        &mut self,
+        scope: usize,
+        sort: Sort,
+        definition: &Sexp,
+        declaration: &Sexp,
+        id: usize,
+        external: Option<String>,
+        mut detail: String,
+        nested: Option<usize>,
+    ) {
+        let (name, name_range) = self.id(declaration, id);
+        for export in inline_exports(definition, self.text) {
+            if !detail.is_empty() {
+                detail.push_str(" · ");
+            }
+            detail.push_str(&format!("exported as \"{}\"", export));
+        }
+        let index = self
+            .symbols
+            .items_in(scope)
+            .filter(|item| item.sort == sort)
+            .count();
+        self.symbols.items.push(ComponentItem {
+            sort,
+            index,
+            scope,
+            name,
+            name_range,
+            range: self.range(definition.span()),
+            external,
+            detail,
+            nested,
+        });
+    }
+
+    /// How a definition such as `(func $f (canon lift ...))` makes its
+    /// item, from the form after its `$id`
+    fn definition_detail(&self, definition: &Sexp, id: usize) -> String {
+        let skip = if self.id(definition, id).0.is_some() {
+            id + 1
+        } else {
+            id
+        };
+        let mut bodies = definition
+            .items()
+            .iter()
+            .skip(skip)
+            .filter(|item| item.keyword(self.text, 0) != Some("export"));
+        let Some(first) = bodies.clone().next() else {
+            return String::new();
+        };
+        let made = bodies.find(|body| {
+            matches!(
+                body.keyword(self.text, 0),
+                Some("canon" | "alias" | "instantiate")
+            )
+        });
+        if let Some(made) = made {
+            return self.describe(made);
+        }
+        if let Some(atom) = first.atom(self.text) {
+            return atom.to_string();
+        }
+        match first.keyword(self.text, 0) {
+            Some(keyword) if !matches!(keyword, "import" | "type") => keyword.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// A form's keywords and names, and for `canon` the function it wraps,
+    /// e.g. `canon lift $f` or `alias export $i "run"`
+    fn describe(&self, form: &Sexp) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        for item in form.items() {
+            if let Some(atom) = item.atom(self.text) {
+                parts.push(atom.to_string());
+                continue;
+            }
+            if form.keyword(self.text, 0) == Some("canon") {
+                let function = item
+                    .items()
+                    .iter()
+                    .filter_map(|i| i.atom(self.text))
+                    .find(|atom| is_index(atom));
+                parts.extend(function.map(str::to_string));
+            }
+            break;
+        }
+        parts.join(" ")
+    }
+
+    /// Record the references in `items` and the lists nested in them. Plain
+    /// sort keywords name core sorts where `core` is set. The list starting
+    /// at `declaration` declares an item rather than referring to one.
+    fn references(&mut self, items: &[Sexp], scope: usize, core: bool, declaration: Option<usize>) {
+        let text = self.text;
+        for list in items.iter().filter(|item| item.is_list()) {
+            let inner = list.items();
+            let is_declaration = declaration == Some(list.span().0);
+            match list.keyword(text, 0) {
+                Some("core") => {
+                    if let Some(sort) = list
+                        .keyword(text, 1)
+                        .and_then(|k| Sort::from_keyword(k, true))
+                    {
+                        if !is_declaration {
+                            self.sort_reference(sort, &inner[2..], scope, true);
+                        }
+                    }
+                    self.references(inner.get(2..).unwrap_or_default(), scope, true, None);
+                }
+                Some("instantiate") => {
+                    let sort = if core {
+                        Sort::CoreModule
+                    } else {
+                        Sort::Component
+                    };
+                    if let Some(target) = inner.get(1) {
+                        self.reference(sort, target, scope);
+                    }
+                    self.references(&inner[1..], scope, core, None);
+                }
+                Some("alias") => {
+                    match (list.keyword(text, 1), list.keyword(text, 2)) {
+                        (Some("export"), _) => {
+                            if let Some(target) = inner.get(2) {
+                                self.reference(Sort::Instance, target, scope);
+                            }
+                        }
+                        (Some("core"), Some("export")) => {
+                            if let Some(target) = inner.get(3) {
+                                self.reference(Sort::CoreInstance, target, scope);
+                            }
+                        }
+                        _ => {}
+                    }
+                    self.references(&inner[1..], scope, core, declaration);
+                }
+                Some("canon") => {
+                    if list
+                        .keyword(text, 1)
+                        .is_some_and(|k| k.starts_with("resource."))
+                    {
+                        if let Some(target) = inner.get(2) {
+                            self.reference(Sort::Type, target, scope);
+                        }
+                    }
+                    self.references(&inner[1..], scope, false, declaration);
+                }
+                Some("start") => {
+                    if let Some(target) = inner.get(1) {
+                        self.reference(Sort::Func, target, scope);
+                    }
+                    self.references(&inner[1..], scope, core, None);
+                }
+                Some("realloc" | "post-return" | "callback") => {
+                    if let Some(target) = inner.get(1) {
+                        self.reference(Sort::CoreFunc, target, scope);
+                    }
+                }
+                Some(keyword) => {
+                    if let Some(sort) = Sort::from_keyword(keyword, core) {
+                        if !is_declaration {
+                            self.sort_reference(sort, &inner[1..], scope, core);
+                        }
+                    }
+                    self.references(&inner[1..], scope, core, None);
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Record the reference of a `(func $f)` form, given what follows its
+    /// sort keywords. `(func $i "name")` names the export of an instance.
+    fn sort_reference(&mut self, sort: Sort, rest: &[Sexp], scope: usize, core: bool) {
+        match rest {
+            [target] => self.reference(sort, target, scope),
+            [instance, name] if name.string(self.text).is_some() => {
+                let sort = if core {
+                    Sort::CoreInstance
+                } else {
+                    Sort::Instance
+                };
+                self.reference(sort, instance, scope);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record `target` as a reference if it is a `$name` or an index
+    fn reference(&mut self, sort: Sort, target: &Sexp, scope: usize) {
+        let Some(atom) = target.atom(self.text).filter(|atom| is_index(atom)) else {
+            return;
+        };
+        let range = self.range(target.span());
+        self.symbols.references.push(ComponentReference {
+            sort,
+            scope,
+            target: atom.to_string(),
+            range,
+        });
+    }
+}
+
+/// The names of `(export "name")` forms inside a definition
+fn inline_exports(definition: &Sexp, text: &str) -> Vec<String> {
+    definition
+        .items()
+        .iter()
+        .filter(|item| item.keyword(text, 0) == Some("export") && item.items().len() == 2)
+        .filter_map(|item| item.items()[1].string(text))
+        .collect()
+}
+
+/// Whether an atom is a `$name` or a numeric index
+fn is_index(atom: &str) -> bool {
+    atom.starts_with('$') || (!atom.is_empty() && atom.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// An atom or a parenthesized list, by byte span
+#[derive(Debug)]
+enum Sexp {
+    Atom {
+        start: usize,
+        end: usize,
+    },
+    List {
+        start: usize,
+        end: usize,
+        items: Vec<Sexp>,
+    },
+}
+
+impl Sexp {
+    fn span(&self) -> (usize, usize) {
+        match self {
+            Sexp::Atom { start, end } | Sexp::List { start, end, .. } => (*start, *end),
+        }
+    }
+
+    fn is_list(&self) -> bool {
+        matches!(self, Sexp::List { .. })
+    }
+
+    fn items(&self) -> &[Sexp] {
+        match self {
+            Sexp::List { items, .. } => items,
+            Sexp::Atom { .. } => &[],
+        }
+    }
+
+    fn atom<'a>(&self, text: &'a str) -> Option<&'a str> {
+        match self {
+            Sexp::Atom { start, end } => text.get(*start..*end),
+            Sexp::List { .. } => None,
+        }
+    }
+
+    /// The contents of a string atom
+    fn string(&self, text: &str) -> Option<String> {
+        let atom = self.atom(text)?;
+        let inner = atom.strip_prefix('"')?;
+        Some(inner.strip_suffix('"').unwrap_or(inner).to_string())
+    }
+
+    /// Item `i` of a list, if it is a keyword or other unquoted atom
+    fn keyword<'a>(&self, text: &'a str, i: usize) -> Option<&'a str> {
+        self.items()
+            .get(i)?
+            .atom(text)
+            .filter(|atom| !atom.starts_with('"'))
+    }
+}
+
+/// Read the s-expressions of `text`, skipping comments. Lists left open
+/// end at the end of the text.
+fn read(text: &str) -> Vec<Sexp> {
+    fn push(stack: &mut [(usize, Vec<Sexp>)], top: &mut Vec<Sexp>, sexp: Sexp) {
+        match stack.last_mut() {
+            Some((_, items)) => items.push(sexp),
+            None => top.push(sexp),
+        }
+    }
+
+    let bytes = text.as_bytes();
+    let mut stack: Vec<(usize, Vec<Sexp>)> = Vec::new();
+    let mut top = Vec::new();
+    let mut i = skip_trivia(bytes, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => {
+                stack.push((i, Vec::new()));
+                i += 1;
+            }
+            b')' => {
+                if let Some((start, items)) = stack.pop() {
+                    let list = Sexp::List {
+                        start,
+                        end: i + 1,
+                        items,
+                    };
+                    push(&mut stack, &mut top, list);
+                }
+                i += 1;
+            }
+            b'"' => {
+                let start = i;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                push(&mut stack, &mut top, Sexp::Atom { start, end: i });
+            }
+            _ => {
+                let start = i;
+                while i < bytes.len() && !is_delimiter(bytes[i]) {
+                    i += 1;
+                }
+                push(&mut stack, &mut top, Sexp::Atom { start, end: i });
+            }
+        }
+        i = skip_trivia(bytes, i);
+    }
+    while let Some((start, items)) = stack.pop() {
+        let list = Sexp::List {
+            start,
+            end: bytes.len(),
+            items,
+        };
+        push(&mut stack, &mut top, list);
+    }
+    top
+}
+
+fn is_delimiter(b: u8) -> bool {
+    b.is_ascii_whitespace() || matches!(b, b'(' | b')' | b'"')
+}
+
+/// Skip whitespace, `;;` line comments and nested `(; ;)` block comments
+fn skip_trivia(bytes: &[u8], mut i: usize) -> usize {
+    loop {
+        match (bytes.get(i), bytes.get(i + 1)) {
+            (Some(b), _) if b.is_ascii_whitespace() => i += 1,
+            (Some(b';'), Some(b';')) => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            (Some(b'('), Some(b';')) => {
+                let mut depth = 0;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"(;") {
+                        depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b";)") {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            _ => return i,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPONENT: &str = r#"(component $app
+  (import "wasi:cli/stdout" (instance $stdout
+    (export "print" (func (param "msg" string)))))
+  (core module $main
+    (import "host" "log" (func $log (param i32 i32)))
+    (memory (export "mem") 1)
+    (func (export "run")))
+  (alias export $stdout "print" (func $print))
+  (core instance $libc (instantiate $main))
+  (alias core export $libc "mem" (core memory $mem))
+  (core func $log (canon lower (func $print) (memory $mem)))
+  (core instance $inst (instantiate $main
+    (with "host" (instance (export "log" (func $log))))))
+  (type $run-type (func))
+  (func $run (type $run-type) (canon lift (core func $inst "run")))
+  (export "run" (func $run)))
+"#;
+
+    fn position_of(text: &str, needle: &str, nth: usize) -> Position {
+        let offset = text.match_indices(needle).nth(nth).unwrap().0;
+        LineIndex::new(text).position(text, offset, PositionEncoding::Utf8)
+    }
+
+    #[test]
+    fn test_component_items() {
+        let symbols = ComponentSymbols::parse(COMPONENT).unwrap();
+        let items: Vec<(String, String, String)> = symbols
+            .items_in(0)
+            .map(|item| (item.sort.to_string(), item.label(), item.detail.clone()))
+            .collect();
+        let expected = [
+            ("instance", "$stdout", "import \"wasi:cli/stdout\""),
+            ("core module", "$main", ""),
+            ("func", "$print", "alias export $stdout \"print\""),
+            ("core instance", "$libc", "instantiate $main"),
+            ("core memory", "$mem", "alias core export $libc \"mem\""),
+            ("core func", "$log", "canon lower $print"),
+            ("core instance", "$inst", "instantiate $main"),
+            ("type", "$run-type", "func"),
+            ("func", "$run", "canon lift $inst"),
+            ("func", "\"run\"", "export \"run\""),
+        ];
+        let expected: Vec<(String, String, String)> = expected
+            .iter()
+            .map(|(a, b, c)| (a.to_string(), b.to_string(), c.to_string()))
+            .collect();
+        assert_eq!(items, expected);
+
+        let run = symbols
+            .items
+            .iter()
+            .find(|i| i.label() == "\"run\"")
+            .unwrap();
+        assert_eq!(run.index, 2);
+        assert!(ComponentSymbols::parse("(module (func))").is_none());
+    }
+
+    #[test]
+    fn test_component_references() {
+        let symbols = ComponentSymbols::parse(COMPONENT).unwrap();
+
+        // (func $print) in canon lower is the component function
+        let print = symbols
+            .definition_at(position_of(COMPONENT, "$print", 1))
+            .unwrap();
+        assert_eq!(print.sort, Sort::Func);
+        assert_eq!(
+            print.name_range.unwrap().start,
+            position_of(COMPONENT, "$print", 0)
+        );
+
+        // (memory $mem) is a core memory, (func $log) in a core instance a core func
+        let mem = symbols
+            .definition_at(position_of(COMPONENT, "$mem", 1))
+            .unwrap();
+        assert_eq!(mem.sort, Sort::CoreMemory);
+        let log = symbols
+            .definition_at(position_of(COMPONENT, "$log)", 0))
+            .unwrap();
+        assert_eq!(log.sort, Sort::CoreFunc);
+
+        // Instantiating from a core instance refers to a core module
+        let main = symbols
+            .definition_at(position_of(COMPONENT, "$main", 1))
+            .unwrap();
+        assert_eq!(main.sort, Sort::CoreModule);
+
+        // $log inside the core module isn't a component reference
+        assert!(symbols
+            .reference_at(position_of(COMPONENT, "$log", 0))
+            .is_none());
+
+        let run = symbols
+            .definition_at(position_of(COMPONENT, "$run)", 0))
+            .unwrap();
+        assert_eq!(run.sort, Sort::Func);
+        assert_eq!(run.index, 1);
+
+        // (core func $inst "run") aliases an export of the core instance
+        let inst = symbols
+            .definition_at(position_of(COMPONENT, "$inst \"run\"", 0))
+            .unwrap();
+        assert_eq!(inst.sort, Sort::CoreInstance);
+    }
+
+    #[test]
+    fn test_component_hover() {
+        let symbols = ComponentSymbols::parse(COMPONENT).unwrap();
+        let hover = symbols
+            .hover(COMPONENT, position_of(COMPONENT, "$print", 1))
+            .unwrap();
+        assert_eq!(
+            hover.contents,
+            "```wat\n(alias export $stdout \"print\" (func $print))\n```\nfunc 0 · alias export $stdout \"print\""
+        );
+        let hover = symbols
+            .hover(COMPONENT, position_of(COMPONENT, "lower", 0))
+            .unwrap();
+        assert!(hover.contents.contains("canonical ABI"));
+    }
+
+    #[test]
+    fn test_core_module_text() {
+        let masked = core_module_text(COMPONENT).unwrap();
+        assert_eq!(masked.len(), COMPONENT.len());
+        assert_eq!(masked.lines().count(), COMPONENT.lines().count());
+        let start = COMPONENT.find("(core module").unwrap();
+        assert!(masked[..start].trim().is_empty());
+        assert!(masked[start..].starts_with("(     module $main"));
+        assert!(masked.contains("(func (export \"run\")))"));
+        assert!(!masked.contains("canon"));
+
+        let texts = core_module_texts(COMPONENT);
+        assert_eq!(texts.len(), 1);
+        assert_eq!(texts[0].0.start, position_of(COMPONENT, "(core module", 0));
+        assert_eq!(core_module_text("(module)"), None);
+        assert!(is_component(";; app\n(; block ;) (component)"));
+        assert!(!is_component("(components)"));
+    }
+}
@@ -0,0 +1,54 @@
+//! Cancellation of long-running features.
+//!
+//! Features that walk a whole document or workspace take a
+//! [`CancellationToken`] and stop early once it is cancelled. The WASM build
+//! never cancels, and passes a default token.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Marks a request as cancelled when dropped.
+///
+/// tower-lsp drops a request's future on `$/cancelRequest`; work already
+/// handed to another thread checks the guard's token to stop early.
+#[derive(Debug, Default)]
+pub struct CancellationGuard {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationGuard {
+    pub fn token(&self) -> CancellationToken {
+        CancellationToken(Arc::clone(&self.cancelled))
+    }
+}
+
+impl Drop for CancellationGuard {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Shared flag set once the request that owns it is gone
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_cancelled_when_guard_dropped() {
+        let guard = CancellationGuard::default();
+        let token = guard.token();
+        assert!(!token.is_cancelled());
+
+        drop(guard);
+        assert!(token.is_cancelled());
+    }
+}
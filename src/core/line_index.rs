@@ -0,0 +1,185 @@
+//! Line index for converting between byte offsets and positions.
+//!
+//! Internally every `Position::character` is a UTF-8 byte column, the same
+//! unit tree-sitter uses. Clients count columns in UTF-16 code units unless
+//! another encoding was negotiated, so positions are converted with a
+//! [`LineIndex`] wherever they cross the protocol boundary.
+
+use super::types::{Position, Range};
+
+/// Unit in which a position's `character` counts columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PositionEncoding {
+    /// UTF-8 code units (bytes), used internally
+    Utf8,
+    /// UTF-16 code units, the LSP default and JavaScript's string unit
+    #[default]
+    Utf16,
+    /// Unicode scalar values
+    Utf32,
+}
+
+impl PositionEncoding {
+    /// Width of a character in this encoding
+    fn len(self, c: char) -> usize {
+        match self {
+            PositionEncoding::Utf8 => c.len_utf8(),
+            PositionEncoding::Utf16 => c.len_utf16(),
+            PositionEncoding::Utf32 => 1,
+        }
+    }
+}
+
+/// Byte offsets of line starts in a text.
+///
+/// Lines end at `\n`; a `\r` before it belongs to the line break, so
+/// `\r\n` files have the same columns as `\n` files.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i, _)| i + 1));
+        Self {
+            line_starts,
+            len: text.len(),
+        }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Byte range of a line's content, without its line break
+    pub fn line_range(&self, text: &str, line: usize) -> Option<std::ops::Range<usize>> {
+        let start = *self.line_starts.get(line)?;
+        let mut end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.len, |&next| next - 1);
+        if text.as_bytes().get(end.wrapping_sub(1)) == Some(&b'\r') && end > start {
+            end -= 1;
+        }
+        Some(start..end)
+    }
+
+    /// Byte offset of a position, clamped to the end of its line or the text
+    pub fn offset(&self, text: &str, position: Position, encoding: PositionEncoding) -> usize {
+        let Some(line) = self.line_range(text, position.line as usize) else {
+            return self.len;
+        };
+
+        let mut column = 0;
+        for (i, c) in text[line.clone()].char_indices() {
+            if column >= position.character as usize {
+                return line.start + i;
+            }
+            column += encoding.len(c);
+        }
+        line.end
+    }
+
+    /// Position of a byte offset, counting columns in `encoding`
+    pub fn position(&self, text: &str, offset: usize, encoding: PositionEncoding) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+
+        // An offset inside a multi-byte character counts from its start
+        let mut end = offset;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        let character: usize = text[start..end].chars().map(|c| encoding.len(c)).sum();
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Convert a position's column from one encoding to another
+    pub fn convert(
+        &self,
+        text: &str,
+        position: Position,
+        from: PositionEncoding,
+        to: PositionEncoding,
+    ) -> Position {
+        if from == to {
+            return position;
+        }
+        let offset = self.offset(text, position, from);
+        self.position(text, offset, to)
+    }
+
+    /// Convert both ends of a range from one encoding to another
+    pub fn convert_range(
+        &self,
+        text: &str,
+        range: Range,
+        from: PositionEncoding,
+        to: PositionEncoding,
+    ) -> Range {
+        Range::new(
+            self.convert(text, range.start, from, to),
+            self.convert(text, range.end, from, to),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_with_crlf() {
+        let text = "(module\r\n  (func)\r\n)";
+        let index = LineIndex::new(text);
+
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line_range(text, 0), Some(0..7));
+        assert_eq!(
+            index.offset(text, Position::new(1, 3), PositionEncoding::Utf8),
+            12
+        );
+        // Columns past the end of a line stop before the "\r\n"
+        assert_eq!(
+            index.offset(text, Position::new(0, 50), PositionEncoding::Utf8),
+            7
+        );
+        assert_eq!(
+            index.position(text, 12, PositionEncoding::Utf16),
+            Position::new(1, 3)
+        );
+    }
+
+    #[test]
+    fn test_utf16_columns_for_non_bmp_characters() {
+        // "𝄞" is 4 bytes in UTF-8 and a surrogate pair in UTF-16
+        let text = "(data \"𝄞é\" $x)";
+        let index = LineIndex::new(text);
+        let dollar = text.find('$').unwrap();
+
+        let utf16 = index.position(text, dollar, PositionEncoding::Utf16);
+        assert_eq!(utf16, Position::new(0, 12));
+        let utf32 = index.position(text, dollar, PositionEncoding::Utf32);
+        assert_eq!(utf32, Position::new(0, 11));
+
+        assert_eq!(index.offset(text, utf16, PositionEncoding::Utf16), dollar);
+        assert_eq!(
+            index.convert(text, utf16, PositionEncoding::Utf16, PositionEncoding::Utf8),
+            Position::new(0, dollar as u32)
+        );
+    }
+
+    #[test]
+    fn test_offset_past_last_line() {
+        let text = "a\nb";
+        let index = LineIndex::new(text);
+        assert_eq!(
+            index.offset(text, Position::new(5, 0), PositionEncoding::Utf16),
+            3
+        );
+    }
+}
@@ -3,6 +3,10 @@
 //! This module contains protocol-independent types that can be used
 //! without depending on tower-lsp or other LSP-specific crates.
 
+pub mod cancellation;
+pub mod line_index;
 pub mod types;
 
+pub use cancellation::{CancellationGuard, CancellationToken};
+pub use line_index::{LineIndex, PositionEncoding};
 pub use types::*;
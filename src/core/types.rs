@@ -8,8 +8,11 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "native")]
 use tower_lsp::lsp_types as lsp;
 
-/// A position in a text document (0-indexed line and character)
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+/// A position in a text document (0-indexed line and character).
+///
+/// `character` is a UTF-8 byte column, as in tree-sitter; see
+/// [`LineIndex`](super::LineIndex) for converting to a client's encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub struct Position {
     pub line: u32,
     pub character: u32,
@@ -40,6 +43,11 @@ impl Range {
             end: Position::new(end_line, end_char),
         }
     }
+
+    /// Whether `position` lies within the range, ends included
+    pub fn contains(&self, position: Position) -> bool {
+        self.start <= position && position <= self.end
+    }
 }
 
 /// Hover result containing markdown content
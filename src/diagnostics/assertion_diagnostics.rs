@@ -0,0 +1,76 @@
+use crate::interpreter::{AssertionResult, Outcome};
+use tower_lsp::lsp_types::*;
+
+/// Provide diagnostics for the commands of a script run that failed or
+/// couldn't run. Passing assertions get code lenses instead.
+pub fn provide_assertion_diagnostics(results: &[AssertionResult]) -> Vec<Diagnostic> {
+    results
+        .iter()
+        .filter_map(|result| {
+            let (severity, code, message) = match &result.outcome {
+                Outcome::Passed => return None,
+                Outcome::Failed { expected, actual } => (
+                    DiagnosticSeverity::ERROR,
+                    "assertion-failed",
+                    format!(
+                        "{} failed: expected {}, got {}",
+                        result.command, expected, actual
+                    ),
+                ),
+                Outcome::Skipped(reason) => (
+                    DiagnosticSeverity::INFORMATION,
+                    "assertion-skipped",
+                    format!("{} was not run: {}", result.command, reason),
+                ),
+            };
+            Some(Diagnostic {
+                range: result.range.into(),
+                severity: Some(severity),
+                code: Some(NumberOrString::String(code.to_string())),
+                code_description: None,
+                source: Some("wat-lsp".to_string()),
+                message,
+                related_information: None,
+                tags: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::run_script;
+
+    #[test]
+    fn test_failures_and_skips_become_diagnostics() {
+        let script = r#"(module
+  (func (export "one") (result i32) (i32.const 1)))
+(assert_return (invoke "one") (i32.const 1))
+(assert_return (invoke "one") (i32.const 2))
+(assert_trap (invoke "one") "unreachable")
+(assert_exception (invoke "one"))"#;
+
+        let results = run_script(script).unwrap();
+        let diagnostics = provide_assertion_diagnostics(&results);
+        assert_eq!(diagnostics.len(), 3, "{:?}", diagnostics);
+
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            diagnostics[0].message,
+            "assert_return failed: expected (i32.const 2), got (i32.const 1)"
+        );
+        assert_eq!(diagnostics[0].range.start, Position::new(3, 0));
+        assert_eq!(diagnostics[0].range.end, Position::new(3, 44));
+
+        assert_eq!(
+            diagnostics[1].message,
+            "assert_trap failed: expected trap \"unreachable\", got (i32.const 1)"
+        );
+        assert_eq!(
+            diagnostics[2].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+    }
+}
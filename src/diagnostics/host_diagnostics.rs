@@ -0,0 +1,82 @@
+use crate::host::{check_host_import, HostError, HostInterface};
+use crate::symbols::SymbolTable;
+use tower_lsp::lsp_types::*;
+
+/// Provide diagnostics for imports the host doesn't provide, or provides
+/// with a different type. Imports from modules `is_linked` accepts are
+/// bound to workspace modules instead and aren't checked; without a host
+/// description nothing is.
+pub fn provide_host_diagnostics(
+    symbols: &SymbolTable,
+    host: &HostInterface,
+    is_linked: impl Fn(&str) -> bool,
+) -> Vec<Diagnostic> {
+    if host.is_empty() {
+        return Vec::new();
+    }
+    symbols
+        .imports
+        .iter()
+        .filter(|import| !is_linked(&import.module))
+        .filter_map(|import| {
+            let error = check_host_import(symbols, import, host).err()?;
+            // The host may be extended by other means, so an unknown module
+            // is only a warning
+            let severity = match error {
+                HostError::UnknownModule => DiagnosticSeverity::WARNING,
+                _ => DiagnosticSeverity::ERROR,
+            };
+            Some(Diagnostic {
+                range: import.range?.into(),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("host-mismatch".to_string())),
+                code_description: None,
+                source: Some("wat-lsp".to_string()),
+                message: error.message(import),
+                related_information: None,
+                tags: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::DescriptionFormat;
+    use crate::parser::parse_document;
+
+    #[test]
+    fn test_host_imports_become_diagnostics() {
+        let host = HostInterface::parse(
+            "[[imports]]\nmodule = \"env\"\nfield = \"log\"\nsignature = \"(param i32)\"\n",
+            DescriptionFormat::Toml,
+        )
+        .unwrap();
+        let symbols = parse_document(
+            r#"(module
+  (import "env" "log" (func (param i64)))
+  (import "env" "abort" (func))
+  (import "js" "print" (func))
+  (import "core" "alloc" (func (param i32) (result i32))))"#,
+        )
+        .unwrap();
+
+        let diagnostics = provide_host_diagnostics(&symbols, &host, |module| module == "core");
+        assert_eq!(diagnostics.len(), 3, "{:?}", diagnostics);
+        assert_eq!(
+            diagnostics[0].message,
+            "Import 'env.log' has type (func (param i64)), but the host provides (func (param i32))"
+        );
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 2));
+        assert_eq!(
+            diagnostics[1].message,
+            "Host module 'env' has no import named 'abort'"
+        );
+        assert_eq!(diagnostics[2].severity, Some(DiagnosticSeverity::WARNING));
+
+        let none = provide_host_diagnostics(&symbols, &HostInterface::default(), |_| false);
+        assert!(none.is_empty());
+    }
+}
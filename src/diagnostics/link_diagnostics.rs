@@ -0,0 +1,66 @@
+use crate::linking::check_import;
+use crate::symbols::SymbolTable;
+use tower_lsp::lsp_types::*;
+
+/// Provide diagnostics for imports that can't bind to the export of the
+/// workspace module providing them. `provider` looks up the symbols of a
+/// linked module by name; imports from other modules aren't checked.
+pub fn provide_link_diagnostics<'a>(
+    symbols: &SymbolTable,
+    provider: impl Fn(&str) -> Option<&'a SymbolTable>,
+) -> Vec<Diagnostic> {
+    symbols
+        .imports
+        .iter()
+        .filter_map(|import| {
+            let error = check_import(symbols, import, provider(&import.module)?).err()?;
+            Some(Diagnostic {
+                range: import.range?.into(),
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String("link-mismatch".to_string())),
+                code_description: None,
+                source: Some("wat-lsp".to_string()),
+                message: error.message(import),
+                related_information: None,
+                tags: None,
+                data: None,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document;
+
+    #[test]
+    fn test_mismatched_imports_become_diagnostics() {
+        let core = parse_document(
+            r#"(module
+  (func (export "alloc") (param i32) (result i32) (local.get 0)))"#,
+        )
+        .unwrap();
+        let symbols = parse_document(
+            r#"(module
+  (import "core" "alloc" (func (param i64) (result i32)))
+  (import "core" "free" (func (param i32)))
+  (import "env" "log" (func (param i32))))"#,
+        )
+        .unwrap();
+
+        let diagnostics =
+            provide_link_diagnostics(&symbols, |module| (module == "core").then_some(&core));
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert_eq!(
+            diagnostics[0].message,
+            "Import 'core.alloc' has type (func (param i64) (result i32)), \
+             but the export has type (func (param i32) (result i32))"
+        );
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 2));
+        assert_eq!(
+            diagnostics[1].message,
+            "Module 'core' has no export named 'free'"
+        );
+    }
+}
@@ -1,13 +1,26 @@
+mod assertion_diagnostics;
+mod host_diagnostics;
 mod instruction_metadata;
+mod link_diagnostics;
+mod proposal_diagnostics;
 mod semantic_diagnostics;
 mod tree_sitter_diagnostics;
 mod wast_validator;
 
+pub use assertion_diagnostics::provide_assertion_diagnostics;
+pub use host_diagnostics::provide_host_diagnostics;
+pub use link_diagnostics::provide_link_diagnostics;
+pub use proposal_diagnostics::provide_proposal_diagnostics;
 pub use semantic_diagnostics::provide_semantic_diagnostics;
 pub use tree_sitter_diagnostics::provide_tree_sitter_diagnostics;
 pub use wast_validator::validate_wat;
 
+use crate::settings::{DiagnosticLevel, Settings};
+use crate::symbols::SymbolTable;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use tower_lsp::lsp_types::Diagnostic;
+use tree_sitter::Tree;
 
 /// Merge diagnostics from tree-sitter, semantic, and wast, sorted by position
 pub fn merge_all_diagnostics(
@@ -27,3 +40,51 @@ pub fn merge_all_diagnostics(
     });
     all
 }
+
+/// All diagnostics for a document up to the configured level: tree-sitter,
+/// semantic and proposal ones from its tree, when it was parsed, and wast
+/// validation of the source
+pub fn provide_all_diagnostics(
+    tree: Option<&Tree>,
+    source: &str,
+    symbols: &SymbolTable,
+    settings: &Settings,
+) -> Vec<Diagnostic> {
+    let level = settings.diagnostics.level;
+    let tree_sitter = tree
+        .map(|tree| provide_tree_sitter_diagnostics(tree, source))
+        .unwrap_or_default();
+    let semantic = match tree {
+        Some(tree) if level >= DiagnosticLevel::Semantic => {
+            let mut semantic = provide_semantic_diagnostics(tree, source, symbols);
+            semantic.extend(provide_proposal_diagnostics(tree, source, &settings.target));
+            semantic
+        }
+        _ => vec![],
+    };
+    let wast = if level == DiagnosticLevel::Full {
+        validate_wat(source)
+    } else {
+        vec![]
+    };
+    merge_all_diagnostics(tree_sitter, semantic, wast)
+}
+
+/// Parse a document that isn't open and return its diagnostics
+pub fn diagnose_source(source: &str, settings: &Settings) -> Vec<Diagnostic> {
+    let tree = crate::parser::parse_tree(source);
+    let symbols = tree
+        .as_ref()
+        .and_then(|tree| crate::parser::parse_document_from_tree(tree, source).ok())
+        .unwrap_or_default();
+    provide_all_diagnostics(tree.as_ref(), source, &symbols, settings)
+}
+
+/// Result id for pull diagnostics; diagnostics only change with the source
+/// and the settings they were computed with
+pub fn diagnostics_result_id(source: &str, settings: &Settings) -> String {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    settings.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
@@ -0,0 +1,492 @@
+use crate::settings::{Proposal, Target};
+use crate::utils::node_to_lsp_range;
+use tower_lsp::lsp_types::*;
+use tree_sitter::{Node, Tree};
+
+/// Provide diagnostics for instructions, types and module fields from
+/// proposals the target doesn't enable
+pub fn provide_proposal_diagnostics(tree: &Tree, source: &str, target: &Target) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    if Proposal::ALL
+        .iter()
+        .all(|&proposal| target.allows(proposal))
+    {
+        return diagnostics;
+    }
+    let mut memories = 0;
+    walk_tree_for_proposals(
+        tree.root_node(),
+        source,
+        target,
+        false,
+        &mut memories,
+        &mut diagnostics,
+    );
+    diagnostics
+}
+
+/// Instructions a constant expression may only use with extended constant
+/// expressions
+const EXTENDED_CONST_OPS: &[&str] = &[
+    "i32.add", "i32.sub", "i32.mul", "i64.add", "i64.sub", "i64.mul",
+];
+
+/// Recursively walk the tree looking for constructs from disabled proposals.
+///
+/// `in_const_expr` is set below global initializers and segment offsets and
+/// items; `memories` counts the memories of the enclosing module so far.
+fn walk_tree_for_proposals(
+    node: Node,
+    source: &str,
+    target: &Target,
+    in_const_expr: bool,
+    memories: &mut usize,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut report = |node: Node, proposal: Proposal| {
+        if !target.allows(proposal) {
+            diagnostics.push(create_proposal_diagnostic(&node, source, proposal, target));
+        }
+    };
+
+    match node.kind() {
+        "instr_plain" => {
+            let text = &source[node.byte_range()];
+            let name = text.split_whitespace().next().unwrap_or("");
+            let proposal = instruction_proposal(name).or_else(|| {
+                (in_const_expr && EXTENDED_CONST_OPS.contains(&name))
+                    .then_some(Proposal::ExtendedConst)
+            });
+            if let Some(proposal) = proposal {
+                report(node, proposal);
+            }
+            // Immediates hold no further instructions
+            return;
+        }
+        "num_type_v128" => report(node, Proposal::Simd),
+        "share" if &source[node.byte_range()] == "shared" => report(node, Proposal::Threads),
+        "address_type" if &source[node.byte_range()] == "i64" => report(node, Proposal::Memory64),
+        "module_field_memory" | "import_desc_memory_type" => {
+            *memories += 1;
+            if *memories > 1 {
+                report(keyword_node(node, "memory"), Proposal::MultiMemory);
+            }
+        }
+        "ref_type" => {
+            if let Some(proposal) = ref_type_proposal(&node) {
+                report(node, proposal);
+            }
+            return;
+        }
+        // GC type definitions; nested types are covered by the outer report
+        "module_field_rec" => {
+            report(keyword_node(node, "rec"), Proposal::Gc);
+            return;
+        }
+        "struct_type" | "array_type" | "sub_type" => {
+            let keyword = node.kind().trim_end_matches("_type");
+            report(keyword_node(node, keyword), Proposal::Gc);
+            return;
+        }
+        "module_field_tag" => {
+            report(keyword_node(node, "tag"), Proposal::ExceptionHandling);
+            return;
+        }
+        "block_try" => report(keyword_node(node, "try"), Proposal::ExceptionHandling),
+        "block_try_table" | "expr1_try_table" => {
+            report(keyword_node(node, "try_table"), Proposal::ExceptionHandling)
+        }
+        "instr_call" | "instr_list_call" | "expr1_call" => {
+            let keyword = keyword_node(node, "return_call_indirect");
+            if keyword.kind() == "return_call_indirect" {
+                report(keyword, Proposal::TailCall);
+            }
+        }
+        _ => {}
+    }
+
+    if let Some(clause) = multi_value_clause(node) {
+        let keyword = match clause.kind() {
+            "func_type_results" => "result",
+            _ => "param",
+        };
+        report(keyword_node(clause, keyword), Proposal::MultiValue);
+    }
+
+    // Each module of a script counts its own memories
+    let mut module_memories = 0;
+    let memories = if node.kind() == "module" {
+        &mut module_memories
+    } else {
+        memories
+    };
+    let in_const_expr =
+        in_const_expr || matches!(node.kind(), "module_field_global" | "offset" | "elem_expr");
+
+    // Recursively check children
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        walk_tree_for_proposals(child, source, target, in_const_expr, memories, diagnostics);
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/grammar_instructions.rs"));
+
+/// Proposal of an `_instruction_*` category in the grammar; `None` for MVP
+/// and for categories without a mapping
+fn category_proposal(category: &str) -> Option<Proposal> {
+    match category {
+        "simd" => Some(Proposal::Simd),
+        "relaxed_simd" => Some(Proposal::RelaxedSimd),
+        "gc" => Some(Proposal::Gc),
+        "atomic" => Some(Proposal::Threads),
+        "bulk" => Some(Proposal::BulkMemory),
+        "ref" => Some(Proposal::ReferenceTypes),
+        "exception" => Some(Proposal::ExceptionHandling),
+        _ => None,
+    }
+}
+
+/// Proposal an instruction belongs to; `None` for MVP instructions.
+///
+/// Instructions the grammar names in an `_instruction_*` rule take that
+/// rule's category. The rest are matched by regular expressions in the
+/// grammar's MVP and SIMD rules, so they are classified by name here.
+fn instruction_proposal(name: &str) -> Option<Proposal> {
+    if let Ok(found) = GRAMMAR_INSTRUCTIONS.binary_search_by_key(&name, |&(name, _)| name) {
+        return category_proposal(GRAMMAR_INSTRUCTIONS[found].1);
+    }
+    if name.contains(".relaxed_") {
+        return Some(Proposal::RelaxedSimd);
+    }
+    if name.contains(".atomic.") {
+        return Some(Proposal::Threads);
+    }
+
+    match name {
+        "table.copy" | "table.init" => return Some(Proposal::BulkMemory),
+        "ref.is_null" => return Some(Proposal::ReferenceTypes),
+        "ref.eq" | "ref.as_non_null" | "br_on_null" | "br_on_non_null" => {
+            return Some(Proposal::Gc)
+        }
+        "return_call" | "return_call_indirect" => return Some(Proposal::TailCall),
+        "i32.extend8_s" | "i32.extend16_s" | "i64.extend8_s" | "i64.extend16_s"
+        | "i64.extend32_s" => return Some(Proposal::SignExtension),
+        _ => {}
+    }
+
+    let (prefix, op) = name.split_once('.')?;
+    if matches!(prefix, "i32" | "i64") && op.starts_with("trunc_sat_") {
+        return Some(Proposal::NontrappingFloatToInt);
+    }
+    match prefix {
+        "v128" | "i8x16" | "i16x8" | "i32x4" | "i64x2" | "f32x4" | "f64x2" => Some(Proposal::Simd),
+        "struct" | "array" | "i31" | "any" | "extern" => Some(Proposal::Gc),
+        _ => None,
+    }
+}
+
+/// Proposal a reference type belongs to. `funcref` is MVP in table types
+/// but needs reference types as a value type.
+fn ref_type_proposal(node: &Node) -> Option<Proposal> {
+    let in_value_type = node
+        .parent()
+        .is_some_and(|parent| parent.kind() == "value_type_ref_type");
+
+    match node.named_child(0).map(|child| child.kind()) {
+        Some("ref_type_funcref") if in_value_type => Some(Proposal::ReferenceTypes),
+        Some("ref_type_funcref") => None,
+        Some("ref_type_externref") => Some(Proposal::ReferenceTypes),
+        // (ref ...) and the GC shorthands like anyref
+        _ => Some(Proposal::Gc),
+    }
+}
+
+/// The clause of a function or block type that needs multi-value: a second
+/// result, or any param of a block
+fn multi_value_clause(node: Node) -> Option<Node> {
+    let is_block = matches!(
+        node.kind(),
+        "block_block"
+            | "block_loop"
+            | "block_if"
+            | "block_try"
+            | "block_try_table"
+            | "expr1_block"
+            | "expr1_loop"
+            | "expr1_try_table"
+            | "if_block"
+    );
+
+    let mut results = 0;
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        // Type definitions wrap each clause in a func_type
+        let clause = match child.kind() {
+            "func_type" => match child.named_child(0) {
+                Some(clause) => clause,
+                None => continue,
+            },
+            _ => child,
+        };
+        let mut value_cursor = clause.walk();
+        let values = clause
+            .named_children(&mut value_cursor)
+            .filter(|c| c.kind() == "value_type")
+            .count();
+        match clause.kind() {
+            "func_type_params_many" if is_block && values > 0 => return Some(clause),
+            "func_type_results" => {
+                results += values;
+                if results > 1 {
+                    return Some(clause);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The keyword token of a construct, or the whole node if it has none
+fn keyword_node<'a>(node: Node<'a>, keyword: &str) -> Node<'a> {
+    let mut cursor = node.walk();
+    let found = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == keyword);
+    found.unwrap_or(node)
+}
+
+fn create_proposal_diagnostic(
+    node: &Node,
+    source: &str,
+    proposal: Proposal,
+    target: &Target,
+) -> Diagnostic {
+    let text = &source[node.byte_range()];
+    let construct = text.split_whitespace().next().unwrap_or(text);
+
+    Diagnostic {
+        range: node_to_lsp_range(node),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("disabled-proposal".to_string())),
+        code_description: None,
+        source: Some("wat-lsp".to_string()),
+        message: format!(
+            "'{}' requires the {} proposal, which {} does not include",
+            construct,
+            proposal.name(),
+            target.describe()
+        ),
+        related_information: None,
+        tags: None,
+        data: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::TargetProfile;
+    use crate::tree_sitter_bindings::create_parser;
+
+    fn diagnose(document: &str, target: Target) -> Vec<Diagnostic> {
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        provide_proposal_diagnostics(&tree, document, &target)
+    }
+
+    #[test]
+    fn test_instruction_categories() {
+        assert_eq!(instruction_proposal("i32.add"), None);
+        assert_eq!(instruction_proposal("local.get"), None);
+        assert_eq!(instruction_proposal("i32x4.add"), Some(Proposal::Simd));
+        assert_eq!(instruction_proposal("v128.load"), Some(Proposal::Simd));
+        assert_eq!(
+            instruction_proposal("f32x4.relaxed_madd"),
+            Some(Proposal::RelaxedSimd)
+        );
+        assert_eq!(
+            instruction_proposal("memory.copy"),
+            Some(Proposal::BulkMemory)
+        );
+        assert_eq!(
+            instruction_proposal("ref.null"),
+            Some(Proposal::ReferenceTypes)
+        );
+        assert_eq!(
+            instruction_proposal("i32.atomic.rmw.add"),
+            Some(Proposal::Threads)
+        );
+        assert_eq!(
+            instruction_proposal("memory.atomic.notify"),
+            Some(Proposal::Threads)
+        );
+        assert_eq!(instruction_proposal("struct.new"), Some(Proposal::Gc));
+        assert_eq!(instruction_proposal("array.len"), Some(Proposal::Gc));
+        assert_eq!(
+            instruction_proposal("throw"),
+            Some(Proposal::ExceptionHandling)
+        );
+        assert_eq!(instruction_proposal("i64.extend_i32_s"), None);
+        assert_eq!(
+            instruction_proposal("i32.extend8_s"),
+            Some(Proposal::SignExtension)
+        );
+        assert_eq!(
+            instruction_proposal("i64.extend32_s"),
+            Some(Proposal::SignExtension)
+        );
+        assert_eq!(
+            instruction_proposal("i32.trunc_sat_f64_u"),
+            Some(Proposal::NontrappingFloatToInt)
+        );
+        assert_eq!(
+            instruction_proposal("i32x4.trunc_sat_f32x4_s"),
+            Some(Proposal::Simd)
+        );
+        assert_eq!(
+            instruction_proposal("return_call"),
+            Some(Proposal::TailCall)
+        );
+        // A typed function reference instruction, under _instruction_gc
+        assert_eq!(instruction_proposal("return_call_ref"), Some(Proposal::Gc));
+    }
+
+    #[test]
+    fn test_grammar_instructions_follow_their_category() {
+        assert!(!GRAMMAR_INSTRUCTIONS.is_empty());
+        for &(name, category) in GRAMMAR_INSTRUCTIONS {
+            assert!(
+                category == "mvp" || category_proposal(category).is_some(),
+                "No proposal for the grammar category '{}'",
+                category
+            );
+            assert_eq!(
+                instruction_proposal(name),
+                category_proposal(category),
+                "'{}' is in _instruction_{}",
+                name,
+                category
+            );
+        }
+    }
+
+    #[test]
+    fn test_mvp_module_has_no_diagnostics() {
+        let document = r#"(module
+  (table 1 funcref)
+  (memory 1)
+  (func $add (param $a i32) (param $b i32) (result i32)
+    (i32.add (local.get $a) (local.get $b))))"#;
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Mvp));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_simd_flagged_for_mvp() {
+        let document = r#"(module
+  (func $splat (param $x i32) (result v128)
+    (i32x4.splat (local.get $x))))"#;
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Mvp));
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.message.contains("SIMD") && d.message.contains("'mvp'")));
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm2));
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_gc_types_flagged_once() {
+        let document = r#"(module
+  (rec (type $a (struct (field i32))) (type $b (array i8)))
+  (func $new (result (ref $a))
+    (struct.new $a (i32.const 1))))"#;
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm2));
+        assert_eq!(diagnostics.len(), 3, "{:?}", diagnostics);
+        assert!(diagnostics.iter().all(|d| d.message.contains("GC")));
+    }
+
+    #[test]
+    fn test_multi_value_flagged_for_mvp() {
+        let document = r#"(module
+  (type $pair (func (result i32 i32)))
+  (func $swap (param i32 i32) (result i32) (result i32)
+    (local.get 1) (local.get 0))
+  (func $one (result i32)
+    (block (param i32) (result i32) (i32.const 1))))"#;
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Mvp));
+        let lines: Vec<u32> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![1, 2, 5], "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.message.contains("multi-value")));
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm2));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_tail_calls_need_wasm3() {
+        let document = r#"(module
+  (type $t (func))
+  (table 1 funcref)
+  (func $loop
+    return_call $loop)
+  (func $dispatch
+    (return_call_indirect (type $t) (i32.const 0))))"#;
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm2));
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert!(diagnostics.iter().all(|d| d.message.contains("tail call")));
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm3));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_wasm3_memory_features() {
+        let document = r#"(module
+  (memory $a i64 1)
+  (memory $b 1)
+  (global i32 (i32.add (i32.const 1) (i32.const 2)))
+  (func (result i32) (i32.add (i32.const 1) (i32.const 2))))"#;
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm2));
+        let flagged: Vec<(u32, bool)> = diagnostics
+            .iter()
+            .map(|d| (d.range.start.line, d.message.contains("memory64")))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![(1, true), (2, false), (3, false)],
+            "{:?}",
+            diagnostics
+        );
+        assert!(diagnostics[1].message.contains("multiple memories"));
+        assert!(diagnostics[2].message.contains("extended constant"));
+
+        let diagnostics = diagnose(document, Target::Profile(TargetProfile::Wasm3));
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_explicit_proposal_list() {
+        let document = r#"(module
+  (memory 1 1 shared)
+  (tag $e (param i32))
+  (func (throw $e (i32.const 0))))"#;
+
+        let diagnostics = diagnose(document, Target::Proposals(vec![Proposal::Threads]));
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.message.contains("exception handling")));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::diagnostics::instruction_metadata::get_instruction_arity_map;
-use crate::symbols::SymbolTable;
+use crate::symbols::{ScriptCommandKind, SymbolTable, TypeDef, TypeKind, ValueType};
 use crate::utils::{
     determine_instruction_context_at_node, find_containing_function, node_to_lsp_range,
     InstructionContext, STRUCT_OPS,
@@ -15,12 +15,80 @@ pub fn provide_semantic_diagnostics(
     symbols: &SymbolTable,
 ) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
-    walk_tree_for_undefined_references(tree.root_node(), source, symbols, &mut diagnostics);
-    walk_tree_for_parameter_counts(tree.root_node(), source, symbols, &mut diagnostics);
-    check_atomic_operations_shared_memory(tree.root_node(), source, symbols, &mut diagnostics);
+    if !symbols.is_script() {
+        check_module(tree.root_node(), source, symbols, &mut diagnostics);
+        return diagnostics;
+    }
+
+    // Each module of a .wast script is checked against its own symbols.
+    // Modules inside assertions are meant to fail and aren't checked.
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let modules = root
+        .children(&mut cursor)
+        .filter(|child| child.kind() == "module" || child.kind() == "script_module");
+    let tables = symbols.modules.iter().filter(|module| !module.in_assertion);
+    for (node, module) in modules.zip(tables) {
+        check_module(node, source, &module.symbols, &mut diagnostics);
+    }
+    check_script_commands(symbols, &mut diagnostics);
     diagnostics
 }
 
+fn check_module(
+    node: Node,
+    source: &str,
+    symbols: &SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    walk_tree_for_undefined_references(node, source, symbols, diagnostics);
+    walk_tree_for_parameter_counts(node, source, symbols, diagnostics);
+    check_atomic_operations_shared_memory(node, source, symbols, diagnostics);
+    check_subtype_declarations(node, symbols, diagnostics);
+}
+
+/// Check that script actions name a module and an export it has
+fn check_script_commands(symbols: &SymbolTable, diagnostics: &mut Vec<Diagnostic>) {
+    for command in &symbols.commands {
+        let (Some(name), Some(name_range)) = (&command.name, command.name_range) else {
+            continue;
+        };
+        if command.kind == ScriptCommandKind::Register {
+            continue;
+        }
+
+        let module = command.module.and_then(|index| symbols.modules.get(index));
+        let message = match module {
+            None => match &command.module_name {
+                Some(id) => format!("Undefined module: {}", id),
+                None => "No module is defined before this command".to_string(),
+            },
+            // The exports of binary and quoted modules aren't known
+            Some(module)
+                if module.quoted || module.symbols.exports.iter().any(|e| &e.name == name) =>
+            {
+                continue
+            }
+            Some(module) => match &module.name {
+                Some(id) => format!("Module {} has no export \"{}\"", id, name),
+                None => format!("Module has no export \"{}\"", name),
+            },
+        };
+
+        diagnostics.push(Diagnostic {
+            range: name_range.into(),
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: Some(NumberOrString::String("undefined-export".to_string())),
+            code_description: None,
+            source: Some("wat-lsp".to_string()),
+            message,
+            related_information: None,
+            tags: None,
+            data: None,
+        });
+    }
+}
+
 /// Recursively walk the tree looking for undefined references
 fn walk_tree_for_undefined_references(
     node: Node,
@@ -824,6 +892,320 @@ fn create_operand_count_diagnostic(
     }
 }
 
+/// Check `(sub $parent ...)` declarations against their supertype.
+/// A final type cannot be subtyped, and a subtype must keep the parent's
+/// fields (or signature) as a compatible prefix.
+fn check_subtype_declarations(
+    root: Node,
+    symbols: &SymbolTable,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    // Type indices follow document order of type_field nodes, across (type) and (rec)
+    let mut type_fields = Vec::new();
+    collect_type_fields(root, &mut type_fields);
+
+    for (type_index, type_field) in type_fields.iter().enumerate() {
+        let mut cursor = type_field.walk();
+        let Some(sub_type) = type_field
+            .children(&mut cursor)
+            .find(|c| c.kind() == "sub_type")
+        else {
+            continue;
+        };
+        let mut sub_cursor = sub_type.walk();
+        let Some(parent_node) = sub_type
+            .children(&mut sub_cursor)
+            .find(|c| c.kind() == "index")
+        else {
+            continue;
+        };
+
+        let Some(child) = symbols.get_type_by_index(type_index) else {
+            continue;
+        };
+        let Some(parent) = child
+            .supertype
+            .and_then(|idx| symbols.get_type_by_index(idx as usize))
+        else {
+            continue;
+        };
+
+        let child_name = type_label(child);
+        let parent_name = type_label(parent);
+
+        let (code, message) = if parent.is_final {
+            (
+                "subtype-final",
+                format!(
+                    "Type '{}' cannot subtype '{}': '{}' is final",
+                    child_name, parent_name, parent_name
+                ),
+            )
+        } else if !is_compatible_extension(&parent.kind, &child.kind, symbols) {
+            (
+                "subtype-incompatible",
+                format!(
+                    "Type '{}' is not a prefix-compatible extension of its supertype '{}'",
+                    child_name, parent_name
+                ),
+            )
+        } else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            range: node_to_lsp_range(&parent_node),
+            severity: Some(DiagnosticSeverity::ERROR),
+            code: Some(NumberOrString::String(code.to_string())),
+            code_description: None,
+            source: Some("wat-lsp".to_string()),
+            message,
+            related_information: None,
+            tags: None,
+            data: None,
+        });
+    }
+}
+
+fn collect_type_fields<'a>(node: Node<'a>, type_fields: &mut Vec<Node<'a>>) {
+    if node.kind() == "type_field" {
+        type_fields.push(node);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_type_fields(child, type_fields);
+    }
+}
+
+fn type_label(type_def: &TypeDef) -> String {
+    type_def
+        .name
+        .clone()
+        .unwrap_or_else(|| type_def.index.to_string())
+}
+
+/// Check that `child` keeps the shape of `parent`: struct fields as a prefix,
+/// a compatible array element, or a signature with contravariant params and
+/// covariant results
+fn is_compatible_extension(parent: &TypeKind, child: &TypeKind, symbols: &SymbolTable) -> bool {
+    match (parent, child) {
+        (
+            TypeKind::Struct {
+                fields: parent_fields,
+            },
+            TypeKind::Struct {
+                fields: child_fields,
+            },
+        ) => {
+            child_fields.len() >= parent_fields.len()
+                && parent_fields.iter().zip(child_fields).all(
+                    |((_, parent_type, parent_mut), (_, child_type, child_mut))| {
+                        is_compatible_field(
+                            parent_type,
+                            *parent_mut,
+                            child_type,
+                            *child_mut,
+                            symbols,
+                        )
+                    },
+                )
+        }
+        (
+            TypeKind::Array {
+                element_type: parent_type,
+                mutable: parent_mut,
+            },
+            TypeKind::Array {
+                element_type: child_type,
+                mutable: child_mut,
+            },
+        ) => is_compatible_field(parent_type, *parent_mut, child_type, *child_mut, symbols),
+        (
+            TypeKind::Func {
+                params: parent_params,
+                results: parent_results,
+            },
+            TypeKind::Func {
+                params: child_params,
+                results: child_results,
+            },
+        ) => {
+            parent_params.len() == child_params.len()
+                && parent_results.len() == child_results.len()
+                && parent_params
+                    .iter()
+                    .zip(child_params)
+                    .all(|(p, c)| is_compatible_value(c, p, symbols))
+                && parent_results
+                    .iter()
+                    .zip(child_results)
+                    .all(|(p, c)| is_compatible_value(p, c, symbols))
+        }
+        _ => false,
+    }
+}
+
+/// Mutable fields are invariant; immutable fields may be refined by reference subtyping
+fn is_compatible_field(
+    parent_type: &ValueType,
+    parent_mut: bool,
+    child_type: &ValueType,
+    child_mut: bool,
+    symbols: &SymbolTable,
+) -> bool {
+    if parent_mut != child_mut {
+        return false;
+    }
+    if parent_mut {
+        return parent_type == child_type
+            || *parent_type == ValueType::Unknown
+            || *child_type == ValueType::Unknown;
+    }
+    is_compatible_value(parent_type, child_type, symbols)
+}
+
+/// Check that `child_type` is a subtype of `parent_type`. Numeric types must
+/// be equal; a reference must not add nullability and its heap type must be
+/// a subtype of the parent's. Unknown types are accepted.
+fn is_compatible_value(
+    parent_type: &ValueType,
+    child_type: &ValueType,
+    symbols: &SymbolTable,
+) -> bool {
+    if parent_type == child_type
+        || *parent_type == ValueType::Unknown
+        || *child_type == ValueType::Unknown
+    {
+        return true;
+    }
+    match (heap_type(parent_type), heap_type(child_type)) {
+        (Some((parent_heap, parent_null)), Some((child_heap, child_null))) => {
+            (parent_null || !child_null) && is_heap_subtype(&parent_heap, &child_heap, symbols)
+        }
+        _ => false,
+    }
+}
+
+/// Heap type of a reference, without its nullability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeapType {
+    Any,
+    Eq,
+    Struct,
+    Array,
+    I31,
+    None,
+    Func,
+    NoFunc,
+    Extern,
+    NoExtern,
+    Concrete(Option<u32>), // None for a type name that didn't resolve
+}
+
+/// Split a reference type into its heap type and whether it is nullable
+fn heap_type(value_type: &ValueType) -> Option<(HeapType, bool)> {
+    let heap = match value_type {
+        ValueType::Anyref => HeapType::Any,
+        ValueType::Eqref => HeapType::Eq,
+        ValueType::Structref => HeapType::Struct,
+        ValueType::Arrayref => HeapType::Array,
+        ValueType::I31ref => HeapType::I31,
+        ValueType::Nullref => HeapType::None,
+        ValueType::Funcref => HeapType::Func,
+        ValueType::NullFuncref => HeapType::NoFunc,
+        ValueType::Externref => HeapType::Extern,
+        ValueType::NullExternref => HeapType::NoExtern,
+        ValueType::Ref(type_ref) => return Some((HeapType::Concrete(type_ref.index), false)),
+        ValueType::RefNull(type_ref) => return Some((HeapType::Concrete(type_ref.index), true)),
+        _ => return None,
+    };
+    Some((heap, true))
+}
+
+/// Subtyping within the any, func and extern hierarchies; references from
+/// different hierarchies are never compatible
+fn is_heap_subtype(parent: &HeapType, child: &HeapType, symbols: &SymbolTable) -> bool {
+    if parent == child {
+        return true;
+    }
+    match child {
+        HeapType::None => is_any_hierarchy(parent, symbols),
+        HeapType::NoFunc => matches!(parent, HeapType::Func) || is_concrete_func(parent, symbols),
+        HeapType::NoExtern => matches!(parent, HeapType::Extern),
+        HeapType::Eq => matches!(parent, HeapType::Any),
+        HeapType::Struct | HeapType::Array | HeapType::I31 => {
+            matches!(parent, HeapType::Any | HeapType::Eq)
+        }
+        HeapType::Concrete(None) => true,
+        HeapType::Concrete(Some(index)) => {
+            let Some(kind) = symbols
+                .get_type_by_index(*index as usize)
+                .map(|type_def| &type_def.kind)
+            else {
+                return true;
+            };
+            match parent {
+                HeapType::Any | HeapType::Eq => !matches!(kind, TypeKind::Func { .. }),
+                HeapType::Struct => matches!(kind, TypeKind::Struct { .. }),
+                HeapType::Array => matches!(kind, TypeKind::Array { .. }),
+                HeapType::Func => matches!(kind, TypeKind::Func { .. }),
+                HeapType::Concrete(None) => true,
+                HeapType::Concrete(Some(parent_index)) => {
+                    is_declared_subtype(*index, *parent_index, symbols)
+                }
+                _ => false,
+            }
+        }
+        HeapType::Any | HeapType::Func | HeapType::Extern => false,
+    }
+}
+
+fn is_any_hierarchy(heap: &HeapType, symbols: &SymbolTable) -> bool {
+    match heap {
+        HeapType::Any
+        | HeapType::Eq
+        | HeapType::Struct
+        | HeapType::Array
+        | HeapType::I31
+        | HeapType::None => true,
+        HeapType::Concrete(Some(index)) => symbols
+            .get_type_by_index(*index as usize)
+            .is_none_or(|type_def| !matches!(type_def.kind, TypeKind::Func { .. })),
+        HeapType::Concrete(None) => true,
+        _ => false,
+    }
+}
+
+fn is_concrete_func(heap: &HeapType, symbols: &SymbolTable) -> bool {
+    match heap {
+        HeapType::Concrete(Some(index)) => symbols
+            .get_type_by_index(*index as usize)
+            .is_none_or(|type_def| matches!(type_def.kind, TypeKind::Func { .. })),
+        HeapType::Concrete(None) => true,
+        _ => false,
+    }
+}
+
+/// Follow the declared supertypes of `index` looking for `ancestor`
+fn is_declared_subtype(index: u32, ancestor: u32, symbols: &SymbolTable) -> bool {
+    let mut current = index;
+    // Bounded by the type count so a cyclic declaration can't loop forever
+    for _ in 0..=symbols.types.len() {
+        if current == ancestor {
+            return true;
+        }
+        match symbols
+            .get_type_by_index(current as usize)
+            .and_then(|type_def| type_def.supertype)
+        {
+            Some(supertype) => current = supertype,
+            None => return false,
+        }
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1786,4 +2168,134 @@ mod tests {
         assert!(!is_atomic_memory_operation("i64.store"));
         assert!(!is_atomic_memory_operation("memory.grow"));
     }
+
+    #[test]
+    fn test_subtype_of_final_type() {
+        let document = r#"(module
+  (type $base (struct (field i32)))
+  (type $derived (sub $base (struct (field i32) (field i64))))
+)"#;
+
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        let symbols = parse_document(document).unwrap();
+
+        let diagnostics = provide_semantic_diagnostics(&tree, document, &symbols);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("subtype-final".to_string()))
+        );
+        assert!(diagnostics[0].message.contains("'$base' is final"));
+        assert_eq!(diagnostics[0].range.start.line, 2);
+    }
+
+    #[test]
+    fn test_subtype_with_incompatible_fields() {
+        let document = r#"(module
+  (type $base (sub (struct (field i32) (field (mut f32)))))
+  (type $reordered (sub $base (struct (field f32) (field i32))))
+  (type $immutable (sub $base (struct (field i32) (field f32))))
+  (type $extended (sub $base (struct (field i32) (field (mut f32)) (field i64))))
+)"#;
+
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        let symbols = parse_document(document).unwrap();
+
+        let diagnostics = provide_semantic_diagnostics(&tree, document, &symbols);
+        let lines: Vec<u32> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(
+            lines,
+            vec![2, 3],
+            "Only $reordered and $immutable are invalid"
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.message.contains("prefix-compatible extension")));
+    }
+
+    #[test]
+    fn test_valid_subtype_chain() {
+        let document = r#"(module
+  (type $shape (sub (struct (field $x f64))))
+  (type $circle (sub $shape (struct (field $x f64) (field $r f64))))
+  (type $unit (sub final $circle (struct (field $x f64) (field $r f64))))
+  (type $fn (sub (func (param i32) (result i32))))
+  (type $fn2 (sub $fn (func (param i32) (result i32))))
+)"#;
+
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        let symbols = parse_document(document).unwrap();
+
+        let diagnostics = provide_semantic_diagnostics(&tree, document, &symbols);
+        assert!(diagnostics.is_empty(), "Unexpected: {:?}", diagnostics);
+    }
+
+    #[test]
+    fn test_subtype_reference_fields() {
+        let document = r#"(module
+  (type $base (sub (struct)))
+  (type $point (sub $base (struct (field i32))))
+  (type $holder (sub (struct (field (ref null $base)) (field anyref))))
+  (type $narrowed (sub $holder (struct (field (ref $point)) (field i31ref))))
+  (type $nullable (sub $holder (struct (field (ref null $point)) (field (ref null $base)))))
+  (type $widened (sub $holder (struct (field anyref) (field anyref))))
+  (type $foreign (sub $holder (struct (field funcref) (field externref))))
+)"#;
+
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        let symbols = parse_document(document).unwrap();
+
+        let diagnostics = provide_semantic_diagnostics(&tree, document, &symbols);
+        let lines: Vec<u32> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![6, 7], "Only $widened and $foreign are invalid");
+    }
+
+    #[test]
+    fn test_subtype_func_params_are_contravariant() {
+        let document = r#"(module
+  (type $base (sub (struct)))
+  (type $point (sub $base (struct (field i32))))
+  (type $fn (sub (func (param (ref $point)) (result (ref null $base)))))
+  (type $wider (sub $fn (func (param (ref null $base)) (result (ref $point)))))
+  (type $narrower (sub $fn (func (param (ref $point)) (result anyref))))
+  (type $swapped (sub $fn (func (param (ref $base)) (result (ref $point)))))
+)"#;
+
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        let symbols = parse_document(document).unwrap();
+
+        let diagnostics = provide_semantic_diagnostics(&tree, document, &symbols);
+        let lines: Vec<u32> = diagnostics.iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![5], "Only $narrower widens a result");
+    }
+
+    #[test]
+    fn test_wast_modules_checked_separately() {
+        let document = r#"(module $a
+  (func $f (export "run")))
+(module $b
+  (func (call $f)))
+(invoke $a "run")
+(invoke $a "missing")
+(assert_invalid (module (func (call $nowhere))) "unknown function")"#;
+
+        let mut parser = create_parser();
+        let tree = parser.parse(document, None).unwrap();
+        let symbols = parse_document(document).unwrap();
+
+        let diagnostics = provide_semantic_diagnostics(&tree, document, &symbols);
+        assert_eq!(diagnostics.len(), 2, "{:?}", diagnostics);
+
+        // $f belongs to $a, not $b
+        assert_eq!(diagnostics[0].range.start.line, 3);
+        assert!(diagnostics[0].message.contains("$f"));
+
+        assert_eq!(diagnostics[1].range.start.line, 5);
+        assert!(diagnostics[1].message.contains("no export \"missing\""));
+    }
 }
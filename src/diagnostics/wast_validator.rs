@@ -1,6 +1,9 @@
 use tower_lsp::lsp_types::*;
 
-/// Validate WAT text using the wast crate for semantic errors
+/// Validate WAT text using the wast crate for semantic errors.
+///
+/// Parsed as a `.wast` script, which also accepts a single module or bare
+/// module fields.
 pub fn validate_wat(source: &str) -> Vec<Diagnostic> {
     if source.trim().is_empty() {
         return vec![];
@@ -12,7 +15,7 @@ pub fn validate_wat(source: &str) -> Vec<Diagnostic> {
         Err(e) => return vec![wast_error_to_diagnostic(&e, source)],
     };
 
-    match wast::parser::parse::<wast::Wat>(&buf) {
+    match wast::parser::parse::<wast::Wast>(&buf) {
         Ok(_) => vec![], // Valid WAT
         Err(e) => vec![wast_error_to_diagnostic(&e, source)],
     }
@@ -83,4 +86,22 @@ mod tests {
         let diags = validate_wat(source);
         assert!(diags.is_empty(), "Expected no errors, got: {:?}", diags);
     }
+
+    #[test]
+    fn test_wast_script() {
+        let source = r#"
+(module $m
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))
+(register "m" $m)
+(assert_return (invoke $m "add" (i32.const 1) (i32.const 2)) (i32.const 3))
+(assert_trap (invoke "add" (i32.const 0)) "type mismatch")
+(assert_invalid (module (func (result i32))) "type mismatch")
+"#;
+        let diags = validate_wat(source);
+        assert!(diags.is_empty(), "Expected no errors, got: {:?}", diags);
+
+        let diags = validate_wat(r#"(module) (assert_return (invoke "f") (i32.const))"#);
+        assert_eq!(diags.len(), 1);
+    }
 }
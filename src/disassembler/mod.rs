@@ -0,0 +1,812 @@
+//! Disassembling binary modules to WAT text.
+//!
+//! The text is what the parser and every editor feature already understand,
+//! so a `.wasm` file can be browsed like a `.wat` one. Names from the name
+//! section become `$ids`; items without one are referred to by index. Each
+//! line that came from the binary remembers its byte offset there.
+//!
+//! Always available: it only needs wasmparser.
+
+mod operators;
+
+use std::collections::{HashMap, HashSet};
+use wasmparser::{
+    CompositeInnerType, ConstExpr, DataKind, ElementItems, ElementKind, ExternalKind, FuncType,
+    HeapType, KnownCustom, Name, NameMap, Operator, Payload, RefType, StorageType, SubType,
+    TableInit, TypeRef, UnpackedIndex, ValType,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The most locals a function may have, as in wasmparser's validator
+const MAX_LOCALS: u32 = 50_000;
+
+/// WAT text for a binary module
+#[derive(Debug, Clone, Default)]
+pub struct Disassembly {
+    pub text: String,
+    /// Byte offset in the binary of the field or instruction on each line,
+    /// sorted by line
+    offsets: Vec<(u32, usize)>,
+}
+
+impl Disassembly {
+    /// Byte offset in the binary of what `line` shows, if it came from it
+    pub fn offset_at_line(&self, line: u32) -> Option<usize> {
+        let index = self.offsets.binary_search_by_key(&line, |&(l, _)| l).ok()?;
+        Some(self.offsets[index].1)
+    }
+}
+
+/// Disassemble a binary module. Fails when the binary is malformed; it
+/// isn't validated, so invalid modules still print.
+pub fn disassemble(bytes: &[u8]) -> Result<Disassembly, String> {
+    let names = Names::read(bytes);
+    let mut printer = Printer {
+        names: &names,
+        types: Vec::new(),
+        func_types: Vec::new(),
+        locals: None,
+        out: Disassembly::default(),
+        line: 0,
+    };
+    printer.module(bytes).map_err(|e| e.to_string())?;
+    Ok(printer.out)
+}
+
+/// Index spaces items are named in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Space {
+    Func,
+    Type,
+    Table,
+    Memory,
+    Global,
+    Elem,
+    Data,
+    Tag,
+}
+
+/// `$ids` from the name section, made valid and unique
+#[derive(Default)]
+struct Names {
+    module: Option<String>,
+    items: HashMap<Space, HashMap<u32, String>>,
+    /// Local names by function index
+    locals: HashMap<u32, HashMap<u32, String>>,
+    /// Field names by struct type index
+    fields: HashMap<u32, HashMap<u32, String>>,
+}
+
+impl Names {
+    /// Names from the module's name section; a malformed section is ignored
+    fn read(bytes: &[u8]) -> Names {
+        let mut names = Names::default();
+        for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+            let Ok(Payload::CustomSection(section)) = payload else {
+                continue;
+            };
+            let KnownCustom::Name(reader) = section.as_known() else {
+                continue;
+            };
+            for name in reader.into_iter().flatten() {
+                let (space, map) = match name {
+                    Name::Module { name, .. } => {
+                        names.module = identifier(name);
+                        continue;
+                    }
+                    Name::Local(indirect) => {
+                        for naming in indirect.into_iter().flatten() {
+                            names.locals.insert(naming.index, name_map(naming.names));
+                        }
+                        continue;
+                    }
+                    Name::Field(indirect) => {
+                        for naming in indirect.into_iter().flatten() {
+                            names.fields.insert(naming.index, name_map(naming.names));
+                        }
+                        continue;
+                    }
+                    Name::Function(map) => (Space::Func, map),
+                    Name::Type(map) => (Space::Type, map),
+                    Name::Table(map) => (Space::Table, map),
+                    Name::Memory(map) => (Space::Memory, map),
+                    Name::Global(map) => (Space::Global, map),
+                    Name::Element(map) => (Space::Elem, map),
+                    Name::Data(map) => (Space::Data, map),
+                    Name::Tag(map) => (Space::Tag, map),
+                    _ => continue,
+                };
+                names.items.insert(space, name_map(map));
+            }
+        }
+        names
+    }
+
+    fn get(&self, space: Space, index: u32) -> Option<&str> {
+        self.items.get(&space)?.get(&index).map(String::as_str)
+    }
+}
+
+/// Ids for a name map; a name already taken gets its index appended
+fn name_map(map: NameMap) -> HashMap<u32, String> {
+    let mut ids = HashMap::new();
+    let mut taken = HashSet::new();
+    for naming in map.into_iter().flatten() {
+        let Some(mut id) = identifier(naming.name) else {
+            continue;
+        };
+        if !taken.insert(id.clone()) {
+            id = format!("{}.{}", id, naming.index);
+            taken.insert(id.clone());
+        }
+        ids.insert(naming.index, id);
+    }
+    ids
+}
+
+/// A name as an id without the `$`, with characters ids can't hold
+/// replaced by `_`
+fn identifier(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+    let id = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(id)
+}
+
+/// Writes the text of one module
+struct Printer<'a> {
+    names: &'a Names,
+    /// Every type, by index
+    types: Vec<SubType>,
+    /// Type index of every function, imports first
+    func_types: Vec<u32>,
+    /// Local names of the function being printed
+    locals: Option<&'a HashMap<u32, String>>,
+    out: Disassembly,
+    line: u32,
+}
+
+impl<'a> Printer<'a> {
+    fn module(&mut self, bytes: &[u8]) -> wasmparser::Result<()> {
+        let mut header = "(module".to_string();
+        if let Some(name) = &self.names.module {
+            header.push_str(&format!(" ${}", name));
+        }
+        self.push(0, header, None);
+
+        let mut counts: HashMap<Space, u32> = HashMap::new();
+        let mut next = |space: Space| {
+            let count = counts.entry(space).or_default();
+            *count += 1;
+            *count - 1
+        };
+
+        for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::TypeSection(reader) => {
+                    for group in reader.into_iter_with_offsets() {
+                        let (offset, group) = group?;
+                        let start = self.types.len() as u32;
+                        let explicit = group.is_explicit_rec_group();
+                        let types: Vec<SubType> = group.into_types().collect();
+                        if explicit {
+                            self.push(1, "(rec".to_string(), Some(offset));
+                        }
+                        for (i, ty) in types.iter().enumerate() {
+                            let index = next(Space::Type);
+                            let text = self.sub_type(index, ty, start);
+                            self.push(1 + explicit as usize, text, Some(offset));
+                            debug_assert_eq!(index, start + i as u32);
+                        }
+                        if explicit {
+                            self.push(1, ")".to_string(), None);
+                        }
+                        self.types.extend(types);
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    let mut offsets = reader.clone().into_iter_with_offsets();
+                    for import in reader {
+                        let import = import?;
+                        let offset = offsets.next().and_then(|item| item.ok()).map(|(o, _)| o);
+                        let desc = match import.ty {
+                            TypeRef::Func(ty) | TypeRef::FuncExact(ty) => {
+                                let index = next(Space::Func);
+                                self.func_types.push(ty);
+                                format!(
+                                    "(func{} {})",
+                                    self.id(Space::Func, index),
+                                    self.type_use(ty)
+                                )
+                            }
+                            TypeRef::Table(ty) => {
+                                let index = next(Space::Table);
+                                format!(
+                                    "(table{} {})",
+                                    self.id(Space::Table, index),
+                                    self.table_type(&ty)
+                                )
+                            }
+                            TypeRef::Memory(ty) => {
+                                let index = next(Space::Memory);
+                                format!(
+                                    "(memory{} {})",
+                                    self.id(Space::Memory, index),
+                                    memory_type(&ty)
+                                )
+                            }
+                            TypeRef::Global(ty) => {
+                                let index = next(Space::Global);
+                                format!(
+                                    "(global{} {})",
+                                    self.id(Space::Global, index),
+                                    self.global_type(&ty)
+                                )
+                            }
+                            TypeRef::Tag(ty) => {
+                                let index = next(Space::Tag);
+                                format!(
+                                    "(tag{} {})",
+                                    self.id(Space::Tag, index),
+                                    self.type_use(ty.func_type_idx)
+                                )
+                            }
+                        };
+                        let text = format!(
+                            "(import {} {} {})",
+                            string(import.module.as_bytes()),
+                            string(import.name.as_bytes()),
+                            desc
+                        );
+                        self.push(1, text, offset);
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for ty in reader {
+                        self.func_types.push(ty?);
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader.into_iter_with_offsets() {
+                        let (offset, table) = table?;
+                        let index = next(Space::Table);
+                        let mut text = format!(
+                            "(table{} {}",
+                            self.id(Space::Table, index),
+                            self.table_type(&table.ty)
+                        );
+                        if let TableInit::Expr(expr) = &table.init {
+                            text.push_str(&format!(" {}", self.const_expr(expr)?));
+                        }
+                        text.push(')');
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader.into_iter_with_offsets() {
+                        let (offset, memory) = memory?;
+                        let index = next(Space::Memory);
+                        let text = format!(
+                            "(memory{} {})",
+                            self.id(Space::Memory, index),
+                            memory_type(&memory)
+                        );
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::TagSection(reader) => {
+                    for tag in reader.into_iter_with_offsets() {
+                        let (offset, tag) = tag?;
+                        let index = next(Space::Tag);
+                        let text = format!(
+                            "(tag{} {})",
+                            self.id(Space::Tag, index),
+                            self.type_use(tag.func_type_idx)
+                        );
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::GlobalSection(reader) => {
+                    for global in reader.into_iter_with_offsets() {
+                        let (offset, global) = global?;
+                        let index = next(Space::Global);
+                        let text = format!(
+                            "(global{} {} {})",
+                            self.id(Space::Global, index),
+                            self.global_type(&global.ty),
+                            self.const_expr(&global.init_expr)?
+                        );
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader.into_iter_with_offsets() {
+                        let (offset, export) = export?;
+                        let (kind, space) = match export.kind {
+                            ExternalKind::Func | ExternalKind::FuncExact => ("func", Space::Func),
+                            ExternalKind::Table => ("table", Space::Table),
+                            ExternalKind::Memory => ("memory", Space::Memory),
+                            ExternalKind::Global => ("global", Space::Global),
+                            ExternalKind::Tag => ("tag", Space::Tag),
+                        };
+                        let text = format!(
+                            "(export {} ({} {}))",
+                            string(export.name.as_bytes()),
+                            kind,
+                            self.index(space, export.index)
+                        );
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::StartSection { func, range } => {
+                    let text = format!("(start {})", self.index(Space::Func, func));
+                    self.push(1, text, Some(range.start));
+                }
+                Payload::ElementSection(reader) => {
+                    for element in reader.into_iter_with_offsets() {
+                        let (offset, element) = element?;
+                        let index = next(Space::Elem);
+                        let mut text = format!("(elem{}", self.id(Space::Elem, index));
+                        match &element.kind {
+                            ElementKind::Passive => {}
+                            ElementKind::Declared => text.push_str(" declare"),
+                            ElementKind::Active {
+                                table_index,
+                                offset_expr,
+                            } => {
+                                if let Some(table) = table_index.filter(|&table| table != 0) {
+                                    let table = self.index(Space::Table, table);
+                                    text.push_str(&format!(" (table {})", table));
+                                }
+                                text.push_str(&format!(" {}", self.const_expr(offset_expr)?));
+                            }
+                        }
+                        match element.items {
+                            ElementItems::Functions(reader) => {
+                                text.push_str(" func");
+                                for func in reader {
+                                    text.push_str(&format!(" {}", self.index(Space::Func, func?)));
+                                }
+                            }
+                            ElementItems::Expressions(ty, reader) => {
+                                text.push_str(&format!(" {}", self.ref_type(ty)));
+                                for expr in reader {
+                                    text.push_str(&format!(" (item {})", self.const_expr(&expr?)?));
+                                }
+                            }
+                        }
+                        text.push(')');
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader.into_iter_with_offsets() {
+                        let (offset, data) = data?;
+                        let index = next(Space::Data);
+                        let mut text = format!("(data{}", self.id(Space::Data, index));
+                        if let DataKind::Active {
+                            memory_index,
+                            offset_expr,
+                        } = &data.kind
+                        {
+                            if *memory_index != 0 {
+                                let memory = self.index(Space::Memory, *memory_index);
+                                text.push_str(&format!(" (memory {})", memory));
+                            }
+                            text.push_str(&format!(" {}", self.const_expr(offset_expr)?));
+                        }
+                        text.push_str(&format!(" {})", string(data.data)));
+                        self.push(1, text, Some(offset));
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let index = next(Space::Func);
+                    self.function(index, body)?;
+                }
+                _ => {}
+            }
+        }
+
+        self.push(0, ")".to_string(), None);
+        Ok(())
+    }
+
+    /// A defined function, with its locals and body
+    fn function(&mut self, index: u32, body: wasmparser::FunctionBody) -> wasmparser::Result<()> {
+        let ty = self
+            .func_types
+            .get(index as usize)
+            .copied()
+            .unwrap_or_default();
+        self.locals = self.names.locals.get(&index);
+        let header = format!(
+            "(func{} {}",
+            self.id(Space::Func, index),
+            self.type_use_named(ty)
+        );
+        self.push(1, header, Some(body.range().start));
+
+        let params = self.func_type(ty).map_or(0, |ty| ty.params().len() as u32);
+        let mut local_index = params;
+        let mut locals = body.get_locals_reader()?;
+        for _ in 0..locals.get_count() {
+            let offset = locals.original_position();
+            let (count, ty) = locals.read()?;
+            // An invalid module could declare billions; print no more than
+            // a valid one may have
+            let count = count.min(MAX_LOCALS.saturating_sub(local_index));
+            let ty = self.val_type(ty);
+            let named = (local_index..local_index + count).any(|i| self.local_name(i).is_some());
+            let text = if named {
+                let locals: Vec<_> = (local_index..local_index + count)
+                    .map(|i| match self.local_name(i) {
+                        Some(name) => format!("(local ${} {})", name, ty),
+                        None => format!("(local {})", ty),
+                    })
+                    .collect();
+                locals.join(" ")
+            } else if count > 0 {
+                format!("(local {})", vec![ty.as_str(); count as usize].join(" "))
+            } else {
+                String::new()
+            };
+            local_index += count;
+            if !text.is_empty() {
+                self.push(2, text, Some(offset));
+            }
+        }
+
+        let mut depth = 0usize;
+        let mut reader = body.get_operators_reader()?;
+        while !reader.eof() {
+            let (op, offset) = reader.read_with_offset()?;
+            let indent = match op {
+                Operator::End if depth == 0 => break,
+                Operator::End | Operator::Delegate { .. } => {
+                    depth -= 1;
+                    depth
+                }
+                Operator::Else | Operator::Catch { .. } | Operator::CatchAll => {
+                    depth.saturating_sub(1)
+                }
+                Operator::Block { .. }
+                | Operator::Loop { .. }
+                | Operator::If { .. }
+                | Operator::Try { .. }
+                | Operator::TryTable { .. } => {
+                    depth += 1;
+                    depth - 1
+                }
+                _ => depth,
+            };
+            let text = self.operator(&op);
+            self.push(2 + indent, text, Some(offset));
+        }
+        self.push(1, ")".to_string(), None);
+        self.locals = None;
+        Ok(())
+    }
+
+    /// A type definition, e.g. `(type $point (struct (field $x i32)))`
+    fn sub_type(&self, index: u32, ty: &SubType, group_start: u32) -> String {
+        let composite = match &ty.composite_type.inner {
+            CompositeInnerType::Func(func) => format!("(func{})", self.signature(func, None)),
+            CompositeInnerType::Array(array) => format!("(array {})", self.field_type(&array.0)),
+            CompositeInnerType::Struct(st) => {
+                let mut text = "(struct".to_string();
+                let names = self.names.fields.get(&index);
+                for (i, field) in st.fields.iter().enumerate() {
+                    match names.and_then(|names| names.get(&(i as u32))) {
+                        Some(name) => {
+                            text.push_str(&format!(" (field ${} {})", name, self.field_type(field)))
+                        }
+                        None => text.push_str(&format!(" (field {})", self.field_type(field))),
+                    }
+                }
+                text.push(')');
+                text
+            }
+            CompositeInnerType::Cont(cont) => {
+                let target = self.unpacked(cont.0.unpack(), group_start);
+                format!("(cont {})", target)
+            }
+        };
+        let composite = if ty.composite_type.shared {
+            format!("(shared {})", composite)
+        } else {
+            composite
+        };
+        let definition = if ty.is_final && ty.supertype_idx.is_none() {
+            composite
+        } else {
+            let mut text = "(sub".to_string();
+            if ty.is_final {
+                text.push_str(" final");
+            }
+            if let Some(supertype) = ty.supertype_idx {
+                text.push_str(&format!(
+                    " {}",
+                    self.unpacked(supertype.unpack(), group_start)
+                ));
+            }
+            format!("{} {})", text, composite)
+        };
+        format!("(type{} {})", self.id(Space::Type, index), definition)
+    }
+
+    /// A type index written inside a rec group, which may be relative to it
+    fn unpacked(&self, index: UnpackedIndex, group_start: u32) -> String {
+        match index {
+            UnpackedIndex::Module(index) => self.index(Space::Type, index),
+            UnpackedIndex::RecGroup(index) => self.index(Space::Type, group_start + index),
+            #[allow(unreachable_patterns)]
+            _ => "?".to_string(),
+        }
+    }
+
+    fn field_type(&self, field: &wasmparser::FieldType) -> String {
+        let storage = match field.element_type {
+            StorageType::I8 => "i8".to_string(),
+            StorageType::I16 => "i16".to_string(),
+            StorageType::Val(ty) => self.val_type(ty),
+        };
+        if field.mutable {
+            format!("(mut {})", storage)
+        } else {
+            storage
+        }
+    }
+
+    fn func_type(&self, index: u32) -> Option<&FuncType> {
+        match &self.types.get(index as usize)?.composite_type.inner {
+            CompositeInnerType::Func(ty) => Some(ty),
+            _ => None,
+        }
+    }
+
+    /// `(type $t) (param i32) (result i32)`, with params unnamed
+    fn type_use(&self, ty: u32) -> String {
+        let mut text = format!("(type {})", self.index(Space::Type, ty));
+        if let Some(func) = self.func_type(ty) {
+            text.push_str(&self.signature(func, None));
+        }
+        text
+    }
+
+    /// A type use for the function being printed, naming its params
+    fn type_use_named(&self, ty: u32) -> String {
+        let mut text = format!("(type {})", self.index(Space::Type, ty));
+        if let Some(func) = self.func_type(ty) {
+            text.push_str(&self.signature(func, self.locals));
+        }
+        text
+    }
+
+    /// ` (param ...) (result ...)`; params are written one by one when
+    /// any of them has a name
+    fn signature(&self, func: &FuncType, names: Option<&HashMap<u32, String>>) -> String {
+        let mut text = String::new();
+        let named = |i: usize| names.and_then(|names| names.get(&(i as u32)));
+        if (0..func.params().len()).any(|i| named(i).is_some()) {
+            for (i, ty) in func.params().iter().enumerate() {
+                match named(i) {
+                    Some(name) => {
+                        text.push_str(&format!(" (param ${} {})", name, self.val_type(*ty)))
+                    }
+                    None => text.push_str(&format!(" (param {})", self.val_type(*ty))),
+                }
+            }
+        } else if !func.params().is_empty() {
+            text.push_str(&format!(" (param {})", self.val_types(func.params())));
+        }
+        if !func.results().is_empty() {
+            text.push_str(&format!(" (result {})", self.val_types(func.results())));
+        }
+        text
+    }
+
+    fn val_types(&self, types: &[ValType]) -> String {
+        let types: Vec<_> = types.iter().map(|ty| self.val_type(*ty)).collect();
+        types.join(" ")
+    }
+
+    fn val_type(&self, ty: ValType) -> String {
+        match ty {
+            ValType::I32 => "i32".to_string(),
+            ValType::I64 => "i64".to_string(),
+            ValType::F32 => "f32".to_string(),
+            ValType::F64 => "f64".to_string(),
+            ValType::V128 => "v128".to_string(),
+            ValType::Ref(ty) => self.ref_type(ty),
+        }
+    }
+
+    /// A reference type, abbreviated when it has a short form like `funcref`
+    fn ref_type(&self, ty: RefType) -> String {
+        let heap = ty.heap_type();
+        if ty.is_nullable() {
+            if let HeapType::Abstract { shared: false, .. } = heap {
+                let name = self.heap_type(heap);
+                return match name.as_str() {
+                    "none" => "nullref".to_string(),
+                    "nofunc" => "nullfuncref".to_string(),
+                    "noextern" => "nullexternref".to_string(),
+                    "noexn" => "nullexnref".to_string(),
+                    "nocont" => "nullcontref".to_string(),
+                    _ => format!("{}ref", name),
+                };
+            }
+            format!("(ref null {})", self.heap_type(heap))
+        } else {
+            format!("(ref {})", self.heap_type(heap))
+        }
+    }
+
+    fn heap_type(&self, ty: HeapType) -> String {
+        use wasmparser::AbstractHeapType::*;
+        match ty {
+            HeapType::Abstract { shared, ty } => {
+                let name = match ty {
+                    Func => "func",
+                    Extern => "extern",
+                    Any => "any",
+                    None => "none",
+                    NoExtern => "noextern",
+                    NoFunc => "nofunc",
+                    Eq => "eq",
+                    Struct => "struct",
+                    Array => "array",
+                    I31 => "i31",
+                    Exn => "exn",
+                    NoExn => "noexn",
+                    Cont => "cont",
+                    NoCont => "nocont",
+                };
+                if shared {
+                    format!("(shared {})", name)
+                } else {
+                    name.to_string()
+                }
+            }
+            HeapType::Concrete(index) => self.unpacked(index, 0),
+            HeapType::Exact(index) => format!("(exact {})", self.unpacked(index, 0)),
+        }
+    }
+
+    fn table_type(&self, ty: &wasmparser::TableType) -> String {
+        let mut text = String::new();
+        if ty.table64 {
+            text.push_str("i64 ");
+        }
+        text.push_str(&ty.initial.to_string());
+        if let Some(max) = ty.maximum {
+            text.push_str(&format!(" {}", max));
+        }
+        if ty.shared {
+            text.push_str(" shared");
+        }
+        format!("{} {}", text, self.ref_type(ty.element_type))
+    }
+
+    fn global_type(&self, ty: &wasmparser::GlobalType) -> String {
+        let content = self.val_type(ty.content_type);
+        match (ty.shared, ty.mutable) {
+            (false, false) => content,
+            (false, true) => format!("(mut {})", content),
+            (true, false) => format!("(shared {})", content),
+            (true, true) => format!("(shared (mut {}))", content),
+        }
+    }
+
+    /// A constant expression as folded instructions, e.g. `(i32.const 8)`
+    fn const_expr(&self, expr: &ConstExpr) -> wasmparser::Result<String> {
+        let mut parts = Vec::new();
+        let mut reader = expr.get_operators_reader();
+        while !reader.eof() {
+            let op = reader.read()?;
+            if let Operator::End = op {
+                break;
+            }
+            parts.push(format!("({})", self.operator(&op)));
+        }
+        Ok(parts.join(" "))
+    }
+
+    /// ` $name` to declare an item with, or nothing when it has no name
+    fn id(&self, space: Space, index: u32) -> String {
+        match self.names.get(space, index) {
+            Some(name) => format!(" ${}", name),
+            None => String::new(),
+        }
+    }
+
+    /// `$name` or the index, to refer to an item
+    fn index(&self, space: Space, index: u32) -> String {
+        match self.names.get(space, index) {
+            Some(name) => format!("${}", name),
+            None => index.to_string(),
+        }
+    }
+
+    /// `$name` or the index of a field of a struct type
+    fn field(&self, ty: u32, index: u32) -> String {
+        match self
+            .names
+            .fields
+            .get(&ty)
+            .and_then(|names| names.get(&index))
+        {
+            Some(name) => format!("${}", name),
+            None => index.to_string(),
+        }
+    }
+
+    fn local_name(&self, index: u32) -> Option<&String> {
+        self.locals?.get(&index)
+    }
+
+    /// `$name` or the index of a local of the function being printed
+    fn local(&self, index: u32) -> String {
+        match self.local_name(index) {
+            Some(name) => format!("${}", name),
+            None => index.to_string(),
+        }
+    }
+
+    fn push(&mut self, indent: usize, text: String, offset: Option<usize>) {
+        for _ in 0..indent {
+            self.out.text.push_str("  ");
+        }
+        self.out.text.push_str(&text);
+        self.out.text.push('\n');
+        if let Some(offset) = offset {
+            self.out.offsets.push((self.line, offset));
+        }
+        self.line += 1;
+    }
+}
+
+fn memory_type(ty: &wasmparser::MemoryType) -> String {
+    let mut text = String::new();
+    if ty.memory64 {
+        text.push_str("i64 ");
+    }
+    text.push_str(&ty.initial.to_string());
+    if let Some(max) = ty.maximum {
+        text.push_str(&format!(" {}", max));
+    }
+    if ty.shared {
+        text.push_str(" shared");
+    }
+    if let Some(log2) = ty.page_size_log2 {
+        text.push_str(&format!(" (pagesize {})", 1u64 << log2));
+    }
+    text
+}
+
+/// A WAT string literal, escaping quotes, backslashes and non-printable bytes
+fn string(bytes: &[u8]) -> String {
+    let mut text = "\"".to_string();
+    for &byte in bytes {
+        match byte {
+            b'"' => text.push_str("\\\""),
+            b'\\' => text.push_str("\\\\"),
+            0x20..=0x7e => text.push(byte as char),
+            _ => text.push_str(&format!("\\{:02x}", byte)),
+        }
+    }
+    text.push('"');
+    text
+}
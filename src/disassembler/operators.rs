@@ -0,0 +1,387 @@
+//! Printing instructions.
+//!
+//! The mnemonic and immediates of every operator come from wasmparser's
+//! `for_each_operator!` listing, so new operators print without changes
+//! here. Immediates are formatted by their field name, which says which
+//! index space an index refers to.
+
+use super::{Printer, Space};
+use wasmparser::{
+    BlockType, BrTable, Catch, Handle, HeapType, Ieee32, Ieee64, MemArg, Operator, Ordering,
+    RefType, ResumeTable, TryTable, ValType, V128,
+};
+
+/// An operator immediate that can print itself in WAT syntax
+trait Immediate {
+    /// Append ` value` to `out`; `field` is the operator field holding it
+    fn write(&self, field: &str, printer: &Printer, out: &mut String);
+
+    /// The value of an index immediate
+    fn as_index(&self) -> Option<u32> {
+        None
+    }
+}
+
+macro_rules! define_operator_parts {
+    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident ($($ann:tt)*) )*) => {
+        /// The visitor name of an operator, e.g. `visit_i32_add`, and its
+        /// immediates with their field names
+        fn operator_parts<'a>(
+            op: &'a Operator<'a>,
+        ) -> (&'static str, Vec<(&'static str, &'a dyn Immediate)>) {
+            match op {
+                $(
+                    Operator::$op $({ $($arg),* })? => (
+                        stringify!($visit),
+                        vec![$($((stringify!($arg), $arg as &dyn Immediate)),*)?],
+                    ),
+                )*
+                _ => ("visit_unknown", Vec::new()),
+            }
+        }
+    };
+}
+wasmparser::for_each_operator!(define_operator_parts);
+
+/// Prefixes followed by a `.` in mnemonics, e.g. `i32.add` and `ref.null`
+const PREFIXES: [&str; 23] = [
+    "i32", "i64", "f32", "f64", "v128", "i8x16", "i16x8", "i32x4", "i64x2", "f32x4", "f64x2",
+    "local", "global", "memory", "table", "ref", "struct", "array", "i31", "any", "extern", "data",
+    "elem",
+];
+
+/// The WAT mnemonic for a visitor name, e.g. `i32.atomic.rmw8.add_u` for
+/// `visit_i32_atomic_rmw8_add_u`
+pub(super) fn mnemonic(visit: &str) -> String {
+    let name = visit.trim_start_matches("visit_");
+    let mut parts = Vec::new();
+    let mut rest = name;
+    if let Some((prefix, tail)) = rest.split_once('_') {
+        if PREFIXES.contains(&prefix) || prefix == "cont" {
+            parts.push(prefix);
+            rest = tail;
+        }
+    }
+    // Atomics nest one level further: `atomic.`, then `rmw.` or `rmw8.`
+    if let Some(tail) = rest.strip_prefix("atomic_") {
+        parts.push("atomic");
+        rest = tail;
+        if rest.starts_with("rmw") {
+            if let Some((rmw, tail)) = rest.split_once('_') {
+                parts.push(rmw);
+                rest = tail;
+            }
+        }
+    }
+    parts.push(rest);
+    parts.join(".")
+}
+
+impl Printer<'_> {
+    /// An instruction's text, e.g. `local.get $x` or `i32.load offset=4`
+    pub(super) fn operator(&self, op: &Operator) -> String {
+        let (visit, immediates) = operator_parts(op);
+        let mut text = match op {
+            Operator::TypedSelect { .. } | Operator::TypedSelectMulti { .. } => {
+                "select".to_string()
+            }
+            _ => mnemonic(visit),
+        };
+
+        match op {
+            // The table comes first and the type is a type use
+            Operator::CallIndirect {
+                type_index,
+                table_index,
+            }
+            | Operator::ReturnCallIndirect {
+                type_index,
+                table_index,
+            } => {
+                if *table_index != 0 {
+                    text.push(' ');
+                    text.push_str(&self.index(Space::Table, *table_index));
+                }
+                text.push_str(&format!(" (type {})", self.index(Space::Type, *type_index)));
+                return text;
+            }
+            // The target's nullability is part of the mnemonic in binary
+            Operator::RefTestNonNull { hty }
+            | Operator::RefCastNonNull { hty }
+            | Operator::RefCastDescNonNull { hty } => {
+                let text = text.trim_end_matches("_non_null");
+                return format!("{} (ref {})", text, self.heap_type(*hty));
+            }
+            Operator::RefTestNullable { hty }
+            | Operator::RefCastNullable { hty }
+            | Operator::RefCastDescNullable { hty } => {
+                let text = text.trim_end_matches("_nullable");
+                return format!("{} (ref null {})", text, self.heap_type(*hty));
+            }
+            // The memory or table comes before the segment
+            Operator::MemoryInit { data_index, mem } => {
+                if *mem != 0 {
+                    text.push_str(&format!(" {}", self.index(Space::Memory, *mem)));
+                }
+                text.push_str(&format!(" {}", self.index(Space::Data, *data_index)));
+                return text;
+            }
+            Operator::TableInit { elem_index, table } => {
+                return format!(
+                    "{} {} {}",
+                    text,
+                    self.index(Space::Table, *table),
+                    self.index(Space::Elem, *elem_index)
+                );
+            }
+            Operator::MemoryCopy {
+                dst_mem: 0,
+                src_mem: 0,
+            } => return text,
+            _ => {}
+        }
+
+        // Struct fields are named per type, so they need the type before them
+        let mut struct_type = None;
+        for (field, immediate) in immediates {
+            match (field, struct_type, immediate.as_index()) {
+                ("struct_type_index", _, index) => struct_type = index,
+                ("field_index", Some(ty), Some(index)) => {
+                    text.push(' ');
+                    text.push_str(&self.field(ty, index));
+                    continue;
+                }
+                _ => {}
+            }
+            immediate.write(field, self, &mut text);
+        }
+        text
+    }
+}
+
+impl Immediate for u32 {
+    fn write(&self, field: &str, printer: &Printer, out: &mut String) {
+        let space = match field {
+            "local_index" => {
+                out.push(' ');
+                out.push_str(&printer.local(*self));
+                return;
+            }
+            // The only memory by default
+            "mem" if *self == 0 => return,
+            "global_index" => Space::Global,
+            "function_index" => Space::Func,
+            "type_index"
+            | "struct_type_index"
+            | "array_type_index"
+            | "array_type_index_dst"
+            | "array_type_index_src"
+            | "cont_type_index" => Space::Type,
+            "table_index" | "table" | "dst_table" | "src_table" => Space::Table,
+            "mem" | "dst_mem" | "src_mem" => Space::Memory,
+            "tag_index" => Space::Tag,
+            "elem_index" | "array_elem_index" => Space::Elem,
+            "data_index" | "array_data_index" => Space::Data,
+            // Label depths, field indices and counts
+            _ => {
+                out.push_str(&format!(" {}", self));
+                return;
+            }
+        };
+        out.push(' ');
+        out.push_str(&printer.index(space, *self));
+    }
+
+    fn as_index(&self) -> Option<u32> {
+        Some(*self)
+    }
+}
+
+impl Immediate for u8 {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        out.push_str(&format!(" {}", self));
+    }
+}
+
+impl Immediate for [u8; 16] {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        for lane in self {
+            out.push_str(&format!(" {}", lane));
+        }
+    }
+}
+
+impl Immediate for i32 {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        out.push_str(&format!(" {}", self));
+    }
+}
+
+impl Immediate for i64 {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        out.push_str(&format!(" {}", self));
+    }
+}
+
+impl Immediate for Ieee32 {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        let bits = self.bits();
+        let value = f32::from_bits(bits);
+        out.push(' ');
+        out.push_str(&float_text(
+            value.is_nan(),
+            bits >> 31 != 0,
+            u64::from(bits & 0x7f_ffff),
+            0x40_0000,
+            || format!("{:?}", value),
+        ));
+    }
+}
+
+impl Immediate for Ieee64 {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        let bits = self.bits();
+        let value = f64::from_bits(bits);
+        out.push(' ');
+        out.push_str(&float_text(
+            value.is_nan(),
+            bits >> 63 != 0,
+            bits & 0xf_ffff_ffff_ffff,
+            0x8_0000_0000_0000,
+            || format!("{:?}", value),
+        ));
+    }
+}
+
+/// A float constant: `nan`, `nan:0x...` for other payloads, `inf`, or the
+/// shortest decimal that reads back as the same value
+fn float_text(
+    nan: bool,
+    negative: bool,
+    payload: u64,
+    canonical: u64,
+    decimal: impl FnOnce() -> String,
+) -> String {
+    let sign = if negative { "-" } else { "" };
+    if nan && payload == canonical {
+        format!("{}nan", sign)
+    } else if nan {
+        format!("{}nan:0x{:x}", sign, payload)
+    } else {
+        decimal()
+    }
+}
+
+impl Immediate for V128 {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        out.push_str(" i32x4");
+        for lane in self.bytes().chunks_exact(4) {
+            let lane = u32::from_le_bytes([lane[0], lane[1], lane[2], lane[3]]);
+            out.push_str(&format!(" 0x{:08x}", lane));
+        }
+    }
+}
+
+impl Immediate for MemArg {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        if self.memory != 0 {
+            out.push_str(&format!(" {}", printer.index(Space::Memory, self.memory)));
+        }
+        if self.offset != 0 {
+            out.push_str(&format!(" offset={}", self.offset));
+        }
+        if self.align != self.max_align {
+            out.push_str(&format!(" align={}", 1u64 << self.align));
+        }
+    }
+}
+
+impl Immediate for Ordering {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        // Sequentially consistent is the default
+        if let Ordering::AcqRel = self {
+            out.push_str(" acq_rel");
+        }
+    }
+}
+
+impl Immediate for BlockType {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        match self {
+            BlockType::Empty => {}
+            BlockType::Type(ty) => out.push_str(&format!(" (result {})", printer.val_type(*ty))),
+            BlockType::FuncType(index) => {
+                out.push_str(&format!(" (type {})", printer.index(Space::Type, *index)))
+            }
+        }
+    }
+}
+
+impl Immediate for BrTable<'_> {
+    fn write(&self, _field: &str, _printer: &Printer, out: &mut String) {
+        for target in self.targets().flatten() {
+            out.push_str(&format!(" {}", target));
+        }
+        out.push_str(&format!(" {}", self.default()));
+    }
+}
+
+impl Immediate for TryTable {
+    fn write(&self, field: &str, printer: &Printer, out: &mut String) {
+        self.ty.write(field, printer, out);
+        for catch in &self.catches {
+            let text = match catch {
+                Catch::One { tag, label } => {
+                    format!("catch {} {}", printer.index(Space::Tag, *tag), label)
+                }
+                Catch::OneRef { tag, label } => {
+                    format!("catch_ref {} {}", printer.index(Space::Tag, *tag), label)
+                }
+                Catch::All { label } => format!("catch_all {}", label),
+                Catch::AllRef { label } => format!("catch_all_ref {}", label),
+            };
+            out.push_str(&format!(" ({})", text));
+        }
+    }
+}
+
+impl Immediate for ResumeTable {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        for handle in &self.handlers {
+            let text = match handle {
+                Handle::OnLabel { tag, label } => {
+                    format!("{} {}", printer.index(Space::Tag, *tag), label)
+                }
+                Handle::OnSwitch { tag } => format!("{} switch", printer.index(Space::Tag, *tag)),
+            };
+            out.push_str(&format!(" (on {})", text));
+        }
+    }
+}
+
+impl Immediate for ValType {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        out.push_str(&format!(" (result {})", printer.val_type(*self)));
+    }
+}
+
+impl Immediate for Vec<ValType> {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        let types: Vec<_> = self.iter().map(|ty| printer.val_type(*ty)).collect();
+        out.push_str(&format!(" (result {})", types.join(" ")));
+    }
+}
+
+impl Immediate for HeapType {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        out.push(' ');
+        out.push_str(&printer.heap_type(*self));
+    }
+}
+
+impl Immediate for RefType {
+    fn write(&self, _field: &str, printer: &Printer, out: &mut String) {
+        out.push(' ');
+        out.push_str(&printer.ref_type(*self));
+    }
+}
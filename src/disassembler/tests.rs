@@ -0,0 +1,148 @@
+use super::operators::mnemonic;
+use super::*;
+
+fn encode(text: &str) -> Vec<u8> {
+    let buf = wast::parser::ParseBuffer::new(text).unwrap();
+    let mut wat = wast::parser::parse::<wast::Wat>(&buf).unwrap();
+    wat.encode().unwrap()
+}
+
+#[test]
+fn test_mnemonics() {
+    let cases = [
+        ("visit_i32_add", "i32.add"),
+        ("visit_local_get", "local.get"),
+        ("visit_br_if", "br_if"),
+        ("visit_call_indirect", "call_indirect"),
+        ("visit_ref_is_null", "ref.is_null"),
+        ("visit_i32_trunc_sat_f32_s", "i32.trunc_sat_f32_s"),
+        ("visit_i32_atomic_rmw8_add_u", "i32.atomic.rmw8.add_u"),
+        ("visit_i64_atomic_rmw_cmpxchg", "i64.atomic.rmw.cmpxchg"),
+        ("visit_memory_atomic_notify", "memory.atomic.notify"),
+        ("visit_atomic_fence", "atomic.fence"),
+        ("visit_v128_load8x8_s", "v128.load8x8_s"),
+        ("visit_i8x16_shuffle", "i8x16.shuffle"),
+        ("visit_struct_new_default", "struct.new_default"),
+        ("visit_any_convert_extern", "any.convert_extern"),
+        ("visit_data_drop", "data.drop"),
+        ("visit_return_call_ref", "return_call_ref"),
+        ("visit_cont_new", "cont.new"),
+    ];
+    for (visit, expected) in cases {
+        assert_eq!(mnemonic(visit), expected);
+    }
+}
+
+#[test]
+fn test_disassemble_keeps_names() {
+    let bytes = encode(
+        r#"(module $demo
+  (import "env" "log" (func $log (param i32)))
+  (memory $mem 1)
+  (global $count (mut i32) (i32.const 0))
+  (func $add (export "add") (param $a i32) (param $b i32) (result i32)
+    (local $sum i32)
+    local.get $a
+    local.get $b
+    i32.add
+    local.tee $sum
+    call $log
+    local.get $sum)
+  (data (i32.const 8) "hi\n"))"#,
+    );
+    let text = disassemble(&bytes).unwrap().text;
+    let expected = r#"(module $demo
+  (type (func (param i32)))
+  (type (func (param i32 i32) (result i32)))
+  (import "env" "log" (func $log (type 0) (param i32)))
+  (memory $mem 1)
+  (global $count (mut i32) (i32.const 0))
+  (export "add" (func $add))
+  (func $add (type 1) (param $a i32) (param $b i32) (result i32)
+    (local $sum i32)
+    local.get $a
+    local.get $b
+    i32.add
+    local.tee $sum
+    call $log
+    local.get $sum
+  )
+  (data (i32.const 8) "hi\0a")
+)
+"#;
+    assert_eq!(text, expected);
+}
+
+#[test]
+fn test_disassemble_indents_blocks() {
+    let bytes = encode(
+        r#"(module
+  (func (param i32) (result i32)
+    (block $done
+      (loop $again
+        (br_if $done (local.get 0))
+        (br $again)))
+    (if (result i32) (local.get 0)
+      (then (i32.const 1))
+      (else (i32.const 2)))))"#,
+    );
+    let text = disassemble(&bytes).unwrap().text;
+    assert!(text.contains(
+        "    block\n      loop\n        local.get 0\n        br_if 1\n        br 0\n      end\n    end\n"
+    ));
+    assert!(text.contains(
+        "    if (result i32)\n      i32.const 1\n    else\n      i32.const 2\n    end\n  )\n"
+    ));
+}
+
+#[test]
+fn test_disassembly_offsets() {
+    let bytes = encode("(module (func (export \"f\") (result i32) i32.const 7))");
+    let disassembly = disassemble(&bytes).unwrap();
+    let lines: Vec<&str> = disassembly.text.lines().collect();
+    let line = lines
+        .iter()
+        .position(|l| l.trim() == "i32.const 7")
+        .unwrap();
+    let offset = disassembly.offset_at_line(line as u32).unwrap();
+    // i32.const is opcode 0x41 followed by its immediate
+    assert_eq!(bytes[offset], 0x41);
+    assert_eq!(bytes[offset + 1], 7);
+    // The closing parenthesis of the module isn't in the binary
+    assert_eq!(disassembly.offset_at_line(lines.len() as u32 - 1), None);
+}
+
+#[test]
+fn test_disassembly_round_trips() {
+    let source = r#"(module
+  (type $point (struct (field $x (mut i32)) (field $y (mut i32))))
+  (type $bytes (array (mut i8)))
+  (table $fns 2 funcref)
+  (memory 1 2)
+  (global $origin (ref null $point) (ref.null $point))
+  (elem (i32.const 0) func $get $store)
+  (func $get (param $p (ref $point)) (result i32)
+    (struct.get $point $x (local.get $p)))
+  (func $store (param i32 i64)
+    (i64.store offset=8 align=4 (local.get 0) (local.get 1))
+    (call_indirect $fns (param i32) (i32.const 5) (i32.const 1))
+    (v128.const i32x4 1 2 3 4)
+    drop
+    (f32.const nan)
+    drop
+    (f64.const -1.5)
+    drop))"#;
+    let bytes = encode(source);
+    let first = disassemble(&bytes).unwrap().text;
+    // The text re-assembles to the same module
+    let again = encode(&first);
+    assert_eq!(disassemble(&again).unwrap().text, first);
+    assert!(first.contains("(type $point (struct (field $x (mut i32)) (field $y (mut i32))))"));
+    assert!(first.contains("i64.store offset=8 align=4"));
+    assert!(first.contains("struct.get $point $x"));
+}
+
+#[test]
+fn test_disassemble_rejects_malformed_binaries() {
+    assert!(disassemble(b"\0asm\x01\0\0\0\x01\xff").is_err());
+}
@@ -0,0 +1,407 @@
+use crate::core::CancellationToken;
+use crate::features::references::{identify_symbol_at_position, ReferenceTarget};
+use crate::features::type_definition::{function_signature, module_node_at};
+use crate::symbols::*;
+use crate::utils::{format_function_signature, node_to_lsp_range};
+use serde_json::json;
+use tower_lsp::lsp_types::*;
+use tree_sitter::{Node, Tree};
+
+#[cfg(test)]
+mod tests;
+
+/// Instructions that reference a function directly by index
+const DIRECT_CALL_OPS: &[&str] = &["call", "return_call", "ref.func"];
+
+/// Instructions that call through a table
+const INDIRECT_CALL_OPS: &[&str] = &["call_indirect", "return_call_indirect"];
+
+/// Every direct and indirect call edge of a document.
+///
+/// Resolving `call_indirect` candidates compares signatures across the
+/// whole module, so the graph is built once per document version and
+/// shared by the incoming and outgoing calls requests.
+#[derive(Debug, Default)]
+pub struct CallGraph {
+    sites: Vec<CallSite>,
+}
+
+/// A single edge in the call graph
+#[derive(Debug)]
+struct CallSite {
+    /// Function containing the site, or None for module-level `ref.func`
+    caller: Option<usize>,
+    callee: usize,
+    range: Range,
+}
+
+/// An entry point into the module: an export or the start function
+struct CallRoot {
+    label: String,
+    function: usize,
+    range: Range,
+}
+
+/// Prepare the call hierarchy for the function under the cursor
+pub fn prepare_call_hierarchy(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+    uri: &str,
+) -> Option<Vec<CallHierarchyItem>> {
+    let lsp_uri = Url::parse(uri).ok()?;
+
+    match identify_symbol_at_position(document, symbols, tree, position)? {
+        ReferenceTarget::Function { index, .. } => {
+            let func = symbols
+                .scope_at(position.into())
+                .get_function_by_index(index)?;
+            Some(vec![function_item(func, tree, &lsp_uri)])
+        }
+        _ => None,
+    }
+}
+
+/// Find all callers of the function represented by `item`.
+///
+/// Exports and the start function are reported as callers too, so that
+/// entry points show up as roots of the hierarchy.
+pub fn incoming_calls(
+    symbols: &SymbolTable,
+    tree: &Tree,
+    graph: &CallGraph,
+    item: &CallHierarchyItem,
+) -> Vec<CallHierarchyIncomingCall> {
+    let Some((index, is_root)) = item_function_index(item) else {
+        return vec![];
+    };
+    if is_root {
+        return vec![];
+    }
+    let module = symbols.module_at(item.selection_range.start.into());
+    let symbols = symbols.scope_at(item.selection_range.start.into());
+
+    let mut calls: Vec<CallHierarchyIncomingCall> = Vec::new();
+
+    for root in collect_call_roots(symbols) {
+        if root.function == index {
+            calls.push(CallHierarchyIncomingCall {
+                from: root_item(&root, &item.uri),
+                from_ranges: vec![root.range],
+            });
+        }
+    }
+
+    let sites: Vec<&CallSite> = graph.sites_in(module).collect();
+    for caller in &symbols.functions {
+        let from_ranges: Vec<Range> = sites
+            .iter()
+            .filter(|site| site.callee == index && site.caller == Some(caller.index))
+            .map(|site| site.range)
+            .collect();
+        if !from_ranges.is_empty() {
+            calls.push(CallHierarchyIncomingCall {
+                from: function_item(caller, tree, &item.uri),
+                from_ranges,
+            });
+        }
+    }
+
+    calls
+}
+
+/// Find all functions called from the function represented by `item`.
+///
+/// `call_indirect` sites list every function placed in a table by an elem
+/// segment whose signature matches the call's type.
+pub fn outgoing_calls(
+    symbols: &SymbolTable,
+    tree: &Tree,
+    graph: &CallGraph,
+    item: &CallHierarchyItem,
+) -> Vec<CallHierarchyOutgoingCall> {
+    let Some((index, is_root)) = item_function_index(item) else {
+        return vec![];
+    };
+    let module = symbols.module_at(item.selection_range.start.into());
+    let symbols = symbols.scope_at(item.selection_range.start.into());
+
+    if is_root {
+        return symbols
+            .get_function_by_index(index)
+            .map(|func| CallHierarchyOutgoingCall {
+                to: function_item(func, tree, &item.uri),
+                from_ranges: vec![item.selection_range],
+            })
+            .into_iter()
+            .collect();
+    }
+
+    let sites: Vec<&CallSite> = graph.sites_in(module).collect();
+    let mut calls = Vec::new();
+
+    for callee in &symbols.functions {
+        let from_ranges: Vec<Range> = sites
+            .iter()
+            .filter(|site| site.caller == Some(index) && site.callee == callee.index)
+            .map(|site| site.range)
+            .collect();
+        if !from_ranges.is_empty() {
+            calls.push(CallHierarchyOutgoingCall {
+                to: function_item(callee, tree, &item.uri),
+                from_ranges,
+            });
+        }
+    }
+
+    calls
+}
+
+impl CallGraph {
+    /// Collect the call edges of every module of the document, or `None`
+    /// once `cancel` is cancelled
+    pub fn build(
+        document: &str,
+        symbols: &SymbolTable,
+        tree: &Tree,
+        cancel: &CancellationToken,
+    ) -> Option<Self> {
+        let mut sites = Vec::new();
+        if symbols.component.is_none() && symbols.is_script() {
+            for module in symbols.modules.iter().filter(|module| !module.quoted) {
+                let scope = module_node_at(tree, document, module.range.start.into());
+                collect_call_sites(document, &module.symbols, tree, scope, cancel, &mut sites)?;
+            }
+        } else {
+            collect_call_sites(
+                document,
+                symbols,
+                tree,
+                tree.root_node(),
+                cancel,
+                &mut sites,
+            )?;
+        }
+        Some(Self { sites })
+    }
+
+    /// Call sites inside `module`, or all of them outside a script
+    fn sites_in<'a>(
+        &'a self,
+        module: Option<&'a ScriptModule>,
+    ) -> impl Iterator<Item = &'a CallSite> + 'a {
+        self.sites.iter().filter(move |site| {
+            module.is_none_or(|module| module.range.contains(site.range.start.into()))
+        })
+    }
+}
+
+/// Read the function index (and whether the item is an entry point) from item data
+fn item_function_index(item: &CallHierarchyItem) -> Option<(usize, bool)> {
+    let data = item.data.as_ref()?;
+    let index = data.get("function")?.as_u64()? as usize;
+    let is_root = data.get("root").and_then(|r| r.as_bool()).unwrap_or(false);
+    Some((index, is_root))
+}
+
+fn function_item(func: &Function, tree: &Tree, uri: &Url) -> CallHierarchyItem {
+    let range = tree
+        .root_node()
+        .descendant_for_byte_range(func.start_byte, func.end_byte)
+        .map(|node| node_to_lsp_range(&node))
+        .unwrap_or_default();
+    let selection_range = func.range.map(Into::into).unwrap_or(range);
+
+    CallHierarchyItem {
+        name: func
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("func {}", func.index)),
+        kind: SymbolKind::FUNCTION,
+        tags: None,
+        detail: Some(format_function_signature(func)),
+        uri: uri.clone(),
+        range,
+        selection_range,
+        data: Some(json!({ "function": func.index })),
+    }
+}
+
+fn root_item(root: &CallRoot, uri: &Url) -> CallHierarchyItem {
+    CallHierarchyItem {
+        name: root.label.clone(),
+        kind: SymbolKind::MODULE,
+        tags: None,
+        detail: Some("entry point".to_string()),
+        uri: uri.clone(),
+        range: root.range,
+        selection_range: root.range,
+        data: Some(json!({ "function": root.function, "root": true })),
+    }
+}
+
+/// Resolve a function index written as `$name` or a number
+fn resolve_function_index(text: &str, symbols: &SymbolTable) -> Option<usize> {
+    if text.starts_with('$') {
+        symbols.get_function_by_name(text).map(|f| f.index)
+    } else {
+        let index = text.parse::<usize>().ok()?;
+        symbols.get_function_by_index(index).map(|f| f.index)
+    }
+}
+
+/// Find the defined function whose body contains the given byte
+fn containing_function(symbols: &SymbolTable, byte: usize) -> Option<usize> {
+    symbols
+        .functions
+        .iter()
+        .find(|f| f.start_byte <= byte && byte < f.end_byte)
+        .map(|f| f.index)
+}
+
+/// Collect every direct and indirect call edge in the module at `scope`
+/// into `sites`, or return `None` once `cancel` is cancelled
+fn collect_call_sites(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    scope: Node,
+    cancel: &CancellationToken,
+    sites: &mut Vec<CallSite>,
+) -> Option<()> {
+    // Candidates of call_indirect, with their signatures resolved up front
+    let table_functions: Vec<_> = collect_table_functions(symbols)
+        .into_iter()
+        .filter_map(|index| symbols.get_function_by_index(index))
+        .map(|func| {
+            (
+                func.index,
+                function_signature(func, symbols, tree, document),
+            )
+        })
+        .collect();
+    let mut stack = vec![scope];
+
+    while let Some(node) = stack.pop() {
+        if cancel.is_cancelled() {
+            return None;
+        }
+        let kind = node.kind();
+        let op = document[node.byte_range()].split_whitespace().next();
+
+        if kind == "instr_plain" && op.is_some_and(|op| DIRECT_CALL_OPS.contains(&op)) {
+            if let Some(index_node) = find_child(&node, "index") {
+                let text = &document[index_node.byte_range()];
+                if let Some(callee) = resolve_function_index(text, symbols) {
+                    sites.push(CallSite {
+                        caller: containing_function(symbols, node.start_byte()),
+                        callee,
+                        range: node_to_lsp_range(&index_node),
+                    });
+                }
+            }
+        } else if matches!(kind, "instr_call" | "expr1_call" | "instr_list_call")
+            && op.is_some_and(|op| INDIRECT_CALL_OPS.contains(&op))
+        {
+            let caller = containing_function(symbols, node.start_byte());
+            let range = node
+                .child(0)
+                .map(|keyword| node_to_lsp_range(&keyword))
+                .unwrap_or_else(|| node_to_lsp_range(&node));
+
+            if let Some(signature) = indirect_call_signature(&node, document, symbols) {
+                for (callee, _) in table_functions
+                    .iter()
+                    .filter(|(_, func_signature)| *func_signature == signature)
+                {
+                    sites.push(CallSite {
+                        caller,
+                        callee: *callee,
+                        range,
+                    });
+                }
+            }
+        }
+
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+
+    Some(())
+}
+
+/// Resolve the signature of a `call_indirect`, from its type use or inline params/results
+fn indirect_call_signature(
+    node: &Node,
+    document: &str,
+    symbols: &SymbolTable,
+) -> Option<(Vec<ValueType>, Vec<ValueType>)> {
+    if let Some(type_use) = find_child(node, "type_use") {
+        let index_node = find_child(&type_use, "index")?;
+        let type_index = symbols.resolve_type_index(&document[index_node.byte_range()])?;
+        return match &symbols.get_type_by_index(type_index)?.kind {
+            TypeKind::Func { params, results } => Some((params.clone(), results.clone())),
+            _ => None,
+        };
+    }
+
+    let mut params = Vec::new();
+    let mut results = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        let target = match child.kind() {
+            "func_type_params_many" => &mut params,
+            "func_type_results" => &mut results,
+            _ => continue,
+        };
+        let mut value_cursor = child.walk();
+        for value_type in child.children(&mut value_cursor) {
+            if value_type.kind() == "value_type" {
+                target.push(ValueType::parse(&document[value_type.byte_range()]));
+            }
+        }
+    }
+    Some((params, results))
+}
+
+/// Functions placed in tables by elem segments, deduplicated, in index order
+fn collect_table_functions(symbols: &SymbolTable) -> Vec<usize> {
+    let mut functions: Vec<usize> = symbols
+        .elem_segments
+        .iter()
+        .flat_map(|elem| elem.func_names.iter())
+        .filter_map(|name| resolve_function_index(name, symbols))
+        .collect();
+    functions.sort_unstable();
+    functions.dedup();
+    functions
+}
+
+/// Collect exported functions and the start function
+fn collect_call_roots(symbols: &SymbolTable) -> Vec<CallRoot> {
+    let exports = symbols
+        .exports
+        .iter()
+        .filter(|export| export.kind == ExternKind::Func)
+        .map(|export| CallRoot {
+            label: format!("export \"{}\"", export.name),
+            function: export.index,
+            range: export.range.map(Into::into).unwrap_or_default(),
+        });
+
+    let start = symbols.start.iter().map(|start| CallRoot {
+        label: "start".to_string(),
+        function: start.index,
+        range: start.range.map(Into::into).unwrap_or_default(),
+    });
+
+    exports.chain(start).collect()
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    let mut cursor = node.walk();
+    let child = node.children(&mut cursor).find(|c| c.kind() == kind);
+    child
+}
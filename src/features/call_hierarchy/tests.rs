@@ -0,0 +1,211 @@
+use super::*;
+use crate::parser::parse_document;
+use crate::tree_sitter_bindings::create_parser;
+use tower_lsp::lsp_types::Position;
+use tree_sitter::Tree;
+
+fn create_test_tree(document: &str) -> Tree {
+    let mut parser = create_parser();
+    parser
+        .parse(document, None)
+        .expect("Failed to parse test document")
+}
+
+fn create_uri() -> String {
+    "file:///test.wat".to_string()
+}
+
+const RUNTIME: &str = r#"(module
+  (type $binop (func (param i32 i32) (result i32)))
+  (table 2 funcref)
+  (elem (i32.const 0) $add $sub)
+  (func $add (type $binop)
+    local.get 0
+    local.get 1
+    i32.add
+  )
+  (func $sub (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.sub
+  )
+  (func $helper (param i32) (result i32)
+    local.get 0
+  )
+  (func $dispatch (export "dispatch") (param $op i32) (result i32)
+    i32.const 1
+    call $helper
+    i32.const 2
+    local.get $op
+    call_indirect (type $binop)
+  )
+  (func $init
+    i32.const 3
+    i32.const 4
+    call $add
+    drop
+  )
+  (start $init)
+)"#;
+
+fn prepare(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    line: u32,
+    col: u32,
+) -> CallHierarchyItem {
+    prepare_call_hierarchy(
+        document,
+        symbols,
+        tree,
+        Position::new(line, col),
+        &create_uri(),
+    )
+    .expect("Expected call hierarchy item")
+    .remove(0)
+}
+
+/// The call graph of a document, built without cancellation
+fn graph(document: &str, symbols: &SymbolTable, tree: &Tree) -> CallGraph {
+    CallGraph::build(document, symbols, tree, &CancellationToken::default())
+        .expect("Expected call graph")
+}
+
+#[test]
+fn test_prepare_call_hierarchy() {
+    let symbols = parse_document(RUNTIME).expect("Failed to parse document");
+    let tree = create_test_tree(RUNTIME);
+
+    // On "$helper" in "call $helper"
+    let item = prepare(RUNTIME, &symbols, &tree, 19, 11);
+
+    assert_eq!(item.name, "$helper");
+    assert_eq!(item.kind, SymbolKind::FUNCTION);
+    assert_eq!(item.selection_range.start.line, 14);
+}
+
+#[test]
+fn test_prepare_call_hierarchy_not_on_function() {
+    let symbols = parse_document(RUNTIME).expect("Failed to parse document");
+    let tree = create_test_tree(RUNTIME);
+
+    // On "$op" in "local.get $op"
+    let result = prepare_call_hierarchy(
+        RUNTIME,
+        &symbols,
+        &tree,
+        Position::new(21, 15),
+        &create_uri(),
+    );
+
+    assert!(result.is_none());
+}
+
+#[test]
+fn test_outgoing_calls_include_indirect_candidates() {
+    let symbols = parse_document(RUNTIME).expect("Failed to parse document");
+    let tree = create_test_tree(RUNTIME);
+    let call_graph = graph(RUNTIME, &symbols, &tree);
+
+    // On "$dispatch" in its definition
+    let item = prepare(RUNTIME, &symbols, &tree, 17, 10);
+    let calls = outgoing_calls(&symbols, &tree, &call_graph, &item);
+
+    let names: Vec<&str> = calls.iter().map(|c| c.to.name.as_str()).collect();
+    // $add and $sub are in the table and match $binop; $helper is called directly
+    assert_eq!(names, vec!["$add", "$sub", "$helper"]);
+}
+
+#[test]
+fn test_incoming_calls_direct_and_indirect() {
+    let symbols = parse_document(RUNTIME).expect("Failed to parse document");
+    let tree = create_test_tree(RUNTIME);
+    let call_graph = graph(RUNTIME, &symbols, &tree);
+
+    // On "$add" in "call $add"
+    let item = prepare(RUNTIME, &symbols, &tree, 27, 10);
+    let calls = incoming_calls(&symbols, &tree, &call_graph, &item);
+
+    let names: Vec<&str> = calls.iter().map(|c| c.from.name.as_str()).collect();
+    assert_eq!(names, vec!["$dispatch", "$init"]);
+
+    // A cancelled build stops before finding every call site
+    let guard = crate::core::CancellationGuard::default();
+    let cancel = guard.token();
+    drop(guard);
+    assert!(CallGraph::build(RUNTIME, &symbols, &tree, &cancel).is_none());
+}
+
+#[test]
+fn test_exports_and_start_are_roots() {
+    let symbols = parse_document(RUNTIME).expect("Failed to parse document");
+    let tree = create_test_tree(RUNTIME);
+    let call_graph = graph(RUNTIME, &symbols, &tree);
+
+    let dispatch = prepare(RUNTIME, &symbols, &tree, 17, 10);
+    let calls = incoming_calls(&symbols, &tree, &call_graph, &dispatch);
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].from.name, "export \"dispatch\"");
+    assert_eq!(calls[0].from.kind, SymbolKind::MODULE);
+
+    // Roots have no callers, and lead back to their function
+    assert!(incoming_calls(&symbols, &tree, &call_graph, &calls[0].from).is_empty());
+    let outgoing = outgoing_calls(&symbols, &tree, &call_graph, &calls[0].from);
+    assert_eq!(outgoing.len(), 1);
+    assert_eq!(outgoing[0].to.name, "$dispatch");
+
+    let init = prepare(RUNTIME, &symbols, &tree, 24, 10);
+    let calls = incoming_calls(&symbols, &tree, &call_graph, &init);
+    let names: Vec<&str> = calls.iter().map(|c| c.from.name.as_str()).collect();
+    assert_eq!(names, vec!["start"]);
+}
+
+#[test]
+fn test_ref_func_is_an_outgoing_call() {
+    let document = r#"(module
+  (func $target)
+  (func $user (result funcref)
+    ref.func $target
+  )
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+    let call_graph = graph(document, &symbols, &tree);
+
+    let item = prepare(document, &symbols, &tree, 2, 10);
+    let calls = outgoing_calls(&symbols, &tree, &call_graph, &item);
+
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].to.name, "$target");
+    assert_eq!(calls[0].from_ranges[0].start.line, 3);
+}
+
+#[test]
+fn test_call_hierarchy_in_script_module() {
+    let document = r#"(module
+  (func $a)
+  (func $b call $a))
+(module
+  (func $c)
+  (func $d call 0)
+  (func $e call $d))
+(assert_return (invoke "x"))
+"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+    let call_graph = graph(document, &symbols, &tree);
+
+    // On "$c", function 0 of the second module
+    let item = prepare(document, &symbols, &tree, 4, 9);
+    assert_eq!(item.name, "$c");
+    let calls = incoming_calls(&symbols, &tree, &call_graph, &item);
+    let names: Vec<&str> = calls.iter().map(|c| c.from.name.as_str()).collect();
+    assert_eq!(names, vec!["$d"]);
+
+    // On "$d" in its definition
+    let item = prepare(document, &symbols, &tree, 5, 9);
+    let calls = outgoing_calls(&symbols, &tree, &call_graph, &item);
+    let names: Vec<&str> = calls.iter().map(|c| c.to.name.as_str()).collect();
+    assert_eq!(names, vec!["$c"]);
+}
@@ -0,0 +1,327 @@
+use crate::compile::SizeReport;
+use crate::core::CancellationToken;
+use crate::features::references::{
+    provide_references_for_target, reference_target_for_item, ReferenceTarget,
+};
+use crate::interpreter::{AssertionResult, Outcome};
+use crate::native::commands::{RUN_ASSERTIONS_COMMAND, RUN_EXPORT_COMMAND};
+use crate::symbols::*;
+use serde_json::json;
+use std::collections::HashMap;
+use tower_lsp::lsp_types::*;
+use tree_sitter::Tree;
+
+#[cfg(test)]
+mod tests;
+
+/// Client-side command that opens the references view.
+/// Arguments are `[uri, position, locations]`.
+pub const SHOW_REFERENCES_COMMAND: &str = "watLsp.showReferences";
+
+/// Client-side command that asks for argument values and then runs
+/// `RUN_EXPORT_COMMAND`. Arguments are `[uri, export name, parameters]`,
+/// each parameter an object with a `name` (or null) and a `type`.
+pub const PROMPT_RUN_EXPORT_COMMAND: &str = "watLsp.promptRunExport";
+
+/// Provide unresolved code lenses above each module-level declaration.
+///
+/// Counting references requires a full tree walk per symbol, so the lenses
+/// returned here only carry the symbol kind and index; the title and command
+/// are filled in by `resolve_code_lens` for the lenses actually on screen.
+/// In a `.wast` script, each module gets the lenses of its own declarations.
+pub fn provide_code_lenses(symbols: &SymbolTable, uri: &str) -> Vec<CodeLens> {
+    if symbols.component.is_none() && symbols.is_script() {
+        return symbols
+            .modules
+            .iter()
+            .flat_map(|module| provide_code_lenses(&module.symbols, uri))
+            .collect();
+    }
+
+    let mut lenses = Vec::new();
+
+    let mut push =
+        |kind: &str, index: usize, line: u32, range: Option<crate::core::types::Range>| {
+            let range = range.map(Into::into).unwrap_or(Range {
+                start: Position::new(line, 0),
+                end: Position::new(line, 0),
+            });
+            lenses.push(CodeLens {
+                range,
+                command: None,
+                data: Some(json!({ "uri": uri, "kind": kind, "index": index })),
+            });
+        };
+
+    for func in &symbols.functions {
+        push("func", func.index, func.line, func.range);
+    }
+    for global in &symbols.globals {
+        push("global", global.index, global.line, global.range);
+    }
+    for type_def in &symbols.types {
+        push("type", type_def.index, type_def.line, type_def.range);
+    }
+    for table in &symbols.tables {
+        push("table", table.index, table.line, table.range);
+    }
+    for memory in &symbols.memories {
+        push("memory", memory.index, memory.line, memory.range);
+    }
+    for tag in &symbols.tags {
+        push("tag", tag.index, tag.line, tag.range);
+    }
+
+    lenses.sort_by_key(|lens| (lens.range.start.line, lens.range.start.character));
+    lenses
+}
+
+/// Provide the lenses of a `.wast` script: one above its first command that
+/// runs the assertions, and once they have run, one above each that passed.
+pub fn provide_assertion_lenses(
+    symbols: &SymbolTable,
+    uri: &str,
+    results: Option<&[AssertionResult]>,
+) -> Vec<CodeLens> {
+    // A component's core modules aren't a script the interpreter can run
+    if symbols.component.is_some() {
+        return Vec::new();
+    }
+    let first = symbols
+        .modules
+        .iter()
+        .map(|module| module.range.start)
+        .chain(symbols.commands.iter().map(|command| command.range.start))
+        .min_by_key(|start| (start.line, start.character));
+    let Some(first) = first else {
+        return Vec::new();
+    };
+
+    let mut title = "▶ Run assertions".to_string();
+    if let Some(results) = results {
+        let count = |matches: fn(&Outcome) -> bool| {
+            results
+                .iter()
+                .filter(|result| result.is_assertion() && matches(&result.outcome))
+                .count()
+        };
+        title.push_str(&format!(
+            " · {} passed, {} failed",
+            count(|outcome| *outcome == Outcome::Passed),
+            count(|outcome| matches!(outcome, Outcome::Failed { .. })),
+        ));
+    }
+
+    let mut lenses = vec![CodeLens {
+        range: Range {
+            start: first.into(),
+            end: first.into(),
+        },
+        command: Some(Command {
+            title,
+            command: RUN_ASSERTIONS_COMMAND.to_string(),
+            arguments: Some(vec![json!(uri)]),
+        }),
+        data: None,
+    }];
+
+    for result in results.unwrap_or_default() {
+        if result.is_assertion() && result.outcome == Outcome::Passed {
+            let start = result.range.start.into();
+            lenses.push(CodeLens {
+                range: Range { start, end: start },
+                command: Some(Command {
+                    title: "✓ passed".to_string(),
+                    command: String::new(),
+                    arguments: None,
+                }),
+                data: None,
+            });
+        }
+    }
+    lenses
+}
+
+/// Provide a `▶ Run` lens above each exported function whose parameters are
+/// all numbers, followed by the results of its last run in `runs`, keyed by
+/// export name.
+///
+/// Functions without parameters run straight away; the others go through
+/// the client, which asks for the arguments first.
+pub fn provide_run_lenses(
+    symbols: &SymbolTable,
+    uri: &str,
+    runs: &HashMap<String, String>,
+) -> Vec<CodeLens> {
+    let mut lenses = Vec::new();
+    for export in &symbols.exports {
+        if export.kind != ExternKind::Func {
+            continue;
+        }
+        let Some(func) = symbols.get_function_by_index(export.index) else {
+            continue;
+        };
+        let numeric = func.parameters.iter().all(|param| {
+            matches!(
+                param.param_type,
+                ValueType::I32 | ValueType::I64 | ValueType::F32 | ValueType::F64
+            )
+        });
+        if !numeric {
+            continue;
+        }
+
+        // Name the export when the function has several
+        let mut title = if symbols.exports_of(ExternKind::Func, func.index).count() > 1 {
+            format!("▶ Run \"{}\"", export.name)
+        } else {
+            "▶ Run".to_string()
+        };
+        if let Some(summary) = runs.get(&export.name) {
+            title.push_str(&format!(" · {}", summary));
+        }
+
+        let command = if func.parameters.is_empty() {
+            Command {
+                title,
+                command: RUN_EXPORT_COMMAND.to_string(),
+                arguments: Some(vec![json!(uri), json!(export.name), json!([])]),
+            }
+        } else {
+            let params: Vec<_> = func
+                .parameters
+                .iter()
+                .map(|param| json!({ "name": param.name, "type": param.param_type.to_string() }))
+                .collect();
+            Command {
+                title,
+                command: PROMPT_RUN_EXPORT_COMMAND.to_string(),
+                arguments: Some(vec![json!(uri), json!(export.name), json!(params)]),
+            }
+        };
+
+        let start = func
+            .range
+            .map(|range| range.start.into())
+            .unwrap_or(Position::new(func.line, 0));
+        lenses.push(CodeLens {
+            range: Range { start, end: start },
+            command: Some(command),
+            data: None,
+        });
+    }
+
+    lenses.sort_by_key(|lens| (lens.range.start.line, lens.range.start.character));
+    lenses
+}
+
+/// Provide a lens with the encoded size of each defined function, e.g.
+/// `412 bytes`, from the size report of the document's binary.
+pub fn provide_size_lenses(symbols: &SymbolTable, report: &SizeReport) -> Vec<CodeLens> {
+    let mut lenses = Vec::new();
+    for func in &symbols.functions {
+        let Some(size) = report.function(func.index as u32) else {
+            continue;
+        };
+        let title = match size.size {
+            1 => "1 byte".to_string(),
+            n => format!("{} bytes", n),
+        };
+        let start = func
+            .range
+            .map(|range| range.start.into())
+            .unwrap_or(Position::new(func.line, 0));
+        lenses.push(CodeLens {
+            range: Range { start, end: start },
+            command: Some(Command {
+                title,
+                command: String::new(),
+                arguments: None,
+            }),
+            data: None,
+        });
+    }
+    lenses
+}
+
+/// Fill in the title and command of a code lens produced by `provide_code_lenses`,
+/// e.g. `3 references · exported as "run"`
+pub fn resolve_code_lens(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    mut lens: CodeLens,
+) -> CodeLens {
+    let Some((uri, kind, index)) = lens_data(&lens) else {
+        return lens;
+    };
+    // In a .wast script, the lens counts references within its own module
+    let module = symbols.module_at(lens.range.start.into());
+    let symbols = symbols.scope_at(lens.range.start.into());
+    let Some(target) = reference_target(&kind, index, symbols) else {
+        return lens;
+    };
+
+    let mut locations = provide_references_for_target(
+        &target,
+        document,
+        symbols,
+        tree,
+        &uri,
+        false,
+        &CancellationToken::default(),
+    );
+    if let Some(module) = module {
+        locations.retain(|location| module.range.contains(location.range.start.into()));
+    }
+
+    let mut title = match locations.len() {
+        1 => "1 reference".to_string(),
+        n => format!("{} references", n),
+    };
+    if let Some(extern_kind) = extern_kind(&kind) {
+        for export in symbols.exports_of(extern_kind, index) {
+            title.push_str(&format!(" · exported as \"{}\"", export.name));
+        }
+        if let Some(import) = symbols.import_of(extern_kind, index) {
+            title.push_str(&format!(" · imported from {}", import.qualified_name()));
+        }
+    }
+
+    lens.command = Some(Command {
+        title,
+        command: SHOW_REFERENCES_COMMAND.to_string(),
+        arguments: Some(vec![json!(uri), json!(lens.range.start), json!(locations)]),
+    });
+    lens
+}
+
+fn lens_data(lens: &CodeLens) -> Option<(String, String, usize)> {
+    let data = lens.data.as_ref()?;
+    Some((
+        data.get("uri")?.as_str()?.to_string(),
+        data.get("kind")?.as_str()?.to_string(),
+        data.get("index")?.as_u64()? as usize,
+    ))
+}
+
+fn reference_target(kind: &str, index: usize, symbols: &SymbolTable) -> Option<ReferenceTarget> {
+    if kind == "type" {
+        return Some(ReferenceTarget::Type {
+            name: symbols.get_type_by_index(index)?.name.clone(),
+            index,
+        });
+    }
+    reference_target_for_item(symbols, extern_kind(kind)?, index)
+}
+
+fn extern_kind(kind: &str) -> Option<ExternKind> {
+    match kind {
+        "func" => Some(ExternKind::Func),
+        "global" => Some(ExternKind::Global),
+        "table" => Some(ExternKind::Table),
+        "memory" => Some(ExternKind::Memory),
+        "tag" => Some(ExternKind::Tag),
+        _ => None,
+    }
+}
@@ -0,0 +1,195 @@
+use super::*;
+use crate::parser::parse_document;
+use crate::tree_sitter_bindings::create_parser;
+use tree_sitter::Tree;
+
+fn create_test_tree(document: &str) -> Tree {
+    let mut parser = create_parser();
+    parser
+        .parse(document, None)
+        .expect("Failed to parse test document")
+}
+
+fn create_uri() -> String {
+    "file:///test.wat".to_string()
+}
+
+const MODULE: &str = r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (global $counter (mut i32) (i32.const 0))
+  (memory $mem 1)
+  (func $bump (export "run")
+    global.get $counter
+    i32.const 1
+    i32.add
+    global.set $counter
+    global.get $counter
+    call $log
+  )
+  (export "bump" (func $bump))
+)"#;
+
+fn resolved_titles(document: &str) -> Vec<String> {
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+    provide_code_lenses(&symbols, &create_uri())
+        .into_iter()
+        .map(|lens| resolve_code_lens(document, &symbols, &tree, lens))
+        .map(|lens| lens.command.expect("Expected resolved command").title)
+        .collect()
+}
+
+#[test]
+fn test_code_lenses_are_unresolved() {
+    let symbols = parse_document(MODULE).expect("Failed to parse document");
+    let lenses = provide_code_lenses(&symbols, &create_uri());
+
+    assert_eq!(lenses.len(), 4);
+    assert!(lenses.iter().all(|lens| lens.command.is_none()));
+
+    let lines: Vec<u32> = lenses.iter().map(|lens| lens.range.start.line).collect();
+    assert_eq!(lines, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_resolve_reference_counts() {
+    let titles = resolved_titles(MODULE);
+
+    assert_eq!(titles[0], "1 reference · imported from env.log");
+    assert_eq!(titles[1], "3 references");
+    assert_eq!(titles[2], "0 references");
+}
+
+#[test]
+fn test_resolve_export_markers() {
+    let titles = resolved_titles(MODULE);
+
+    assert_eq!(
+        titles[3],
+        "1 reference · exported as \"run\" · exported as \"bump\""
+    );
+}
+
+#[test]
+fn test_resolved_command_opens_references() {
+    let symbols = parse_document(MODULE).expect("Failed to parse document");
+    let tree = create_test_tree(MODULE);
+    let lens = provide_code_lenses(&symbols, &create_uri()).remove(1);
+    let lens = resolve_code_lens(MODULE, &symbols, &tree, lens);
+
+    let command = lens.command.expect("Expected resolved command");
+    assert_eq!(command.command, SHOW_REFERENCES_COMMAND);
+
+    let arguments = command.arguments.expect("Expected command arguments");
+    assert_eq!(arguments[0], create_uri());
+    let locations: Vec<Location> =
+        serde_json::from_value(arguments[2].clone()).expect("Expected locations");
+    assert_eq!(locations.len(), 3);
+    assert!(locations.iter().all(|loc| loc.range.start.line >= 5));
+}
+
+#[test]
+fn test_code_lenses_of_script_modules() {
+    let document = r#"(module
+  (global $g i32 (i32.const 0))
+  (func $f (result i32) global.get $g))
+(module
+  (global $h i32 (i32.const 1))
+  (func (result i32) global.get 0 drop global.get $h))
+"#;
+    let titles = resolved_titles(document);
+
+    // Each global counts the references in its own module only
+    assert_eq!(
+        titles,
+        vec![
+            "1 reference",
+            "0 references",
+            "2 references",
+            "0 references"
+        ]
+    );
+}
+
+#[test]
+fn test_assertion_lenses() {
+    let script = r#"(module
+  (func (export "one") (result i32) (i32.const 1)))
+(assert_return (invoke "one") (i32.const 1))
+(assert_return (invoke "one") (i32.const 2))"#;
+    let symbols = parse_document(script).expect("Failed to parse script");
+
+    let lenses = provide_assertion_lenses(&symbols, &create_uri(), None);
+    assert_eq!(lenses.len(), 1);
+    let command = lenses[0].command.as_ref().unwrap();
+    assert_eq!(command.title, "▶ Run assertions");
+    assert_eq!(command.command, RUN_ASSERTIONS_COMMAND);
+    assert_eq!(lenses[0].range.start, Position::new(0, 0));
+
+    let results = crate::interpreter::run_script(script).unwrap();
+    let lenses = provide_assertion_lenses(&symbols, &create_uri(), Some(&results));
+    let titles: Vec<_> = lenses
+        .iter()
+        .map(|lens| lens.command.as_ref().unwrap().title.as_str())
+        .collect();
+    assert_eq!(
+        titles,
+        ["▶ Run assertions · 1 passed, 1 failed", "✓ passed"]
+    );
+    assert_eq!(lenses[1].range.start, Position::new(2, 0));
+
+    let module = parse_document(MODULE).expect("Failed to parse module");
+    assert!(provide_assertion_lenses(&module, &create_uri(), None).is_empty());
+}
+
+#[test]
+fn test_run_lenses() {
+    let document = r#"(module
+  (func (export "answer") (result i32) (i32.const 42))
+  (func $add (export "add") (export "plus") (param $a i32) (param f64) (result f64)
+    (f64.add (f64.convert_i32_s (local.get $a)) (local.get 1)))
+  (func (export "refs") (param externref))
+  (func $hidden (result i32) (i32.const 0))
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let mut runs = HashMap::new();
+    runs.insert("answer".to_string(), "(i32.const 42)".to_string());
+
+    let lenses = provide_run_lenses(&symbols, &create_uri(), &runs);
+    let commands: Vec<_> = lenses
+        .iter()
+        .map(|lens| lens.command.as_ref().unwrap())
+        .collect();
+    let titles: Vec<_> = commands.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        ["▶ Run · (i32.const 42)", "▶ Run \"add\"", "▶ Run \"plus\""]
+    );
+
+    assert_eq!(commands[0].command, RUN_EXPORT_COMMAND);
+    assert_eq!(lenses[0].range.start, Position::new(1, 2));
+
+    assert_eq!(commands[1].command, PROMPT_RUN_EXPORT_COMMAND);
+    let arguments = commands[1].arguments.as_ref().unwrap();
+    assert_eq!(arguments[1], json!("add"));
+    assert_eq!(
+        arguments[2],
+        json!([{ "name": "$a", "type": "i32" }, { "name": null, "type": "f64" }])
+    );
+}
+
+#[test]
+fn test_size_lenses() {
+    let symbols = parse_document(MODULE).expect("Failed to parse document");
+    let report = crate::compile::size_report(&crate::compile::compile(MODULE).unwrap()).unwrap();
+
+    // The imported function has no body to measure
+    let lenses = provide_size_lenses(&symbols, &report);
+    assert_eq!(lenses.len(), 1);
+    let size = report.function(1).unwrap().size;
+    assert_eq!(
+        lenses[0].command.as_ref().unwrap().title,
+        format!("{} bytes", size)
+    );
+    assert_eq!(lenses[0].range.start, Position::new(4, 2));
+}
@@ -1,3 +1,5 @@
+use crate::host::ImportDescription;
+use crate::settings::CompletionSettings;
 use crate::symbols::*;
 use crate::utils::{
     determine_context_from_line, find_containing_function, get_line_at_position, InstructionContext,
@@ -11,10 +13,27 @@ use tree_sitter::Tree;
 mod tests;
 
 pub fn provide_completion(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+) -> Vec<CompletionItem> {
+    provide_completion_with_settings(
+        document,
+        symbols,
+        tree,
+        position,
+        &CompletionSettings::default(),
+    )
+}
+
+/// Completion honoring the emmet and snippet toggles from the server settings
+pub fn provide_completion_with_settings(
     document: &str,
     symbols: &SymbolTable,
     _tree: &Tree, // Kept for API compatibility; completion uses line-based context detection
     position: Position,
+    settings: &CompletionSettings,
 ) -> Vec<CompletionItem> {
     let mut completions = Vec::new();
 
@@ -25,134 +44,10 @@ pub fn provide_completion(
 
     let line_prefix = &line[..position.character.min(line.len() as u32) as usize];
 
-    // Emmet-like number constant expansion (e.g., 5i32 -> (i32.const 5))
-    if let Some(caps) = NUMBER_CONST_REGEX.captures(line_prefix) {
-        let number = caps.get(1).unwrap().as_str();
-        let type_str = caps.get(2).unwrap().as_str();
-        let clean_number = number.replace('_', "");
-        let insert_text = format!("({}.const {})", type_str, clean_number);
-
-        completions.push(CompletionItem {
-            label: format!("{}{}", number, type_str),
-            kind: Some(CompletionItemKind::SNIPPET),
-            detail: Some(format!("Expand to: {}", insert_text)),
-            insert_text: Some(insert_text),
-            ..Default::default()
-        });
-        return completions;
-    }
-
-    // Emmet-like local.get expansion (e.g., l$var -> (local.get $var))
-    if line_prefix.ends_with("l$") {
-        if let Some(func) = find_containing_function(symbols, position.into()) {
-            for param in &func.parameters {
-                if let Some(ref name) = param.name {
-                    let insert_text = format!("(local.get {})", name);
-                    completions.push(CompletionItem {
-                        label: format!("l{}", name),
-                        kind: Some(CompletionItemKind::SNIPPET),
-                        detail: Some(format!("(param) {}", param.param_type)),
-                        insert_text: Some(insert_text.clone()),
-                        documentation: Some(Documentation::String(format!(
-                            "Expands to: {}",
-                            insert_text
-                        ))),
-                        ..Default::default()
-                    });
-                }
-            }
-            for local in &func.locals {
-                if let Some(ref name) = local.name {
-                    let insert_text = format!("(local.get {})", name);
-                    completions.push(CompletionItem {
-                        label: format!("l{}", name),
-                        kind: Some(CompletionItemKind::SNIPPET),
-                        detail: Some(format!("(local) {}", local.var_type)),
-                        insert_text: Some(insert_text.clone()),
-                        documentation: Some(Documentation::String(format!(
-                            "Expands to: {}",
-                            insert_text
-                        ))),
-                        ..Default::default()
-                    });
-                }
-            }
-        }
-        return completions;
-    }
-
-    // Emmet-like local.set expansion (e.g., l=$var -> (local.set $var ))
-    if line_prefix.ends_with("l=$") {
-        if let Some(func) = find_containing_function(symbols, position.into()) {
-            for param in &func.parameters {
-                if let Some(ref name) = param.name {
-                    let insert_text = format!("(local.set {} $0)", name);
-                    completions.push(CompletionItem {
-                        label: format!("l={}", name),
-                        kind: Some(CompletionItemKind::SNIPPET),
-                        detail: Some(format!("(param) {}", param.param_type)),
-                        insert_text_format: Some(InsertTextFormat::SNIPPET),
-                        insert_text: Some(insert_text.clone()),
-                        ..Default::default()
-                    });
-                }
-            }
-            for local in &func.locals {
-                if let Some(ref name) = local.name {
-                    let insert_text = format!("(local.set {} $0)", name);
-                    completions.push(CompletionItem {
-                        label: format!("l={}", name),
-                        kind: Some(CompletionItemKind::SNIPPET),
-                        detail: Some(format!("(local) {}", local.var_type)),
-                        insert_text_format: Some(InsertTextFormat::SNIPPET),
-                        insert_text: Some(insert_text.clone()),
-                        ..Default::default()
-                    });
-                }
-            }
-        }
-        return completions;
-    }
-
-    // Emmet-like global.get expansion (e.g., g$var -> (global.get $var))
-    if line_prefix.ends_with("g$") {
-        for global in &symbols.globals {
-            if let Some(ref name) = global.name {
-                let insert_text = format!("(global.get {})", name);
-                completions.push(CompletionItem {
-                    label: format!("g{}", name),
-                    kind: Some(CompletionItemKind::SNIPPET),
-                    detail: Some(format!("(global) {}", global.var_type)),
-                    insert_text: Some(insert_text.clone()),
-                    documentation: Some(Documentation::String(format!(
-                        "Expands to: {}",
-                        insert_text
-                    ))),
-                    ..Default::default()
-                });
-            }
-        }
-        return completions;
-    }
-
-    // Emmet-like global.set expansion (e.g., g=$var -> (global.set $var ))
-    if line_prefix.ends_with("g=$") {
-        for global in &symbols.globals {
-            if let Some(ref name) = global.name {
-                if global.is_mutable {
-                    let insert_text = format!("(global.set {} $0)", name);
-                    completions.push(CompletionItem {
-                        label: format!("g={}", name),
-                        kind: Some(CompletionItemKind::SNIPPET),
-                        detail: Some(format!("(global mut) {}", global.var_type)),
-                        insert_text_format: Some(InsertTextFormat::SNIPPET),
-                        insert_text: Some(insert_text.clone()),
-                        ..Default::default()
-                    });
-                }
-            }
+    if settings.emmet {
+        if let Some(expansions) = emmet_completions(line_prefix, symbols, position) {
+            return finish(expansions, settings);
         }
-        return completions;
     }
 
     // Type-prefixed instruction completion (e.g., i32., f64.)
@@ -467,6 +362,241 @@ pub fn provide_completion(
     completions
 }
 
+/// Emmet-like expansions, or None when the line doesn't end in an abbreviation
+fn emmet_completions(
+    line_prefix: &str,
+    symbols: &SymbolTable,
+    position: Position,
+) -> Option<Vec<CompletionItem>> {
+    let mut completions = Vec::new();
+
+    // Emmet-like number constant expansion (e.g., 5i32 -> (i32.const 5))
+    if let Some(caps) = NUMBER_CONST_REGEX.captures(line_prefix) {
+        let number = caps.get(1).unwrap().as_str();
+        let type_str = caps.get(2).unwrap().as_str();
+        let clean_number = number.replace('_', "");
+        let insert_text = format!("({}.const {})", type_str, clean_number);
+
+        completions.push(CompletionItem {
+            label: format!("{}{}", number, type_str),
+            kind: Some(CompletionItemKind::SNIPPET),
+            detail: Some(format!("Expand to: {}", insert_text)),
+            insert_text: Some(insert_text),
+            ..Default::default()
+        });
+        return Some(completions);
+    }
+
+    // Emmet-like local.get expansion (e.g., l$var -> (local.get $var))
+    if line_prefix.ends_with("l$") {
+        if let Some(func) = find_containing_function(symbols, position.into()) {
+            for param in &func.parameters {
+                if let Some(ref name) = param.name {
+                    let insert_text = format!("(local.get {})", name);
+                    completions.push(CompletionItem {
+                        label: format!("l{}", name),
+                        kind: Some(CompletionItemKind::SNIPPET),
+                        detail: Some(format!("(param) {}", param.param_type)),
+                        insert_text: Some(insert_text.clone()),
+                        documentation: Some(Documentation::String(format!(
+                            "Expands to: {}",
+                            insert_text
+                        ))),
+                        ..Default::default()
+                    });
+                }
+            }
+            for local in &func.locals {
+                if let Some(ref name) = local.name {
+                    let insert_text = format!("(local.get {})", name);
+                    completions.push(CompletionItem {
+                        label: format!("l{}", name),
+                        kind: Some(CompletionItemKind::SNIPPET),
+                        detail: Some(format!("(local) {}", local.var_type)),
+                        insert_text: Some(insert_text.clone()),
+                        documentation: Some(Documentation::String(format!(
+                            "Expands to: {}",
+                            insert_text
+                        ))),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        return Some(completions);
+    }
+
+    // Emmet-like local.set expansion (e.g., l=$var -> (local.set $var ))
+    if line_prefix.ends_with("l=$") {
+        if let Some(func) = find_containing_function(symbols, position.into()) {
+            for param in &func.parameters {
+                if let Some(ref name) = param.name {
+                    let insert_text = format!("(local.set {} $0)", name);
+                    completions.push(CompletionItem {
+                        label: format!("l={}", name),
+                        kind: Some(CompletionItemKind::SNIPPET),
+                        detail: Some(format!("(param) {}", param.param_type)),
+                        insert_text_format: Some(InsertTextFormat::SNIPPET),
+                        insert_text: Some(insert_text.clone()),
+                        ..Default::default()
+                    });
+                }
+            }
+            for local in &func.locals {
+                if let Some(ref name) = local.name {
+                    let insert_text = format!("(local.set {} $0)", name);
+                    completions.push(CompletionItem {
+                        label: format!("l={}", name),
+                        kind: Some(CompletionItemKind::SNIPPET),
+                        detail: Some(format!("(local) {}", local.var_type)),
+                        insert_text_format: Some(InsertTextFormat::SNIPPET),
+                        insert_text: Some(insert_text.clone()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        return Some(completions);
+    }
+
+    // Emmet-like global.get expansion (e.g., g$var -> (global.get $var))
+    if line_prefix.ends_with("g$") {
+        for global in &symbols.globals {
+            if let Some(ref name) = global.name {
+                let insert_text = format!("(global.get {})", name);
+                completions.push(CompletionItem {
+                    label: format!("g{}", name),
+                    kind: Some(CompletionItemKind::SNIPPET),
+                    detail: Some(format!("(global) {}", global.var_type)),
+                    insert_text: Some(insert_text.clone()),
+                    documentation: Some(Documentation::String(format!(
+                        "Expands to: {}",
+                        insert_text
+                    ))),
+                    ..Default::default()
+                });
+            }
+        }
+        return Some(completions);
+    }
+
+    // Emmet-like global.set expansion (e.g., g=$var -> (global.set $var ))
+    if line_prefix.ends_with("g=$") {
+        for global in &symbols.globals {
+            if let Some(ref name) = global.name {
+                if global.is_mutable {
+                    let insert_text = format!("(global.set {} $0)", name);
+                    completions.push(CompletionItem {
+                        label: format!("g={}", name),
+                        kind: Some(CompletionItemKind::SNIPPET),
+                        detail: Some(format!("(global mut) {}", global.var_type)),
+                        insert_text_format: Some(InsertTextFormat::SNIPPET),
+                        insert_text: Some(insert_text.clone()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        return Some(completions);
+    }
+
+    None
+}
+
+/// Completion of the module and field names of an import, from the items
+/// the host and linked modules provide. Picking a field fills in the
+/// import descriptor after it. `None` when the cursor isn't in the name
+/// strings of an import.
+pub fn provide_import_completions(
+    document: &str,
+    position: Position,
+    importable: &[ImportDescription],
+) -> Option<Vec<CompletionItem>> {
+    let line = get_line_at_position(document, position.line as usize)?;
+    let cursor = (position.character as usize).min(line.len());
+    let line_prefix = line.get(..cursor)?;
+    // A quote the client closed automatically is replaced with the name
+    let closing = u32::from(line[cursor..].starts_with('"'));
+
+    if let Some(captures) = IMPORT_FIELD_REGEX.captures(line_prefix) {
+        let module = &captures[1];
+        let range = name_range(position, captures[2].len(), closing);
+        let items = importable
+            .iter()
+            .filter(|item| item.module == module)
+            .map(|item| CompletionItem {
+                label: item.field.clone(),
+                kind: Some(match item.ty.kind() {
+                    ExternKind::Func => CompletionItemKind::FUNCTION,
+                    ExternKind::Global => CompletionItemKind::VARIABLE,
+                    _ => CompletionItemKind::VALUE,
+                }),
+                detail: Some(item.ty.to_string()),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: format!("{}\" {}", item.field, item.descriptor()),
+                })),
+                ..Default::default()
+            })
+            .collect();
+        return Some(items);
+    }
+
+    let captures = IMPORT_MODULE_REGEX.captures(line_prefix)?;
+    let range = name_range(position, captures[1].len(), closing);
+    let mut modules: Vec<&str> = importable.iter().map(|item| item.module.as_str()).collect();
+    modules.sort_unstable();
+    modules.dedup();
+    let items = modules
+        .into_iter()
+        .map(|module| CompletionItem {
+            label: module.to_string(),
+            kind: Some(CompletionItemKind::MODULE),
+            text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                range,
+                new_text: format!("{}\"", module),
+            })),
+            ..Default::default()
+        })
+        .collect();
+    Some(items)
+}
+
+/// Range of the `typed` bytes before the cursor and `closing` quote after it
+fn name_range(position: Position, typed: usize, closing: u32) -> Range {
+    Range {
+        start: Position::new(
+            position.line,
+            position.character.saturating_sub(typed as u32),
+        ),
+        end: Position::new(position.line, position.character + closing),
+    }
+}
+
+/// Turn snippets into plain text when the client shouldn't get tab stops
+fn finish(
+    mut completions: Vec<CompletionItem>,
+    settings: &CompletionSettings,
+) -> Vec<CompletionItem> {
+    if !settings.snippets {
+        for item in &mut completions {
+            if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+                item.insert_text = item.insert_text.as_deref().map(strip_tab_stops);
+                item.insert_text_format = Some(InsertTextFormat::PLAIN_TEXT);
+            }
+        }
+    }
+    completions
+}
+
+/// Remove `$0` and `${1:text}` tab stops, keeping placeholder text and `$name` identifiers
+fn strip_tab_stops(snippet: &str) -> String {
+    let without_placeholders = PLACEHOLDER_REGEX.replace_all(snippet, "$text");
+    TAB_STOP_REGEX
+        .replace_all(&without_placeholders, "")
+        .into_owned()
+}
+
 fn get_type_completions(type_prefix: &str) -> Vec<CompletionItem> {
     let mut completions = Vec::new();
 
@@ -601,5 +731,16 @@ fn make_completion(label: &str, detail: &str) -> CompletionItem {
     }
 }
 
+static IMPORT_MODULE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\(import\s+"([^"]*)$"#).unwrap());
+
+static IMPORT_FIELD_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\(import\s+"([^"]*)"\s+"([^"]*)$"#).unwrap());
+
 static NUMBER_CONST_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"([\d._]+)((?:i|f)(?:32|64))$").unwrap());
+
+static PLACEHOLDER_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$\{\d+:(?P<text>[^}]*)\}").unwrap());
+
+static TAB_STOP_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\$(?:\d+|\{\d+\})").unwrap());
@@ -31,6 +31,7 @@ fn create_test_symbols() -> SymbolTable {
             },
         ],
         results: vec![ValueType::I32],
+        type_use: None,
         locals: vec![Variable {
             name: Some("$temp".to_string()),
             var_type: ValueType::I32,
@@ -55,6 +56,7 @@ fn create_test_symbols() -> SymbolTable {
         is_mutable: true,
         initial_value: Some("0".to_string()),
         line: 0,
+        start_byte: 0,
         range: None,
     };
     table.add_global(global);
@@ -478,3 +480,104 @@ fn test_new_keyword_completions() {
     assert!(keywords.iter().any(|c| c.label == "throw"));
     assert!(keywords.iter().any(|c| c.label == "try_table"));
 }
+
+#[test]
+fn test_emmet_disabled_by_settings() {
+    let document = "5i32";
+    let symbols = create_test_symbols();
+    let settings = CompletionSettings {
+        emmet: false,
+        ..Default::default()
+    };
+
+    let completions = provide_completion_with_settings(
+        document,
+        &symbols,
+        &create_test_tree(document),
+        Position::new(0, 4),
+        &settings,
+    );
+    assert!(!completions.iter().any(|c| c
+        .insert_text
+        .as_deref()
+        .is_some_and(|text| text.contains("i32.const 5"))));
+}
+
+#[test]
+fn test_snippets_disabled_by_settings() {
+    let document = "(func $test (param $x i32)\n  l=$";
+    let symbols = parser::parse_document(document).unwrap();
+    let settings = CompletionSettings {
+        snippets: false,
+        ..Default::default()
+    };
+
+    let completions = provide_completion_with_settings(
+        document,
+        &symbols,
+        &create_test_tree(document),
+        Position::new(1, 5),
+        &settings,
+    );
+    assert!(completions
+        .iter()
+        .all(|c| c.insert_text_format != Some(InsertTextFormat::SNIPPET)));
+    if let Some(set) = completions.iter().find(|c| c.label == "l=$x") {
+        assert_eq!(set.insert_text.as_deref(), Some("(local.set $x )"));
+    }
+}
+
+#[test]
+fn test_strip_tab_stops() {
+    assert_eq!(strip_tab_stops("(call $f ${1:arg})$0"), "(call $f arg)");
+    assert_eq!(strip_tab_stops("(block $1 ${2})"), "(block  )");
+}
+
+#[test]
+fn test_import_completions() {
+    let host = crate::host::HostInterface::parse(
+        r#"
+[[imports]]
+module = "env"
+field = "log"
+signature = "(param i32 i32)"
+
+[[imports]]
+module = "env"
+field = "memory"
+kind = "memory"
+signature = "1"
+
+[[imports]]
+module = "wasi"
+field = "proc_exit"
+signature = "(param i32)"
+"#,
+        crate::host::DescriptionFormat::Toml,
+    )
+    .unwrap();
+
+    let document = "(module\n  (import \"e\")";
+    let items = provide_import_completions(document, Position::new(1, 12), host.imports()).unwrap();
+    let labels: Vec<_> = items.iter().map(|i| i.label.as_str()).collect();
+    assert_eq!(labels, vec!["env", "wasi"]);
+    let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+        panic!("expected a text edit");
+    };
+    assert_eq!(edit.new_text, "env\"");
+    assert_eq!(edit.range.start, Position::new(1, 11));
+    assert_eq!(edit.range.end, Position::new(1, 13));
+
+    let document = "(module\n  (import \"env\" \"l";
+    let items = provide_import_completions(document, Position::new(1, 18), host.imports()).unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].kind, Some(CompletionItemKind::FUNCTION));
+    let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+        panic!("expected a text edit");
+    };
+    assert_eq!(edit.new_text, "log\" (func $log (param i32 i32))");
+    assert_eq!(edit.range.start, Position::new(1, 17));
+
+    let document = "(module\n  (func $f";
+    assert!(provide_import_completions(document, Position::new(1, 10), host.imports()).is_none());
+}
@@ -29,8 +29,28 @@ pub fn provide_definition(
     position: Position,
     uri: &str,
 ) -> Option<Location> {
+    // The export name of a .wast command, e.g. "add" in (invoke "add" ...)
+    if let Some(command) = symbols.command_name_at(position.into()) {
+        let (_, export) = symbols.command_export(command)?;
+        return Some(range_to_location(export.range?, &Url::parse(uri).ok()?));
+    }
+    // Component-level items of a component, e.g. $main in (instantiate $main)
+    if let Some(component) = &symbols.component {
+        if component.core_module_at(position.into()).is_none() {
+            let item = component.definition_at(position.into())?;
+            let range = item.name_range.unwrap_or(item.range);
+            return Some(range_to_location(range, &Url::parse(uri).ok()?));
+        }
+    }
+
     let word = get_word_at_position(document, position.into())?;
 
+    // The module a .wast command names, e.g. $m in (invoke $m "add")
+    if let Some(location) = provide_module_definition(&word, symbols, position, uri) {
+        return Some(location);
+    }
+    let symbols = symbols.scope_at(position.into());
+
     // Check if it's a symbol reference (starts with $)
     if word.starts_with('$') {
         let result = provide_symbol_definition(&word, symbols, document, tree, position, uri);
@@ -54,6 +74,21 @@ pub fn provide_definition(
     None
 }
 
+/// Location of the script module a command names with `$id`
+fn provide_module_definition(
+    word: &str,
+    symbols: &SymbolTable,
+    position: Position,
+    uri: &str,
+) -> Option<Location> {
+    let command = symbols
+        .commands
+        .iter()
+        .find(|c| c.range.contains(position.into()) && c.module_name.as_deref() == Some(word))?;
+    let module = symbols.modules.get(command.module?)?;
+    Some(range_to_location(module.range, &Url::parse(uri).ok()?))
+}
+
 /// Check if cursor is on a definition and return that location
 fn provide_definition_at_cursor(
     word: &str,
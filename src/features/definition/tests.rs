@@ -744,3 +744,37 @@ fn test_goto_definition_export_with_array_in_name() {
         "$lookup_table definition should be on line 7"
     );
 }
+
+#[test]
+fn test_wast_definition_scoped_to_module() {
+    let document = r#"(module $a
+  (func $f (export "run"))
+  (func (call $f)))
+(module $b
+  (func $f)
+  (func (call $f)))
+(invoke $a "run")"#;
+
+    let symbols = parse_document(document).unwrap();
+    let tree = create_test_tree(document);
+    let uri = create_uri();
+
+    // $f in the second module resolves within that module
+    let location = provide_definition(document, &symbols, &tree, Position::new(5, 15), &uri)
+        .expect("definition in second module");
+    assert_eq!(location.range.start.line, 4);
+
+    let location = provide_definition(document, &symbols, &tree, Position::new(2, 15), &uri)
+        .expect("definition in first module");
+    assert_eq!(location.range.start.line, 1);
+
+    // The quoted name of an invoke goes to the export
+    let location = provide_definition(document, &symbols, &tree, Position::new(6, 13), &uri)
+        .expect("definition of invoked export");
+    assert_eq!(location.range.start.line, 1);
+
+    // The module id of an invoke goes to the module
+    let location = provide_definition(document, &symbols, &tree, Position::new(6, 9), &uri)
+        .expect("definition of invoked module");
+    assert_eq!(location.range.start.line, 0);
+}
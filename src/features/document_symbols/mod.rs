@@ -0,0 +1,300 @@
+use crate::component::{ComponentSymbols, Sort};
+use crate::features::type_definition::type_declaration_range;
+use crate::symbols::*;
+use crate::utils::node_to_lsp_range;
+use tower_lsp::lsp_types::*;
+use tree_sitter::{Node, Tree};
+
+#[cfg(test)]
+mod tests;
+
+/// Provide the document outline: every module-level declaration, in source order.
+///
+/// The detail of functions, globals, tables, memories and tags also says where
+/// the item is exported as and imported from.
+pub fn provide_document_symbols(symbols: &SymbolTable, tree: &Tree) -> Vec<DocumentSymbol> {
+    if let Some(component) = &symbols.component {
+        return component_symbols(component, 0, symbols, tree);
+    }
+
+    let fields = collect_module_fields(tree);
+    let mut result = Vec::new();
+
+    for func in &symbols.functions {
+        let types: Vec<String> = func
+            .parameters
+            .iter()
+            .map(|p| p.param_type.to_string())
+            .collect();
+        let detail = signature_detail(&types, &func.results);
+        result.push(document_symbol(
+            func.name.clone(),
+            format!("func {}", func.index),
+            SymbolKind::FUNCTION,
+            with_linkage(detail, symbols, ExternKind::Func, func.index),
+            declaration_range(&fields, "module_field_func", func.line, func.range),
+            func.range,
+        ));
+    }
+
+    for global in &symbols.globals {
+        let detail = if global.is_mutable {
+            format!("mut {}", global.var_type)
+        } else {
+            global.var_type.to_string()
+        };
+        result.push(document_symbol(
+            global.name.clone(),
+            format!("global {}", global.index),
+            if global.is_mutable {
+                SymbolKind::VARIABLE
+            } else {
+                SymbolKind::CONSTANT
+            },
+            with_linkage(detail, symbols, ExternKind::Global, global.index),
+            declaration_range(&fields, "module_field_global", global.line, global.range),
+            global.range,
+        ));
+    }
+
+    for type_def in &symbols.types {
+        let (kind, detail) = match &type_def.kind {
+            TypeKind::Func { params, results } => {
+                let types: Vec<String> = params.iter().map(|t| t.to_string()).collect();
+                (
+                    SymbolKind::INTERFACE,
+                    format!("func {}", signature_detail(&types, results)),
+                )
+            }
+            TypeKind::Struct { fields } => (
+                SymbolKind::STRUCT,
+                format!("struct, {} fields", fields.len()),
+            ),
+            TypeKind::Array { element_type, .. } => {
+                (SymbolKind::ARRAY, format!("array of {}", element_type))
+            }
+        };
+        result.push(document_symbol(
+            type_def.name.clone(),
+            format!("type {}", type_def.index),
+            kind,
+            detail.trim_end().to_string(),
+            type_declaration_range(type_def, tree.root_node())
+                .or(type_def.range.map(Into::into))
+                .unwrap_or_default(),
+            type_def.range,
+        ));
+    }
+
+    for table in &symbols.tables {
+        result.push(document_symbol(
+            table.name.clone(),
+            format!("table {}", table.index),
+            SymbolKind::ARRAY,
+            with_linkage(
+                table.ref_type.to_string(),
+                symbols,
+                ExternKind::Table,
+                table.index,
+            ),
+            declaration_range(&fields, "module_field_table", table.line, table.range),
+            table.range,
+        ));
+    }
+
+    for memory in &symbols.memories {
+        let detail = match memory.limits.1 {
+            Some(max) => format!("{} {}", memory.limits.0, max),
+            None => memory.limits.0.to_string(),
+        };
+        result.push(document_symbol(
+            memory.name.clone(),
+            format!("memory {}", memory.index),
+            SymbolKind::OBJECT,
+            with_linkage(detail, symbols, ExternKind::Memory, memory.index),
+            declaration_range(&fields, "module_field_memory", memory.line, memory.range),
+            memory.range,
+        ));
+    }
+
+    for tag in &symbols.tags {
+        let types: Vec<String> = tag.params.iter().map(|t| t.to_string()).collect();
+        result.push(document_symbol(
+            tag.name.clone(),
+            format!("tag {}", tag.index),
+            SymbolKind::EVENT,
+            with_linkage(
+                signature_detail(&types, &[]),
+                symbols,
+                ExternKind::Tag,
+                tag.index,
+            ),
+            declaration_range(&fields, "module_field_tag", tag.line, tag.range),
+            tag.range,
+        ));
+    }
+
+    for data in &symbols.data_segments {
+        result.push(document_symbol(
+            data.name.clone(),
+            format!("data {}", data.index),
+            SymbolKind::CONSTANT,
+            format!("{} bytes", data.byte_length),
+            declaration_range(&fields, "module_field_data", data.line, data.range),
+            data.range,
+        ));
+    }
+
+    for elem in &symbols.elem_segments {
+        result.push(document_symbol(
+            elem.name.clone(),
+            format!("elem {}", elem.index),
+            SymbolKind::ARRAY,
+            format!("{} functions", elem.func_names.len()),
+            declaration_range(&fields, "module_field_elem", elem.line, elem.range),
+            elem.range,
+        ));
+    }
+
+    result.sort_by_key(|symbol| (symbol.range.start.line, symbol.range.start.character));
+    result
+}
+
+/// Outline of a component's items, with the fields of each core module and
+/// the items of each nested component as children
+fn component_symbols(
+    component: &ComponentSymbols,
+    scope: usize,
+    symbols: &SymbolTable,
+    tree: &Tree,
+) -> Vec<DocumentSymbol> {
+    component
+        .items_in(scope)
+        .map(|item| {
+            let kind = match item.sort {
+                Sort::CoreModule | Sort::Component => SymbolKind::MODULE,
+                Sort::CoreInstance | Sort::Instance => SymbolKind::OBJECT,
+                Sort::CoreFunc | Sort::Func => SymbolKind::FUNCTION,
+                Sort::CoreType | Sort::Type => SymbolKind::INTERFACE,
+                Sort::CoreTable | Sort::CoreMemory => SymbolKind::ARRAY,
+                Sort::CoreGlobal => SymbolKind::VARIABLE,
+                Sort::Value => SymbolKind::CONSTANT,
+            };
+            let detail = if item.detail.is_empty() {
+                item.sort.to_string()
+            } else {
+                format!("{} · {}", item.sort, item.detail)
+            };
+            let children = match item.sort {
+                Sort::CoreModule => symbols
+                    .module_at(item.range.start)
+                    .map(|module| provide_document_symbols(&module.symbols, tree)),
+                Sort::Component => item
+                    .nested
+                    .map(|nested| component_symbols(component, nested, symbols, tree)),
+                _ => None,
+            };
+            DocumentSymbol {
+                children: children.filter(|children| !children.is_empty()),
+                ..document_symbol(
+                    Some(item.label()),
+                    String::new(),
+                    kind,
+                    detail,
+                    item.range.into(),
+                    item.name_range,
+                )
+            }
+        })
+        .collect()
+}
+
+#[allow(deprecated)] // `deprecated` is superseded by `tags` but still a required field
+fn document_symbol(
+    name: Option<String>,
+    fallback_name: String,
+    kind: SymbolKind,
+    detail: String,
+    range: Range,
+    selection_range: Option<crate::core::types::Range>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name: name.unwrap_or(fallback_name),
+        detail: (!detail.is_empty()).then_some(detail),
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: selection_range.map(Into::into).unwrap_or(range),
+        children: None,
+    }
+}
+
+/// Format parameter and result types as `(param i32 i32) (result i32)`
+fn signature_detail(params: &[String], results: &[ValueType]) -> String {
+    let mut detail = Vec::new();
+    if !params.is_empty() {
+        detail.push(format!("(param {})", params.join(" ")));
+    }
+    if !results.is_empty() {
+        let results: Vec<String> = results.iter().map(|t| t.to_string()).collect();
+        detail.push(format!("(result {})", results.join(" ")));
+    }
+    detail.join(" ")
+}
+
+/// Append where an item is exported as and imported from, if anywhere
+fn with_linkage(detail: String, symbols: &SymbolTable, kind: ExternKind, index: usize) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if !detail.is_empty() {
+        parts.push(detail);
+    }
+    for export in symbols.exports_of(kind, index) {
+        parts.push(format!("exported as \"{}\"", export.name));
+    }
+    if let Some(import) = symbols.import_of(kind, index) {
+        parts.push(format!("imported from {}", import.qualified_name()));
+    }
+    parts.join(" · ")
+}
+
+/// Top-level module fields, e.g. `module_field_func` and `module_field_import` nodes
+fn collect_module_fields(tree: &Tree) -> Vec<Node<'_>> {
+    let mut fields = Vec::new();
+    let mut stack = vec![tree.root_node()];
+
+    while let Some(node) = stack.pop() {
+        if matches!(node.kind(), "ROOT" | "module" | "module_field") {
+            let mut cursor = node.walk();
+            stack.extend(node.children(&mut cursor));
+        } else if node.kind().starts_with("module_field_") {
+            fields.push(node);
+        }
+    }
+
+    fields
+}
+
+/// Range of the field declaring an item on `line`, including imports of that item
+fn declaration_range(
+    fields: &[Node],
+    field_kind: &str,
+    line: u32,
+    name_range: Option<crate::core::types::Range>,
+) -> Range {
+    let declared_on_line = |node: &&Node| {
+        (node.kind() == field_kind || node.kind() == "module_field_import")
+            && node.start_position().row as u32 <= line
+            && line <= node.end_position().row as u32
+    };
+
+    fields
+        .iter()
+        .find(declared_on_line)
+        .map(node_to_lsp_range)
+        .or_else(|| name_range.map(Into::into))
+        .unwrap_or(Range {
+            start: Position::new(line, 0),
+            end: Position::new(line, 0),
+        })
+}
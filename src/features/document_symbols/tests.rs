@@ -0,0 +1,83 @@
+use super::*;
+use crate::parser::parse_document;
+use crate::tree_sitter_bindings::create_parser;
+
+fn create_test_tree(document: &str) -> Tree {
+    let mut parser = create_parser();
+    parser
+        .parse(document, None)
+        .expect("Failed to parse test document")
+}
+
+const MODULE: &str = r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (type $pair (struct (field i32) (field i32)))
+  (global $counter (mut i32) (i32.const 0))
+  (memory (export "memory") 1)
+  (func $run (export "run") (param $n i32) (result i32)
+    local.get $n
+  )
+)"#;
+
+fn outline(document: &str) -> Vec<DocumentSymbol> {
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+    provide_document_symbols(&symbols, &tree)
+}
+
+#[test]
+fn test_document_symbols_in_source_order() {
+    let names: Vec<String> = outline(MODULE).into_iter().map(|s| s.name).collect();
+    assert_eq!(names, vec!["$log", "$pair", "$counter", "memory 0", "$run"]);
+}
+
+#[test]
+fn test_document_symbol_details_show_linkage() {
+    let symbols = outline(MODULE);
+
+    assert_eq!(
+        symbols[0].detail.as_deref(),
+        Some("(param i32) · imported from env.log")
+    );
+    assert_eq!(symbols[2].detail.as_deref(), Some("mut i32"));
+    assert_eq!(
+        symbols[3].detail.as_deref(),
+        Some("1 · exported as \"memory\"")
+    );
+    assert_eq!(
+        symbols[4].detail.as_deref(),
+        Some("(param i32) (result i32) · exported as \"run\"")
+    );
+}
+
+#[test]
+fn test_document_symbol_ranges() {
+    let symbols = outline(MODULE);
+    let run = &symbols[4];
+
+    assert_eq!(run.kind, SymbolKind::FUNCTION);
+    assert_eq!(run.range.start.line, 5);
+    assert_eq!(run.range.end.line, 7);
+    assert_eq!(run.selection_range.start, Position::new(5, 8));
+}
+
+#[test]
+fn test_component_outline() {
+    let document = r#"(component
+  (import "wasi:cli/env" (instance $env))
+  (core module $main
+    (func $run (export "run") (result i32)
+      i32.const 0))
+  (core instance $inst (instantiate $main))
+  (func $run (export "run") (result u32)
+    (canon lift (core func $inst "run")))
+)"#;
+    let symbols = outline(document);
+    let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["$env", "$main", "$inst", "$run"]);
+
+    assert_eq!(symbols[1].kind, SymbolKind::MODULE);
+    let fields = symbols[1].children.as_ref().expect("core module fields");
+    assert_eq!(fields[0].name, "$run");
+    assert_eq!(symbols[3].kind, SymbolKind::FUNCTION);
+}
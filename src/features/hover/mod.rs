@@ -35,6 +35,18 @@ pub fn provide_hover_core(
         return None;
     }
 
+    // The export name of a .wast command, e.g. "add" in (invoke "add" ...)
+    if let Some(command) = symbols.command_name_at(position) {
+        return provide_command_hover(command, symbols);
+    }
+    // Component-level items of a component; its core modules are hovered below
+    if let Some(component) = &symbols.component {
+        if component.core_module_at(position).is_none() {
+            return component.hover(document, position);
+        }
+    }
+    let symbols = symbols.scope_at(position);
+
     let word = get_word_at_position(document, position)?;
 
     // Check if it's an instruction
@@ -93,14 +105,14 @@ fn provide_symbol_hover(
     // Check for function
     if context == InstructionContext::Call || context == InstructionContext::Function {
         if let Some(func) = symbols.get_function_by_name(word) {
-            return Some(format_function_hover(func));
+            return Some(format_function_hover(func, symbols));
         }
     }
 
     // Check for global (both usage and declaration)
     if context == InstructionContext::Global {
         if let Some(global) = symbols.get_global_by_name(word) {
-            return Some(format_global_hover(word, global));
+            return Some(format_global_hover(word, global, symbols));
         }
     }
 
@@ -128,21 +140,21 @@ fn provide_symbol_hover(
     // Check for table (including call_indirect context)
     if context == InstructionContext::Table || context == InstructionContext::Call {
         if let Some(table) = symbols.get_table_by_name(word) {
-            return Some(format_table_hover(word, table));
+            return Some(format_table_hover(word, table, symbols));
         }
     }
 
     // Check for memory (both declaration and references)
     if context == InstructionContext::Memory || context == InstructionContext::General {
         if let Some(memory) = symbols.get_memory_by_name(word) {
-            return Some(format_memory_hover(word, memory));
+            return Some(format_memory_hover(word, memory, symbols));
         }
     }
 
     // Check for type
     if context == InstructionContext::Type {
         if let Some(type_def) = symbols.get_type_by_name(word) {
-            return Some(format_type_hover(word, type_def));
+            return Some(format_type_hover(word, type_def, symbols));
         }
 
         // Check for struct field reference in Type context (struct.get/set $type $field)
@@ -154,7 +166,7 @@ fn provide_symbol_hover(
     // Check for tag (throw, catch, tag definition)
     if context == InstructionContext::Tag {
         if let Some(tag) = symbols.get_tag_by_name(word) {
-            return Some(format_tag_hover(word, tag));
+            return Some(format_tag_hover(word, tag, symbols));
         }
     }
 
@@ -231,22 +243,22 @@ fn try_all_symbol_types(
 ) -> Option<HoverResult> {
     // Try function
     if let Some(func) = symbols.get_function_by_name(word) {
-        return Some(format_function_hover(func));
+        return Some(format_function_hover(func, symbols));
     }
 
     // Try global
     if let Some(global) = symbols.get_global_by_name(word) {
-        return Some(format_global_hover(word, global));
+        return Some(format_global_hover(word, global, symbols));
     }
 
     // Try table
     if let Some(table) = symbols.get_table_by_name(word) {
-        return Some(format_table_hover(word, table));
+        return Some(format_table_hover(word, table, symbols));
     }
 
     // Try type
     if let Some(type_def) = symbols.get_type_by_name(word) {
-        return Some(format_type_hover(word, type_def));
+        return Some(format_type_hover(word, type_def, symbols));
     }
 
     // Try local/param in containing function
@@ -265,12 +277,12 @@ fn try_all_symbol_types(
 
     // Try memory
     if let Some(memory) = symbols.get_memory_by_name(word) {
-        return Some(format_memory_hover(word, memory));
+        return Some(format_memory_hover(word, memory, symbols));
     }
 
     // Try tag
     if let Some(tag) = symbols.get_tag_by_name(word) {
-        return Some(format_tag_hover(word, tag));
+        return Some(format_tag_hover(word, tag, symbols));
     }
 
     // Try data segment
@@ -385,10 +397,7 @@ fn provide_index_hover(
 
     if context == InstructionContext::Call {
         if let Some(func) = symbols.get_function_by_index(index) {
-            return Some(HoverResult::new(format!(
-                "```wat\n{}\n```",
-                format_function_signature(func)
-            )));
+            return Some(format_function_hover(func, symbols));
         }
     }
 
@@ -433,11 +442,39 @@ fn provide_index_hover(
 // Hover Formatters - shared formatting functions to avoid duplication
 // ============================================================================
 
-fn format_function_hover(func: &Function) -> HoverResult {
-    HoverResult::new(format!("```wat\n{}\n```", format_function_signature(func)))
+/// Hover for the export a script command names, shown as the exported item
+fn provide_command_hover(command: &ScriptCommand, symbols: &SymbolTable) -> Option<HoverResult> {
+    let (module, export) = symbols.command_export(command)?;
+    let items = &module.symbols;
+    let mut hover = match export.kind {
+        ExternKind::Func => {
+            format_function_hover(items.get_function_by_index(export.index)?, items)
+        }
+        ExternKind::Global => {
+            let global = items.get_global_by_index(export.index)?;
+            let name = global
+                .name
+                .clone()
+                .unwrap_or_else(|| export.index.to_string());
+            format_global_hover(&name, global, items)
+        }
+        _ => HoverResult::new(format!(
+            "```wat\n(export \"{}\" ({} {}))\n```",
+            export.name, export.kind, export.index
+        )),
+    };
+    if let Some(name) = &module.name {
+        hover.contents.push_str(&format!("\n\nModule `{}`", name));
+    }
+    Some(hover)
+}
+
+fn format_function_hover(func: &Function, symbols: &SymbolTable) -> HoverResult {
+    let content = format!("```wat\n{}\n```", format_function_signature(func));
+    HoverResult::new(with_linkage(content, symbols, ExternKind::Func, func.index))
 }
 
-fn format_global_hover(word: &str, global: &Global) -> HoverResult {
+fn format_global_hover(word: &str, global: &Global, symbols: &SymbolTable) -> HoverResult {
     let mut info = format!(
         "```wat\n(global {} {}{})\n```",
         word,
@@ -447,7 +484,12 @@ fn format_global_hover(word: &str, global: &Global) -> HoverResult {
     if let Some(ref val) = global.initial_value {
         info.push_str(&format!("\n\nInitial value: `{}`", val));
     }
-    HoverResult::new(info)
+    HoverResult::new(with_linkage(
+        info,
+        symbols,
+        ExternKind::Global,
+        global.index,
+    ))
 }
 
 fn format_param_hover(word: &str, param: &Parameter) -> HoverResult {
@@ -470,33 +512,90 @@ fn format_block_hover(block: &BlockLabel) -> HoverResult {
     ))
 }
 
-fn format_table_hover(word: &str, table: &Table) -> HoverResult {
+fn format_table_hover(word: &str, table: &Table, symbols: &SymbolTable) -> HoverResult {
     let limits_str = match table.limits.1 {
         Some(max) => format!("{} {}", table.limits.0, max),
         None => table.limits.0.to_string(),
     };
-    HoverResult::new(format!(
+    let content = format!(
         "```wat\n(table {} {} {})\n```",
         word, limits_str, table.ref_type
+    );
+    HoverResult::new(with_linkage(
+        content,
+        symbols,
+        ExternKind::Table,
+        table.index,
     ))
 }
 
-fn format_memory_hover(word: &str, memory: &Memory) -> HoverResult {
+fn format_memory_hover(word: &str, memory: &Memory, symbols: &SymbolTable) -> HoverResult {
     let limits_str = match memory.limits.1 {
         Some(max) => format!("{} {}", memory.limits.0, max),
         None => memory.limits.0.to_string(),
     };
-    HoverResult::new(format!("```wat\n(memory {} {})\n```", word, limits_str))
+    let content = format!("```wat\n(memory {} {})\n```", word, limits_str);
+    HoverResult::new(with_linkage(
+        content,
+        symbols,
+        ExternKind::Memory,
+        memory.index,
+    ))
 }
 
-fn format_type_hover(word: &str, type_def: &TypeDef) -> HoverResult {
-    HoverResult::new(format!(
-        "```wat\n{}\n```",
-        format_type_signature(word, type_def)
-    ))
+fn format_type_hover(word: &str, type_def: &TypeDef, symbols: &SymbolTable) -> HoverResult {
+    let mut content = format!("```wat\n{}\n```", format_type_signature(word, type_def));
+
+    // Supertype chain, e.g. $circle <: $shape <: $base
+    let mut chain = vec![word.to_string()];
+    let mut current = type_def;
+    while let Some(parent) = current
+        .supertype
+        .and_then(|idx| symbols.get_type_by_index(idx as usize))
+    {
+        // Guard against malformed cycles
+        if chain.len() > symbols.types.len() {
+            break;
+        }
+        chain.push(type_display_name(parent));
+        current = parent;
+    }
+    if chain.len() > 1 {
+        content.push_str(&format!("\n\nSupertypes: {}", chain.join(" <: ")));
+    }
+
+    if type_def.supertype.is_some() || !type_def.is_final {
+        content.push_str(if type_def.is_final {
+            "\n\nFinal: cannot be subtyped"
+        } else {
+            "\n\nOpen: can be subtyped"
+        });
+    }
+
+    if let Some(group) = type_def.rec_group_id {
+        let siblings: Vec<String> = symbols
+            .types
+            .iter()
+            .filter(|t| t.rec_group_id == Some(group) && t.index != type_def.index)
+            .map(type_display_name)
+            .collect();
+        if !siblings.is_empty() {
+            content.push_str(&format!("\n\nRec group: {}", siblings.join(", ")));
+        }
+    }
+
+    HoverResult::new(content)
+}
+
+/// Name of a type for display, falling back to its index
+fn type_display_name(type_def: &TypeDef) -> String {
+    type_def
+        .name
+        .clone()
+        .unwrap_or_else(|| type_def.index.to_string())
 }
 
-fn format_tag_hover(word: &str, tag: &Tag) -> HoverResult {
+fn format_tag_hover(word: &str, tag: &Tag, symbols: &SymbolTable) -> HoverResult {
     let params_str = if tag.params.is_empty() {
         String::new()
     } else {
@@ -509,7 +608,24 @@ fn format_tag_hover(word: &str, tag: &Tag) -> HoverResult {
                 .join(" ")
         )
     };
-    HoverResult::new(format!("```wat\n(tag {}{})\n```", word, params_str))
+    let content = format!("```wat\n(tag {}{})\n```", word, params_str);
+    HoverResult::new(with_linkage(content, symbols, ExternKind::Tag, tag.index))
+}
+
+/// Append where an item is exported as and imported from, if anywhere
+fn with_linkage(
+    mut content: String,
+    symbols: &SymbolTable,
+    kind: ExternKind,
+    index: usize,
+) -> String {
+    for export in symbols.exports_of(kind, index) {
+        content.push_str(&format!("\n\nExported as `\"{}\"`", export.name));
+    }
+    if let Some(import) = symbols.import_of(kind, index) {
+        content.push_str(&format!("\n\nImported from `{}`", import.qualified_name()));
+    }
+    content
 }
 
 fn format_data_hover(word: &str, data: &DataSegment) -> HoverResult {
@@ -32,6 +32,7 @@ fn create_test_symbols() -> SymbolTable {
             },
         ],
         results: vec![ValueType::I32],
+        type_use: None,
         locals: vec![Variable {
             name: Some("$temp".to_string()),
             var_type: ValueType::I32,
@@ -62,6 +63,7 @@ fn create_test_symbols() -> SymbolTable {
         is_mutable: true,
         initial_value: Some("0".to_string()),
         line: 0,
+        start_byte: 0,
         range: None,
     };
     table.add_global(global);
@@ -73,6 +75,7 @@ fn create_test_symbols() -> SymbolTable {
         ref_type: ValueType::Funcref,
         limits: (10, None),
         line: 0,
+        start_byte: 0,
         range: None,
     };
     table.add_table(tbl);
@@ -404,6 +407,7 @@ fn test_format_function_signature() {
             },
         ],
         results: vec![ValueType::F32],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -484,3 +488,110 @@ fn test_hover_outside_comment() {
     let hover = provide_hover(document, &symbols, &tree, position.into());
     assert!(hover.is_some());
 }
+
+#[test]
+fn test_hover_on_subtype_shows_chain_and_rec_group() {
+    let document = r#"(module
+  (type $shape (sub (struct (field $x f64))))
+  (rec
+    (type $circle (sub $shape (struct (field $x f64) (field $r f64))))
+    (type $ring (sub final $circle (struct (field $x f64) (field $r f64) (field $inner f64)))))
+)"#;
+    let symbols = crate::parser::parse_document(document).unwrap();
+    let tree = create_test_tree(document);
+
+    // On "$ring"
+    let hover = provide_hover(document, &symbols, &tree, Position::new(4, 11).into())
+        .expect("Expected hover");
+
+    match hover.contents {
+        HoverContents::Markup(content) => {
+            assert!(content
+                .value
+                .contains("Supertypes: $ring <: $circle <: $shape"));
+            assert!(content.value.contains("Final: cannot be subtyped"));
+            assert!(content.value.contains("Rec group: $circle"));
+        }
+        _ => panic!("Expected Markup content"),
+    }
+}
+
+#[test]
+fn test_hover_shows_export_and_import() {
+    let document = r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (func $run (export "run")
+    i32.const 1
+    call $log
+  )
+  (export "main" (func $run))
+)"#;
+    let symbols = crate::parser::parse_document(document).unwrap();
+    let tree = create_test_tree(document);
+
+    // On "$log" in "call $log"
+    let hover = provide_hover(document, &symbols, &tree, Position::new(4, 11).into())
+        .expect("Expected hover");
+    match hover.contents {
+        HoverContents::Markup(content) => {
+            assert!(content.value.contains("Imported from `env.log`"));
+        }
+        _ => panic!("Expected Markup content"),
+    }
+
+    // On "$run" in the export declaration
+    let hover = provide_hover(document, &symbols, &tree, Position::new(6, 23).into())
+        .expect("Expected hover");
+    match hover.contents {
+        HoverContents::Markup(content) => {
+            assert!(content.value.contains("Exported as `\"run\"`"));
+            assert!(content.value.contains("Exported as `\"main\"`"));
+        }
+        _ => panic!("Expected Markup content"),
+    }
+}
+
+#[test]
+fn test_hover_shows_named_ref_types() {
+    let document = r#"(module
+  (type $point (struct (field $x i32) (field $y i32)))
+  (func $norm (param $p (ref $point)) (result i32)
+    i32.const 0
+  )
+  (func $main
+    call $norm
+  )
+)"#;
+    let symbols = crate::parser::parse_document(document).unwrap();
+    let tree = create_test_tree(document);
+
+    // On "$norm" in "call $norm"
+    let hover = provide_hover(document, &symbols, &tree, Position::new(6, 11).into())
+        .expect("Expected hover");
+    match hover.contents {
+        HoverContents::Markup(content) => {
+            assert!(content.value.contains("(param $p (ref $point))"));
+        }
+        _ => panic!("Expected Markup content"),
+    }
+}
+
+#[test]
+fn test_hover_on_wast_invoke_name() {
+    let document = r#"(module $math
+  (func $add (export "add") (param $a i32) (param $b i32) (result i32)
+    (i32.add (local.get $a) (local.get $b))))
+(assert_return (invoke "add" (i32.const 1) (i32.const 2)) (i32.const 3))"#;
+    let symbols = crate::parser::parse_document(document).unwrap();
+
+    // On "add" in the invoke
+    let hover = provide_hover_core(
+        document,
+        &symbols,
+        &create_test_tree(document),
+        Position::new(3, 25),
+    )
+    .expect("hover on invoked export");
+    assert!(hover.contents.contains("$add"));
+    assert!(hover.contents.contains("Module `$math`"));
+}
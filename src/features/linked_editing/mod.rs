@@ -0,0 +1,116 @@
+use crate::features::references::build_block_stack_at_position;
+use crate::utils::{
+    determine_instruction_context, is_labeled_block_kind, node_at_position, node_to_lsp_range,
+    InstructionContext,
+};
+use tower_lsp::lsp_types::*;
+use tree_sitter::{Node, Tree};
+
+#[cfg(test)]
+mod tests;
+
+/// Characters allowed in a WAT identifier after the leading `$`
+const LABEL_WORD_PATTERN: &str = r"\$[0-9A-Za-z!#$%&'*+\-./:<=>?@\\^_`|~]+";
+
+/// Provide linked editing ranges for the block label under the cursor.
+///
+/// The cursor may be on the label of a `block`/`loop`/`if` (including a
+/// trailing `end $label`) or on a branch that targets it. All occurrences
+/// resolving to the same block are linked; an inner block reusing the label
+/// shadows the outer one, so its branches are left alone.
+pub fn provide_linked_editing_ranges(
+    document: &str,
+    tree: &Tree,
+    position: Position,
+) -> Option<LinkedEditingRanges> {
+    let node = node_at_position(tree, document, position.into())?;
+    if !is_label_occurrence(&node, document) {
+        return None;
+    }
+
+    let label = &document[node.byte_range()];
+    let target = resolve_label_block(tree, document, &node, label)?;
+    let block = find_block_node(tree, &target)?;
+
+    let mut ranges = Vec::new();
+    collect_label_ranges(block, tree, document, label, &target, &mut ranges);
+    if ranges.is_empty() {
+        return None;
+    }
+
+    Some(LinkedEditingRanges {
+        ranges,
+        word_pattern: Some(LABEL_WORD_PATTERN.to_string()),
+    })
+}
+
+/// Whether an identifier node names a block label, either in a block
+/// header/`end` or as a branch target
+fn is_label_occurrence(node: &Node, document: &str) -> bool {
+    if node.kind() != "identifier" {
+        return false;
+    }
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+
+    if is_labeled_block_kind(parent.kind()) {
+        return true;
+    }
+
+    parent.kind() == "index"
+        && determine_instruction_context(*node, document) == InstructionContext::Branch
+}
+
+/// Find the byte range of the innermost enclosing block declaring `label`
+fn resolve_label_block(
+    tree: &Tree,
+    document: &str,
+    node: &Node,
+    label: &str,
+) -> Option<std::ops::Range<usize>> {
+    let start = node.start_position();
+    let position = Position::new(start.row as u32, start.column as u32);
+
+    build_block_stack_at_position(tree, document, position)
+        .iter()
+        .rev()
+        .find(|block| block.label.as_deref() == Some(label))
+        .map(|block| block.byte_range.clone())
+}
+
+/// Find the labeled block node spanning exactly `byte_range`
+fn find_block_node<'a>(tree: &'a Tree, byte_range: &std::ops::Range<usize>) -> Option<Node<'a>> {
+    let mut node = tree
+        .root_node()
+        .descendant_for_byte_range(byte_range.start, byte_range.end)?;
+    loop {
+        if is_labeled_block_kind(node.kind()) && node.byte_range() == *byte_range {
+            return Some(node);
+        }
+        node = node.parent()?;
+    }
+}
+
+/// Collect every occurrence of `label` inside `node` that resolves to the block spanning `target`
+fn collect_label_ranges(
+    node: Node,
+    tree: &Tree,
+    document: &str,
+    label: &str,
+    target: &std::ops::Range<usize>,
+    ranges: &mut Vec<Range>,
+) {
+    if node.kind() == "identifier"
+        && &document[node.byte_range()] == label
+        && is_label_occurrence(&node, document)
+        && resolve_label_block(tree, document, &node, label).as_ref() == Some(target)
+    {
+        ranges.push(node_to_lsp_range(&node));
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_label_ranges(child, tree, document, label, target, ranges);
+    }
+}
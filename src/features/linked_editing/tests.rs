@@ -0,0 +1,92 @@
+use super::*;
+use crate::tree_sitter_bindings::create_parser;
+use tower_lsp::lsp_types::Position;
+use tree_sitter::Tree;
+
+fn create_test_tree(document: &str) -> Tree {
+    let mut parser = create_parser();
+    parser
+        .parse(document, None)
+        .expect("Failed to parse test document")
+}
+
+const LOOPS: &str = r#"(module
+  (func $count (param $n i32)
+    (block $done
+      (loop $next
+        local.get $n
+        i32.eqz
+        br_if $done
+        (block $done
+          br $done
+        )
+        br $next
+      )
+    )
+  )
+)"#;
+
+fn linked_lines(document: &str, line: u32, col: u32) -> Option<Vec<(u32, u32)>> {
+    let tree = create_test_tree(document);
+    provide_linked_editing_ranges(document, &tree, Position::new(line, col)).map(|linked| {
+        linked
+            .ranges
+            .iter()
+            .map(|r| (r.start.line, r.start.character))
+            .collect()
+    })
+}
+
+#[test]
+fn test_linked_editing_from_block_label() {
+    // On "$done" in the outer "(block $done"
+    let ranges = linked_lines(LOOPS, 2, 12).expect("Expected linked ranges");
+
+    // The inner block shadows $done, so its branch is not linked
+    assert_eq!(ranges, vec![(2, 11), (6, 14)]);
+}
+
+#[test]
+fn test_linked_editing_from_branch() {
+    // On "$next" in "br $next"
+    let ranges = linked_lines(LOOPS, 10, 12).expect("Expected linked ranges");
+
+    assert_eq!(ranges, vec![(3, 12), (10, 11)]);
+}
+
+#[test]
+fn test_linked_editing_shadowed_label() {
+    // On "$done" in the inner "br $done"
+    let ranges = linked_lines(LOOPS, 8, 14).expect("Expected linked ranges");
+
+    assert_eq!(ranges, vec![(7, 15), (8, 13)]);
+}
+
+#[test]
+fn test_linked_editing_plain_block_end_label() {
+    let document = r#"(module
+  (func
+    block $exit
+      br $exit
+    end $exit
+  )
+)"#;
+    let ranges = linked_lines(document, 3, 11).expect("Expected linked ranges");
+
+    assert_eq!(ranges, vec![(2, 10), (3, 9), (4, 8)]);
+}
+
+#[test]
+fn test_no_linked_editing_outside_labels() {
+    // On "$n" in "local.get $n"
+    assert!(linked_lines(LOOPS, 4, 19).is_none());
+}
+
+#[test]
+fn test_linked_editing_nested_blocks_on_one_line() {
+    let document = "(module\n  (func (block $l (block $l (br $l))))\n)";
+
+    // The outer "$l" has no branches; the inner one owns "br $l"
+    assert_eq!(linked_lines(document, 1, 15), Some(vec![(1, 15)]));
+    assert_eq!(linked_lines(document, 1, 25), Some(vec![(1, 25), (1, 32)]));
+}
@@ -1,6 +1,14 @@
 // LSP feature modules
 // Each module implements a specific Language Server Protocol capability
 
+// Call hierarchy - incoming and outgoing calls, including indirect calls through tables
+#[cfg(feature = "native")]
+pub mod call_hierarchy;
+
+// Code lens - reference counts and export/import markers above declarations
+#[cfg(feature = "native")]
+pub mod code_lens;
+
 // Completion - provides code completion suggestions
 #[cfg(feature = "native")]
 pub mod completion;
@@ -9,10 +17,18 @@ pub mod completion;
 #[cfg(feature = "native")]
 pub mod definition;
 
+// Document symbols - outline of module-level declarations
+#[cfg(feature = "native")]
+pub mod document_symbols;
+
 // Hover - provides hover documentation
 #[cfg(any(feature = "native", feature = "wasm"))]
 pub mod hover;
 
+// Linked editing - live editing of block labels together with their branches
+#[cfg(feature = "native")]
+pub mod linked_editing;
+
 // References - find all references to a symbol
 #[cfg(feature = "native")]
 pub mod references;
@@ -21,5 +37,13 @@ pub mod references;
 #[cfg(feature = "native")]
 pub mod signature;
 
+// Type definition - go-to-type-definition and go-to-implementation
+#[cfg(feature = "native")]
+pub mod type_definition;
+
+// Type hierarchy - supertypes and subtypes of WasmGC types
+#[cfg(feature = "native")]
+pub mod type_hierarchy;
+
 // Symbols - document symbol extraction
 pub mod symbols;
@@ -1,3 +1,4 @@
+use crate::core::CancellationToken;
 use crate::symbols::*;
 use crate::utils::{
     determine_context_from_line, determine_instruction_context_at_node, find_containing_function,
@@ -62,6 +63,20 @@ pub enum ReferenceTarget {
     },
 }
 
+impl ReferenceTarget {
+    /// Kind and index of the target, if it is an importable/exportable module item
+    pub fn extern_item(&self) -> Option<(ExternKind, usize)> {
+        match self {
+            ReferenceTarget::Function { index, .. } => Some((ExternKind::Func, *index)),
+            ReferenceTarget::Global { index, .. } => Some((ExternKind::Global, *index)),
+            ReferenceTarget::Table { index, .. } => Some((ExternKind::Table, *index)),
+            ReferenceTarget::Memory { index, .. } => Some((ExternKind::Memory, *index)),
+            ReferenceTarget::Tag { index, .. } => Some((ExternKind::Tag, *index)),
+            _ => None,
+        }
+    }
+}
+
 /// Context for reference search operations
 struct ReferenceSearchContext<'a> {
     document: &'a str,
@@ -93,6 +108,28 @@ pub fn provide_references(
     position: Position,
     uri: &str,
     include_declaration: bool,
+) -> Vec<Location> {
+    provide_references_cancellable(
+        document,
+        symbols,
+        tree,
+        position,
+        uri,
+        include_declaration,
+        &CancellationToken::default(),
+    )
+}
+
+/// Find-references that stops walking the document once `cancel` is
+/// cancelled, returning the references found so far
+pub fn provide_references_cancellable(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+    uri: &str,
+    include_declaration: bool,
+    cancel: &CancellationToken,
 ) -> Vec<Location> {
     // Identify what symbol the cursor is on
     let target = match identify_symbol_at_position(document, symbols, tree, position) {
@@ -100,12 +137,40 @@ pub fn provide_references(
         None => return vec![],
     };
 
+    // In a .wast script, names only refer to items of the module they're in
+    let module = symbols.module_at(position.into());
+    let symbols = symbols.scope_at(position.into());
+    let mut references = provide_references_for_target(
+        &target,
+        document,
+        symbols,
+        tree,
+        uri,
+        include_declaration,
+        cancel,
+    );
+    if let Some(module) = module {
+        references.retain(|location| module.range.contains(location.range.start.into()));
+    }
+    references
+}
+
+/// Find all references to an already identified symbol
+pub fn provide_references_for_target(
+    target: &ReferenceTarget,
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    uri: &str,
+    include_declaration: bool,
+    cancel: &CancellationToken,
+) -> Vec<Location> {
     // Find all references to this symbol
-    let mut references = find_all_references(&target, tree, document, symbols, uri);
+    let mut references = find_all_references(target, tree, document, symbols, uri, cancel);
 
     // Optionally include the declaration
     if include_declaration {
-        if let Some(def_location) = get_definition_location(&target, symbols, uri) {
+        if let Some(def_location) = get_definition_location(target, symbols, uri) {
             // Prepend definition to results
             references.insert(0, def_location);
         }
@@ -134,6 +199,13 @@ pub fn identify_symbol_at_position(
     tree: &Tree,
     position: Position,
 ) -> Option<ReferenceTarget> {
+    let symbols = symbols.scope_at(position.into());
+
+    // On the name string of an export, e.g. "run" in (export "run" (func $f))
+    if let Some(target) = identify_export_name(document, symbols, tree, position) {
+        return Some(target);
+    }
+
     let word = get_word_at_position(document, position.into())?;
 
     // Determine context using AST, with fallback to line matching
@@ -190,6 +262,59 @@ pub fn identify_symbol_at_position(
     }
 }
 
+/// Identify the item exported by the export whose name string is under the cursor
+fn identify_export_name(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+) -> Option<ReferenceTarget> {
+    let node = node_at_position(tree, document, position.into())?;
+    let name_node = std::iter::successors(Some(node), |n| n.parent())
+        .take(3)
+        .find(|n| n.kind() == "name")?;
+    let export_node = name_node
+        .parent()
+        .filter(|p| matches!(p.kind(), "export" | "module_field_export"))?;
+
+    let export_range = node_to_lsp_range(&export_node);
+    let export = symbols.exports.iter().find(|e| {
+        e.range
+            .is_some_and(|r| Range::from(r).start == export_range.start)
+    })?;
+    reference_target_for_item(symbols, export.kind, export.index)
+}
+
+/// Build the reference target for an importable/exportable module item
+pub fn reference_target_for_item(
+    symbols: &SymbolTable,
+    kind: ExternKind,
+    index: usize,
+) -> Option<ReferenceTarget> {
+    Some(match kind {
+        ExternKind::Func => ReferenceTarget::Function {
+            name: symbols.get_function_by_index(index)?.name.clone(),
+            index,
+        },
+        ExternKind::Global => ReferenceTarget::Global {
+            name: symbols.get_global_by_index(index)?.name.clone(),
+            index,
+        },
+        ExternKind::Table => ReferenceTarget::Table {
+            name: symbols.get_table_by_index(index)?.name.clone(),
+            index,
+        },
+        ExternKind::Memory => ReferenceTarget::Memory {
+            name: symbols.get_memory_by_index(index)?.name.clone(),
+            index,
+        },
+        ExternKind::Tag => ReferenceTarget::Tag {
+            name: symbols.get_tag_by_index(index)?.name.clone(),
+            index,
+        },
+    })
+}
+
 /// Identify a named symbol (e.g., $funcName, $varName)
 fn identify_named_symbol(
     word: &str,
@@ -487,19 +612,34 @@ fn find_all_references(
     document: &str,
     symbols: &SymbolTable,
     uri: &str,
+    cancel: &CancellationToken,
 ) -> Vec<Location> {
     let mut results = Vec::new();
     let mut block_stack = Vec::new();
 
-    walk_tree_for_references(
-        tree.root_node(),
-        target,
-        document,
-        symbols,
-        uri,
-        &mut results,
-        &mut block_stack,
-    );
+    // Walk one module field or script command at a time, so a cancelled
+    // request stops between them
+    let mut stack = vec![tree.root_node()];
+    while let Some(node) = stack.pop() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if matches!(node.kind(), "ROOT" | "module") {
+            let mut cursor = node.walk();
+            let children: Vec<Node> = node.children(&mut cursor).collect();
+            stack.extend(children.into_iter().rev());
+        } else {
+            walk_tree_for_references(
+                node,
+                target,
+                document,
+                symbols,
+                uri,
+                &mut results,
+                &mut block_stack,
+            );
+        }
+    }
 
     results
 }
@@ -536,6 +676,7 @@ fn walk_tree_for_references(
         block_stack.push(BlockInfo {
             label,
             line: node.start_position().row as u32,
+            byte_range: node.byte_range(),
         });
     }
 
@@ -943,8 +1084,8 @@ fn resolve_block_by_depth(depth: usize, block_stack: &[BlockInfo]) -> Option<&Bl
     block_stack.get(stack_len - 1 - depth)
 }
 
-/// Build the block stack at a given position
-fn build_block_stack_at_position(
+/// Build the stack of labeled blocks enclosing a position, outermost first
+pub fn build_block_stack_at_position(
     tree: &Tree,
     document: &str,
     position: Position,
@@ -980,6 +1121,7 @@ fn build_block_stack_recursive(
         block_stack.push(BlockInfo {
             label,
             line: node.start_position().row as u32,
+            byte_range: node.byte_range(),
         });
     }
 
@@ -1097,7 +1239,8 @@ fn parse_nat(text: &str) -> Result<usize, std::num::ParseIntError> {
 
 /// Block information for tracking nesting depth
 #[derive(Debug, Clone)]
-struct BlockInfo {
-    label: Option<String>,
-    line: u32,
+pub struct BlockInfo {
+    pub label: Option<String>,
+    pub line: u32,
+    pub byte_range: std::ops::Range<usize>, // The block's extent, which tells apart blocks on one line
 }
@@ -38,6 +38,21 @@ fn test_function_references_named() {
     assert_eq!(refs.len(), 2);
     assert_eq!(refs[0].range.start.line, 9);
     assert_eq!(refs[1].range.start.line, 14);
+
+    // A cancelled request stops walking the document
+    let guard = crate::core::CancellationGuard::default();
+    let cancel = guard.token();
+    drop(guard);
+    let refs = provide_references_cancellable(
+        source,
+        &symbols,
+        &tree,
+        position,
+        "file:///test.wat",
+        false,
+        &cancel,
+    );
+    assert!(refs.is_empty());
 }
 
 #[test]
@@ -1124,3 +1139,61 @@ fn test_tag_references_from_throw() {
         refs.iter().map(|r| r.range.start.line).collect::<Vec<_>>()
     );
 }
+
+#[test]
+fn test_references_from_export_name() {
+    let source = r#"(module
+  (func $run (export "run")
+    call $helper
+  )
+  (func $helper)
+  (export "helper" (func $helper))
+)"#;
+
+    let symbols = parse_document(source).unwrap();
+    let mut parser = tree_sitter_bindings::create_parser();
+    let tree = parser.parse(source, None).unwrap();
+
+    // On "helper" in the export name string
+    let position = Position {
+        line: 5,
+        character: 12,
+    };
+    let refs = provide_references(source, &symbols, &tree, position, "file:///test.wat", false);
+
+    let lines: Vec<u32> = refs.iter().map(|r| r.range.start.line).collect();
+    assert_eq!(lines, vec![2, 5]);
+
+    // On "run" in the inline export
+    let position = Position {
+        line: 1,
+        character: 23,
+    };
+    let refs = provide_references(source, &symbols, &tree, position, "file:///test.wat", true);
+    assert_eq!(refs.len(), 1);
+    assert_eq!(refs[0].range.start.line, 1);
+}
+
+#[test]
+fn test_wast_references_scoped_to_module() {
+    let source = r#"(module
+  (func $f)
+  (func (call $f)))
+(module
+  (func $f)
+  (func (call $f) (call $f)))"#;
+
+    let symbols = parse_document(source).unwrap();
+    let mut parser = tree_sitter_bindings::create_parser();
+    let tree = parser.parse(source, None).unwrap();
+
+    // On "$f" in the second module's body
+    let position = Position {
+        line: 5,
+        character: 15,
+    };
+    let refs = provide_references(source, &symbols, &tree, position, "file:///test.wast", true);
+
+    assert_eq!(refs.len(), 3);
+    assert!(refs.iter().all(|r| r.range.start.line >= 3));
+}
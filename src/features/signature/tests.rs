@@ -30,6 +30,7 @@ fn create_test_symbols() -> SymbolTable {
             },
         ],
         results: vec![ValueType::I32],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -64,6 +65,7 @@ fn create_test_symbols() -> SymbolTable {
             },
         ],
         results: vec![ValueType::F64],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -261,6 +263,7 @@ fn test_format_function_signature_simple() {
         index: 0,
         parameters: vec![],
         results: vec![],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -286,6 +289,7 @@ fn test_format_function_signature_with_params() {
             range: None,
         }],
         results: vec![],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -308,6 +312,7 @@ fn test_format_function_signature_with_results() {
         index: 0,
         parameters: vec![],
         results: vec![ValueType::I32, ValueType::I64],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -335,6 +340,7 @@ fn test_format_function_signature_unnamed_params() {
             range: None,
         }],
         results: vec![],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
-use crate::core::types::Range;
+use crate::component::ComponentSymbols;
+use crate::core::types::{Position, Range};
 
 #[cfg(test)]
 mod tests;
@@ -24,11 +25,59 @@ pub enum ValueType {
     Nullref,
     NullFuncref,
     NullExternref,
-    Ref(u32),     // Typed reference to a type index
-    RefNull(u32), // Nullable typed reference
+    Ref(TypeRef),     // Typed reference to a type index
+    RefNull(TypeRef), // Nullable typed reference
     Unknown,
 }
 
+/// Target type of a typed reference such as `(ref $point)`.
+///
+/// Keeps the `$name` the reference was written with for display. Two
+/// references are equal when they point at the same type index.
+#[derive(Debug, Clone)]
+pub struct TypeRef {
+    pub index: Option<u32>, // None for a name that hasn't been resolved
+    pub name: Option<String>,
+}
+
+impl TypeRef {
+    /// A reference written as a type index, e.g. `(ref 3)`
+    pub fn numeric(index: u32) -> Self {
+        Self {
+            index: Some(index),
+            name: None,
+        }
+    }
+
+    /// A reference written as a type name, e.g. `(ref $point)`, to be resolved later
+    pub fn named(name: impl Into<String>) -> Self {
+        Self {
+            index: None,
+            name: Some(name.into()),
+        }
+    }
+}
+
+impl PartialEq for TypeRef {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.index, other.index) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => self.name == other.name,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for TypeRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.name, self.index) {
+            (Some(name), _) => write!(f, "{}", name),
+            (None, Some(index)) => write!(f, "{}", index),
+            (None, None) => write!(f, "?"),
+        }
+    }
+}
+
 impl std::fmt::Display for ValueType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -49,8 +98,8 @@ impl std::fmt::Display for ValueType {
             ValueType::Nullref => write!(f, "nullref"),
             ValueType::NullFuncref => write!(f, "nullfuncref"),
             ValueType::NullExternref => write!(f, "nullexternref"),
-            ValueType::Ref(idx) => write!(f, "(ref {})", idx),
-            ValueType::RefNull(idx) => write!(f, "(ref null {})", idx),
+            ValueType::Ref(target) => write!(f, "(ref {})", target),
+            ValueType::RefNull(target) => write!(f, "(ref null {})", target),
             ValueType::Unknown => write!(f, "unknown"),
         }
     }
@@ -100,32 +149,41 @@ impl From<&wast::core::ValType<'_>> for ValueType {
             wast::core::ValType::F32 => ValueType::F32,
             wast::core::ValType::F64 => ValueType::F64,
             wast::core::ValType::V128 => ValueType::V128,
-            wast::core::ValType::Ref(ref_type) => match &ref_type.heap {
-                wast::core::HeapType::Abstract { ty, .. } => match ty {
-                    wast::core::AbstractHeapType::Func => ValueType::Funcref,
-                    wast::core::AbstractHeapType::Extern => ValueType::Externref,
-                    wast::core::AbstractHeapType::Struct => ValueType::Structref,
-                    wast::core::AbstractHeapType::Array => ValueType::Arrayref,
-                    wast::core::AbstractHeapType::I31 => ValueType::I31ref,
-                    wast::core::AbstractHeapType::Any => ValueType::Anyref,
-                    wast::core::AbstractHeapType::Eq => ValueType::Eqref,
-                    wast::core::AbstractHeapType::None => ValueType::Nullref,
-                    wast::core::AbstractHeapType::NoFunc => ValueType::NullFuncref,
-                    wast::core::AbstractHeapType::NoExtern => ValueType::NullExternref,
-                    _ => ValueType::Unknown,
-                },
-                wast::core::HeapType::Concrete(idx) => match idx {
-                    wast::token::Index::Num(n, _) => {
-                        if ref_type.nullable {
-                            ValueType::RefNull(*n)
-                        } else {
-                            ValueType::Ref(*n)
-                        }
-                    }
-                    wast::token::Index::Id(_) => ValueType::Unknown,
-                },
-                wast::core::HeapType::Exact(_) => ValueType::Unknown,
+            wast::core::ValType::Ref(ref_type) => ValueType::from(ref_type),
+        }
+    }
+}
+
+/// Convert from wast crate's RefType, e.g. a table's element type.
+impl From<&wast::core::RefType<'_>> for ValueType {
+    fn from(ref_type: &wast::core::RefType) -> Self {
+        match &ref_type.heap {
+            wast::core::HeapType::Abstract { ty, .. } => match ty {
+                wast::core::AbstractHeapType::Func => ValueType::Funcref,
+                wast::core::AbstractHeapType::Extern => ValueType::Externref,
+                wast::core::AbstractHeapType::Struct => ValueType::Structref,
+                wast::core::AbstractHeapType::Array => ValueType::Arrayref,
+                wast::core::AbstractHeapType::I31 => ValueType::I31ref,
+                wast::core::AbstractHeapType::Any => ValueType::Anyref,
+                wast::core::AbstractHeapType::Eq => ValueType::Eqref,
+                wast::core::AbstractHeapType::None => ValueType::Nullref,
+                wast::core::AbstractHeapType::NoFunc => ValueType::NullFuncref,
+                wast::core::AbstractHeapType::NoExtern => ValueType::NullExternref,
+                _ => ValueType::Unknown,
             },
+            wast::core::HeapType::Concrete(idx) => {
+                // Named targets are resolved by SymbolTable::resolve_type_refs
+                let target = match idx {
+                    wast::token::Index::Num(n, _) => TypeRef::numeric(*n),
+                    wast::token::Index::Id(id) => TypeRef::named(format!("${}", id.name())),
+                };
+                if ref_type.nullable {
+                    ValueType::RefNull(target)
+                } else {
+                    ValueType::Ref(target)
+                }
+            }
+            wast::core::HeapType::Exact(_) => ValueType::Unknown,
         }
     }
 }
@@ -170,6 +228,7 @@ pub struct Function {
     pub index: usize,
     pub parameters: Vec<Parameter>,
     pub results: Vec<ValueType>,
+    pub type_use: Option<TypeRef>, // The `(type $t)` it is declared with, if any
     pub locals: Vec<Variable>,
     pub blocks: Vec<BlockLabel>,
     pub line: u32,
@@ -193,6 +252,7 @@ pub struct Global {
     pub initial_value: Option<String>,
     #[allow(dead_code)] // Useful for go-to-definition
     pub line: u32,
+    pub start_byte: usize, // Byte offset where the declaring field starts
     #[allow(dead_code)] // Useful for go-to-definition
     pub range: Option<Range>,
 }
@@ -206,6 +266,7 @@ pub struct Table {
     pub limits: (u32, Option<u32>), // (min, max)
     #[allow(dead_code)] // Useful for go-to-definition
     pub line: u32,
+    pub start_byte: usize, // Byte offset where the declaring field starts
     #[allow(dead_code)] // Useful for go-to-definition
     pub range: Option<Range>,
 }
@@ -223,6 +284,7 @@ pub struct Memory {
     pub shared: bool, // true if memory is shared (for threads)
     #[allow(dead_code)] // Useful for go-to-definition
     pub line: u32,
+    pub start_byte: usize, // Byte offset where the declaring field starts
     #[allow(dead_code)] // Useful for go-to-definition
     pub range: Option<Range>,
 }
@@ -268,6 +330,7 @@ pub struct Tag {
     pub params: Vec<ValueType>,
     #[allow(dead_code)] // Useful for go-to-definition
     pub line: u32,
+    pub start_byte: usize, // Byte offset where the declaring field starts
     #[allow(dead_code)] // Useful for go-to-definition
     pub range: Option<Range>,
 }
@@ -301,6 +364,121 @@ pub struct ElemSegment {
     pub range: Option<Range>,
 }
 
+/// Kind of module item that can be imported or exported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternKind {
+    Func,
+    Table,
+    Memory,
+    Global,
+    Tag,
+}
+
+impl std::fmt::Display for ExternKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExternKind::Func => write!(f, "func"),
+            ExternKind::Table => write!(f, "table"),
+            ExternKind::Memory => write!(f, "memory"),
+            ExternKind::Global => write!(f, "global"),
+            ExternKind::Tag => write!(f, "tag"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub name: String, // Export name, without quotes
+    pub kind: ExternKind,
+    pub index: usize, // Index of the exported item within its kind
+    pub line: u32,
+    pub range: Option<Range>, // Range of the export declaration
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub module: String, // Module name, without quotes
+    pub field: String,  // Field name, without quotes
+    pub kind: ExternKind,
+    pub index: usize, // Index of the imported item within its kind
+    pub line: u32,
+    pub range: Option<Range>, // Range of the import declaration
+}
+
+impl Import {
+    /// The import's `module.field` path, e.g. `env.log`
+    pub fn qualified_name(&self) -> String {
+        format!("{}.{}", self.module, self.field)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StartFunction {
+    pub index: usize, // Index of the start function
+    pub line: u32,
+    pub range: Option<Range>, // Range of the start declaration
+}
+
+/// A top-level module of a `.wast` script, with its own index spaces
+#[derive(Debug, Clone)]
+pub struct ScriptModule {
+    pub name: Option<String>, // $id, if the module has one
+    pub range: Range,         // Range of the whole (module ...) form
+    pub symbols: SymbolTable, // Empty for binary and quoted modules
+    pub quoted: bool,         // Given as binary or quoted text, so its items are unknown
+    pub in_assertion: bool,   // Defined by an assertion, so no later command refers to it
+}
+
+/// Kind of a `.wast` script command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptCommandKind {
+    Register,
+    Invoke,
+    Get,
+    AssertReturn,
+    AssertTrap,
+    AssertExhaustion,
+    AssertException,
+    AssertInvalid,
+    AssertMalformed,
+    AssertUnlinkable,
+}
+
+impl ScriptCommandKind {
+    /// The command for a keyword such as `assert_return`
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        Some(match keyword {
+            "register" => ScriptCommandKind::Register,
+            "invoke" => ScriptCommandKind::Invoke,
+            "get" => ScriptCommandKind::Get,
+            "assert_return" => ScriptCommandKind::AssertReturn,
+            "assert_trap" => ScriptCommandKind::AssertTrap,
+            "assert_exhaustion" => ScriptCommandKind::AssertExhaustion,
+            "assert_exception" => ScriptCommandKind::AssertException,
+            "assert_invalid" => ScriptCommandKind::AssertInvalid,
+            "assert_malformed" => ScriptCommandKind::AssertMalformed,
+            "assert_unlinkable" => ScriptCommandKind::AssertUnlinkable,
+            _ => return None,
+        })
+    }
+}
+
+/// A `.wast` script command such as `(invoke "add" ...)` or `(assert_return ...)`
+#[derive(Debug, Clone)]
+pub struct ScriptCommand {
+    pub kind: ScriptCommandKind,
+    pub range: Range,
+    /// Index in `SymbolTable::modules` of the module the command uses: the one
+    /// named with `$id`, or else the latest module before it
+    pub module: Option<usize>,
+    /// `$id` the command names its module with
+    pub module_name: Option<String>,
+    /// Quoted name, without quotes: the export an action uses, or the name
+    /// a module is registered under
+    pub name: Option<String>,
+    pub name_range: Option<Range>,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct SymbolTable {
     pub functions: Vec<Function>,
@@ -311,6 +489,18 @@ pub struct SymbolTable {
     pub tags: Vec<Tag>,
     pub data_segments: Vec<DataSegment>,
     pub elem_segments: Vec<ElemSegment>,
+    pub exports: Vec<Export>,
+    pub imports: Vec<Import>,
+    pub start: Option<StartFunction>,
+
+    // Modules and commands of a .wast script; empty for a single module,
+    // whose items are the fields above
+    pub modules: Vec<ScriptModule>,
+    pub commands: Vec<ScriptCommand>,
+
+    // Component-level items of a component, whose core modules are the
+    // modules above
+    pub component: Option<ComponentSymbols>,
 
     // Maps for quick lookup by name
     pub function_map: HashMap<String, usize>,
@@ -415,4 +605,136 @@ impl SymbolTable {
         elem_segments,
         elem_map
     );
+
+    /// Resolve the `$name` targets of typed references to type indices,
+    /// once all types are known
+    pub fn resolve_type_refs(&mut self) {
+        let type_map = self.type_map.clone();
+        let resolve = |value_type: &mut ValueType| {
+            if let ValueType::Ref(target) | ValueType::RefNull(target) = value_type {
+                if target.index.is_none() {
+                    target.index = target
+                        .name
+                        .as_ref()
+                        .and_then(|name| type_map.get(name))
+                        .map(|&index| index as u32);
+                }
+            }
+        };
+
+        for func in &mut self.functions {
+            if let Some(type_use) = &mut func.type_use {
+                if type_use.index.is_none() {
+                    type_use.index = type_use
+                        .name
+                        .as_ref()
+                        .and_then(|name| type_map.get(name))
+                        .map(|&index| index as u32);
+                }
+            }
+            func.parameters
+                .iter_mut()
+                .for_each(|p| resolve(&mut p.param_type));
+            func.results.iter_mut().for_each(resolve);
+            func.locals
+                .iter_mut()
+                .for_each(|l| resolve(&mut l.var_type));
+        }
+        for global in &mut self.globals {
+            resolve(&mut global.var_type);
+        }
+        for table in &mut self.tables {
+            resolve(&mut table.ref_type);
+        }
+        for type_def in &mut self.types {
+            match &mut type_def.kind {
+                TypeKind::Func { params, results } => {
+                    params
+                        .iter_mut()
+                        .chain(results.iter_mut())
+                        .for_each(resolve);
+                }
+                TypeKind::Struct { fields } => {
+                    fields.iter_mut().for_each(|(_, t, _)| resolve(t));
+                }
+                TypeKind::Array { element_type, .. } => resolve(element_type),
+            }
+        }
+        for tag in &mut self.tags {
+            tag.params.iter_mut().for_each(resolve);
+        }
+    }
+
+    /// Resolve an item reference written as `$name` or a number
+    pub fn resolve_index(&self, kind: ExternKind, text: &str) -> Option<usize> {
+        if !text.starts_with('$') {
+            return text.parse().ok();
+        }
+        match kind {
+            ExternKind::Func => self.function_map.get(text),
+            ExternKind::Table => self.table_map.get(text),
+            ExternKind::Memory => self.memory_map.get(text),
+            ExternKind::Global => self.global_map.get(text),
+            ExternKind::Tag => self.tag_map.get(text),
+        }
+        .copied()
+    }
+
+    /// Resolve a type reference written as `$name` or a number
+    pub fn resolve_type_index(&self, text: &str) -> Option<usize> {
+        if text.starts_with('$') {
+            self.type_map.get(text).copied()
+        } else {
+            text.parse().ok()
+        }
+    }
+
+    /// All exports of the given item, in declaration order
+    pub fn exports_of(&self, kind: ExternKind, index: usize) -> impl Iterator<Item = &Export> {
+        self.exports
+            .iter()
+            .filter(move |e| e.kind == kind && e.index == index)
+    }
+
+    /// Whether the document is a `.wast` script rather than a single module
+    pub fn is_script(&self) -> bool {
+        !self.modules.is_empty() || !self.commands.is_empty()
+    }
+
+    /// The script module containing `position`
+    pub fn module_at(&self, position: Position) -> Option<&ScriptModule> {
+        self.modules.iter().find(|m| m.range.contains(position))
+    }
+
+    /// Symbols in scope at `position`: the enclosing module's in a script,
+    /// otherwise the whole table
+    pub fn scope_at(&self, position: Position) -> &SymbolTable {
+        self.module_at(position).map_or(self, |m| &m.symbols)
+    }
+
+    /// The script command whose quoted name is at `position`
+    pub fn command_name_at(&self, position: Position) -> Option<&ScriptCommand> {
+        self.commands
+            .iter()
+            .find(|c| c.name_range.is_some_and(|range| range.contains(position)))
+    }
+
+    /// The module and export a command's `invoke` or `get` action names
+    pub fn command_export(&self, command: &ScriptCommand) -> Option<(&ScriptModule, &Export)> {
+        // A registered name is not an export
+        if command.kind == ScriptCommandKind::Register {
+            return None;
+        }
+        let module = self.modules.get(command.module?)?;
+        let name = command.name.as_deref()?;
+        let export = module.symbols.exports.iter().find(|e| e.name == name)?;
+        Some((module, export))
+    }
+
+    /// The import that declares the given item, if it is imported
+    pub fn import_of(&self, kind: ExternKind, index: usize) -> Option<&Import> {
+        self.imports
+            .iter()
+            .find(|i| i.kind == kind && i.index == index)
+    }
 }
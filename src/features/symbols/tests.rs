@@ -18,6 +18,7 @@ fn test_add_function() {
         index: 0,
         parameters: vec![],
         results: vec![ValueType::I32],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -48,6 +49,7 @@ fn test_add_global() {
         is_mutable: true,
         initial_value: Some("0".to_string()),
         line: 0,
+        start_byte: 0,
         range: None,
     };
 
@@ -71,6 +73,7 @@ fn test_add_table() {
         ref_type: ValueType::Funcref,
         limits: (10, Some(100)),
         line: 0,
+        start_byte: 0,
         range: None,
     };
 
@@ -127,6 +130,7 @@ fn test_get_function_by_index() {
             index: i,
             parameters: vec![],
             results: vec![],
+            type_use: None,
             locals: vec![],
             blocks: vec![],
             line: i as u32,
@@ -158,6 +162,7 @@ fn test_get_global_by_index() {
             is_mutable: false,
             initial_value: None,
             line: i as u32,
+            start_byte: 0,
             range: None,
         };
         table.add_global(global);
@@ -177,6 +182,7 @@ fn test_unnamed_symbols() {
         index: 0,
         parameters: vec![],
         results: vec![],
+        type_use: None,
         locals: vec![],
         blocks: vec![],
         line: 0,
@@ -203,6 +209,7 @@ fn test_multiple_symbols_same_type() {
             index: i,
             parameters: vec![],
             results: vec![],
+            type_use: None,
             locals: vec![],
             blocks: vec![],
             line: 0,
@@ -295,6 +302,7 @@ fn test_complex_function() {
             },
         ],
         results: vec![ValueType::I32, ValueType::I64],
+        type_use: None,
         locals: vec![Variable {
             name: Some("$temp".to_string()),
             var_type: ValueType::F32,
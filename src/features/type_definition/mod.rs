@@ -0,0 +1,444 @@
+use crate::features::references::{identify_symbol_at_position, ReferenceTarget};
+use crate::symbols::*;
+use crate::utils::{node_at_position, node_to_lsp_range, position_to_byte};
+use tower_lsp::lsp_types::*;
+use tree_sitter::{Node, Tree};
+
+#[cfg(test)]
+mod tests;
+
+/// Instructions whose first immediate is a type index
+const TYPE_OPERAND_OPS: &[&str] = &["call_ref", "return_call_ref", "ref.cast", "ref.test"];
+
+/// Main entry point for go-to-type-definition.
+///
+/// Resolves the type of the symbol under the cursor to the `(type ...)` declaration
+/// it refers to. Works for locals, params, globals and tables declared with a
+/// `(ref $T)` type, struct fields whose storage type is a typed reference,
+/// functions declared with a type use, and type operands of GC instructions.
+pub fn provide_type_definition(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+    uri: &str,
+) -> Option<Location> {
+    let lsp_uri = Url::parse(uri).ok()?;
+    let target = identify_symbol_at_position(document, symbols, tree, position);
+    let symbols = symbols.scope_at(position.into());
+    let scope = module_node_at(tree, document, position);
+
+    if let Some(type_index) = type_of_instruction_operand(document, symbols, tree, scope, position)
+    {
+        return type_location(type_index, symbols, scope, &lsp_uri);
+    }
+
+    let target = target?;
+    let type_index = match target {
+        ReferenceTarget::Type { index, .. } => Some(index),
+        ReferenceTarget::Parameter {
+            index,
+            function_start_byte,
+            ..
+        }
+        | ReferenceTarget::Local {
+            index,
+            function_start_byte,
+            ..
+        } => {
+            // Params may be declared by a type use alone, so take them from the signature
+            let func = symbols
+                .functions
+                .iter()
+                .find(|f| f.start_byte == function_start_byte)?;
+            let (params, _) = function_signature(func, symbols, tree, document);
+            let locals = func.locals.iter().map(|local| &local.var_type);
+            params
+                .iter()
+                .chain(locals)
+                .nth(index)
+                .and_then(value_type_index)
+        }
+        ReferenceTarget::Global { index, .. } => {
+            let global = symbols.get_global_by_index(index)?;
+            find_decl_node(tree, global.start_byte, |kind| {
+                kind == "module_field_global" || kind == "import_desc_global_type"
+            })
+            .and_then(|node| find_child(&node, "global_type"))
+            .and_then(|node| ref_type_index(&node, document, symbols))
+            .or_else(|| value_type_index(&global.var_type))
+        }
+        ReferenceTarget::Table { index, .. } => {
+            let table = symbols.get_table_by_index(index)?;
+            find_decl_node(tree, table.start_byte, |kind| {
+                kind == "module_field_table" || kind == "import_desc_table_type"
+            })
+            .and_then(|node| find_child(&node, "table_type"))
+            .and_then(|node| ref_type_index(&node, document, symbols))
+            .or_else(|| value_type_index(&table.ref_type))
+        }
+        ReferenceTarget::Function { index, .. } => {
+            let func = symbols.get_function_by_index(index)?;
+            function_type_index(func, symbols, tree, document)
+        }
+        _ => None,
+    }?;
+
+    type_location(type_index, symbols, scope, &lsp_uri)
+}
+
+/// Main entry point for go-to-implementation.
+///
+/// On a type, lists every type that declares it (directly or transitively) as its
+/// supertype. On a func type, also lists every function whose signature matches,
+/// which includes the candidates of `call_indirect` and `call_ref` with that type.
+pub fn provide_implementation(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+    uri: &str,
+) -> Vec<Location> {
+    let Ok(lsp_uri) = Url::parse(uri) else {
+        return vec![];
+    };
+
+    let target = identify_symbol_at_position(document, symbols, tree, position);
+    let symbols = symbols.scope_at(position.into());
+    let scope = module_node_at(tree, document, position);
+
+    let type_index = match type_of_instruction_operand(document, symbols, tree, scope, position) {
+        Some(index) => index,
+        None => match target {
+            Some(ReferenceTarget::Type { index, .. }) => index,
+            _ => return vec![],
+        },
+    };
+
+    let Some(type_def) = symbols.get_type_by_index(type_index) else {
+        return vec![];
+    };
+
+    let mut locations = Vec::new();
+
+    for subtype in find_subtypes(type_index, symbols) {
+        if let Some(location) = type_location(subtype.index, symbols, scope, &lsp_uri) {
+            locations.push(location);
+        }
+    }
+
+    if matches!(type_def.kind, TypeKind::Func { .. }) {
+        for func in &symbols.functions {
+            if function_matches_type(func, type_def, symbols, tree, document) {
+                if let Some(location) = function_location(func, tree, &lsp_uri) {
+                    locations.push(location);
+                }
+            }
+        }
+    }
+
+    locations
+}
+
+/// Find all types that have `type_index` as a direct or transitive supertype
+pub fn find_subtypes(type_index: usize, symbols: &SymbolTable) -> Vec<&TypeDef> {
+    let mut subtypes: Vec<&TypeDef> = Vec::new();
+    let mut pending = vec![type_index];
+
+    while let Some(parent) = pending.pop() {
+        for type_def in &symbols.types {
+            if type_def.supertype == Some(parent as u32)
+                && !subtypes.iter().any(|t| t.index == type_def.index)
+            {
+                subtypes.push(type_def);
+                pending.push(type_def.index);
+            }
+        }
+    }
+
+    subtypes.sort_by_key(|t| t.index);
+    subtypes
+}
+
+/// Resolve the type index a function was declared with via `(type ...)`, if any
+pub fn function_type_index(
+    func: &Function,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    document: &str,
+) -> Option<usize> {
+    let func_node = find_function_node(tree, func.start_byte)?;
+    let type_use = find_child(&func_node, "type_use")?;
+    let index_node = find_child(&type_use, "index")?;
+    symbols.resolve_type_index(&document[index_node.byte_range()])
+}
+
+/// Resolve a function's parameter and result types, following its `(type ...)` use
+pub fn function_signature(
+    func: &Function,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    document: &str,
+) -> (Vec<ValueType>, Vec<ValueType>) {
+    if let Some(index) = function_type_index(func, symbols, tree, document) {
+        if let Some(TypeKind::Func { params, results }) =
+            symbols.get_type_by_index(index).map(|t| &t.kind)
+        {
+            return (params.clone(), results.clone());
+        }
+    }
+
+    let params = func
+        .parameters
+        .iter()
+        .map(|p| p.param_type.clone())
+        .collect();
+    (params, func.results.clone())
+}
+
+/// Check whether a function's signature matches a func type.
+///
+/// A function matches if it was declared with that type index, or if its
+/// parameter and result types are identical to the type's.
+pub fn function_matches_type(
+    func: &Function,
+    type_def: &TypeDef,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    document: &str,
+) -> bool {
+    let TypeKind::Func { params, results } = &type_def.kind else {
+        return false;
+    };
+
+    if function_type_index(func, symbols, tree, document) == Some(type_def.index) {
+        return true;
+    }
+
+    let (func_params, func_results) = function_signature(func, symbols, tree, document);
+    func_params == *params && func_results == *results
+}
+
+/// Extract the type index from a resolved ValueType, if it is a typed reference
+fn value_type_index(value_type: &ValueType) -> Option<usize> {
+    match value_type {
+        ValueType::Ref(target) | ValueType::RefNull(target) => target.index.map(|i| i as usize),
+        _ => None,
+    }
+}
+
+/// Resolve the type behind a type or field operand of a GC instruction.
+///
+/// `struct.get $T $field` resolves the field to the type of its storage type,
+/// while the type operand of struct/array instructions and `call_ref` resolves
+/// to the type itself.
+fn type_of_instruction_operand(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    scope: Node,
+    position: Position,
+) -> Option<usize> {
+    let node = node_at_position(tree, document, position.into())?;
+    let index_node = ancestor_of_kind(node, "index")?;
+    let instr = index_node.parent()?;
+    if instr.kind() != "instr_plain" {
+        return None;
+    }
+
+    let op = document[instr.byte_range()].split_whitespace().next()?;
+    let is_struct_op = op.starts_with("struct.");
+    if !is_struct_op && !op.starts_with("array.") && !TYPE_OPERAND_OPS.contains(&op) {
+        return None;
+    }
+
+    let mut cursor = instr.walk();
+    let operands: Vec<Node> = instr
+        .children(&mut cursor)
+        .filter(|c| c.kind() == "index")
+        .collect();
+    let operand_position = operands.iter().position(|c| c.id() == index_node.id())?;
+    let type_index = symbols.resolve_type_index(&document[operands.first()?.byte_range()])?;
+
+    match operand_position {
+        0 => Some(type_index),
+        1 if is_struct_op => {
+            let field_text = &document[index_node.byte_range()];
+            struct_field_type_index(type_index, field_text, symbols, scope, document)
+        }
+        _ => None,
+    }
+}
+
+/// Resolve the type index of a struct field's `(ref $T)` storage type
+fn struct_field_type_index(
+    type_index: usize,
+    field_text: &str,
+    symbols: &SymbolTable,
+    scope: Node,
+    document: &str,
+) -> Option<usize> {
+    let type_def = symbols.get_type_by_index(type_index)?;
+    let TypeKind::Struct { fields } = &type_def.kind else {
+        return None;
+    };
+
+    let field_index = if field_text.starts_with('$') {
+        fields
+            .iter()
+            .position(|(name, _, _)| name.as_deref() == Some(field_text))?
+    } else {
+        field_text.parse::<usize>().ok()?
+    };
+
+    let type_field = find_type_field_node(scope, type_index)?;
+    let struct_node = find_descendant(&type_field, "struct_type")?;
+    let mut cursor = struct_node.walk();
+    let field_node = struct_node
+        .children(&mut cursor)
+        .filter(|c| c.kind() == "field_type")
+        .nth(field_index)?;
+
+    ref_type_index(&field_node, document, symbols)
+        .or_else(|| value_type_index(&fields.get(field_index)?.1))
+}
+
+/// Find the first `(ref $T)` inside a type node and resolve its index
+fn ref_type_index(node: &Node, document: &str, symbols: &SymbolTable) -> Option<usize> {
+    let ref_node = find_descendant(node, "ref_type_ref")?;
+    let index_node = find_child(&ref_node, "index")?;
+    symbols.resolve_type_index(&document[index_node.byte_range()])
+}
+
+/// Find the node for a function (defined or imported) by its start byte
+fn find_function_node(tree: &Tree, start_byte: usize) -> Option<Node<'_>> {
+    find_decl_node(tree, start_byte, |kind| {
+        kind == "module_field_func" || kind.starts_with("import_desc")
+    })
+}
+
+/// Find the declaration node starting at `start_byte` whose kind matches `is_decl`
+fn find_decl_node(
+    tree: &Tree,
+    start_byte: usize,
+    is_decl: impl Fn(&str) -> bool,
+) -> Option<Node<'_>> {
+    let mut node = tree
+        .root_node()
+        .descendant_for_byte_range(start_byte, start_byte)?;
+    loop {
+        if node.start_byte() == start_byte && is_decl(node.kind()) {
+            return Some(node);
+        }
+        node = node.parent()?;
+    }
+}
+
+/// The module whose index spaces are in effect at `position`: in a `.wast`
+/// script, the script module around it, otherwise the whole tree
+pub fn module_node_at<'a>(tree: &'a Tree, document: &str, position: Position) -> Node<'a> {
+    let root = tree.root_node();
+    let byte = position_to_byte(document, position.into());
+    let mut node = root.descendant_for_byte_range(byte, byte);
+    while let Some(current) = node {
+        if current.is_named() && current.kind() == "module" {
+            return current;
+        }
+        node = current.parent();
+    }
+    root
+}
+
+/// Find the `type_field` node of the type with the given index in `scope`.
+/// Type indices follow document order across `(type ...)` and `(rec ...)` fields.
+fn find_type_field_node(scope: Node<'_>, type_index: usize) -> Option<Node<'_>> {
+    let mut count = 0;
+    let mut stack = vec![scope];
+    while let Some(node) = stack.pop() {
+        if node.kind() == "type_field" {
+            if count == type_index {
+                return Some(node);
+            }
+            count += 1;
+            continue;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<Node> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    None
+}
+
+/// Full range of a type's declaration: the `(type ...)` field, or for types
+/// inside a rec group, the span from its name to the end of its definition.
+/// `scope` is the module node that declares the type.
+pub fn type_declaration_range(type_def: &TypeDef, scope: Node) -> Option<Range> {
+    let type_field = find_type_field_node(scope, type_def.index)?;
+    let parent = type_field.parent()?;
+    if parent.kind() == "module_field_type" {
+        return Some(node_to_lsp_range(&parent));
+    }
+
+    let mut range = node_to_lsp_range(&type_field);
+    if let Some(name_range) = type_def.range {
+        range.start = Range::from(name_range).start;
+    }
+    Some(range)
+}
+
+fn type_location(
+    type_index: usize,
+    symbols: &SymbolTable,
+    scope: Node,
+    uri: &Url,
+) -> Option<Location> {
+    let type_def = symbols.get_type_by_index(type_index)?;
+    let range = match type_def.range {
+        Some(range) => range.into(),
+        None => node_to_lsp_range(&find_type_field_node(scope, type_index)?.parent()?),
+    };
+    Some(Location {
+        uri: uri.clone(),
+        range,
+    })
+}
+
+fn function_location(func: &Function, tree: &Tree, uri: &Url) -> Option<Location> {
+    let range = match func.range {
+        Some(range) => range.into(),
+        None => node_to_lsp_range(&find_function_node(tree, func.start_byte)?),
+    };
+    Some(Location {
+        uri: uri.clone(),
+        range,
+    })
+}
+
+fn ancestor_of_kind<'a>(node: Node<'a>, kind: &str) -> Option<Node<'a>> {
+    let mut current = node;
+    loop {
+        if current.kind() == kind {
+            return Some(current);
+        }
+        current = current.parent()?;
+    }
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    let mut cursor = node.walk();
+    let child = node.children(&mut cursor).find(|c| c.kind() == kind);
+    child
+}
+
+fn find_descendant<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.kind() == kind {
+            return Some(child);
+        }
+        if let Some(found) = find_descendant(&child, kind) {
+            return Some(found);
+        }
+    }
+    None
+}
@@ -0,0 +1,323 @@
+use super::*;
+use crate::parser::parse_document;
+use crate::tree_sitter_bindings::create_parser;
+use tower_lsp::lsp_types::Position;
+use tree_sitter::Tree;
+
+fn create_test_tree(document: &str) -> Tree {
+    let mut parser = create_parser();
+    parser
+        .parse(document, None)
+        .expect("Failed to parse test document")
+}
+
+fn create_uri() -> String {
+    "file:///test.wat".to_string()
+}
+
+const GC_MODULE: &str = r#"(module
+  (type $point (struct (field $x i32) (field $y i32)))
+  (type $line (struct (field $from (ref $point)) (field $to (ref $point))))
+  (global $origin (ref null $point) (ref.null $point))
+  (func $length (param $l (ref $line)) (result i32)
+    (local $p (ref null $point))
+    local.get $l
+    struct.get $line $from
+    local.set $p
+    local.get $p
+    struct.get $point $x
+  )
+)"#;
+
+#[test]
+fn test_type_definition_of_param() {
+    let symbols = parse_document(GC_MODULE).expect("Failed to parse document");
+    let tree = create_test_tree(GC_MODULE);
+
+    // "local.get $l" - on "$l"
+    let location = provide_type_definition(
+        GC_MODULE,
+        &symbols,
+        &tree,
+        Position::new(6, 15),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+
+    // $line is declared on line 2
+    assert_eq!(location.range.start.line, 2);
+}
+
+#[test]
+fn test_type_definition_of_local() {
+    let symbols = parse_document(GC_MODULE).expect("Failed to parse document");
+    let tree = create_test_tree(GC_MODULE);
+
+    // "local.get $p" - on "$p"
+    let location = provide_type_definition(
+        GC_MODULE,
+        &symbols,
+        &tree,
+        Position::new(9, 15),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+
+    assert_eq!(location.range.start.line, 1);
+}
+
+#[test]
+fn test_type_definition_of_type_use_param() {
+    let document = r#"(module
+  (type $point (struct (field $x i32)))
+  (type $box (struct (field $p (ref $point))))
+  (type $take (func (param (ref $point))))
+  (func (type $take) (local (ref $box))
+    local.get 0
+    drop
+  )
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // "local.get 0" - the param declared by (type $take)
+    let location = provide_type_definition(
+        document,
+        &symbols,
+        &tree,
+        Position::new(5, 14),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+
+    // $point, not the $box of the local that follows it
+    assert_eq!(location.range.start.line, 1);
+}
+
+#[test]
+fn test_type_definition_of_global() {
+    let symbols = parse_document(GC_MODULE).expect("Failed to parse document");
+    let tree = create_test_tree(GC_MODULE);
+
+    // "(global $origin ..." - on "$origin"
+    let location = provide_type_definition(
+        GC_MODULE,
+        &symbols,
+        &tree,
+        Position::new(3, 12),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+
+    assert_eq!(location.range.start.line, 1);
+}
+
+#[test]
+fn test_type_definition_of_globals_on_one_line() {
+    let document = r#"(module
+  (type $point (struct (field $x i32)))
+  (type $line (struct (field $from (ref $point))))
+  (global $a (ref null $point) (ref.null $point)) (global $b (ref null $line) (ref.null $line))
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // "(global $b ..." - on "$b", the second global on the line
+    let location = provide_type_definition(
+        document,
+        &symbols,
+        &tree,
+        Position::new(3, 59),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+
+    assert_eq!(location.range.start.line, 2);
+}
+
+#[test]
+fn test_type_definition_of_struct_field() {
+    let symbols = parse_document(GC_MODULE).expect("Failed to parse document");
+    let tree = create_test_tree(GC_MODULE);
+
+    // "struct.get $line $from" - on "$from", whose type is (ref $point)
+    let location = provide_type_definition(
+        GC_MODULE,
+        &symbols,
+        &tree,
+        Position::new(7, 23),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+
+    assert_eq!(location.range.start.line, 1);
+}
+
+#[test]
+fn test_type_definition_of_scalar_field_is_none() {
+    let symbols = parse_document(GC_MODULE).expect("Failed to parse document");
+    let tree = create_test_tree(GC_MODULE);
+
+    // "struct.get $point $x" - $x is an i32 field
+    let location = provide_type_definition(
+        GC_MODULE,
+        &symbols,
+        &tree,
+        Position::new(10, 23),
+        &create_uri(),
+    );
+
+    assert!(location.is_none());
+}
+
+#[test]
+fn test_type_definition_of_call_ref_operand() {
+    let document = r#"(module
+  (type $binop (func (param i32 i32) (result i32)))
+  (func $apply (param $f (ref $binop)) (result i32)
+    i32.const 1
+    i32.const 2
+    local.get $f
+    call_ref $binop
+  )
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // "call_ref $binop" - on "$binop"
+    let location = provide_type_definition(
+        document,
+        &symbols,
+        &tree,
+        Position::new(6, 14),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+    assert_eq!(location.range.start.line, 1);
+
+    // "local.get $f" - param of type (ref $binop)
+    let location = provide_type_definition(
+        document,
+        &symbols,
+        &tree,
+        Position::new(5, 15),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+    assert_eq!(location.range.start.line, 1);
+}
+
+#[test]
+fn test_type_definition_in_script_module() {
+    let document = r#"(module
+  (type (func)))
+(module
+  (type (func (result i32)))
+  (func (param (ref 0)) (result i32)
+    local.get 0
+    call_ref 0))
+"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // "call_ref 0" - type 0 of the second module
+    let location = provide_type_definition(
+        document,
+        &symbols,
+        &tree,
+        Position::new(6, 13),
+        &create_uri(),
+    )
+    .expect("Expected type definition");
+    assert_eq!(location.range.start.line, 3);
+}
+
+#[test]
+fn test_implementation_lists_subtypes() {
+    let document = r#"(module
+  (type $shape (sub (struct (field $id i32))))
+  (type $circle (sub $shape (struct (field $id i32) (field $r f32))))
+  (type $unit_circle (sub final $circle (struct (field $id i32) (field $r f32))))
+  (type $other (struct))
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // On "$shape"
+    let locations = provide_implementation(
+        document,
+        &symbols,
+        &tree,
+        Position::new(1, 10),
+        &create_uri(),
+    );
+
+    let lines: Vec<u32> = locations.iter().map(|l| l.range.start.line).collect();
+    assert_eq!(lines, vec![2, 3]);
+}
+
+#[test]
+fn test_implementation_lists_matching_functions() {
+    let document = r#"(module
+  (type $binop (func (param i32 i32) (result i32)))
+  (table 2 funcref)
+  (elem (i32.const 0) $add $neg)
+  (func $add (type $binop)
+    local.get 0
+    local.get 1
+    i32.add
+  )
+  (func $sub (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.sub
+  )
+  (func $neg (param i32) (result i32)
+    i32.const 0
+    local.get 0
+    i32.sub
+  )
+  (func $dispatch (result i32)
+    i32.const 1
+    i32.const 2
+    i32.const 0
+    call_indirect (type $binop)
+  )
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // On "$binop" in the type definition
+    let locations = provide_implementation(
+        document,
+        &symbols,
+        &tree,
+        Position::new(1, 10),
+        &create_uri(),
+    );
+
+    let lines: Vec<u32> = locations.iter().map(|l| l.range.start.line).collect();
+    assert_eq!(lines, vec![4, 9]);
+}
+
+#[test]
+fn test_function_matches_type() {
+    let document = r#"(module
+  (type $unary (func (param i32) (result i32)))
+  (func $id (param i32) (result i32) local.get 0)
+  (func $nothing)
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+    let type_def = symbols.get_type_by_name("$unary").unwrap();
+
+    let id = symbols.get_function_by_name("$id").unwrap();
+    let nothing = symbols.get_function_by_name("$nothing").unwrap();
+
+    assert!(function_matches_type(
+        id, type_def, &symbols, &tree, document
+    ));
+    assert!(!function_matches_type(
+        nothing, type_def, &symbols, &tree, document
+    ));
+}
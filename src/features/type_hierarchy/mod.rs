@@ -0,0 +1,112 @@
+use crate::features::references::{identify_symbol_at_position, ReferenceTarget};
+use crate::features::type_definition::{module_node_at, type_declaration_range};
+use crate::symbols::*;
+use serde_json::json;
+use tower_lsp::lsp_types::*;
+use tree_sitter::{Node, Tree};
+
+#[cfg(test)]
+mod tests;
+
+/// Prepare the type hierarchy for the struct, array or func type under the cursor
+pub fn prepare_type_hierarchy(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    position: Position,
+    uri: &str,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let lsp_uri = Url::parse(uri).ok()?;
+
+    match identify_symbol_at_position(document, symbols, tree, position)? {
+        ReferenceTarget::Type { index, .. } => {
+            let type_def = symbols.scope_at(position.into()).get_type_by_index(index)?;
+            let scope = module_node_at(tree, document, position);
+            Some(vec![type_item(type_def, scope, &lsp_uri)])
+        }
+        _ => None,
+    }
+}
+
+/// The direct supertype of the type represented by `item`
+pub fn supertypes(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    item: &TypeHierarchyItem,
+) -> Vec<TypeHierarchyItem> {
+    let symbols = symbols.scope_at(item.selection_range.start.into());
+    let scope = module_node_at(tree, document, item.selection_range.start);
+    item_type_index(item)
+        .and_then(|index| symbols.get_type_by_index(index))
+        .and_then(|type_def| type_def.supertype)
+        .and_then(|parent| symbols.get_type_by_index(parent as usize))
+        .map(|parent| type_item(parent, scope, &item.uri))
+        .into_iter()
+        .collect()
+}
+
+/// The types that directly declare the type represented by `item` as their supertype
+pub fn subtypes(
+    document: &str,
+    symbols: &SymbolTable,
+    tree: &Tree,
+    item: &TypeHierarchyItem,
+) -> Vec<TypeHierarchyItem> {
+    let Some(index) = item_type_index(item) else {
+        return vec![];
+    };
+    let symbols = symbols.scope_at(item.selection_range.start.into());
+    let scope = module_node_at(tree, document, item.selection_range.start);
+
+    symbols
+        .types
+        .iter()
+        .filter(|type_def| type_def.supertype == Some(index as u32))
+        .map(|type_def| type_item(type_def, scope, &item.uri))
+        .collect()
+}
+
+fn item_type_index(item: &TypeHierarchyItem) -> Option<usize> {
+    Some(item.data.as_ref()?.get("type")?.as_u64()? as usize)
+}
+
+fn type_item(type_def: &TypeDef, scope: Node, uri: &Url) -> TypeHierarchyItem {
+    let selection_range = type_def.range.map(Into::into);
+    let range = type_declaration_range(type_def, scope)
+        .or(selection_range)
+        .unwrap_or_default();
+
+    let (kind, shape) = match &type_def.kind {
+        TypeKind::Struct { fields } => (
+            SymbolKind::STRUCT,
+            format!("struct, {} fields", fields.len()),
+        ),
+        TypeKind::Array { element_type, .. } => {
+            (SymbolKind::ARRAY, format!("array of {}", element_type))
+        }
+        TypeKind::Func { params, results } => (
+            SymbolKind::FUNCTION,
+            format!("func, {} params, {} results", params.len(), results.len()),
+        ),
+    };
+    let detail = if type_def.is_final {
+        shape
+    } else {
+        format!("{}, open", shape)
+    };
+
+    TypeHierarchyItem {
+        name: type_def
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("type {}", type_def.index)),
+        kind,
+        tags: None,
+        detail: Some(detail),
+        uri: uri.clone(),
+        range,
+        selection_range: selection_range.unwrap_or(range),
+        data: Some(json!({ "type": type_def.index })),
+    }
+}
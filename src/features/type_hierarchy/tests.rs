@@ -0,0 +1,140 @@
+use super::*;
+use crate::parser::parse_document;
+use crate::tree_sitter_bindings::create_parser;
+use tower_lsp::lsp_types::Position;
+use tree_sitter::Tree;
+
+fn create_test_tree(document: &str) -> Tree {
+    let mut parser = create_parser();
+    parser
+        .parse(document, None)
+        .expect("Failed to parse test document")
+}
+
+fn create_uri() -> String {
+    "file:///test.wat".to_string()
+}
+
+const SHAPES: &str = r#"(module
+  (type $shape (sub (struct (field $x f64) (field $y f64))))
+  (type $circle (sub $shape (struct (field $x f64) (field $y f64) (field $r f64))))
+  (type $rect (sub $shape (struct (field $x f64) (field $y f64) (field $w f64) (field $h f64))))
+  (type $square (sub final $rect (struct (field $x f64) (field $y f64) (field $w f64) (field $h f64))))
+)"#;
+
+fn prepare(line: u32, col: u32) -> (SymbolTable, Tree, TypeHierarchyItem) {
+    let symbols = parse_document(SHAPES).expect("Failed to parse document");
+    let tree = create_test_tree(SHAPES);
+    let item = prepare_type_hierarchy(
+        SHAPES,
+        &symbols,
+        &tree,
+        Position::new(line, col),
+        &create_uri(),
+    )
+    .expect("Expected type hierarchy item")
+    .remove(0);
+    (symbols, tree, item)
+}
+
+#[test]
+fn test_prepare_type_hierarchy() {
+    // On "$rect"
+    let (_, _, item) = prepare(3, 10);
+
+    assert_eq!(item.name, "$rect");
+    assert_eq!(item.kind, SymbolKind::STRUCT);
+    assert_eq!(item.detail.as_deref(), Some("struct, 4 fields, open"));
+    assert_eq!(item.range.start.line, 3);
+    assert_eq!(item.selection_range.start.line, 3);
+}
+
+#[test]
+fn test_supertypes() {
+    // On "$square"
+    let (symbols, tree, item) = prepare(4, 10);
+
+    let parents = supertypes(SHAPES, &symbols, &tree, &item);
+    assert_eq!(parents.len(), 1);
+    assert_eq!(parents[0].name, "$rect");
+
+    let grandparents = supertypes(SHAPES, &symbols, &tree, &parents[0]);
+    assert_eq!(grandparents.len(), 1);
+    assert_eq!(grandparents[0].name, "$shape");
+
+    assert!(supertypes(SHAPES, &symbols, &tree, &grandparents[0]).is_empty());
+}
+
+#[test]
+fn test_subtypes() {
+    // On "$shape"
+    let (symbols, tree, item) = prepare(1, 10);
+
+    let children = subtypes(SHAPES, &symbols, &tree, &item);
+    let names: Vec<&str> = children.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["$circle", "$rect"]);
+
+    let grandchildren = subtypes(SHAPES, &symbols, &tree, &children[1]);
+    assert_eq!(grandchildren.len(), 1);
+    assert_eq!(grandchildren[0].name, "$square");
+    assert_eq!(grandchildren[0].detail.as_deref(), Some("struct, 4 fields"));
+}
+
+#[test]
+fn test_rec_group_type_range() {
+    let document = r#"(module
+  (rec
+    (type $a (struct (field (ref null $b))))
+    (type $b (struct (field (ref null $a)))))
+)"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    let item = prepare_type_hierarchy(
+        document,
+        &symbols,
+        &tree,
+        Position::new(3, 11),
+        &create_uri(),
+    )
+    .expect("Expected type hierarchy item")
+    .remove(0);
+
+    assert_eq!(item.name, "$b");
+    assert_eq!(item.range.start, item.selection_range.start);
+    assert_eq!(item.range.end.line, 3);
+}
+
+#[test]
+fn test_type_hierarchy_in_script_module() {
+    let document = r#"(module
+  (type $a (sub (struct))))
+(module
+  (type $base (sub (struct)))
+  (type $derived (sub $base (struct))))
+"#;
+    let symbols = parse_document(document).expect("Failed to parse document");
+    let tree = create_test_tree(document);
+
+    // On "$derived"
+    let item = prepare_type_hierarchy(
+        document,
+        &symbols,
+        &tree,
+        Position::new(4, 10),
+        &create_uri(),
+    )
+    .expect("Expected type hierarchy item")
+    .remove(0);
+    assert_eq!(item.name, "$derived");
+    assert_eq!(item.range.start.line, 4);
+
+    let parents = supertypes(document, &symbols, &tree, &item);
+    assert_eq!(parents.len(), 1);
+    assert_eq!(parents[0].name, "$base");
+    assert_eq!(parents[0].range.start.line, 3);
+
+    let children = subtypes(document, &symbols, &tree, &parents[0]);
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].name, "$derived");
+}
@@ -0,0 +1,359 @@
+//! Descriptions of the imports a host provides.
+//!
+//! A JS or Rust host provides a module's imports that no workspace module
+//! does. A host description lists them in TOML or JSON, each with the
+//! signature written as in an import descriptor, without the keyword:
+//!
+//! ```toml
+//! [[imports]]
+//! module = "env"
+//! field = "log"
+//! kind = "func"
+//! signature = "(param i32 i32)"
+//! ```
+//!
+//! `kind` defaults to `func`. Descriptions are listed in `wat-lsp.toml`
+//! with `host = ["host/env.toml"]`.
+
+use crate::linking::ExternType;
+use crate::symbols::{ExternKind, Import, SymbolTable};
+use crate::wast_parser;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Imports a host provides
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HostInterface {
+    imports: Vec<ImportDescription>,
+}
+
+/// An import the host provides
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportDescription {
+    pub module: String,
+    pub field: String,
+    pub ty: ExternType,
+}
+
+impl ImportDescription {
+    /// The import descriptor declaring the item, named after the field,
+    /// e.g. `(func $log (param i32 i32))`
+    pub fn descriptor(&self) -> String {
+        let name: String = self
+            .field
+            .chars()
+            .map(|c| if is_id_char(c) { c } else { '_' })
+            .collect();
+        self.ty.descriptor(&format!("${}", name))
+    }
+}
+
+/// Characters allowed in a WAT `$id`
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DescriptionFile {
+    #[serde(default)]
+    imports: Vec<DescriptionEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DescriptionEntry {
+    module: String,
+    field: String,
+    #[serde(default = "default_kind")]
+    kind: String,
+    #[serde(default)]
+    signature: String,
+}
+
+fn default_kind() -> String {
+    "func".to_string()
+}
+
+/// Syntax of a host description file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptionFormat {
+    Toml,
+    Json,
+}
+
+impl DescriptionFormat {
+    /// JSON for `.json` files, TOML otherwise
+    pub fn of(path: &Path) -> Self {
+        if path.extension().is_some_and(|ext| ext == "json") {
+            DescriptionFormat::Json
+        } else {
+            DescriptionFormat::Toml
+        }
+    }
+}
+
+impl HostInterface {
+    /// Read a host description
+    pub fn parse(text: &str, format: DescriptionFormat) -> Result<Self, String> {
+        let file: DescriptionFile = match format {
+            DescriptionFormat::Toml => toml::from_str(text).map_err(|e| e.to_string())?,
+            DescriptionFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string())?,
+        };
+        let imports = file
+            .imports
+            .into_iter()
+            .map(|entry| {
+                let ty = parse_type(&entry.kind, &entry.signature).map_err(|e| {
+                    format!(
+                        "Invalid signature for {}.{}: {}",
+                        entry.module, entry.field, e
+                    )
+                })?;
+                Ok(ImportDescription {
+                    module: entry.module,
+                    field: entry.field,
+                    ty,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { imports })
+    }
+
+    /// Read a host description file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+        Self::parse(&text, DescriptionFormat::of(path))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Add the imports of another description
+    pub fn extend(&mut self, other: HostInterface) {
+        self.imports.extend(other.imports);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.imports.is_empty()
+    }
+
+    pub fn imports(&self) -> &[ImportDescription] {
+        &self.imports
+    }
+
+    /// Whether the host provides any import from `module`
+    pub fn has_module(&self, module: &str) -> bool {
+        self.imports.iter().any(|i| i.module == module)
+    }
+
+    /// The description of an import
+    pub fn find(&self, module: &str, field: &str) -> Option<&ImportDescription> {
+        self.imports
+            .iter()
+            .find(|i| i.module == module && i.field == field)
+    }
+}
+
+/// Parse a signature by declaring an import with it
+fn parse_type(kind: &str, signature: &str) -> Result<ExternType, String> {
+    if !matches!(kind, "func" | "global" | "table" | "memory" | "tag") {
+        return Err(format!("unknown kind '{}'", kind));
+    }
+    let source = format!("(module (import \"\" \"\" ({} {})))", kind, signature);
+    let symbols = wast_parser::parse_document(&source)?;
+    let import = symbols
+        .imports
+        .first()
+        .ok_or_else(|| format!("'{}' is not a {} type", signature, kind))?;
+    ExternType::of(&symbols, import.kind, import.index)
+        .ok_or_else(|| format!("'{}' is not a {} type", signature, kind))
+}
+
+/// Why an import doesn't match the host's description
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostError {
+    /// The host provides nothing from the import's module
+    UnknownModule,
+    /// The host provides the module, but not the field
+    UnknownField,
+    /// The host provides a different kind of item
+    KindMismatch { host: ExternKind },
+    /// The host's item doesn't satisfy the import's type
+    TypeMismatch {
+        import: ExternType,
+        host: ExternType,
+    },
+}
+
+impl HostError {
+    /// Message for an import of `import`
+    pub fn message(&self, import: &Import) -> String {
+        match self {
+            HostError::UnknownModule => format!(
+                "Module '{}' is not provided by the host or a linked module",
+                import.module
+            ),
+            HostError::UnknownField => format!(
+                "Host module '{}' has no import named '{}'",
+                import.module, import.field
+            ),
+            HostError::KindMismatch { host } => format!(
+                "Import '{}' expects a {}, but the host provides a {}",
+                import.qualified_name(),
+                import.kind,
+                host
+            ),
+            HostError::TypeMismatch {
+                import: expected,
+                host,
+            } => format!(
+                "Import '{}' has type {}, but the host provides {}",
+                import.qualified_name(),
+                expected,
+                host
+            ),
+        }
+    }
+}
+
+/// Check an import of `symbols` against the host's description of it
+pub fn check_host_import(
+    symbols: &SymbolTable,
+    import: &Import,
+    host: &HostInterface,
+) -> Result<(), HostError> {
+    let Some(description) = host.find(&import.module, &import.field) else {
+        return Err(if host.has_module(&import.module) {
+            HostError::UnknownField
+        } else {
+            HostError::UnknownModule
+        });
+    };
+    if description.ty.kind() != import.kind {
+        return Err(HostError::KindMismatch {
+            host: description.ty.kind(),
+        });
+    }
+
+    let Some(expected) = ExternType::of(symbols, import.kind, import.index) else {
+        return Ok(());
+    };
+    if description.ty.satisfies(&expected) {
+        Ok(())
+    } else {
+        Err(HostError::TypeMismatch {
+            import: expected,
+            host: description.ty.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wast_parser::parse_document;
+
+    const ENV: &str = r#"
+[[imports]]
+module = "env"
+field = "log"
+signature = "(param i32 i32)"
+
+[[imports]]
+module = "env"
+field = "memory"
+kind = "memory"
+signature = "1"
+
+[[imports]]
+module = "env"
+field = "now"
+signature = "(result f64)"
+"#;
+
+    #[test]
+    fn test_parse_descriptions() {
+        let host = HostInterface::parse(ENV, DescriptionFormat::Toml).unwrap();
+        assert_eq!(host.imports().len(), 3);
+        assert_eq!(
+            host.find("env", "log").unwrap().descriptor(),
+            "(func $log (param i32 i32))"
+        );
+        assert_eq!(
+            host.find("env", "memory").unwrap().descriptor(),
+            "(memory $memory 1)"
+        );
+
+        let json = r#"{ "imports": [
+            { "module": "wasi", "field": "fd-write", "kind": "global", "signature": "(mut i64)" }
+        ] }"#;
+        let host = HostInterface::parse(json, DescriptionFormat::Json).unwrap();
+        assert_eq!(
+            host.imports()[0].descriptor(),
+            "(global $fd-write (mut i64))"
+        );
+
+        let bad = "[[imports]]\nmodule = \"env\"\nfield = \"f\"\nsignature = \"(param x)\"\n";
+        let err = HostInterface::parse(bad, DescriptionFormat::Toml).unwrap_err();
+        assert!(err.starts_with("Invalid signature for env.f"), "{}", err);
+        let bad = "[[imports]]\nmodule = \"env\"\nfield = \"f\"\nkind = \"event\"\n";
+        assert!(HostInterface::parse(bad, DescriptionFormat::Toml).is_err());
+    }
+
+    #[test]
+    fn test_check_imports() {
+        let host = HostInterface::parse(ENV, DescriptionFormat::Toml).unwrap();
+        let symbols = parse_document(
+            r#"(module
+  (import "env" "log" (func (param i32 i32)))
+  (import "env" "now" (func (result i32)))
+  (import "env" "memory" (global i32))
+  (import "env" "exit" (func))
+  (import "wasi" "exit" (func)))"#,
+        )
+        .unwrap();
+        let results: Vec<_> = symbols
+            .imports
+            .iter()
+            .map(|import| check_host_import(&symbols, import, &host))
+            .collect();
+
+        assert_eq!(results[0], Ok(()));
+        assert!(matches!(results[1], Err(HostError::TypeMismatch { .. })));
+        assert_eq!(
+            results[1].as_ref().unwrap_err().message(&symbols.imports[1]),
+            "Import 'env.now' has type (func (result i32)), but the host provides (func (result f64))"
+        );
+        assert_eq!(
+            results[2],
+            Err(HostError::KindMismatch {
+                host: ExternKind::Memory
+            })
+        );
+        assert_eq!(results[3], Err(HostError::UnknownField));
+        assert_eq!(results[4], Err(HostError::UnknownModule));
+    }
+
+    #[test]
+    fn test_check_type_use_imports() {
+        let host = HostInterface::parse(ENV, DescriptionFormat::Toml).unwrap();
+        let symbols = parse_document(
+            r#"(module
+  (type $log_t (func (param i32 i32)))
+  (type $now_t (func (result i32)))
+  (import "env" "log" (func (type $log_t)))
+  (import "env" "now" (func (type $now_t))))"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            check_host_import(&symbols, &symbols.imports[0], &host),
+            Ok(())
+        );
+        assert!(matches!(
+            check_host_import(&symbols, &symbols.imports[1], &host),
+            Err(HostError::TypeMismatch { .. })
+        ));
+    }
+}
@@ -0,0 +1,1117 @@
+//! The store of runtime objects and the stack machine that runs functions.
+
+use super::module::{ImportKind, Instr, Module, SegmentMode};
+use super::run::ImportStubs;
+use super::value::{Trap, Value};
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasmparser::{ExternalKind, FuncType, RefType, ValType};
+
+const PAGE_SIZE: u64 = 65536;
+/// Pages a memory may grow to, well below the 4 GiB the spec allows, so a
+/// runaway script can't exhaust the editor's memory; growing past it fails
+/// the way an engine out of memory would
+const MAX_PAGES: u64 = 4096;
+/// Elements a table may grow to
+const MAX_TABLE_ELEMENTS: u64 = 1 << 20;
+/// Nested calls before "call stack exhausted"
+const MAX_CALL_DEPTH: usize = 10_000;
+/// Instructions a single invocation may run before it is stopped
+const FUEL: u64 = 100_000_000;
+
+/// An exported or importable runtime object, by store address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extern {
+    Func(u32),
+    Table(u32),
+    Memory(u32),
+    Global(u32),
+}
+
+/// Why instantiation failed before the module could run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkError(pub String);
+
+impl std::fmt::Display for LinkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+enum Function {
+    Wasm {
+        ty: FuncType,
+        instance: usize,
+        code: Rc<Code>,
+    },
+    /// A host function; the spectest ones only print, so they do nothing
+    Host { ty: FuncType, stub: Option<Stub> },
+}
+
+impl Function {
+    fn ty(&self) -> &FuncType {
+        match self {
+            Function::Wasm { ty, .. } | Function::Host { ty, .. } => ty,
+        }
+    }
+}
+
+/// A host function standing in for an import nothing provides
+struct Stub {
+    /// `module.name` of the import
+    name: String,
+    behavior: ImportStubs,
+}
+
+struct Code {
+    locals: Vec<ValType>,
+    body: Vec<Instr>,
+}
+
+struct Table {
+    elements: Vec<Value>,
+    max: Option<u64>,
+    ty: RefType,
+    table64: bool,
+}
+
+struct Memory {
+    data: Vec<u8>,
+    max: Option<u64>,
+    memory64: bool,
+}
+
+struct Global {
+    value: Value,
+    mutable: bool,
+    ty: ValType,
+}
+
+/// Address spaces of one instantiated module
+#[derive(Default)]
+struct Instance {
+    types: Vec<FuncType>,
+    funcs: Vec<u32>,
+    tables: Vec<u32>,
+    memories: Vec<u32>,
+    globals: Vec<u32>,
+    /// Element segments; `None` once dropped
+    elements: Vec<Option<Rc<[Value]>>>,
+    data: Vec<Option<Rc<[u8]>>>,
+    exports: HashMap<String, Extern>,
+}
+
+/// All runtime objects of a script, shared by the modules it instantiates
+pub struct Store {
+    funcs: Vec<Function>,
+    tables: Vec<Table>,
+    memories: Vec<Memory>,
+    globals: Vec<Global>,
+    instances: Vec<Instance>,
+    /// Instances available for import, by module name
+    registry: HashMap<String, usize>,
+    /// Calls to logging import stubs since the last `take_log`
+    log: Vec<String>,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Store {
+    /// A store with the `spectest` module registered
+    pub fn new() -> Self {
+        let mut store = Store {
+            funcs: Vec::new(),
+            tables: Vec::new(),
+            memories: Vec::new(),
+            globals: Vec::new(),
+            instances: Vec::new(),
+            registry: HashMap::new(),
+            log: Vec::new(),
+        };
+        store.add_spectest();
+        store
+    }
+
+    /// The host module the spec tests import from
+    fn add_spectest(&mut self) {
+        let mut exports = HashMap::new();
+        let prints: [(&str, &[ValType]); 7] = [
+            ("print", &[]),
+            ("print_i32", &[ValType::I32]),
+            ("print_i64", &[ValType::I64]),
+            ("print_f32", &[ValType::F32]),
+            ("print_f64", &[ValType::F64]),
+            ("print_i32_f32", &[ValType::I32, ValType::F32]),
+            ("print_f64_f64", &[ValType::F64, ValType::F64]),
+        ];
+        for (name, params) in prints {
+            let ty = FuncType::new(params.iter().copied(), []);
+            exports.insert(name.to_string(), Extern::Func(self.funcs.len() as u32));
+            self.funcs.push(Function::Host { ty, stub: None });
+        }
+
+        let globals = [
+            ("global_i32", Value::I32(666), ValType::I32),
+            ("global_i64", Value::I64(666), ValType::I64),
+            ("global_f32", Value::F32(666.6f32.to_bits()), ValType::F32),
+            ("global_f64", Value::F64(666.6f64.to_bits()), ValType::F64),
+        ];
+        for (name, value, ty) in globals {
+            exports.insert(name.to_string(), Extern::Global(self.globals.len() as u32));
+            self.globals.push(Global {
+                value,
+                mutable: false,
+                ty,
+            });
+        }
+
+        exports.insert("table".to_string(), Extern::Table(self.tables.len() as u32));
+        self.tables.push(Table {
+            elements: vec![Value::FuncRef(None); 10],
+            max: Some(20),
+            ty: RefType::FUNCREF,
+            table64: false,
+        });
+        exports.insert(
+            "memory".to_string(),
+            Extern::Memory(self.memories.len() as u32),
+        );
+        self.memories.push(Memory {
+            data: vec![0; PAGE_SIZE as usize],
+            max: Some(2),
+            memory64: false,
+        });
+
+        self.instances.push(Instance {
+            exports,
+            ..Instance::default()
+        });
+        self.registry.insert("spectest".to_string(), 0);
+    }
+
+    /// Make an instance's exports importable under a module name
+    pub fn register(&mut self, name: &str, instance: usize) {
+        self.registry.insert(name.to_string(), instance);
+    }
+
+    /// Provide every import of a module that no registered module exports.
+    /// Functions behave as `stubs` says; tables, memories and globals start
+    /// out null or zero. Imports too large to allocate are left unresolved.
+    pub fn stub_imports(&mut self, module: &Module, stubs: ImportStubs) {
+        for import in &module.imports {
+            let registered = self.registry.get(&import.module).copied();
+            if registered
+                .is_some_and(|index| self.instances[index].exports.contains_key(&import.name))
+            {
+                continue;
+            }
+
+            let provided = match &import.kind {
+                ImportKind::Func(type_index) => {
+                    self.funcs.push(Function::Host {
+                        ty: module.types[*type_index as usize].clone(),
+                        stub: Some(Stub {
+                            name: format!("{}.{}", import.module, import.name),
+                            behavior: stubs,
+                        }),
+                    });
+                    Extern::Func(self.funcs.len() as u32 - 1)
+                }
+                ImportKind::Table(ty) => {
+                    if ty.initial > MAX_TABLE_ELEMENTS {
+                        continue;
+                    }
+                    self.tables.push(Table {
+                        elements: vec![Value::null(ty.element_type); ty.initial as usize],
+                        max: ty.maximum,
+                        ty: ty.element_type,
+                        table64: ty.table64,
+                    });
+                    Extern::Table(self.tables.len() as u32 - 1)
+                }
+                ImportKind::Memory(ty) => {
+                    if ty.initial > MAX_PAGES {
+                        continue;
+                    }
+                    self.memories.push(Memory {
+                        data: vec![0; (ty.initial * PAGE_SIZE) as usize],
+                        max: ty.maximum,
+                        memory64: ty.memory64,
+                    });
+                    Extern::Memory(self.memories.len() as u32 - 1)
+                }
+                ImportKind::Global(ty) => {
+                    self.globals.push(Global {
+                        value: Value::default_for(ty.content_type),
+                        mutable: ty.mutable,
+                        ty: ty.content_type,
+                    });
+                    Extern::Global(self.globals.len() as u32 - 1)
+                }
+            };
+
+            let instance = registered.unwrap_or_else(|| {
+                self.instances.push(Instance::default());
+                let index = self.instances.len() - 1;
+                self.register(&import.module, index);
+                index
+            });
+            self.instances[instance]
+                .exports
+                .insert(import.name.clone(), provided);
+        }
+    }
+
+    /// Calls logged by import stubs so far, oldest first
+    pub fn take_log(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.log)
+    }
+
+    /// An export of an instance
+    pub fn export(&self, instance: usize, name: &str) -> Option<Extern> {
+        self.instances.get(instance)?.exports.get(name).copied()
+    }
+
+    /// Signature of a function
+    pub fn func_type(&self, func: u32) -> &FuncType {
+        self.funcs[func as usize].ty()
+    }
+
+    /// Current value of a global
+    pub fn global_value(&self, global: u32) -> Value {
+        self.globals[global as usize].value
+    }
+
+    /// Instantiate a module: resolve its imports, allocate its objects,
+    /// initialize segments and run its start function. Returns the
+    /// instance index.
+    ///
+    /// A trap during initialization leaves the objects it already wrote to
+    /// changed, as the spec requires; the instance itself isn't returned.
+    pub fn instantiate(&mut self, module: &Module) -> Result<Result<usize, Trap>, LinkError> {
+        let mut instance = Instance {
+            types: module.types.clone(),
+            ..Instance::default()
+        };
+
+        for import in &module.imports {
+            let provided = self
+                .registry
+                .get(&import.module)
+                .and_then(|&index| self.instances[index].exports.get(&import.name))
+                .copied()
+                .ok_or_else(|| {
+                    LinkError(format!("unknown import {}.{}", import.module, import.name))
+                })?;
+            let incompatible = || {
+                LinkError(format!(
+                    "incompatible import type for {}.{}",
+                    import.module, import.name
+                ))
+            };
+            match (&import.kind, provided) {
+                (ImportKind::Func(type_index), Extern::Func(address)) => {
+                    if self.funcs[address as usize].ty() != &module.types[*type_index as usize] {
+                        return Err(incompatible());
+                    }
+                    instance.funcs.push(address);
+                }
+                (ImportKind::Table(ty), Extern::Table(address)) => {
+                    let table = &self.tables[address as usize];
+                    if table.ty != ty.element_type
+                        || table.table64 != ty.table64
+                        || !limits_match(
+                            table.elements.len() as u64,
+                            table.max,
+                            ty.initial,
+                            ty.maximum,
+                        )
+                    {
+                        return Err(incompatible());
+                    }
+                    instance.tables.push(address);
+                }
+                (ImportKind::Memory(ty), Extern::Memory(address)) => {
+                    let memory = &self.memories[address as usize];
+                    let pages = memory.data.len() as u64 / PAGE_SIZE;
+                    if memory.memory64 != ty.memory64
+                        || !limits_match(pages, memory.max, ty.initial, ty.maximum)
+                    {
+                        return Err(incompatible());
+                    }
+                    instance.memories.push(address);
+                }
+                (ImportKind::Global(ty), Extern::Global(address)) => {
+                    let global = &self.globals[address as usize];
+                    if global.mutable != ty.mutable || global.ty != ty.content_type {
+                        return Err(incompatible());
+                    }
+                    instance.globals.push(address);
+                }
+                _ => return Err(incompatible()),
+            }
+        }
+
+        let instance_index = self.instances.len();
+
+        // Functions first, so constant expressions can take their references
+        for function in &module.functions {
+            instance.funcs.push(self.funcs.len() as u32);
+            self.funcs.push(Function::Wasm {
+                ty: module.types[function.type_index as usize].clone(),
+                instance: instance_index,
+                code: Rc::new(Code {
+                    locals: function.locals.clone(),
+                    body: function.body.clone(),
+                }),
+            });
+        }
+
+        for global in &module.globals {
+            let value = self.eval_const(&instance, &global.init);
+            instance.globals.push(self.globals.len() as u32);
+            self.globals.push(Global {
+                value,
+                mutable: global.ty.mutable,
+                ty: global.ty.content_type,
+            });
+        }
+
+        for table in &module.tables {
+            let init = match &table.init {
+                Some(init) => self.eval_const(&instance, init),
+                None => Value::null(table.ty.element_type),
+            };
+            if table.ty.initial > MAX_TABLE_ELEMENTS {
+                return Err(LinkError("table too large".to_string()));
+            }
+            instance.tables.push(self.tables.len() as u32);
+            self.tables.push(Table {
+                elements: vec![init; table.ty.initial as usize],
+                max: table.ty.maximum,
+                ty: table.ty.element_type,
+                table64: table.ty.table64,
+            });
+        }
+
+        for memory in &module.memories {
+            if memory.initial > MAX_PAGES {
+                return Err(LinkError("memory too large".to_string()));
+            }
+            instance.memories.push(self.memories.len() as u32);
+            self.memories.push(Memory {
+                data: vec![0; (memory.initial * PAGE_SIZE) as usize],
+                max: memory.maximum,
+                memory64: memory.memory64,
+            });
+        }
+
+        for export in &module.exports {
+            let index = export.index as usize;
+            let item = match export.kind {
+                ExternalKind::Func | ExternalKind::FuncExact => Extern::Func(instance.funcs[index]),
+                ExternalKind::Table => Extern::Table(instance.tables[index]),
+                ExternalKind::Memory => Extern::Memory(instance.memories[index]),
+                ExternalKind::Global => Extern::Global(instance.globals[index]),
+                // Decoding rejects modules with tags
+                ExternalKind::Tag => continue,
+            };
+            instance.exports.insert(export.name.clone(), item);
+        }
+
+        for element in &module.elements {
+            let items: Rc<[Value]> = element
+                .items
+                .iter()
+                .map(|item| self.eval_const(&instance, item))
+                .collect();
+            instance.elements.push(Some(items));
+        }
+        for data in &module.data {
+            instance.data.push(Some(data.bytes.as_slice().into()));
+        }
+
+        self.instances.push(instance);
+
+        // Active segments, then the start function
+        let initialized = (|| {
+            for (index, element) in module.elements.iter().enumerate() {
+                match &element.mode {
+                    SegmentMode::Active {
+                        index: table,
+                        offset,
+                    } => {
+                        let offset = self.eval_const(&self.instances[instance_index], offset);
+                        let len = element.items.len() as u64;
+                        self.table_init(
+                            instance_index,
+                            *table,
+                            index as u32,
+                            offset.address(),
+                            0,
+                            len,
+                        )?;
+                        self.instances[instance_index].elements[index] = None;
+                    }
+                    SegmentMode::Declared => {
+                        self.instances[instance_index].elements[index] = None;
+                    }
+                    SegmentMode::Passive => {}
+                }
+            }
+            for (index, data) in module.data.iter().enumerate() {
+                if let SegmentMode::Active {
+                    index: memory,
+                    offset,
+                } = &data.mode
+                {
+                    let offset = self.eval_const(&self.instances[instance_index], offset);
+                    let len = data.bytes.len() as u64;
+                    self.memory_init(
+                        instance_index,
+                        *memory,
+                        index as u32,
+                        offset.address(),
+                        0,
+                        len,
+                    )?;
+                    self.instances[instance_index].data[index] = None;
+                }
+            }
+            if let Some(start) = module.start {
+                let address = self.instances[instance_index].funcs[start as usize];
+                self.invoke(address, Vec::new())?;
+            }
+            Ok(())
+        })();
+
+        Ok(initialized.map(|()| instance_index))
+    }
+
+    /// Evaluate a constant expression
+    fn eval_const(&self, instance: &Instance, expr: &[Instr]) -> Value {
+        let mut stack = Vec::new();
+        for instr in expr {
+            match instr {
+                Instr::Const(value) | Instr::RefNull(value) => stack.push(*value),
+                Instr::GlobalGet(index) => {
+                    stack.push(self.globals[instance.globals[*index as usize] as usize].value)
+                }
+                Instr::RefFunc(index) => {
+                    stack.push(Value::FuncRef(Some(instance.funcs[*index as usize])))
+                }
+                Instr::Binary(op) => {
+                    let b = stack.pop().expect("validated constant expression");
+                    let a = stack.pop().expect("validated constant expression");
+                    // The extended constant operators never trap
+                    stack.push(op(a, b).unwrap_or(a));
+                }
+                _ => break,
+            }
+        }
+        stack.pop().expect("validated constant expression")
+    }
+
+    /// Call a function with arguments, returning its results
+    pub fn invoke(&mut self, func: u32, args: Vec<Value>) -> Result<Vec<Value>, Trap> {
+        let ty = self.funcs[func as usize].ty();
+        if args.len() != ty.params().len()
+            || !args
+                .iter()
+                .zip(ty.params())
+                .all(|(arg, &ty)| arg.has_type(ty))
+        {
+            return Err(Trap::new("type mismatch"));
+        }
+        Machine {
+            store: self,
+            stack: args,
+            frames: Vec::new(),
+            fuel: FUEL,
+        }
+        .run(func)
+    }
+
+    fn table_init(
+        &mut self,
+        instance: usize,
+        table: u32,
+        elem: u32,
+        dst: u64,
+        src: u64,
+        len: u64,
+    ) -> Result<(), Trap> {
+        let instance = &self.instances[instance];
+        let items = instance.elements[elem as usize]
+            .clone()
+            .unwrap_or_else(|| Rc::from([]));
+        let table = &mut self.tables[instance.tables[table as usize] as usize];
+        let (Some(src_end), Some(dst_end)) = (src.checked_add(len), dst.checked_add(len)) else {
+            return Err(Trap::table_out_of_bounds());
+        };
+        if src_end > items.len() as u64 || dst_end > table.elements.len() as u64 {
+            return Err(Trap::table_out_of_bounds());
+        }
+        table.elements[dst as usize..dst_end as usize]
+            .copy_from_slice(&items[src as usize..src_end as usize]);
+        Ok(())
+    }
+
+    fn memory_init(
+        &mut self,
+        instance: usize,
+        memory: u32,
+        data: u32,
+        dst: u64,
+        src: u64,
+        len: u64,
+    ) -> Result<(), Trap> {
+        let instance = &self.instances[instance];
+        let bytes = instance.data[data as usize]
+            .clone()
+            .unwrap_or_else(|| Rc::from([]));
+        let memory = &mut self.memories[instance.memories[memory as usize] as usize];
+        let (Some(src_end), Some(dst_end)) = (src.checked_add(len), dst.checked_add(len)) else {
+            return Err(Trap::memory_out_of_bounds());
+        };
+        if src_end > bytes.len() as u64 || dst_end > memory.data.len() as u64 {
+            return Err(Trap::memory_out_of_bounds());
+        }
+        memory.data[dst as usize..dst_end as usize]
+            .copy_from_slice(&bytes[src as usize..src_end as usize]);
+        Ok(())
+    }
+}
+
+/// Whether an import's limits are satisfied by the provided object's
+fn limits_match(size: u64, max: Option<u64>, min: u64, expected_max: Option<u64>) -> bool {
+    size >= min
+        && match (max, expected_max) {
+            (_, None) => true,
+            (Some(max), Some(expected_max)) => max <= expected_max,
+            (None, Some(_)) => false,
+        }
+}
+
+/// A branch target inside a function
+struct Label {
+    /// Values a branch carries: results for blocks, parameters for loops
+    arity: usize,
+    /// Operand stack height below the label's values
+    height: usize,
+    /// Instruction a branch continues at
+    continuation: usize,
+}
+
+struct Frame {
+    instance: usize,
+    code: Rc<Code>,
+    pc: usize,
+    locals: Vec<Value>,
+    labels: Vec<Label>,
+    /// Operand stack height at entry
+    base: usize,
+    results: usize,
+}
+
+/// One invocation's operand stack and call frames
+struct Machine<'a> {
+    store: &'a mut Store,
+    stack: Vec<Value>,
+    frames: Vec<Frame>,
+    fuel: u64,
+}
+
+impl Machine<'_> {
+    fn run(mut self, func: u32) -> Result<Vec<Value>, Trap> {
+        self.call(func)?;
+        while !self.frames.is_empty() {
+            self.step()?;
+        }
+        Ok(self.stack)
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("validated operand stack")
+    }
+
+    fn frame(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("a running frame")
+    }
+
+    fn instance(&self) -> &Instance {
+        let frame = self.frames.last().expect("a running frame");
+        &self.store.instances[frame.instance]
+    }
+
+    /// Enter a function, taking its arguments from the stack
+    fn call(&mut self, func: u32) -> Result<(), Trap> {
+        let (ty, instance, code) = match &self.store.funcs[func as usize] {
+            Function::Host { ty, stub } => {
+                let args = self.stack.split_off(self.stack.len() - ty.params().len());
+                let Some(stub) = stub else {
+                    return Ok(());
+                };
+                match stub.behavior {
+                    ImportStubs::Trap => {
+                        return Err(Trap::new(format!("called unprovided import {}", stub.name)))
+                    }
+                    ImportStubs::Zero => {}
+                    ImportStubs::Log => {
+                        let args: String = args.iter().map(|arg| format!(" {}", arg)).collect();
+                        self.store.log.push(format!("{}{}", stub.name, args));
+                    }
+                }
+                let results = ty.results().iter().map(|&ty| Value::default_for(ty));
+                self.stack.extend(results);
+                return Ok(());
+            }
+            Function::Wasm { ty, instance, code } => (ty, *instance, Rc::clone(code)),
+        };
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Trap::new("call stack exhausted"));
+        }
+
+        let base = self.stack.len() - ty.params().len();
+        let mut locals: Vec<Value> = self.stack.drain(base..).collect();
+        locals.extend(code.locals.iter().map(|&ty| Value::default_for(ty)));
+        let results = ty.results().len();
+        self.frames.push(Frame {
+            instance,
+            pc: 0,
+            locals,
+            labels: vec![Label {
+                arity: results,
+                height: base,
+                continuation: code.body.len(),
+            }],
+            code,
+            base,
+            results,
+        });
+        Ok(())
+    }
+
+    /// Leave the current function, keeping its results on the stack
+    fn return_from_frame(&mut self) {
+        let frame = self.frames.pop().expect("a running frame");
+        let results = self.stack.split_off(self.stack.len() - frame.results);
+        self.stack.truncate(frame.base);
+        self.stack.extend(results);
+    }
+
+    /// Branch to the label `depth` levels out
+    fn branch(&mut self, depth: u32) {
+        let frame = self.frames.last_mut().expect("a running frame");
+        let target = frame.labels.len() - 1 - depth as usize;
+        let label = &frame.labels[target];
+        let values = self.stack.split_off(self.stack.len() - label.arity);
+        self.stack.truncate(label.height);
+        self.stack.extend(values);
+        frame.pc = label.continuation;
+        frame.labels.truncate(target);
+    }
+
+    /// The function a `call_indirect` selects, checked against its type
+    fn indirect_target(&mut self, type_index: u32, table: u32) -> Result<u32, Trap> {
+        let index = self.pop().address();
+        let instance = self.instance();
+        let expected = &instance.types[type_index as usize];
+        let table = &self.store.tables[instance.tables[table as usize] as usize];
+        let func = match table.elements.get(index as usize) {
+            None => return Err(Trap::new("undefined element")),
+            Some(Value::FuncRef(Some(func))) => *func,
+            Some(_) => return Err(Trap::new("uninitialized element")),
+        };
+        if self.store.funcs[func as usize].ty() != expected {
+            return Err(Trap::new("indirect call type mismatch"));
+        }
+        Ok(func)
+    }
+
+    fn memory(&mut self, index: u32) -> &mut Memory {
+        let frame = self.frames.last().expect("a running frame");
+        let address = self.store.instances[frame.instance].memories[index as usize];
+        &mut self.store.memories[address as usize]
+    }
+
+    fn table(&mut self, index: u32) -> &mut Table {
+        let frame = self.frames.last().expect("a running frame");
+        let address = self.store.instances[frame.instance].tables[index as usize];
+        &mut self.store.tables[address as usize]
+    }
+
+    /// Run one instruction of the current frame
+    fn step(&mut self) -> Result<(), Trap> {
+        if self.fuel == 0 {
+            return Err(Trap::new("fuel exhausted: the script ran too long"));
+        }
+        self.fuel -= 1;
+
+        let frame = self.frame();
+        if frame.pc >= frame.code.body.len() {
+            self.return_from_frame();
+            return Ok(());
+        }
+        let code = Rc::clone(&frame.code);
+        let pc = frame.pc;
+        frame.pc += 1;
+
+        match &code.body[pc] {
+            Instr::Unreachable => return Err(Trap::unreachable()),
+            Instr::Nop => {}
+            Instr::Block {
+                params,
+                results,
+                end,
+            } => {
+                let height = self.stack.len() - *params as usize;
+                self.frame().labels.push(Label {
+                    arity: *results as usize,
+                    height,
+                    continuation: *end as usize + 1,
+                });
+            }
+            Instr::Loop { params } => {
+                let height = self.stack.len() - *params as usize;
+                self.frame().labels.push(Label {
+                    arity: *params as usize,
+                    height,
+                    continuation: pc,
+                });
+            }
+            Instr::If {
+                params,
+                results,
+                else_,
+                end,
+            } => {
+                let condition = self.pop().i32();
+                let height = self.stack.len() - *params as usize;
+                let frame = self.frame();
+                frame.labels.push(Label {
+                    arity: *results as usize,
+                    height,
+                    continuation: *end as usize + 1,
+                });
+                if condition == 0 {
+                    frame.pc = if else_ == end {
+                        *end as usize
+                    } else {
+                        *else_ as usize + 1
+                    };
+                }
+            }
+            // Reached at the end of the then branch
+            Instr::Else { end } => self.frame().pc = *end as usize,
+            Instr::End => {
+                let frame = self.frame();
+                frame.labels.pop();
+                if frame.labels.is_empty() {
+                    self.return_from_frame();
+                }
+            }
+            Instr::Br(depth) => self.branch(*depth),
+            Instr::BrIf(depth) => {
+                if self.pop().i32() != 0 {
+                    self.branch(*depth);
+                }
+            }
+            Instr::BrTable(depths) => {
+                let index = self.pop().i32() as u32 as usize;
+                let depth = depths.get(index).unwrap_or(&depths[depths.len() - 1]);
+                self.branch(*depth);
+            }
+            Instr::Return => self.return_from_frame(),
+            Instr::Call(index) => {
+                let func = self.instance().funcs[*index as usize];
+                self.call(func)?;
+            }
+            Instr::CallIndirect { type_index, table } => {
+                let func = self.indirect_target(*type_index, *table)?;
+                self.call(func)?;
+            }
+            Instr::ReturnCall(index) => {
+                let func = self.instance().funcs[*index as usize];
+                self.tail_call(func)?;
+            }
+            Instr::ReturnCallIndirect { type_index, table } => {
+                let func = self.indirect_target(*type_index, *table)?;
+                self.tail_call(func)?;
+            }
+            Instr::Drop => {
+                self.pop();
+            }
+            Instr::Select => {
+                let condition = self.pop().i32();
+                let b = self.pop();
+                let a = self.pop();
+                self.stack.push(if condition != 0 { a } else { b });
+            }
+            Instr::LocalGet(index) => {
+                let value = self.frame().locals[*index as usize];
+                self.stack.push(value);
+            }
+            Instr::LocalSet(index) => {
+                let value = self.pop();
+                self.frame().locals[*index as usize] = value;
+            }
+            Instr::LocalTee(index) => {
+                let value = *self.stack.last().expect("validated operand stack");
+                self.frame().locals[*index as usize] = value;
+            }
+            Instr::GlobalGet(index) => {
+                let address = self.instance().globals[*index as usize];
+                self.stack.push(self.store.globals[address as usize].value);
+            }
+            Instr::GlobalSet(index) => {
+                let value = self.pop();
+                let address = self.instance().globals[*index as usize];
+                self.store.globals[address as usize].value = value;
+            }
+            Instr::Load {
+                ty,
+                bytes,
+                signed,
+                memory,
+                offset,
+            } => {
+                let address = self.pop().address();
+                let memory = self.memory(*memory);
+                let start = address
+                    .checked_add(*offset)
+                    .filter(|start| {
+                        start
+                            .checked_add(*bytes as u64)
+                            .is_some_and(|end| end <= memory.data.len() as u64)
+                    })
+                    .ok_or_else(Trap::memory_out_of_bounds)? as usize;
+                let mut raw = [0u8; 8];
+                raw[..*bytes as usize]
+                    .copy_from_slice(&memory.data[start..start + *bytes as usize]);
+                let mut bits = u64::from_le_bytes(raw);
+                if *signed {
+                    let shift = 64 - 8 * *bytes as u32;
+                    bits = (((bits << shift) as i64) >> shift) as u64;
+                }
+                self.stack.push(match ty {
+                    ValType::I32 => Value::I32(bits as i32),
+                    ValType::F32 => Value::F32(bits as u32),
+                    ValType::F64 => Value::F64(bits),
+                    _ => Value::I64(bits as i64),
+                });
+            }
+            Instr::Store {
+                bytes,
+                memory,
+                offset,
+            } => {
+                let bits = match self.pop() {
+                    Value::I32(value) => value as u32 as u64,
+                    Value::I64(value) => value as u64,
+                    Value::F32(bits) => bits as u64,
+                    Value::F64(bits) => bits,
+                    other => unreachable!("stored a reference {:?}", other),
+                };
+                let address = self.pop().address();
+                let memory = self.memory(*memory);
+                let start = address
+                    .checked_add(*offset)
+                    .filter(|start| {
+                        start
+                            .checked_add(*bytes as u64)
+                            .is_some_and(|end| end <= memory.data.len() as u64)
+                    })
+                    .ok_or_else(Trap::memory_out_of_bounds)? as usize;
+                memory.data[start..start + *bytes as usize]
+                    .copy_from_slice(&bits.to_le_bytes()[..*bytes as usize]);
+            }
+            Instr::MemorySize(index) => {
+                let memory = self.memory(*index);
+                let pages = memory.data.len() as u64 / PAGE_SIZE;
+                let value = address_value(memory.memory64, pages);
+                self.stack.push(value);
+            }
+            Instr::MemoryGrow(index) => {
+                let delta = self.pop().address();
+                let memory = self.memory(*index);
+                let pages = memory.data.len() as u64 / PAGE_SIZE;
+                let limit = memory.max.unwrap_or(u64::MAX).min(MAX_PAGES);
+                let result = match pages.checked_add(delta) {
+                    Some(new_pages) if new_pages <= limit => {
+                        memory.data.resize((new_pages * PAGE_SIZE) as usize, 0);
+                        pages
+                    }
+                    _ => u64::MAX,
+                };
+                let value = address_value(memory.memory64, result);
+                self.stack.push(value);
+            }
+            Instr::MemoryInit { data, memory } => {
+                let len = self.pop().i32() as u32 as u64;
+                let src = self.pop().i32() as u32 as u64;
+                let dst = self.pop().address();
+                let instance = self.frames.last().expect("a running frame").instance;
+                self.store
+                    .memory_init(instance, *memory, *data, dst, src, len)?;
+            }
+            Instr::DataDrop(index) => {
+                let instance = self.frames.last().expect("a running frame").instance;
+                self.store.instances[instance].data[*index as usize] = None;
+            }
+            Instr::MemoryCopy { dst, src } => {
+                let len = self.pop().address();
+                let src_offset = self.pop().address();
+                let dst_offset = self.pop().address();
+                let src_len = self.memory(*src).data.len() as u64;
+                let dst_len = self.memory(*dst).data.len() as u64;
+                if src_offset.checked_add(len).is_none_or(|end| end > src_len)
+                    || dst_offset.checked_add(len).is_none_or(|end| end > dst_len)
+                {
+                    return Err(Trap::memory_out_of_bounds());
+                }
+                let bytes = self.memory(*src).data
+                    [src_offset as usize..(src_offset + len) as usize]
+                    .to_vec();
+                self.memory(*dst).data[dst_offset as usize..(dst_offset + len) as usize]
+                    .copy_from_slice(&bytes);
+            }
+            Instr::MemoryFill(index) => {
+                let len = self.pop().address();
+                let value = self.pop().i32() as u8;
+                let dst = self.pop().address();
+                let memory = self.memory(*index);
+                if dst
+                    .checked_add(len)
+                    .is_none_or(|end| end > memory.data.len() as u64)
+                {
+                    return Err(Trap::memory_out_of_bounds());
+                }
+                memory.data[dst as usize..(dst + len) as usize].fill(value);
+            }
+            Instr::Const(value) | Instr::RefNull(value) => self.stack.push(*value),
+            Instr::Unary(op) => {
+                let a = self.pop();
+                self.stack.push(op(a)?);
+            }
+            Instr::Binary(op) => {
+                let b = self.pop();
+                let a = self.pop();
+                self.stack.push(op(a, b)?);
+            }
+            Instr::RefIsNull => {
+                let value = self.pop();
+                self.stack.push(Value::I32(value.is_null() as i32));
+            }
+            Instr::RefFunc(index) => {
+                let func = self.instance().funcs[*index as usize];
+                self.stack.push(Value::FuncRef(Some(func)));
+            }
+            Instr::TableGet(index) => {
+                let element = self.pop().address();
+                let table = self.table(*index);
+                let value = *table
+                    .elements
+                    .get(element as usize)
+                    .ok_or_else(Trap::table_out_of_bounds)?;
+                self.stack.push(value);
+            }
+            Instr::TableSet(index) => {
+                let value = self.pop();
+                let element = self.pop().address();
+                let table = self.table(*index);
+                *table
+                    .elements
+                    .get_mut(element as usize)
+                    .ok_or_else(Trap::table_out_of_bounds)? = value;
+            }
+            Instr::TableSize(index) => {
+                let table = self.table(*index);
+                let value = address_value(table.table64, table.elements.len() as u64);
+                self.stack.push(value);
+            }
+            Instr::TableGrow(index) => {
+                let delta = self.pop().address();
+                let init = self.pop();
+                let table = self.table(*index);
+                let size = table.elements.len() as u64;
+                let limit = table.max.unwrap_or(u64::MAX).min(MAX_TABLE_ELEMENTS);
+                let result = match size.checked_add(delta) {
+                    Some(new_size) if new_size <= limit => {
+                        table.elements.resize(new_size as usize, init);
+                        size
+                    }
+                    _ => u64::MAX,
+                };
+                let value = address_value(table.table64, result);
+                self.stack.push(value);
+            }
+            Instr::TableFill(index) => {
+                let len = self.pop().address();
+                let value = self.pop();
+                let dst = self.pop().address();
+                let table = self.table(*index);
+                if dst
+                    .checked_add(len)
+                    .is_none_or(|end| end > table.elements.len() as u64)
+                {
+                    return Err(Trap::table_out_of_bounds());
+                }
+                table.elements[dst as usize..(dst + len) as usize].fill(value);
+            }
+            Instr::TableCopy { dst, src } => {
+                let len = self.pop().address();
+                let src_offset = self.pop().address();
+                let dst_offset = self.pop().address();
+                let src_len = self.table(*src).elements.len() as u64;
+                let dst_len = self.table(*dst).elements.len() as u64;
+                if src_offset.checked_add(len).is_none_or(|end| end > src_len)
+                    || dst_offset.checked_add(len).is_none_or(|end| end > dst_len)
+                {
+                    return Err(Trap::table_out_of_bounds());
+                }
+                let values = self.table(*src).elements
+                    [src_offset as usize..(src_offset + len) as usize]
+                    .to_vec();
+                self.table(*dst).elements[dst_offset as usize..(dst_offset + len) as usize]
+                    .copy_from_slice(&values);
+            }
+            Instr::TableInit { elem, table } => {
+                let len = self.pop().i32() as u32 as u64;
+                let src = self.pop().i32() as u32 as u64;
+                let dst = self.pop().address();
+                let instance = self.frames.last().expect("a running frame").instance;
+                self.store
+                    .table_init(instance, *table, *elem, dst, src, len)?;
+            }
+            Instr::ElemDrop(index) => {
+                let instance = self.frames.last().expect("a running frame").instance;
+                self.store.instances[instance].elements[*index as usize] = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the current frame with a call to `func`
+    fn tail_call(&mut self, func: u32) -> Result<(), Trap> {
+        let params = self.store.funcs[func as usize].ty().params().len();
+        let args = self.stack.split_off(self.stack.len() - params);
+        let frame = self.frames.pop().expect("a running frame");
+        self.stack.truncate(frame.base);
+        self.stack.extend(args);
+        self.call(func)
+    }
+}
+
+/// A size or address result, i64 for 64-bit memories and tables
+fn address_value(is_64: bool, value: u64) -> Value {
+    if is_64 {
+        Value::I64(value as i64)
+    } else {
+        Value::I32(value as i32)
+    }
+}
@@ -0,0 +1,27 @@
+//! Embedded interpreter for running `.wast` spec-test scripts.
+//!
+//! Modules are encoded with the wast crate, validated and decoded with
+//! wasmparser, and run by a small stack machine. It covers the MVP plus the
+//! sign-extension, saturating conversion, multi-value, bulk memory and
+//! reference types proposals; modules using anything else are reported as
+//! unsupported rather than run.
+//!
+//! Besides whole scripts, a single export of a module can be run with its
+//! imports stubbed out, for quick experiments.
+//!
+//! Always available: it needs neither tree-sitter nor the LSP types.
+
+mod exec;
+mod module;
+mod run;
+mod script;
+mod value;
+
+pub use exec::{Extern, LinkError, Store};
+pub use module::{Module, ModuleError};
+pub use run::{run_export, ImportStubs, RunOutput};
+pub use script::{run_script, AssertionResult, Outcome};
+pub use value::{Trap, Value};
+
+#[cfg(test)]
+mod tests;
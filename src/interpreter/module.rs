@@ -0,0 +1,781 @@
+//! Decoding a binary module into the interpreter's own instruction form.
+//!
+//! Decoding resolves what the executor would otherwise look up on every
+//! step: block types become value counts and each block records where its
+//! `else` and `end` are.
+
+use super::value::{self as ops, BinaryOp, UnaryOp, Value};
+use wasmparser::{
+    BlockType, CompositeInnerType, DataKind, ElementItems, ElementKind, ExternalKind, FuncType,
+    GlobalType, MemoryType, Operator, OperatorsReader, Payload, TableInit, TableType, TypeRef,
+    ValType, Validator,
+};
+
+/// Why a module can't be run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleError {
+    /// The binary is malformed or fails validation
+    Invalid(String),
+    /// The module is valid but uses a feature the interpreter doesn't run
+    Unsupported(String),
+}
+
+impl std::fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleError::Invalid(message) => write!(f, "invalid module: {}", message),
+            ModuleError::Unsupported(feature) => write!(f, "unsupported: {}", feature),
+        }
+    }
+}
+
+impl From<wasmparser::BinaryReaderError> for ModuleError {
+    fn from(error: wasmparser::BinaryReaderError) -> Self {
+        ModuleError::Invalid(error.message().to_string())
+    }
+}
+
+/// A validated module, ready to instantiate
+#[derive(Debug, Default)]
+pub struct Module {
+    pub(crate) types: Vec<FuncType>,
+    pub(crate) imports: Vec<Import>,
+    pub(crate) functions: Vec<Function>,
+    pub(crate) tables: Vec<Table>,
+    pub(crate) memories: Vec<MemoryType>,
+    pub(crate) globals: Vec<Global>,
+    pub(crate) exports: Vec<Export>,
+    pub(crate) start: Option<u32>,
+    pub(crate) elements: Vec<Element>,
+    pub(crate) data: Vec<Data>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Import {
+    pub module: String,
+    pub name: String,
+    pub kind: ImportKind,
+}
+
+#[derive(Debug)]
+pub(crate) enum ImportKind {
+    Func(u32),
+    Table(TableType),
+    Memory(MemoryType),
+    Global(GlobalType),
+}
+
+#[derive(Debug)]
+pub(crate) struct Function {
+    pub type_index: u32,
+    pub locals: Vec<ValType>,
+    pub body: Vec<Instr>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Table {
+    pub ty: TableType,
+    /// Initial element for every slot; null when absent
+    pub init: Option<Vec<Instr>>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Global {
+    pub ty: GlobalType,
+    pub init: Vec<Instr>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Export {
+    pub name: String,
+    pub kind: ExternalKind,
+    pub index: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct Element {
+    /// One constant expression per item
+    pub items: Vec<Vec<Instr>>,
+    pub mode: SegmentMode,
+}
+
+#[derive(Debug)]
+pub(crate) struct Data {
+    pub bytes: Vec<u8>,
+    pub mode: SegmentMode,
+}
+
+#[derive(Debug)]
+pub(crate) enum SegmentMode {
+    Passive,
+    /// Copied into the table or memory at the offset during instantiation
+    Active {
+        index: u32,
+        offset: Vec<Instr>,
+    },
+    /// Only declares functions for `ref.func`; dropped at instantiation
+    Declared,
+}
+
+/// A decoded instruction. Block instructions hold instruction indices into
+/// the function body.
+#[derive(Debug, Clone)]
+pub(crate) enum Instr {
+    Unreachable,
+    Nop,
+    Block {
+        params: u32,
+        results: u32,
+        end: u32,
+    },
+    Loop {
+        params: u32,
+    },
+    /// `else_` is the index of the `else`, or of the `end` when there is none
+    If {
+        params: u32,
+        results: u32,
+        else_: u32,
+        end: u32,
+    },
+    Else {
+        end: u32,
+    },
+    End,
+    Br(u32),
+    BrIf(u32),
+    /// Label depths, with the default last
+    BrTable(Box<[u32]>),
+    Return,
+    Call(u32),
+    CallIndirect {
+        type_index: u32,
+        table: u32,
+    },
+    ReturnCall(u32),
+    ReturnCallIndirect {
+        type_index: u32,
+        table: u32,
+    },
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+    /// Loads `bytes` bytes, extended to `ty`
+    Load {
+        ty: ValType,
+        bytes: u8,
+        signed: bool,
+        memory: u32,
+        offset: u64,
+    },
+    /// Stores the low `bytes` bytes of the operand
+    Store {
+        bytes: u8,
+        memory: u32,
+        offset: u64,
+    },
+    MemorySize(u32),
+    MemoryGrow(u32),
+    MemoryInit {
+        data: u32,
+        memory: u32,
+    },
+    DataDrop(u32),
+    MemoryCopy {
+        dst: u32,
+        src: u32,
+    },
+    MemoryFill(u32),
+    Const(Value),
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    RefNull(Value),
+    RefIsNull,
+    RefFunc(u32),
+    TableGet(u32),
+    TableSet(u32),
+    TableSize(u32),
+    TableGrow(u32),
+    TableFill(u32),
+    TableCopy {
+        dst: u32,
+        src: u32,
+    },
+    TableInit {
+        elem: u32,
+        table: u32,
+    },
+    ElemDrop(u32),
+}
+
+impl Module {
+    /// Validate and decode a binary module
+    pub fn new(bytes: &[u8]) -> Result<Module, ModuleError> {
+        Validator::new().validate_all(bytes)?;
+
+        let mut module = Module::default();
+        let mut function_types = Vec::new();
+        for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+            match payload? {
+                Payload::TypeSection(reader) => {
+                    for group in reader {
+                        for ty in group?.into_types() {
+                            match ty.composite_type.inner {
+                                CompositeInnerType::Func(ty) => module.types.push(ty),
+                                _ => return Err(unsupported("GC types")),
+                            }
+                        }
+                    }
+                }
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import?;
+                        let kind = match import.ty {
+                            TypeRef::Func(index) | TypeRef::FuncExact(index) => {
+                                ImportKind::Func(index)
+                            }
+                            TypeRef::Table(ty) => ImportKind::Table(ty),
+                            TypeRef::Memory(ty) => ImportKind::Memory(ty),
+                            TypeRef::Global(ty) => ImportKind::Global(ty),
+                            TypeRef::Tag(_) => return Err(unsupported("exception handling")),
+                        };
+                        module.imports.push(Import {
+                            module: import.module.to_string(),
+                            name: import.name.to_string(),
+                            kind,
+                        });
+                    }
+                }
+                Payload::FunctionSection(reader) => {
+                    for type_index in reader {
+                        function_types.push(type_index?);
+                    }
+                }
+                Payload::TableSection(reader) => {
+                    for table in reader {
+                        let table = table?;
+                        let init = match table.init {
+                            TableInit::RefNull => None,
+                            TableInit::Expr(expr) => {
+                                Some(decode_const(&module, expr.get_operators_reader())?)
+                            }
+                        };
+                        module.tables.push(Table { ty: table.ty, init });
+                    }
+                }
+                Payload::MemorySection(reader) => {
+                    for memory in reader {
+                        module.memories.push(memory?);
+                    }
+                }
+                Payload::TagSection(_) => return Err(unsupported("exception handling")),
+                Payload::GlobalSection(reader) => {
+                    for global in reader {
+                        let global = global?;
+                        let init = decode_const(&module, global.init_expr.get_operators_reader())?;
+                        module.globals.push(Global {
+                            ty: global.ty,
+                            init,
+                        });
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export?;
+                        module.exports.push(Export {
+                            name: export.name.to_string(),
+                            kind: export.kind,
+                            index: export.index,
+                        });
+                    }
+                }
+                Payload::StartSection { func, .. } => module.start = Some(func),
+                Payload::ElementSection(reader) => {
+                    for element in reader {
+                        let element = element?;
+                        let items = match element.items {
+                            ElementItems::Functions(indices) => {
+                                let mut items = Vec::new();
+                                for index in indices {
+                                    items.push(vec![Instr::RefFunc(index?), Instr::End]);
+                                }
+                                items
+                            }
+                            ElementItems::Expressions(_, exprs) => {
+                                let mut items = Vec::new();
+                                for expr in exprs {
+                                    items
+                                        .push(decode_const(&module, expr?.get_operators_reader())?);
+                                }
+                                items
+                            }
+                        };
+                        let mode = match element.kind {
+                            ElementKind::Passive => SegmentMode::Passive,
+                            ElementKind::Declared => SegmentMode::Declared,
+                            ElementKind::Active {
+                                table_index,
+                                offset_expr,
+                            } => SegmentMode::Active {
+                                index: table_index.unwrap_or(0),
+                                offset: decode_const(&module, offset_expr.get_operators_reader())?,
+                            },
+                        };
+                        module.elements.push(Element { items, mode });
+                    }
+                }
+                Payload::DataSection(reader) => {
+                    for data in reader {
+                        let data = data?;
+                        let mode = match data.kind {
+                            DataKind::Passive => SegmentMode::Passive,
+                            DataKind::Active {
+                                memory_index,
+                                offset_expr,
+                            } => SegmentMode::Active {
+                                index: memory_index,
+                                offset: decode_const(&module, offset_expr.get_operators_reader())?,
+                            },
+                        };
+                        module.data.push(Data {
+                            bytes: data.data.to_vec(),
+                            mode,
+                        });
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let type_index = function_types[module.functions.len()];
+                    let mut locals = Vec::new();
+                    for group in body.get_locals_reader()? {
+                        let (count, ty) = group?;
+                        locals.extend(std::iter::repeat_n(ty, count as usize));
+                    }
+                    let body = decode_body(&module, body.get_operators_reader()?)?;
+                    module.functions.push(Function {
+                        type_index,
+                        locals,
+                        body,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if module.memories.iter().any(|memory| memory.shared)
+            || module.imports.iter().any(|import| {
+                matches!(
+                    import.kind,
+                    ImportKind::Memory(MemoryType { shared: true, .. })
+                )
+            })
+        {
+            return Err(unsupported("threads"));
+        }
+        if module.types.iter().any(|ty| {
+            ty.params()
+                .iter()
+                .chain(ty.results())
+                .any(|ty| matches!(ty, ValType::V128))
+        }) {
+            return Err(unsupported("SIMD"));
+        }
+        Ok(module)
+    }
+}
+
+fn unsupported(feature: &str) -> ModuleError {
+    ModuleError::Unsupported(feature.to_string())
+}
+
+/// Decode a constant expression, including its final `end`
+fn decode_const(module: &Module, reader: OperatorsReader) -> Result<Vec<Instr>, ModuleError> {
+    decode_body(module, reader)
+}
+
+/// Decode a function body, matching each block with its `else` and `end`
+fn decode_body(module: &Module, mut reader: OperatorsReader) -> Result<Vec<Instr>, ModuleError> {
+    let mut body = Vec::new();
+    // Indices of the open blocks' instructions
+    let mut open = Vec::new();
+
+    while !reader.eof() {
+        let op = reader.read()?;
+        let index = body.len() as u32;
+        let instr = match op {
+            Operator::Block { blockty } => {
+                let (params, results) = block_arity(module, blockty);
+                open.push(index);
+                Instr::Block {
+                    params,
+                    results,
+                    end: 0,
+                }
+            }
+            Operator::Loop { blockty } => {
+                open.push(index);
+                Instr::Loop {
+                    params: block_arity(module, blockty).0,
+                }
+            }
+            Operator::If { blockty } => {
+                let (params, results) = block_arity(module, blockty);
+                open.push(index);
+                Instr::If {
+                    params,
+                    results,
+                    else_: 0,
+                    end: 0,
+                }
+            }
+            Operator::Else => {
+                if let Some(&start) = open.last() {
+                    if let Instr::If { else_, .. } = &mut body[start as usize] {
+                        *else_ = index;
+                    }
+                }
+                Instr::Else { end: 0 }
+            }
+            Operator::End => {
+                // The function's own `end` closes no block
+                if let Some(start) = open.pop() {
+                    let mut else_index = None;
+                    match &mut body[start as usize] {
+                        Instr::Block { end, .. } => *end = index,
+                        Instr::If { end, else_, .. } => {
+                            *end = index;
+                            if *else_ == 0 {
+                                *else_ = index;
+                            } else {
+                                else_index = Some(*else_);
+                            }
+                        }
+                        _ => {}
+                    }
+                    if let Some(else_index) = else_index {
+                        body[else_index as usize] = Instr::Else { end: index };
+                    }
+                }
+                Instr::End
+            }
+            other => decode_plain(other)?,
+        };
+        body.push(instr);
+    }
+    Ok(body)
+}
+
+/// Parameter and result counts of a block type
+fn block_arity(module: &Module, blockty: BlockType) -> (u32, u32) {
+    match blockty {
+        BlockType::Empty => (0, 0),
+        BlockType::Type(_) => (0, 1),
+        BlockType::FuncType(index) => {
+            let ty = &module.types[index as usize];
+            (ty.params().len() as u32, ty.results().len() as u32)
+        }
+    }
+}
+
+/// Decode an instruction that doesn't open or close a block
+fn decode_plain(op: Operator) -> Result<Instr, ModuleError> {
+    use Operator as O;
+
+    macro_rules! load {
+        ($memarg:expr, $ty:ident, $bytes:expr, $signed:expr) => {
+            Instr::Load {
+                ty: ValType::$ty,
+                bytes: $bytes,
+                signed: $signed,
+                memory: $memarg.memory,
+                offset: $memarg.offset,
+            }
+        };
+    }
+    macro_rules! store {
+        ($memarg:expr, $bytes:expr) => {
+            Instr::Store {
+                bytes: $bytes,
+                memory: $memarg.memory,
+                offset: $memarg.offset,
+            }
+        };
+    }
+
+    let instr = match op {
+        O::Unreachable => Instr::Unreachable,
+        O::Nop => Instr::Nop,
+        O::Br { relative_depth } => Instr::Br(relative_depth),
+        O::BrIf { relative_depth } => Instr::BrIf(relative_depth),
+        O::BrTable { targets } => {
+            let mut depths = targets.targets().collect::<Result<Vec<_>, _>>()?;
+            depths.push(targets.default());
+            Instr::BrTable(depths.into_boxed_slice())
+        }
+        O::Return => Instr::Return,
+        O::Call { function_index } => Instr::Call(function_index),
+        O::CallIndirect {
+            type_index,
+            table_index,
+        } => Instr::CallIndirect {
+            type_index,
+            table: table_index,
+        },
+        O::ReturnCall { function_index } => Instr::ReturnCall(function_index),
+        O::ReturnCallIndirect {
+            type_index,
+            table_index,
+        } => Instr::ReturnCallIndirect {
+            type_index,
+            table: table_index,
+        },
+        O::Drop => Instr::Drop,
+        O::Select | O::TypedSelect { .. } => Instr::Select,
+        O::LocalGet { local_index } => Instr::LocalGet(local_index),
+        O::LocalSet { local_index } => Instr::LocalSet(local_index),
+        O::LocalTee { local_index } => Instr::LocalTee(local_index),
+        O::GlobalGet { global_index } => Instr::GlobalGet(global_index),
+        O::GlobalSet { global_index } => Instr::GlobalSet(global_index),
+
+        O::I32Load { memarg } => load!(memarg, I32, 4, false),
+        O::I64Load { memarg } => load!(memarg, I64, 8, false),
+        O::F32Load { memarg } => load!(memarg, F32, 4, false),
+        O::F64Load { memarg } => load!(memarg, F64, 8, false),
+        O::I32Load8S { memarg } => load!(memarg, I32, 1, true),
+        O::I32Load8U { memarg } => load!(memarg, I32, 1, false),
+        O::I32Load16S { memarg } => load!(memarg, I32, 2, true),
+        O::I32Load16U { memarg } => load!(memarg, I32, 2, false),
+        O::I64Load8S { memarg } => load!(memarg, I64, 1, true),
+        O::I64Load8U { memarg } => load!(memarg, I64, 1, false),
+        O::I64Load16S { memarg } => load!(memarg, I64, 2, true),
+        O::I64Load16U { memarg } => load!(memarg, I64, 2, false),
+        O::I64Load32S { memarg } => load!(memarg, I64, 4, true),
+        O::I64Load32U { memarg } => load!(memarg, I64, 4, false),
+        O::I32Store { memarg } | O::F32Store { memarg } | O::I64Store32 { memarg } => {
+            store!(memarg, 4)
+        }
+        O::I64Store { memarg } | O::F64Store { memarg } => store!(memarg, 8),
+        O::I32Store8 { memarg } | O::I64Store8 { memarg } => store!(memarg, 1),
+        O::I32Store16 { memarg } | O::I64Store16 { memarg } => store!(memarg, 2),
+        O::MemorySize { mem } => Instr::MemorySize(mem),
+        O::MemoryGrow { mem } => Instr::MemoryGrow(mem),
+        O::MemoryInit { data_index, mem } => Instr::MemoryInit {
+            data: data_index,
+            memory: mem,
+        },
+        O::DataDrop { data_index } => Instr::DataDrop(data_index),
+        O::MemoryCopy { dst_mem, src_mem } => Instr::MemoryCopy {
+            dst: dst_mem,
+            src: src_mem,
+        },
+        O::MemoryFill { mem } => Instr::MemoryFill(mem),
+
+        O::I32Const { value } => Instr::Const(Value::I32(value)),
+        O::I64Const { value } => Instr::Const(Value::I64(value)),
+        O::F32Const { value } => Instr::Const(Value::F32(value.bits())),
+        O::F64Const { value } => Instr::Const(Value::F64(value.bits())),
+
+        O::RefNull { hty } => match hty {
+            wasmparser::HeapType::Abstract {
+                ty: wasmparser::AbstractHeapType::Func,
+                ..
+            } => Instr::RefNull(Value::FuncRef(None)),
+            wasmparser::HeapType::Abstract {
+                ty: wasmparser::AbstractHeapType::Extern,
+                ..
+            } => Instr::RefNull(Value::ExternRef(None)),
+            _ => return Err(unsupported("GC types")),
+        },
+        O::RefIsNull => Instr::RefIsNull,
+        O::RefFunc { function_index } => Instr::RefFunc(function_index),
+        O::TableGet { table } => Instr::TableGet(table),
+        O::TableSet { table } => Instr::TableSet(table),
+        O::TableSize { table } => Instr::TableSize(table),
+        O::TableGrow { table } => Instr::TableGrow(table),
+        O::TableFill { table } => Instr::TableFill(table),
+        O::TableCopy {
+            dst_table,
+            src_table,
+        } => Instr::TableCopy {
+            dst: dst_table,
+            src: src_table,
+        },
+        O::TableInit { elem_index, table } => Instr::TableInit {
+            elem: elem_index,
+            table,
+        },
+        O::ElemDrop { elem_index } => Instr::ElemDrop(elem_index),
+
+        other => match numeric(&other) {
+            Some(instr) => instr,
+            None => return Err(ModuleError::Unsupported(instruction_name(&other))),
+        },
+    };
+    Ok(instr)
+}
+
+/// The numeric instructions, which all map to an operator function
+fn numeric(op: &Operator) -> Option<Instr> {
+    use Operator as O;
+
+    let unary = |f: UnaryOp| Some(Instr::Unary(f));
+    let binary = |f: BinaryOp| Some(Instr::Binary(f));
+
+    match op {
+        O::I32Eqz => unary(ops::i32_eqz),
+        O::I32Eq => binary(ops::i32_eq),
+        O::I32Ne => binary(ops::i32_ne),
+        O::I32LtS => binary(ops::i32_lt_s),
+        O::I32LtU => binary(ops::i32_lt_u),
+        O::I32GtS => binary(ops::i32_gt_s),
+        O::I32GtU => binary(ops::i32_gt_u),
+        O::I32LeS => binary(ops::i32_le_s),
+        O::I32LeU => binary(ops::i32_le_u),
+        O::I32GeS => binary(ops::i32_ge_s),
+        O::I32GeU => binary(ops::i32_ge_u),
+        O::I64Eqz => unary(ops::i64_eqz),
+        O::I64Eq => binary(ops::i64_eq),
+        O::I64Ne => binary(ops::i64_ne),
+        O::I64LtS => binary(ops::i64_lt_s),
+        O::I64LtU => binary(ops::i64_lt_u),
+        O::I64GtS => binary(ops::i64_gt_s),
+        O::I64GtU => binary(ops::i64_gt_u),
+        O::I64LeS => binary(ops::i64_le_s),
+        O::I64LeU => binary(ops::i64_le_u),
+        O::I64GeS => binary(ops::i64_ge_s),
+        O::I64GeU => binary(ops::i64_ge_u),
+        O::F32Eq => binary(ops::f32_eq),
+        O::F32Ne => binary(ops::f32_ne),
+        O::F32Lt => binary(ops::f32_lt),
+        O::F32Gt => binary(ops::f32_gt),
+        O::F32Le => binary(ops::f32_le),
+        O::F32Ge => binary(ops::f32_ge),
+        O::F64Eq => binary(ops::f64_eq),
+        O::F64Ne => binary(ops::f64_ne),
+        O::F64Lt => binary(ops::f64_lt),
+        O::F64Gt => binary(ops::f64_gt),
+        O::F64Le => binary(ops::f64_le),
+        O::F64Ge => binary(ops::f64_ge),
+
+        O::I32Clz => unary(ops::i32_clz),
+        O::I32Ctz => unary(ops::i32_ctz),
+        O::I32Popcnt => unary(ops::i32_popcnt),
+        O::I32Add => binary(ops::i32_add),
+        O::I32Sub => binary(ops::i32_sub),
+        O::I32Mul => binary(ops::i32_mul),
+        O::I32DivS => binary(ops::i32_div_s),
+        O::I32DivU => binary(ops::i32_div_u),
+        O::I32RemS => binary(ops::i32_rem_s),
+        O::I32RemU => binary(ops::i32_rem_u),
+        O::I32And => binary(ops::i32_and),
+        O::I32Or => binary(ops::i32_or),
+        O::I32Xor => binary(ops::i32_xor),
+        O::I32Shl => binary(ops::i32_shl),
+        O::I32ShrS => binary(ops::i32_shr_s),
+        O::I32ShrU => binary(ops::i32_shr_u),
+        O::I32Rotl => binary(ops::i32_rotl),
+        O::I32Rotr => binary(ops::i32_rotr),
+        O::I64Clz => unary(ops::i64_clz),
+        O::I64Ctz => unary(ops::i64_ctz),
+        O::I64Popcnt => unary(ops::i64_popcnt),
+        O::I64Add => binary(ops::i64_add),
+        O::I64Sub => binary(ops::i64_sub),
+        O::I64Mul => binary(ops::i64_mul),
+        O::I64DivS => binary(ops::i64_div_s),
+        O::I64DivU => binary(ops::i64_div_u),
+        O::I64RemS => binary(ops::i64_rem_s),
+        O::I64RemU => binary(ops::i64_rem_u),
+        O::I64And => binary(ops::i64_and),
+        O::I64Or => binary(ops::i64_or),
+        O::I64Xor => binary(ops::i64_xor),
+        O::I64Shl => binary(ops::i64_shl),
+        O::I64ShrS => binary(ops::i64_shr_s),
+        O::I64ShrU => binary(ops::i64_shr_u),
+        O::I64Rotl => binary(ops::i64_rotl),
+        O::I64Rotr => binary(ops::i64_rotr),
+
+        O::F32Abs => unary(ops::f32_abs),
+        O::F32Neg => unary(ops::f32_neg),
+        O::F32Ceil => unary(ops::f32_ceil),
+        O::F32Floor => unary(ops::f32_floor),
+        O::F32Trunc => unary(ops::f32_trunc),
+        O::F32Nearest => unary(ops::f32_nearest),
+        O::F32Sqrt => unary(ops::f32_sqrt),
+        O::F32Add => binary(ops::f32_add),
+        O::F32Sub => binary(ops::f32_sub),
+        O::F32Mul => binary(ops::f32_mul),
+        O::F32Div => binary(ops::f32_div),
+        O::F32Min => binary(ops::f32_min),
+        O::F32Max => binary(ops::f32_max),
+        O::F32Copysign => binary(ops::f32_copysign),
+        O::F64Abs => unary(ops::f64_abs),
+        O::F64Neg => unary(ops::f64_neg),
+        O::F64Ceil => unary(ops::f64_ceil),
+        O::F64Floor => unary(ops::f64_floor),
+        O::F64Trunc => unary(ops::f64_trunc),
+        O::F64Nearest => unary(ops::f64_nearest),
+        O::F64Sqrt => unary(ops::f64_sqrt),
+        O::F64Add => binary(ops::f64_add),
+        O::F64Sub => binary(ops::f64_sub),
+        O::F64Mul => binary(ops::f64_mul),
+        O::F64Div => binary(ops::f64_div),
+        O::F64Min => binary(ops::f64_min),
+        O::F64Max => binary(ops::f64_max),
+        O::F64Copysign => binary(ops::f64_copysign),
+
+        O::I32WrapI64 => unary(ops::i32_wrap_i64),
+        O::I32TruncF32S => unary(ops::i32_trunc_f32_s),
+        O::I32TruncF32U => unary(ops::i32_trunc_f32_u),
+        O::I32TruncF64S => unary(ops::i32_trunc_f64_s),
+        O::I32TruncF64U => unary(ops::i32_trunc_f64_u),
+        O::I64ExtendI32S => unary(ops::i64_extend_i32_s),
+        O::I64ExtendI32U => unary(ops::i64_extend_i32_u),
+        O::I64TruncF32S => unary(ops::i64_trunc_f32_s),
+        O::I64TruncF32U => unary(ops::i64_trunc_f32_u),
+        O::I64TruncF64S => unary(ops::i64_trunc_f64_s),
+        O::I64TruncF64U => unary(ops::i64_trunc_f64_u),
+        O::F32ConvertI32S => unary(ops::f32_convert_i32_s),
+        O::F32ConvertI32U => unary(ops::f32_convert_i32_u),
+        O::F32ConvertI64S => unary(ops::f32_convert_i64_s),
+        O::F32ConvertI64U => unary(ops::f32_convert_i64_u),
+        O::F32DemoteF64 => unary(ops::f32_demote_f64),
+        O::F64ConvertI32S => unary(ops::f64_convert_i32_s),
+        O::F64ConvertI32U => unary(ops::f64_convert_i32_u),
+        O::F64ConvertI64S => unary(ops::f64_convert_i64_s),
+        O::F64ConvertI64U => unary(ops::f64_convert_i64_u),
+        O::F64PromoteF32 => unary(ops::f64_promote_f32),
+        O::I32ReinterpretF32 => unary(ops::i32_reinterpret_f32),
+        O::I64ReinterpretF64 => unary(ops::i64_reinterpret_f64),
+        O::F32ReinterpretI32 => unary(ops::f32_reinterpret_i32),
+        O::F64ReinterpretI64 => unary(ops::f64_reinterpret_i64),
+
+        O::I32Extend8S => unary(ops::i32_extend8_s),
+        O::I32Extend16S => unary(ops::i32_extend16_s),
+        O::I64Extend8S => unary(ops::i64_extend8_s),
+        O::I64Extend16S => unary(ops::i64_extend16_s),
+        O::I64Extend32S => unary(ops::i64_extend32_s),
+
+        O::I32TruncSatF32S => unary(ops::i32_trunc_sat_f32_s),
+        O::I32TruncSatF32U => unary(ops::i32_trunc_sat_f32_u),
+        O::I32TruncSatF64S => unary(ops::i32_trunc_sat_f64_s),
+        O::I32TruncSatF64U => unary(ops::i32_trunc_sat_f64_u),
+        O::I64TruncSatF32S => unary(ops::i64_trunc_sat_f32_s),
+        O::I64TruncSatF32U => unary(ops::i64_trunc_sat_f32_u),
+        O::I64TruncSatF64S => unary(ops::i64_trunc_sat_f64_s),
+        O::I64TruncSatF64U => unary(ops::i64_trunc_sat_f64_u),
+        _ => None,
+    }
+}
+
+/// Operator name for unsupported-instruction messages, e.g. `V128Load`
+fn instruction_name(op: &Operator) -> String {
+    let debug = format!("{:?}", op);
+    let name = debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or(&debug);
+    format!("instruction {}", name)
+}
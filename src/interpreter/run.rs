@@ -0,0 +1,126 @@
+//! Running one exported function of a module, for quick experiments.
+
+use super::exec::{Extern, Store};
+use super::module::Module;
+use super::script::argument;
+use super::value::{Trap, Value};
+use crate::compile::compile;
+use serde::{Deserialize, Serialize};
+use wasmparser::ValType;
+use wast::WastArg;
+
+/// What an import behaves like when nothing provides it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "native", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStubs {
+    /// Calling an import function traps (default)
+    #[default]
+    Trap,
+    /// Import functions return zero for every result
+    Zero,
+    /// Import functions log their arguments and return zero
+    Log,
+}
+
+/// The outcome of running an export
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOutput {
+    /// Results, or the trap that stopped the function or the start function
+    pub results: Result<Vec<Value>, Trap>,
+    /// Calls to logging import stubs, oldest first
+    pub log: Vec<String>,
+}
+
+impl RunOutput {
+    /// Results as a short line, e.g. `(i32.const 3)` or `trap: unreachable`
+    pub fn summary(&self) -> String {
+        match &self.results {
+            Ok(values) if values.is_empty() => "no results".to_string(),
+            Ok(values) => values
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            Err(trap) => format!("trap: {}", trap),
+        }
+    }
+}
+
+/// Instantiate a module and call one of its exported functions.
+///
+/// `source` is WAT text or a binary module. Arguments use WAT constant
+/// syntax, e.g. `42`, `-0x10` or `nan`, one per parameter. Imports are
+/// satisfied with `stubs`. Fails when the module doesn't compile or link,
+/// the export isn't a function, or the arguments don't fit its parameters;
+/// traps are part of the output.
+pub fn run_export(
+    source: &[u8],
+    name: &str,
+    args: &[String],
+    stubs: ImportStubs,
+) -> Result<RunOutput, String> {
+    let bytes = if source.starts_with(b"\0asm") {
+        source.to_vec()
+    } else {
+        let text = std::str::from_utf8(source).map_err(|e| e.to_string())?;
+        compile(text)?
+    };
+    let module = Module::new(&bytes).map_err(|e| e.to_string())?;
+
+    let mut store = Store::new();
+    store.stub_imports(&module, stubs);
+    let instance = match store.instantiate(&module).map_err(|e| e.0)? {
+        Ok(instance) => instance,
+        Err(trap) => {
+            return Ok(RunOutput {
+                results: Err(trap),
+                log: store.take_log(),
+            })
+        }
+    };
+
+    let Some(Extern::Func(func)) = store.export(instance, name) else {
+        return Err(format!("no exported function named \"{}\"", name));
+    };
+    let params = store.func_type(func).params().to_vec();
+    if params.len() != args.len() {
+        return Err(format!(
+            "\"{}\" takes {} argument(s), got {}",
+            name,
+            params.len(),
+            args.len()
+        ));
+    }
+    let args = params
+        .iter()
+        .zip(args)
+        .map(|(&ty, text)| parse_argument(ty, text))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let results = store.invoke(func, args);
+    Ok(RunOutput {
+        results,
+        log: store.take_log(),
+    })
+}
+
+/// Whether values of a type can be given as text arguments
+fn is_numeric(ty: ValType) -> bool {
+    matches!(
+        ty,
+        ValType::I32 | ValType::I64 | ValType::F32 | ValType::F64
+    )
+}
+
+/// Parse an argument the way a `(t.const ...)` operand is written
+fn parse_argument(ty: ValType, text: &str) -> Result<Value, String> {
+    if !is_numeric(ty) {
+        return Err(format!("parameters of type {} can't be given as text", ty));
+    }
+    let invalid = || format!("\"{}\" is not a valid {} value", text.trim(), ty);
+    let constant = format!("{}.const {}", ty, text.trim());
+    let buf = wast::parser::ParseBuffer::new(&constant).map_err(|_| invalid())?;
+    let arg = wast::parser::parse::<WastArg>(&buf).map_err(|_| invalid())?;
+    argument(&arg).map_err(|_| invalid())
+}
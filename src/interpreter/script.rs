@@ -0,0 +1,563 @@
+//! Running the commands of a `.wast` script.
+
+use super::exec::{Extern, Store};
+use super::module::{Module, ModuleError};
+use super::value::{Trap, Value};
+use crate::core::{LineIndex, PositionEncoding, Range};
+use std::collections::HashMap;
+use std::fmt;
+use wast::core::{AbstractHeapType, HeapType, NanPattern, WastArgCore, WastRetCore};
+use wast::token::Id;
+use wast::{QuoteWat, Wast, WastArg, WastDirective, WastExecute, WastInvoke, WastRet, Wat};
+
+/// What running one command found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed {
+        expected: String,
+        actual: String,
+    },
+    /// The command needs something the interpreter doesn't run
+    Skipped(String),
+}
+
+/// The outcome of one command, with the range of its S-expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssertionResult {
+    /// Command keyword, e.g. `assert_return`
+    pub command: &'static str,
+    pub range: Range,
+    pub outcome: Outcome,
+}
+
+impl AssertionResult {
+    /// Whether the command is an assertion, as opposed to a module or a
+    /// bare action, which only report failures
+    pub fn is_assertion(&self) -> bool {
+        self.command.starts_with("assert_")
+    }
+}
+
+/// Run every command of a script in order.
+///
+/// Returns one result per assertion, plus one per module or action that
+/// failed or was skipped. Commands against a module that didn't instantiate
+/// are left out; the module's own result explains why. Fails only when the
+/// script doesn't parse, which syntax diagnostics already report.
+pub fn run_script(source: &str) -> Result<Vec<AssertionResult>, String> {
+    let buf = wast::parser::ParseBuffer::new(source).map_err(|e| e.to_string())?;
+    let script = wast::parser::parse::<Wast>(&buf).map_err(|e| e.to_string())?;
+
+    let mut runner = Runner {
+        source,
+        line_index: LineIndex::new(source),
+        store: Store::new(),
+        current: None,
+        named: HashMap::new(),
+        results: Vec::new(),
+    };
+    for directive in script.directives {
+        runner.run(directive);
+    }
+    Ok(runner.results)
+}
+
+/// A module command's instance, or `None` when it didn't instantiate
+type ModuleSlot = Option<usize>;
+
+struct Runner<'a> {
+    source: &'a str,
+    line_index: LineIndex,
+    store: Store,
+    /// The most recent module, which commands without a `$name` use
+    current: Option<ModuleSlot>,
+    named: HashMap<&'a str, ModuleSlot>,
+    results: Vec<AssertionResult>,
+}
+
+/// What an action produced
+enum Run {
+    Returned(Vec<Value>),
+    Trapped(Trap),
+    /// The action can't run; the reason is reported as a skip
+    Unavailable(Option<String>),
+}
+
+impl<'a> Runner<'a> {
+    fn run(&mut self, directive: WastDirective<'a>) {
+        let offset = directive.span().offset();
+        let (command, outcome) = match directive {
+            WastDirective::Module(module) => {
+                let name = module.name();
+                let (slot, outcome) = self.instantiate_module(module);
+                self.current = Some(slot);
+                if let Some(name) = name {
+                    self.named.insert(name.name(), slot);
+                }
+                match outcome {
+                    Outcome::Passed => return,
+                    outcome => ("module", outcome),
+                }
+            }
+            WastDirective::Register { name, module, .. } => {
+                if let Some(Some(instance)) = self.module_slot(module) {
+                    self.store.register(name, instance);
+                }
+                return;
+            }
+            WastDirective::Invoke(invoke) => match self.invoke(&invoke) {
+                Run::Trapped(trap) => (
+                    "invoke",
+                    Outcome::Failed {
+                        expected: "a result".to_string(),
+                        actual: format!("trap \"{}\"", trap),
+                    },
+                ),
+                Run::Returned(_) | Run::Unavailable(None) => return,
+                Run::Unavailable(Some(reason)) => ("invoke", Outcome::Skipped(reason)),
+            },
+            WastDirective::AssertReturn { exec, results, .. } => {
+                let expected = match results
+                    .iter()
+                    .map(Expected::from_ret)
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(expected) => expected,
+                    Err(reason) => {
+                        self.report("assert_return", offset, Outcome::Skipped(reason));
+                        return;
+                    }
+                };
+                match self.execute(exec) {
+                    Run::Unavailable(None) => return,
+                    Run::Unavailable(Some(reason)) => ("assert_return", Outcome::Skipped(reason)),
+                    Run::Trapped(trap) => (
+                        "assert_return",
+                        Outcome::Failed {
+                            expected: format_values(&expected),
+                            actual: format!("trap \"{}\"", trap),
+                        },
+                    ),
+                    Run::Returned(values) => {
+                        let matches = values.len() == expected.len()
+                            && values.iter().zip(&expected).all(|(v, e)| e.matches(v));
+                        let outcome = if matches {
+                            Outcome::Passed
+                        } else {
+                            Outcome::Failed {
+                                expected: format_values(&expected),
+                                actual: format_values(&values),
+                            }
+                        };
+                        ("assert_return", outcome)
+                    }
+                }
+            }
+            WastDirective::AssertTrap { exec, message, .. } => {
+                let run = self.execute(exec);
+                match expect_trap(run, message) {
+                    Some(outcome) => ("assert_trap", outcome),
+                    None => return,
+                }
+            }
+            WastDirective::AssertExhaustion { call, message, .. } => {
+                let run = self.invoke(&call);
+                match expect_trap(run, message) {
+                    Some(outcome) => ("assert_exhaustion", outcome),
+                    None => return,
+                }
+            }
+            WastDirective::AssertInvalid {
+                module, message, ..
+            } => ("assert_invalid", expect_rejected(module, message)),
+            WastDirective::AssertMalformed {
+                module, message, ..
+            } => ("assert_malformed", expect_rejected(module, message)),
+            WastDirective::AssertUnlinkable {
+                mut module,
+                message,
+                ..
+            } => {
+                let outcome = match encode_wat(&mut module) {
+                    Err(reason) => Outcome::Skipped(reason),
+                    Ok(module) => match self.store.instantiate(&module) {
+                        Err(_) => Outcome::Passed,
+                        Ok(result) => Outcome::Failed {
+                            expected: format!("link error \"{}\"", message),
+                            actual: match result {
+                                Ok(_) => "the module linked".to_string(),
+                                Err(trap) => format!("trap \"{}\"", trap),
+                            },
+                        },
+                    },
+                };
+                ("assert_unlinkable", outcome)
+            }
+            WastDirective::AssertException { .. } => (
+                "assert_exception",
+                Outcome::Skipped("exception handling is not supported".to_string()),
+            ),
+            WastDirective::AssertSuspension { .. } => (
+                "assert_suspension",
+                Outcome::Skipped("stack switching is not supported".to_string()),
+            ),
+            // Threads and module definitions aren't run
+            _ => return,
+        };
+        self.report(command, offset, outcome);
+    }
+
+    fn report(&mut self, command: &'static str, offset: usize, outcome: Outcome) {
+        let span = sexpr_span(self.source, offset);
+        let range = Range::new(
+            self.line_index
+                .position(self.source, span.start, PositionEncoding::Utf8),
+            self.line_index
+                .position(self.source, span.end, PositionEncoding::Utf8),
+        );
+        self.results.push(AssertionResult {
+            command,
+            range,
+            outcome,
+        });
+    }
+
+    /// Encode, decode and instantiate a module command
+    fn instantiate_module(&mut self, mut module: QuoteWat) -> (ModuleSlot, Outcome) {
+        let bytes = match module.encode() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return (
+                    None,
+                    Outcome::Failed {
+                        expected: "a valid module".to_string(),
+                        actual: e.message(),
+                    },
+                )
+            }
+        };
+        let module = match Module::new(&bytes) {
+            Ok(module) => module,
+            Err(ModuleError::Invalid(message)) => {
+                return (
+                    None,
+                    Outcome::Failed {
+                        expected: "a valid module".to_string(),
+                        actual: message,
+                    },
+                )
+            }
+            Err(ModuleError::Unsupported(feature)) => {
+                return (
+                    None,
+                    Outcome::Skipped(format!("the module uses {}, which can't be run", feature)),
+                )
+            }
+        };
+        match self.store.instantiate(&module) {
+            Ok(Ok(instance)) => (Some(instance), Outcome::Passed),
+            Ok(Err(trap)) => (
+                None,
+                Outcome::Failed {
+                    expected: "the module to instantiate".to_string(),
+                    actual: format!("trap \"{}\"", trap),
+                },
+            ),
+            Err(error) => (
+                None,
+                Outcome::Failed {
+                    expected: "the module to link".to_string(),
+                    actual: error.0,
+                },
+            ),
+        }
+    }
+
+    /// The module a command names, or the current one
+    fn module_slot(&self, name: Option<Id>) -> Option<ModuleSlot> {
+        match name {
+            Some(name) => self.named.get(name.name()).copied(),
+            None => self.current,
+        }
+    }
+
+    /// The instance a command runs against; `Err` holds the reason it can't
+    fn instance(&self, name: Option<Id>) -> Result<usize, Option<String>> {
+        match self.module_slot(name) {
+            Some(Some(instance)) => Ok(instance),
+            // Already reported with the module
+            Some(None) => Err(None),
+            None => Err(Some(match name {
+                Some(name) => format!("no module named ${}", name.name()),
+                None => "no module has been defined".to_string(),
+            })),
+        }
+    }
+
+    fn execute(&mut self, exec: WastExecute) -> Run {
+        match exec {
+            WastExecute::Invoke(invoke) => self.invoke(&invoke),
+            WastExecute::Get { module, global, .. } => {
+                let instance = match self.instance(module) {
+                    Ok(instance) => instance,
+                    Err(reason) => return Run::Unavailable(reason),
+                };
+                match self.store.export(instance, global) {
+                    Some(Extern::Global(address)) => {
+                        Run::Returned(vec![self.store.global_value(address)])
+                    }
+                    _ => Run::Unavailable(Some(format!("no global export \"{}\"", global))),
+                }
+            }
+            WastExecute::Wat(mut wat) => {
+                let module = match encode_wat(&mut wat) {
+                    Ok(module) => module,
+                    Err(reason) => return Run::Unavailable(Some(reason)),
+                };
+                match self.store.instantiate(&module) {
+                    Ok(Ok(_)) => Run::Returned(Vec::new()),
+                    Ok(Err(trap)) => Run::Trapped(trap),
+                    Err(error) => Run::Unavailable(Some(error.0)),
+                }
+            }
+        }
+    }
+
+    fn invoke(&mut self, invoke: &WastInvoke) -> Run {
+        let instance = match self.instance(invoke.module) {
+            Ok(instance) => instance,
+            Err(reason) => return Run::Unavailable(reason),
+        };
+        let Some(Extern::Func(func)) = self.store.export(instance, invoke.name) else {
+            return Run::Unavailable(Some(format!("no function export \"{}\"", invoke.name)));
+        };
+        let args = match invoke.args.iter().map(argument).collect() {
+            Ok(args) => args,
+            Err(reason) => return Run::Unavailable(Some(reason)),
+        };
+        match self.store.invoke(func, args) {
+            Ok(values) => Run::Returned(values),
+            Err(trap) => Run::Trapped(trap),
+        }
+    }
+}
+
+/// Check that an action trapped with the expected message; `None` when it
+/// couldn't run against an already reported module
+fn expect_trap(run: Run, message: &str) -> Option<Outcome> {
+    let expected = format!("trap \"{}\"", message);
+    Some(match run {
+        Run::Unavailable(None) => return None,
+        Run::Unavailable(Some(reason)) => Outcome::Skipped(reason),
+        // Scripts may give just the start of the message
+        Run::Trapped(trap) if trap.0.starts_with(message) => Outcome::Passed,
+        Run::Trapped(trap) => Outcome::Failed {
+            expected,
+            actual: format!("trap \"{}\"", trap),
+        },
+        Run::Returned(values) => Outcome::Failed {
+            expected,
+            actual: format_values(&values),
+        },
+    })
+}
+
+/// Check that a module fails to encode, decode or validate. Any failure
+/// passes: error wording differs between tools.
+fn expect_rejected(mut module: QuoteWat, message: &str) -> Outcome {
+    let bytes = match module.encode() {
+        Ok(bytes) => bytes,
+        Err(_) => return Outcome::Passed,
+    };
+    match Module::new(&bytes) {
+        Err(ModuleError::Invalid(_)) => Outcome::Passed,
+        Ok(_) | Err(ModuleError::Unsupported(_)) => Outcome::Failed {
+            expected: format!("the module to be rejected: \"{}\"", message),
+            actual: "the module is valid".to_string(),
+        },
+    }
+}
+
+fn encode_wat(wat: &mut Wat) -> Result<Module, String> {
+    let bytes = wat.encode().map_err(|e| e.message())?;
+    Module::new(&bytes).map_err(|e| e.to_string())
+}
+
+pub(super) fn argument(arg: &WastArg) -> Result<Value, String> {
+    match arg {
+        WastArg::Core(WastArgCore::I32(value)) => Ok(Value::I32(*value)),
+        WastArg::Core(WastArgCore::I64(value)) => Ok(Value::I64(*value)),
+        WastArg::Core(WastArgCore::F32(value)) => Ok(Value::F32(value.bits)),
+        WastArg::Core(WastArgCore::F64(value)) => Ok(Value::F64(value.bits)),
+        WastArg::Core(WastArgCore::RefNull(heap)) if is_abstract(heap, AbstractHeapType::Func) => {
+            Ok(Value::FuncRef(None))
+        }
+        WastArg::Core(WastArgCore::RefNull(heap))
+            if is_abstract(heap, AbstractHeapType::Extern) =>
+        {
+            Ok(Value::ExternRef(None))
+        }
+        WastArg::Core(WastArgCore::RefExtern(value)) => Ok(Value::ExternRef(Some(*value))),
+        _ => Err("the argument's type is not supported".to_string()),
+    }
+}
+
+fn is_abstract(heap: &HeapType, expected: AbstractHeapType) -> bool {
+    matches!(heap, HeapType::Abstract { ty, .. } if *ty == expected)
+}
+
+/// An expected result of `assert_return`
+#[derive(Debug)]
+enum Expected {
+    Value(Value),
+    CanonicalNan32,
+    ArithmeticNan32,
+    CanonicalNan64,
+    ArithmeticNan64,
+    /// Any non-null function reference
+    Func,
+    /// Any non-null external reference
+    Extern,
+    Either(Vec<Expected>),
+}
+
+impl Expected {
+    fn from_ret(ret: &WastRet) -> Result<Expected, String> {
+        match ret {
+            WastRet::Core(ret) => Expected::from_core(ret),
+            _ => Err("component results are not supported".to_string()),
+        }
+    }
+
+    fn from_core(ret: &WastRetCore) -> Result<Expected, String> {
+        Ok(match ret {
+            WastRetCore::I32(value) => Expected::Value(Value::I32(*value)),
+            WastRetCore::I64(value) => Expected::Value(Value::I64(*value)),
+            WastRetCore::F32(NanPattern::Value(value)) => Expected::Value(Value::F32(value.bits)),
+            WastRetCore::F32(NanPattern::CanonicalNan) => Expected::CanonicalNan32,
+            WastRetCore::F32(NanPattern::ArithmeticNan) => Expected::ArithmeticNan32,
+            WastRetCore::F64(NanPattern::Value(value)) => Expected::Value(Value::F64(value.bits)),
+            WastRetCore::F64(NanPattern::CanonicalNan) => Expected::CanonicalNan64,
+            WastRetCore::F64(NanPattern::ArithmeticNan) => Expected::ArithmeticNan64,
+            WastRetCore::RefNull(Some(heap)) if is_abstract(heap, AbstractHeapType::Func) => {
+                Expected::Value(Value::FuncRef(None))
+            }
+            WastRetCore::RefNull(Some(heap)) if is_abstract(heap, AbstractHeapType::Extern) => {
+                Expected::Value(Value::ExternRef(None))
+            }
+            WastRetCore::RefExtern(Some(value)) => Expected::Value(Value::ExternRef(Some(*value))),
+            WastRetCore::RefExtern(None) => Expected::Extern,
+            WastRetCore::RefFunc(_) => Expected::Func,
+            WastRetCore::Either(options) => Expected::Either(
+                options
+                    .iter()
+                    .map(Expected::from_core)
+                    .collect::<Result<_, _>>()?,
+            ),
+            WastRetCore::V128(_) => return Err("v128 results are not supported".to_string()),
+            _ => return Err("the result's type is not supported".to_string()),
+        })
+    }
+
+    fn matches(&self, value: &Value) -> bool {
+        const F32_QUIET: u32 = 0x7fc0_0000;
+        const F64_QUIET: u64 = 0x7ff8_0000_0000_0000;
+        match (self, value) {
+            (Expected::Value(expected), value) => expected == value,
+            (Expected::CanonicalNan32, Value::F32(bits)) => bits & !(1 << 31) == F32_QUIET,
+            (Expected::ArithmeticNan32, Value::F32(bits)) => bits & F32_QUIET == F32_QUIET,
+            (Expected::CanonicalNan64, Value::F64(bits)) => bits & !(1 << 63) == F64_QUIET,
+            (Expected::ArithmeticNan64, Value::F64(bits)) => bits & F64_QUIET == F64_QUIET,
+            (Expected::Func, Value::FuncRef(Some(_))) => true,
+            (Expected::Extern, Value::ExternRef(Some(_))) => true,
+            (Expected::Either(options), value) => options.iter().any(|e| e.matches(value)),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expected::Value(value) => write!(f, "{}", value),
+            Expected::CanonicalNan32 => write!(f, "(f32.const nan:canonical)"),
+            Expected::ArithmeticNan32 => write!(f, "(f32.const nan:arithmetic)"),
+            Expected::CanonicalNan64 => write!(f, "(f64.const nan:canonical)"),
+            Expected::ArithmeticNan64 => write!(f, "(f64.const nan:arithmetic)"),
+            Expected::Func => write!(f, "(ref.func)"),
+            Expected::Extern => write!(f, "(ref.extern)"),
+            Expected::Either(options) => write!(f, "(either {})", format_values(options)),
+        }
+    }
+}
+
+/// Space-separated values, or "no values"
+fn format_values<T: fmt::Display>(values: &[T]) -> String {
+    if values.is_empty() {
+        return "no values".to_string();
+    }
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Byte span of the S-expression whose keyword starts at `offset`: from its
+/// opening parenthesis to just past the matching closing one. Strings and
+/// comments are skipped while matching.
+fn sexpr_span(source: &str, offset: usize) -> std::ops::Range<usize> {
+    let start = source[..offset].rfind('(').unwrap_or(offset);
+    let bytes = source.as_bytes();
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' if bytes.get(i + 1) == Some(&b';') => {
+                // Block comments nest
+                let mut comment_depth = 0usize;
+                while i < bytes.len() {
+                    if bytes[i..].starts_with(b"(;") {
+                        comment_depth += 1;
+                        i += 2;
+                    } else if bytes[i..].starts_with(b";)") {
+                        comment_depth -= 1;
+                        i += 2;
+                        if comment_depth == 0 {
+                            break;
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+                continue;
+            }
+            b';' if bytes.get(i + 1) == Some(&b';') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+            }
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return start..i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    start..source.len()
+}
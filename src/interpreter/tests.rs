@@ -0,0 +1,319 @@
+use super::*;
+
+fn outcomes(script: &str) -> Vec<(&'static str, Outcome)> {
+    run_script(script)
+        .unwrap()
+        .into_iter()
+        .map(|result| (result.command, result.outcome))
+        .collect()
+}
+
+#[test]
+fn test_assert_return_passes_and_fails() {
+    let script = r#"
+(module
+  (func (export "add") (param i32 i32) (result i32)
+    (i32.add (local.get 0) (local.get 1))))
+(assert_return (invoke "add" (i32.const 1) (i32.const 2)) (i32.const 3))
+(assert_return (invoke "add" (i32.const 1) (i32.const 2)) (i32.const 4))
+"#;
+    let results = run_script(script).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].outcome, Outcome::Passed);
+    assert_eq!(
+        results[1].outcome,
+        Outcome::Failed {
+            expected: "(i32.const 4)".to_string(),
+            actual: "(i32.const 3)".to_string(),
+        }
+    );
+    // The range covers the whole S-expression
+    assert_eq!(results[1].range.start.line, 5);
+    assert_eq!(results[1].range.start.character, 0);
+    assert_eq!(results[1].range.end.line, 5);
+    assert_eq!(results[1].range.end.character, 72);
+}
+
+#[test]
+fn test_control_flow_and_recursion() {
+    let script = r#"
+(module
+  (func $fac (export "fac") (param i64) (result i64)
+    (if (result i64) (i64.eqz (local.get 0))
+      (then (i64.const 1))
+      (else (i64.mul (local.get 0) (call $fac (i64.sub (local.get 0) (i64.const 1)))))))
+  (func (export "sum") (param $n i32) (result i32) (local $acc i32)
+    (block $done
+      (loop $next
+        (br_if $done (i32.eqz (local.get $n)))
+        (local.set $acc (i32.add (local.get $acc) (local.get $n)))
+        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+        (br $next)))
+    (local.get $acc))
+  (func (export "switch") (param i32) (result i32)
+    (block $b2 (block $b1 (block $b0
+      (br_table $b0 $b1 $b2 (local.get 0)))
+      (return (i32.const 10)))
+      (return (i32.const 11)))
+    (i32.const 12))
+  (func (export "pair") (result i32 i64)
+    (i32.const 1) (i64.const 2))
+  (func $loop (export "loop") (call $loop)))
+(assert_return (invoke "fac" (i64.const 20)) (i64.const 2432902008176640000))
+(assert_return (invoke "sum" (i32.const 100)) (i32.const 5050))
+(assert_return (invoke "switch" (i32.const 0)) (i32.const 10))
+(assert_return (invoke "switch" (i32.const 1)) (i32.const 11))
+(assert_return (invoke "switch" (i32.const 7)) (i32.const 12))
+(assert_return (invoke "pair") (i32.const 1) (i64.const 2))
+(assert_exhaustion (invoke "loop") "call stack exhausted")
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 7);
+    assert!(
+        results
+            .iter()
+            .all(|(_, outcome)| *outcome == Outcome::Passed),
+        "{:?}",
+        results
+    );
+}
+
+#[test]
+fn test_traps() {
+    let script = r#"
+(module
+  (memory 1)
+  (table 2 funcref)
+  (elem (i32.const 0) $f)
+  (func $f (result i32) (i32.const 7))
+  (func (export "div") (param i32 i32) (result i32)
+    (i32.div_s (local.get 0) (local.get 1)))
+  (func (export "load") (param i32) (result i32)
+    (i32.load (local.get 0)))
+  (func (export "call") (param i32) (result i32)
+    (call_indirect (result i32) (local.get 0)))
+  (func (export "trunc") (param f32) (result i32)
+    (i32.trunc_f32_s (local.get 0))))
+(assert_trap (invoke "div" (i32.const 1) (i32.const 0)) "integer divide by zero")
+(assert_trap (invoke "div" (i32.const 0x80000000) (i32.const -1)) "integer overflow")
+(assert_trap (invoke "load" (i32.const 65534)) "out of bounds memory access")
+(assert_return (invoke "call" (i32.const 0)) (i32.const 7))
+(assert_trap (invoke "call" (i32.const 1)) "uninitialized element")
+(assert_trap (invoke "call" (i32.const 2)) "undefined element")
+(assert_trap (invoke "trunc" (f32.const nan)) "invalid conversion to integer")
+(assert_trap (invoke "div" (i32.const 4) (i32.const 2)) "integer divide by zero")
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 8);
+    assert!(
+        results[..7]
+            .iter()
+            .all(|(_, outcome)| *outcome == Outcome::Passed),
+        "{:?}",
+        results
+    );
+    assert_eq!(
+        results[7].1,
+        Outcome::Failed {
+            expected: "trap \"integer divide by zero\"".to_string(),
+            actual: "(i32.const 2)".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_float_results() {
+    let script = r#"
+(module
+  (func (export "div") (param f32 f32) (result f32)
+    (f32.div (local.get 0) (local.get 1)))
+  (func (export "min") (param f64 f64) (result f64)
+    (f64.min (local.get 0) (local.get 1)))
+  (func (export "nearest") (param f64) (result f64)
+    (f64.nearest (local.get 0))))
+(assert_return (invoke "div" (f32.const 1) (f32.const 4)) (f32.const 0.25))
+(assert_return (invoke "div" (f32.const 0) (f32.const 0)) (f32.const nan:canonical))
+(assert_return (invoke "min" (f64.const -0) (f64.const 0)) (f64.const -0))
+(assert_return (invoke "min" (f64.const nan:0x1) (f64.const 0)) (f64.const nan:arithmetic))
+(assert_return (invoke "nearest" (f64.const 2.5)) (f64.const 2))
+(assert_return (invoke "nearest" (f64.const -3.5)) (f64.const -4))
+"#;
+    let results = outcomes(script);
+    assert!(
+        results
+            .iter()
+            .all(|(_, outcome)| *outcome == Outcome::Passed),
+        "{:?}",
+        results
+    );
+}
+
+#[test]
+fn test_invalid_malformed_and_unlinkable() {
+    let script = r#"
+(assert_invalid (module (func (result i32) (i64.const 0))) "type mismatch")
+(assert_malformed (module quote "(func (i32.const))") "unexpected token")
+(assert_invalid (module (func)) "type mismatch")
+(assert_unlinkable (module (import "spectest" "nothing" (func))) "unknown import")
+"#;
+    let results = outcomes(script);
+    assert_eq!(results[0], ("assert_invalid", Outcome::Passed));
+    assert_eq!(results[1], ("assert_malformed", Outcome::Passed));
+    assert!(matches!(results[2].1, Outcome::Failed { .. }));
+    assert_eq!(results[3], ("assert_unlinkable", Outcome::Passed));
+}
+
+#[test]
+fn test_registered_modules_and_globals() {
+    let script = r#"
+(module $a
+  (global (export "g") (mut i32) (i32.const 1))
+  (func (export "inc") (global.set 0 (i32.add (global.get 0) (i32.const 1)))))
+(register "a" $a)
+(module $b
+  (import "a" "inc" (func $inc))
+  (import "spectest" "global_i32" (global i32))
+  (func (export "twice") (call $inc) (call $inc))
+  (func (export "spec") (result i32) (global.get 0)))
+(invoke "twice")
+(assert_return (get $a "g") (i32.const 3))
+(assert_return (invoke $b "spec") (i32.const 666))
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|(_, outcome)| *outcome == Outcome::Passed));
+}
+
+#[test]
+fn test_bulk_memory_and_segments() {
+    let script = r#"
+(module
+  (memory 1)
+  (data $d "\01\02\03\04")
+  (func (export "init") (memory.init $d (i32.const 8) (i32.const 1) (i32.const 3)))
+  (func (export "drop") (data.drop $d))
+  (func (export "copy") (memory.copy (i32.const 16) (i32.const 8) (i32.const 3)))
+  (func (export "fill") (memory.fill (i32.const 32) (i32.const 0xff) (i32.const 2)))
+  (func (export "load8") (param i32) (result i32) (i32.load8_u (local.get 0)))
+  (func (export "load16s") (param i32) (result i32) (i32.load16_s (local.get 0))))
+(invoke "init")
+(invoke "copy")
+(invoke "fill")
+(assert_return (invoke "load8" (i32.const 18)) (i32.const 4))
+(assert_return (invoke "load16s" (i32.const 32)) (i32.const -1))
+(invoke "drop")
+(assert_trap (invoke "init") "out of bounds")
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 3, "{:?}", results);
+    assert!(results
+        .iter()
+        .all(|(_, outcome)| *outcome == Outcome::Passed));
+}
+
+#[test]
+fn test_memory64_address_near_max_traps() {
+    let script = r#"
+(module
+  (memory i64 1)
+  (func (export "load") (param i64) (result i64) (i64.load (local.get 0)))
+  (func (export "store") (param i64) (i64.store (local.get 0) (i64.const 1))))
+(assert_trap (invoke "load" (i64.const 0xFFFF_FFFF_FFFF_FFFC)) "out of bounds memory access")
+(assert_trap (invoke "store" (i64.const 0xFFFF_FFFF_FFFF_FFFC)) "out of bounds memory access")
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 2, "{:?}", results);
+    assert!(results
+        .iter()
+        .all(|(_, outcome)| *outcome == Outcome::Passed));
+}
+
+#[test]
+fn test_unsupported_module_skips_its_commands() {
+    let script = r#"
+(module (func (export "f") (result v128) (v128.const i32x4 0 0 0 0)))
+(assert_return (invoke "f") (v128.const i32x4 0 0 0 0))
+(module (func (export "g") (result i32) (i32.const 1)))
+(assert_return (invoke "g") (i32.const 1))
+(assert_exception (invoke "g"))
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 4, "{:?}", results);
+    assert_eq!(results[0].0, "module");
+    assert!(matches!(results[0].1, Outcome::Skipped(_)));
+    assert!(matches!(results[1].1, Outcome::Skipped(_)));
+    assert_eq!(results[2], ("assert_return", Outcome::Passed));
+    assert!(matches!(results[3].1, Outcome::Skipped(_)));
+}
+
+#[test]
+fn test_start_function_trap_fails_module() {
+    let script = r#"
+(module (func $start unreachable) (start $start))
+(assert_trap (module (func $start unreachable) (start $start)) "unreachable")
+"#;
+    let results = outcomes(script);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].0, "module");
+    assert_eq!(
+        results[0].1,
+        Outcome::Failed {
+            expected: "the module to instantiate".to_string(),
+            actual: "trap \"unreachable\"".to_string(),
+        }
+    );
+    assert_eq!(results[1], ("assert_trap", Outcome::Passed));
+}
+
+#[test]
+fn test_parse_error() {
+    assert!(run_script("(module (func (i32.const)))").is_err());
+}
+
+const RUNNABLE: &str = r#"
+(module
+  (import "env" "log" (func $log (param i32)))
+  (import "env" "random" (func $random (result i32)))
+  (func (export "add") (param i32 i64) (result i64)
+    (i64.add (i64.extend_i32_s (local.get 0)) (local.get 1)))
+  (func (export "noisy") (result i32)
+    (call $log (i32.const 7))
+    (i32.add (call $random) (i32.const 1)))
+  (func (export "half") (param f32) (result f32)
+    (f32.mul (local.get 0) (f32.const 0.5))))
+"#;
+
+fn run(name: &str, args: &[&str], stubs: ImportStubs) -> Result<RunOutput, String> {
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    run_export(RUNNABLE.as_bytes(), name, &args, stubs)
+}
+
+#[test]
+fn test_run_export_with_arguments() {
+    let output = run("add", &["-1", "0x10"], ImportStubs::Trap).unwrap();
+    assert_eq!(output.results, Ok(vec![Value::I64(15)]));
+    assert_eq!(output.summary(), "(i64.const 15)");
+
+    let output = run("half", &["3"], ImportStubs::Trap).unwrap();
+    assert_eq!(output.summary(), "(f32.const 1.5)");
+
+    assert!(run("add", &["1"], ImportStubs::Trap).is_err());
+    assert!(run("add", &["one", "2"], ImportStubs::Trap).is_err());
+    assert!(run("missing", &[], ImportStubs::Trap).is_err());
+}
+
+#[test]
+fn test_run_export_import_stubs() {
+    let output = run("noisy", &[], ImportStubs::Trap).unwrap();
+    assert_eq!(output.summary(), "trap: called unprovided import env.log");
+
+    let output = run("noisy", &[], ImportStubs::Zero).unwrap();
+    assert_eq!(output.results, Ok(vec![Value::I32(1)]));
+    assert!(output.log.is_empty());
+
+    let output = run("noisy", &[], ImportStubs::Log).unwrap();
+    assert_eq!(output.results, Ok(vec![Value::I32(1)]));
+    assert_eq!(output.log, ["env.log (i32.const 7)", "env.random"]);
+}
@@ -0,0 +1,609 @@
+//! Runtime values, traps and the numeric instructions.
+
+use std::fmt;
+use wasmparser::{RefType, ValType};
+
+/// A WebAssembly value. Floats are kept as bits so NaN payloads survive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    I32(i32),
+    I64(i64),
+    F32(u32),
+    F64(u64),
+    /// Function address in the store, or null
+    FuncRef(Option<u32>),
+    /// Host value handed in by the embedder, or null
+    ExternRef(Option<u32>),
+}
+
+impl Value {
+    /// The zero value locals and tables of a type start with
+    pub fn default_for(ty: ValType) -> Value {
+        match ty {
+            ValType::I32 => Value::I32(0),
+            ValType::I64 => Value::I64(0),
+            ValType::F32 => Value::F32(0),
+            ValType::F64 => Value::F64(0),
+            ValType::Ref(ty) => Value::null(ty),
+            // Decoding rejects modules that use v128
+            ValType::V128 => Value::I64(0),
+        }
+    }
+
+    pub fn null(ty: RefType) -> Value {
+        if ty.is_func_ref() {
+            Value::FuncRef(None)
+        } else {
+            Value::ExternRef(None)
+        }
+    }
+
+    /// Whether the value can be stored in a slot of the given type
+    pub fn has_type(&self, ty: ValType) -> bool {
+        match (self, ty) {
+            (Value::I32(_), ValType::I32)
+            | (Value::I64(_), ValType::I64)
+            | (Value::F32(_), ValType::F32)
+            | (Value::F64(_), ValType::F64) => true,
+            (Value::FuncRef(value), ValType::Ref(ty)) => {
+                ty.is_func_ref() && (value.is_some() || ty.is_nullable())
+            }
+            (Value::ExternRef(value), ValType::Ref(ty)) => {
+                ty.is_extern_ref() && (value.is_some() || ty.is_nullable())
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::FuncRef(None) | Value::ExternRef(None))
+    }
+
+    // Accessors for operands whose type validation has already checked
+
+    pub(crate) fn i32(self) -> i32 {
+        match self {
+            Value::I32(value) => value,
+            other => unreachable!("expected i32, found {:?}", other),
+        }
+    }
+
+    pub(crate) fn i64(self) -> i64 {
+        match self {
+            Value::I64(value) => value,
+            other => unreachable!("expected i64, found {:?}", other),
+        }
+    }
+
+    pub(crate) fn f32(self) -> f32 {
+        match self {
+            Value::F32(bits) => f32::from_bits(bits),
+            other => unreachable!("expected f32, found {:?}", other),
+        }
+    }
+
+    pub(crate) fn f64(self) -> f64 {
+        match self {
+            Value::F64(bits) => f64::from_bits(bits),
+            other => unreachable!("expected f64, found {:?}", other),
+        }
+    }
+
+    /// A memory or table address, from an i32 or, for 64-bit ones, an i64
+    pub(crate) fn address(self) -> u64 {
+        match self {
+            Value::I32(value) => value as u32 as u64,
+            Value::I64(value) => value as u64,
+            other => unreachable!("expected an address, found {:?}", other),
+        }
+    }
+}
+
+/// Values print the way a script writes them, e.g. `(i32.const 42)`
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::I32(value) => write!(f, "(i32.const {})", value),
+            Value::I64(value) => write!(f, "(i64.const {})", value),
+            Value::F32(bits) => write!(
+                f,
+                "(f32.const {})",
+                format_float(f32::from_bits(*bits) as f64, *bits as u64, 23)
+            ),
+            Value::F64(bits) => write!(
+                f,
+                "(f64.const {})",
+                format_float(f64::from_bits(*bits), *bits, 52)
+            ),
+            Value::FuncRef(None) => write!(f, "(ref.null func)"),
+            Value::FuncRef(Some(_)) => write!(f, "(ref.func)"),
+            Value::ExternRef(None) => write!(f, "(ref.null extern)"),
+            Value::ExternRef(Some(value)) => write!(f, "(ref.extern {})", value),
+        }
+    }
+}
+
+/// Shortest decimal form, with the payload spelled out for NaNs
+fn format_float(value: f64, bits: u64, mantissa_bits: u32) -> String {
+    if value.is_nan() {
+        let sign = if value.is_sign_negative() { "-" } else { "" };
+        let payload = bits & ((1 << mantissa_bits) - 1);
+        if payload == 1 << (mantissa_bits - 1) {
+            format!("{}nan", sign)
+        } else {
+            format!("{}nan:0x{:x}", sign, payload)
+        }
+    } else if value.is_infinite() {
+        if value < 0.0 { "-inf" } else { "inf" }.to_string()
+    } else if mantissa_bits == 23 {
+        format!("{:?}", value as f32)
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+/// Why execution stopped abnormally. Messages use the spec interpreter's
+/// wording, which is what `assert_trap` scripts expect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trap(pub String);
+
+impl Trap {
+    pub fn new(message: impl Into<String>) -> Self {
+        Trap(message.into())
+    }
+
+    pub fn unreachable() -> Self {
+        Trap::new("unreachable")
+    }
+
+    pub fn memory_out_of_bounds() -> Self {
+        Trap::new("out of bounds memory access")
+    }
+
+    pub fn table_out_of_bounds() -> Self {
+        Trap::new("out of bounds table access")
+    }
+
+    fn divide_by_zero() -> Self {
+        Trap::new("integer divide by zero")
+    }
+
+    fn integer_overflow() -> Self {
+        Trap::new("integer overflow")
+    }
+
+    fn invalid_conversion() -> Self {
+        Trap::new("invalid conversion to integer")
+    }
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub(crate) type UnaryOp = fn(Value) -> Result<Value, Trap>;
+pub(crate) type BinaryOp = fn(Value, Value) -> Result<Value, Trap>;
+
+macro_rules! unary {
+    ($name:ident, $get:ident, $wrap:expr, |$a:ident| $body:expr) => {
+        pub(crate) fn $name(a: Value) -> Result<Value, Trap> {
+            let $a = a.$get();
+            Ok($wrap($body))
+        }
+    };
+}
+
+macro_rules! binary {
+    ($name:ident, $get:ident, $wrap:expr, |$a:ident, $b:ident| $body:expr) => {
+        pub(crate) fn $name(a: Value, b: Value) -> Result<Value, Trap> {
+            let ($a, $b) = (a.$get(), b.$get());
+            Ok($wrap($body))
+        }
+    };
+}
+
+fn bool_value(value: bool) -> Value {
+    Value::I32(value as i32)
+}
+
+fn f32_value(value: f32) -> Value {
+    Value::F32(value.to_bits())
+}
+
+fn f64_value(value: f64) -> Value {
+    Value::F64(value.to_bits())
+}
+
+// Integer tests and comparisons
+
+unary!(i32_eqz, i32, bool_value, |a| a == 0);
+binary!(i32_eq, i32, bool_value, |a, b| a == b);
+binary!(i32_ne, i32, bool_value, |a, b| a != b);
+binary!(i32_lt_s, i32, bool_value, |a, b| a < b);
+binary!(i32_lt_u, i32, bool_value, |a, b| (a as u32) < (b as u32));
+binary!(i32_gt_s, i32, bool_value, |a, b| a > b);
+binary!(i32_gt_u, i32, bool_value, |a, b| (a as u32) > (b as u32));
+binary!(i32_le_s, i32, bool_value, |a, b| a <= b);
+binary!(i32_le_u, i32, bool_value, |a, b| (a as u32) <= (b as u32));
+binary!(i32_ge_s, i32, bool_value, |a, b| a >= b);
+binary!(i32_ge_u, i32, bool_value, |a, b| (a as u32) >= (b as u32));
+
+unary!(i64_eqz, i64, bool_value, |a| a == 0);
+binary!(i64_eq, i64, bool_value, |a, b| a == b);
+binary!(i64_ne, i64, bool_value, |a, b| a != b);
+binary!(i64_lt_s, i64, bool_value, |a, b| a < b);
+binary!(i64_lt_u, i64, bool_value, |a, b| (a as u64) < (b as u64));
+binary!(i64_gt_s, i64, bool_value, |a, b| a > b);
+binary!(i64_gt_u, i64, bool_value, |a, b| (a as u64) > (b as u64));
+binary!(i64_le_s, i64, bool_value, |a, b| a <= b);
+binary!(i64_le_u, i64, bool_value, |a, b| (a as u64) <= (b as u64));
+binary!(i64_ge_s, i64, bool_value, |a, b| a >= b);
+binary!(i64_ge_u, i64, bool_value, |a, b| (a as u64) >= (b as u64));
+
+// Float comparisons
+
+binary!(f32_eq, f32, bool_value, |a, b| a == b);
+binary!(f32_ne, f32, bool_value, |a, b| a != b);
+binary!(f32_lt, f32, bool_value, |a, b| a < b);
+binary!(f32_gt, f32, bool_value, |a, b| a > b);
+binary!(f32_le, f32, bool_value, |a, b| a <= b);
+binary!(f32_ge, f32, bool_value, |a, b| a >= b);
+
+binary!(f64_eq, f64, bool_value, |a, b| a == b);
+binary!(f64_ne, f64, bool_value, |a, b| a != b);
+binary!(f64_lt, f64, bool_value, |a, b| a < b);
+binary!(f64_gt, f64, bool_value, |a, b| a > b);
+binary!(f64_le, f64, bool_value, |a, b| a <= b);
+binary!(f64_ge, f64, bool_value, |a, b| a >= b);
+
+// Integer arithmetic
+
+unary!(i32_clz, i32, Value::I32, |a| a.leading_zeros() as i32);
+unary!(i32_ctz, i32, Value::I32, |a| a.trailing_zeros() as i32);
+unary!(i32_popcnt, i32, Value::I32, |a| a.count_ones() as i32);
+binary!(i32_add, i32, Value::I32, |a, b| a.wrapping_add(b));
+binary!(i32_sub, i32, Value::I32, |a, b| a.wrapping_sub(b));
+binary!(i32_mul, i32, Value::I32, |a, b| a.wrapping_mul(b));
+binary!(i32_and, i32, Value::I32, |a, b| a & b);
+binary!(i32_or, i32, Value::I32, |a, b| a | b);
+binary!(i32_xor, i32, Value::I32, |a, b| a ^ b);
+binary!(i32_shl, i32, Value::I32, |a, b| a.wrapping_shl(b as u32));
+binary!(i32_shr_s, i32, Value::I32, |a, b| a.wrapping_shr(b as u32));
+binary!(
+    i32_shr_u,
+    i32,
+    Value::I32,
+    |a, b| (a as u32).wrapping_shr(b as u32) as i32
+);
+binary!(i32_rotl, i32, Value::I32, |a, b| a
+    .rotate_left(b as u32 % 32));
+binary!(i32_rotr, i32, Value::I32, |a, b| a
+    .rotate_right(b as u32 % 32));
+
+unary!(i64_clz, i64, Value::I64, |a| a.leading_zeros() as i64);
+unary!(i64_ctz, i64, Value::I64, |a| a.trailing_zeros() as i64);
+unary!(i64_popcnt, i64, Value::I64, |a| a.count_ones() as i64);
+binary!(i64_add, i64, Value::I64, |a, b| a.wrapping_add(b));
+binary!(i64_sub, i64, Value::I64, |a, b| a.wrapping_sub(b));
+binary!(i64_mul, i64, Value::I64, |a, b| a.wrapping_mul(b));
+binary!(i64_and, i64, Value::I64, |a, b| a & b);
+binary!(i64_or, i64, Value::I64, |a, b| a | b);
+binary!(i64_xor, i64, Value::I64, |a, b| a ^ b);
+binary!(i64_shl, i64, Value::I64, |a, b| a.wrapping_shl(b as u32));
+binary!(i64_shr_s, i64, Value::I64, |a, b| a.wrapping_shr(b as u32));
+binary!(
+    i64_shr_u,
+    i64,
+    Value::I64,
+    |a, b| (a as u64).wrapping_shr(b as u32) as i64
+);
+binary!(i64_rotl, i64, Value::I64, |a, b| a
+    .rotate_left((b as u64 % 64) as u32));
+binary!(i64_rotr, i64, Value::I64, |a, b| a
+    .rotate_right((b as u64 % 64) as u32));
+
+macro_rules! division {
+    ($div_s:ident, $div_u:ident, $rem_s:ident, $rem_u:ident, $get:ident, $wrap:expr, $unsigned:ty) => {
+        pub(crate) fn $div_s(a: Value, b: Value) -> Result<Value, Trap> {
+            let (a, b) = (a.$get(), b.$get());
+            if b == 0 {
+                return Err(Trap::divide_by_zero());
+            }
+            a.checked_div(b)
+                .map($wrap)
+                .ok_or_else(Trap::integer_overflow)
+        }
+
+        pub(crate) fn $div_u(a: Value, b: Value) -> Result<Value, Trap> {
+            let (a, b) = (a.$get() as $unsigned, b.$get() as $unsigned);
+            if b == 0 {
+                return Err(Trap::divide_by_zero());
+            }
+            Ok($wrap((a / b) as _))
+        }
+
+        pub(crate) fn $rem_s(a: Value, b: Value) -> Result<Value, Trap> {
+            let (a, b) = (a.$get(), b.$get());
+            if b == 0 {
+                return Err(Trap::divide_by_zero());
+            }
+            Ok($wrap(a.wrapping_rem(b)))
+        }
+
+        pub(crate) fn $rem_u(a: Value, b: Value) -> Result<Value, Trap> {
+            let (a, b) = (a.$get() as $unsigned, b.$get() as $unsigned);
+            if b == 0 {
+                return Err(Trap::divide_by_zero());
+            }
+            Ok($wrap((a % b) as _))
+        }
+    };
+}
+
+division!(
+    i32_div_s,
+    i32_div_u,
+    i32_rem_s,
+    i32_rem_u,
+    i32,
+    Value::I32,
+    u32
+);
+division!(
+    i64_div_s,
+    i64_div_u,
+    i64_rem_s,
+    i64_rem_u,
+    i64,
+    Value::I64,
+    u64
+);
+
+// Float arithmetic. Sign operations work on the bits so they keep NaN
+// payloads, as the spec requires.
+
+macro_rules! float_ops {
+    (
+        $get:ident, $wrap:expr, $variant:ident, $sign:expr,
+        $abs:ident, $neg:ident, $copysign:ident, $ceil:ident, $floor:ident, $trunc:ident,
+        $nearest:ident, $sqrt:ident, $add:ident, $sub:ident, $mul:ident, $div:ident,
+        $min:ident, $max:ident
+    ) => {
+        pub(crate) fn $abs(a: Value) -> Result<Value, Trap> {
+            match a {
+                Value::$variant(bits) => Ok(Value::$variant(bits & !$sign)),
+                other => unreachable!("expected a float, found {:?}", other),
+            }
+        }
+
+        pub(crate) fn $neg(a: Value) -> Result<Value, Trap> {
+            match a {
+                Value::$variant(bits) => Ok(Value::$variant(bits ^ $sign)),
+                other => unreachable!("expected a float, found {:?}", other),
+            }
+        }
+
+        pub(crate) fn $copysign(a: Value, b: Value) -> Result<Value, Trap> {
+            match (a, b) {
+                (Value::$variant(a), Value::$variant(b)) => {
+                    Ok(Value::$variant((a & !$sign) | (b & $sign)))
+                }
+                other => unreachable!("expected floats, found {:?}", other),
+            }
+        }
+
+        unary!($ceil, $get, $wrap, |a| a.ceil());
+        unary!($floor, $get, $wrap, |a| a.floor());
+        unary!($trunc, $get, $wrap, |a| a.trunc());
+        unary!($nearest, $get, $wrap, |a| a.round_ties_even());
+        unary!($sqrt, $get, $wrap, |a| a.sqrt());
+        binary!($add, $get, $wrap, |a, b| a + b);
+        binary!($sub, $get, $wrap, |a, b| a - b);
+        binary!($mul, $get, $wrap, |a, b| a * b);
+        binary!($div, $get, $wrap, |a, b| a / b);
+
+        pub(crate) fn $min(a: Value, b: Value) -> Result<Value, Trap> {
+            let (a, b) = (a.$get(), b.$get());
+            Ok($wrap(if a.is_nan() || b.is_nan() {
+                a + b
+            } else if a == b {
+                // min(-0, +0) is -0
+                if a.is_sign_negative() {
+                    a
+                } else {
+                    b
+                }
+            } else {
+                a.min(b)
+            }))
+        }
+
+        pub(crate) fn $max(a: Value, b: Value) -> Result<Value, Trap> {
+            let (a, b) = (a.$get(), b.$get());
+            Ok($wrap(if a.is_nan() || b.is_nan() {
+                a + b
+            } else if a == b {
+                if a.is_sign_positive() {
+                    a
+                } else {
+                    b
+                }
+            } else {
+                a.max(b)
+            }))
+        }
+    };
+}
+
+float_ops!(
+    f32,
+    f32_value,
+    F32,
+    1u32 << 31,
+    f32_abs,
+    f32_neg,
+    f32_copysign,
+    f32_ceil,
+    f32_floor,
+    f32_trunc,
+    f32_nearest,
+    f32_sqrt,
+    f32_add,
+    f32_sub,
+    f32_mul,
+    f32_div,
+    f32_min,
+    f32_max
+);
+float_ops!(
+    f64,
+    f64_value,
+    F64,
+    1u64 << 63,
+    f64_abs,
+    f64_neg,
+    f64_copysign,
+    f64_ceil,
+    f64_floor,
+    f64_trunc,
+    f64_nearest,
+    f64_sqrt,
+    f64_add,
+    f64_sub,
+    f64_mul,
+    f64_div,
+    f64_min,
+    f64_max
+);
+
+// Conversions
+
+unary!(i32_wrap_i64, i64, Value::I32, |a| a as i32);
+unary!(i64_extend_i32_s, i32, Value::I64, |a| a as i64);
+unary!(i64_extend_i32_u, i32, Value::I64, |a| a as u32 as i64);
+unary!(i32_extend8_s, i32, Value::I32, |a| a as i8 as i32);
+unary!(i32_extend16_s, i32, Value::I32, |a| a as i16 as i32);
+unary!(i64_extend8_s, i64, Value::I64, |a| a as i8 as i64);
+unary!(i64_extend16_s, i64, Value::I64, |a| a as i16 as i64);
+unary!(i64_extend32_s, i64, Value::I64, |a| a as i32 as i64);
+
+unary!(f32_convert_i32_s, i32, f32_value, |a| a as f32);
+unary!(f32_convert_i32_u, i32, f32_value, |a| a as u32 as f32);
+unary!(f32_convert_i64_s, i64, f32_value, |a| a as f32);
+unary!(f32_convert_i64_u, i64, f32_value, |a| a as u64 as f32);
+unary!(f32_demote_f64, f64, f32_value, |a| a as f32);
+unary!(f64_convert_i32_s, i32, f64_value, |a| a as f64);
+unary!(f64_convert_i32_u, i32, f64_value, |a| a as u32 as f64);
+unary!(f64_convert_i64_s, i64, f64_value, |a| a as f64);
+unary!(f64_convert_i64_u, i64, f64_value, |a| a as u64 as f64);
+unary!(f64_promote_f32, f32, f64_value, |a| a as f64);
+
+pub(crate) fn i32_reinterpret_f32(a: Value) -> Result<Value, Trap> {
+    Ok(Value::I32(a.f32().to_bits() as i32))
+}
+
+pub(crate) fn i64_reinterpret_f64(a: Value) -> Result<Value, Trap> {
+    Ok(Value::I64(a.f64().to_bits() as i64))
+}
+
+pub(crate) fn f32_reinterpret_i32(a: Value) -> Result<Value, Trap> {
+    Ok(Value::F32(a.i32() as u32))
+}
+
+pub(crate) fn f64_reinterpret_i64(a: Value) -> Result<Value, Trap> {
+    Ok(Value::F64(a.i64() as u64))
+}
+
+/// Trapping float-to-int truncation. `min` and `max` are the exclusive
+/// bounds of the target range, exact in the source float type.
+macro_rules! truncate {
+    ($name:ident, $get:ident, $wrap:expr, $target:ty, $min:expr, $max:expr) => {
+        pub(crate) fn $name(a: Value) -> Result<Value, Trap> {
+            let a = a.$get();
+            if a.is_nan() {
+                return Err(Trap::invalid_conversion());
+            }
+            if a <= $min || a >= $max {
+                return Err(Trap::integer_overflow());
+            }
+            Ok($wrap(a.trunc() as $target as _))
+        }
+    };
+}
+
+truncate!(
+    i32_trunc_f32_s,
+    f32,
+    Value::I32,
+    i32,
+    -2147483904.0f32,
+    2147483648.0f32
+);
+truncate!(
+    i32_trunc_f32_u,
+    f32,
+    Value::I32,
+    u32,
+    -1.0f32,
+    4294967296.0f32
+);
+truncate!(
+    i32_trunc_f64_s,
+    f64,
+    Value::I32,
+    i32,
+    -2147483649.0f64,
+    2147483648.0f64
+);
+truncate!(
+    i32_trunc_f64_u,
+    f64,
+    Value::I32,
+    u32,
+    -1.0f64,
+    4294967296.0f64
+);
+truncate!(
+    i64_trunc_f32_s,
+    f32,
+    Value::I64,
+    i64,
+    -9223373136366403584.0f32,
+    9223372036854775808.0f32
+);
+truncate!(
+    i64_trunc_f32_u,
+    f32,
+    Value::I64,
+    u64,
+    -1.0f32,
+    18446744073709551616.0f32
+);
+truncate!(
+    i64_trunc_f64_s,
+    f64,
+    Value::I64,
+    i64,
+    -9223372036854777856.0f64,
+    9223372036854775808.0f64
+);
+truncate!(
+    i64_trunc_f64_u,
+    f64,
+    Value::I64,
+    u64,
+    -1.0f64,
+    18446744073709551616.0f64
+);
+
+// Rust's `as` saturates and maps NaN to zero, exactly the spec semantics
+unary!(i32_trunc_sat_f32_s, f32, Value::I32, |a| a as i32);
+unary!(i32_trunc_sat_f32_u, f32, Value::I32, |a| a as u32 as i32);
+unary!(i32_trunc_sat_f64_s, f64, Value::I32, |a| a as i32);
+unary!(i32_trunc_sat_f64_u, f64, Value::I32, |a| a as u32 as i32);
+unary!(i64_trunc_sat_f32_s, f32, Value::I64, |a| a as i64);
+unary!(i64_trunc_sat_f32_u, f32, Value::I64, |a| a as u64 as i64);
+unary!(i64_trunc_sat_f64_s, f64, Value::I64, |a| a as i64);
+unary!(i64_trunc_sat_f64_u, f64, Value::I64, |a| a as u64 as i64);
@@ -6,6 +6,30 @@ pub mod core;
 // Wast-based parser (works in WASM, always available)
 pub mod wast_parser;
 
+// Interpreter for running .wast assertions (always available)
+pub mod interpreter;
+
+// Disassembler for viewing binary modules as WAT (always available)
+pub mod disassembler;
+
+// Items and references of component-model components (always available)
+pub mod component;
+
+// Encoding modules to binaries and measuring them (always available)
+pub mod compile;
+
+// Server settings (always available)
+pub mod settings;
+
+// Links between workspace modules from wat-lsp.toml (always available)
+pub mod linking;
+
+// Descriptions of the imports a host provides (always available)
+pub mod host;
+
+// TypeScript declarations for a module's exports and imports (always available)
+pub mod typescript;
+
 // Tree-sitter facade (unified interface for native and WASM)
 #[cfg(any(feature = "native", feature = "wasm"))]
 pub mod ts_facade;
@@ -26,6 +50,10 @@ pub mod utils;
 #[cfg(any(feature = "native", feature = "wasm"))]
 pub mod symbol_lookup;
 
+// Differential comparison of the tree-sitter and wast parsers
+#[cfg(any(feature = "native", feature = "wasm"))]
+pub mod parity;
+
 // LSP feature modules (completion, definition, hover, references, signature, symbols)
 pub mod features;
 
@@ -35,18 +63,36 @@ pub use features::symbols;
 #[cfg(any(feature = "native", feature = "wasm"))]
 pub use features::hover;
 
+#[cfg(feature = "native")]
+pub use features::call_hierarchy;
+
+#[cfg(feature = "native")]
+pub use features::code_lens;
+
 #[cfg(feature = "native")]
 pub use features::completion;
 
 #[cfg(feature = "native")]
 pub use features::definition;
 
+#[cfg(feature = "native")]
+pub use features::document_symbols;
+
+#[cfg(feature = "native")]
+pub use features::linked_editing;
+
 #[cfg(feature = "native")]
 pub use features::references;
 
 #[cfg(feature = "native")]
 pub use features::signature;
 
+#[cfg(feature = "native")]
+pub use features::type_definition;
+
+#[cfg(feature = "native")]
+pub use features::type_hierarchy;
+
 // Diagnostics (native only)
 #[cfg(feature = "native")]
 pub mod diagnostics;
@@ -0,0 +1,585 @@
+//! Links between the modules of a workspace.
+//!
+//! A `wat-lsp.toml` at a workspace root names the file that provides each
+//! import module, with paths relative to the config file:
+//!
+//! ```toml
+//! [modules]
+//! core = "runtime/core.wat"
+//! ```
+//!
+//! An import from a configured module binds to the export of that file with
+//! the same name as its field. Imports from other modules are left to the
+//! host, whose imports can be described in the files `host` lists; see
+//! [`crate::host`].
+
+use crate::core::Position;
+use crate::host::HostInterface;
+use crate::symbols::{Export, ExternKind, Import, SymbolTable, TypeKind, ValueType};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the link config file looked for at each workspace root
+pub const CONFIG_FILE: &str = "wat-lsp.toml";
+
+/// Files that provide import modules, by module name, and the imports
+/// the host provides
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LinkConfig {
+    modules: BTreeMap<String, PathBuf>,
+    host_files: Vec<PathBuf>,
+    host: HostInterface,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    modules: BTreeMap<String, PathBuf>,
+    #[serde(default)]
+    host: Vec<PathBuf>,
+}
+
+impl LinkConfig {
+    /// Read a config, resolving its paths against `dir`. Host descriptions
+    /// are only read by [`LinkConfig::load`].
+    pub fn parse(text: &str, dir: &Path) -> Result<Self, String> {
+        let file: ConfigFile =
+            toml::from_str(text).map_err(|e| format!("Invalid {}: {}", CONFIG_FILE, e))?;
+        let modules = file
+            .modules
+            .into_iter()
+            .map(|(name, path)| (name, dir.join(path)))
+            .collect();
+        let host_files = file.host.iter().map(|path| dir.join(path)).collect();
+        Ok(Self {
+            modules,
+            host_files,
+            host: HostInterface::default(),
+        })
+    }
+
+    /// Read the config of every root that has one, and the host
+    /// descriptions they list. A module configured by more than one root
+    /// keeps the path of the first.
+    pub fn load(roots: &[PathBuf]) -> Result<Self, String> {
+        let mut config = Self::default();
+        for root in roots {
+            let path = root.join(CONFIG_FILE);
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let loaded =
+                Self::parse(&text, root).map_err(|e| format!("{}: {}", path.display(), e))?;
+            for (name, path) in loaded.modules {
+                config.modules.entry(name).or_insert(path);
+            }
+            config.host_files.extend(loaded.host_files);
+        }
+        for path in &config.host_files {
+            config.host.extend(HostInterface::load(path)?);
+        }
+        Ok(config)
+    }
+
+    /// Whether the config links no modules and describes no host
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty() && self.host.is_empty()
+    }
+
+    /// Imports the host provides
+    pub fn host(&self) -> &HostInterface {
+        &self.host
+    }
+
+    /// Whether `path` is one of the host descriptions
+    pub fn is_host_file(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        self.host_files.iter().any(|file| normalize(file) == path)
+    }
+
+    /// Configured modules and the files providing them
+    pub fn modules(&self) -> impl Iterator<Item = (&str, &Path)> {
+        self.modules
+            .iter()
+            .map(|(name, path)| (name.as_str(), path.as_path()))
+    }
+
+    /// The file that provides `module`
+    pub fn module_path(&self, module: &str) -> Option<&Path> {
+        self.modules.get(module).map(PathBuf::as_path)
+    }
+
+    /// Names of the modules `path` provides
+    pub fn modules_provided_by<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a str> {
+        let path = normalize(path);
+        self.modules
+            .iter()
+            .filter(move |(_, provider)| normalize(provider) == path)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// A path with symlinks and `..` resolved, if it exists
+fn normalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The export an import binds to in the module providing it
+pub fn find_export<'a>(provider: &'a SymbolTable, import: &Import) -> Option<&'a Export> {
+    provider.exports.iter().find(|e| e.name == import.field)
+}
+
+/// The imports bound to any of the given exports, when `symbols` imports
+/// them from `module`
+pub fn imports_of<'a>(
+    symbols: &'a SymbolTable,
+    module: &'a str,
+    exports: &'a [&'a Export],
+) -> impl Iterator<Item = &'a Import> {
+    symbols.imports.iter().filter(move |import| {
+        import.module == module
+            && exports
+                .iter()
+                .any(|e| e.name == import.field && e.kind == import.kind)
+    })
+}
+
+/// The import whose declaration contains `position`
+pub fn import_at(symbols: &SymbolTable, position: Position) -> Option<&Import> {
+    symbols
+        .imports
+        .iter()
+        .find(|i| i.range.is_some_and(|range| range.contains(position)))
+}
+
+/// Type of an imported or exported item
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExternType {
+    Func {
+        params: Vec<ValueType>,
+        results: Vec<ValueType>,
+    },
+    Global {
+        value_type: ValueType,
+        mutable: bool,
+    },
+    Table {
+        ref_type: ValueType,
+        limits: (u32, Option<u32>),
+    },
+    Memory {
+        limits: (u64, Option<u64>),
+        is_memory64: bool,
+        shared: bool,
+    },
+    Tag {
+        params: Vec<ValueType>,
+    },
+}
+
+impl ExternType {
+    /// Type of the item of the given kind and index. A function declared
+    /// with a `(type $t)` use has the signature of `$t`.
+    pub fn of(symbols: &SymbolTable, kind: ExternKind, index: usize) -> Option<Self> {
+        Some(match kind {
+            ExternKind::Func => {
+                let func = symbols.get_function_by_index(index)?;
+                let declared = func
+                    .type_use
+                    .as_ref()
+                    .and_then(|type_use| type_use.index)
+                    .and_then(|index| symbols.get_type_by_index(index as usize));
+                if let Some(TypeKind::Func { params, results }) = declared.map(|t| &t.kind) {
+                    return Some(ExternType::Func {
+                        params: params.clone(),
+                        results: results.clone(),
+                    });
+                }
+                ExternType::Func {
+                    params: func
+                        .parameters
+                        .iter()
+                        .map(|p| p.param_type.clone())
+                        .collect(),
+                    results: func.results.clone(),
+                }
+            }
+            ExternKind::Global => {
+                let global = symbols.get_global_by_index(index)?;
+                ExternType::Global {
+                    value_type: global.var_type.clone(),
+                    mutable: global.is_mutable,
+                }
+            }
+            ExternKind::Table => {
+                let table = symbols.get_table_by_index(index)?;
+                ExternType::Table {
+                    ref_type: table.ref_type.clone(),
+                    limits: table.limits,
+                }
+            }
+            ExternKind::Memory => {
+                let memory = symbols.get_memory_by_index(index)?;
+                ExternType::Memory {
+                    limits: memory.limits,
+                    is_memory64: memory.is_memory64,
+                    shared: memory.shared,
+                }
+            }
+            ExternKind::Tag => ExternType::Tag {
+                params: symbols.get_tag_by_index(index)?.params.clone(),
+            },
+        })
+    }
+
+    /// The kind of item this is a type of
+    pub fn kind(&self) -> ExternKind {
+        match self {
+            ExternType::Func { .. } => ExternKind::Func,
+            ExternType::Global { .. } => ExternKind::Global,
+            ExternType::Table { .. } => ExternKind::Table,
+            ExternType::Memory { .. } => ExternKind::Memory,
+            ExternType::Tag { .. } => ExternKind::Tag,
+        }
+    }
+
+    /// The type as an import descriptor declaring an item named `name`,
+    /// e.g. `(func $log (param i32))`
+    pub fn descriptor(&self, name: &str) -> String {
+        let text = self.to_string();
+        let keyword_end = text.find([' ', ')']).unwrap_or(text.len());
+        format!("{} {}{}", &text[..keyword_end], name, &text[keyword_end..])
+    }
+
+    /// Whether an export of this type satisfies an import of type `import`,
+    /// following the spec's import matching: exact types, and limits at
+    /// least as tight as the import's
+    pub fn satisfies(&self, import: &ExternType) -> bool {
+        match (self, import) {
+            (
+                ExternType::Func { params, results },
+                ExternType::Func {
+                    params: import_params,
+                    results: import_results,
+                },
+            ) => same_types(params, import_params) && same_types(results, import_results),
+            (
+                ExternType::Global {
+                    value_type,
+                    mutable,
+                },
+                ExternType::Global {
+                    value_type: import_type,
+                    mutable: import_mutable,
+                },
+            ) => mutable == import_mutable && same_type(value_type, import_type),
+            (
+                ExternType::Table { ref_type, limits },
+                ExternType::Table {
+                    ref_type: import_type,
+                    limits: import_limits,
+                },
+            ) => same_type(ref_type, import_type) && limits_match(*limits, *import_limits),
+            (
+                ExternType::Memory {
+                    limits,
+                    is_memory64,
+                    shared,
+                },
+                ExternType::Memory {
+                    limits: import_limits,
+                    is_memory64: import_memory64,
+                    shared: import_shared,
+                },
+            ) => {
+                is_memory64 == import_memory64
+                    && shared == import_shared
+                    && limits_match(*limits, *import_limits)
+            }
+            (
+                ExternType::Tag { params },
+                ExternType::Tag {
+                    params: import_params,
+                },
+            ) => same_types(params, import_params),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ExternType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let write_types = |f: &mut fmt::Formatter<'_>, keyword: &str, types: &[ValueType]| {
+            if types.is_empty() {
+                return Ok(());
+            }
+            write!(f, " ({}", keyword)?;
+            for value_type in types {
+                write!(f, " {}", value_type)?;
+            }
+            write!(f, ")")
+        };
+        let write_limits = |f: &mut fmt::Formatter<'_>, min: u64, max: Option<u64>| {
+            write!(f, " {}", min)?;
+            match max {
+                Some(max) => write!(f, " {}", max),
+                None => Ok(()),
+            }
+        };
+
+        match self {
+            ExternType::Func { params, results } => {
+                write!(f, "(func")?;
+                write_types(f, "param", params)?;
+                write_types(f, "result", results)?;
+            }
+            ExternType::Global {
+                value_type,
+                mutable: true,
+            } => write!(f, "(global (mut {})", value_type)?,
+            ExternType::Global { value_type, .. } => write!(f, "(global {}", value_type)?,
+            ExternType::Table { ref_type, limits } => {
+                write!(f, "(table")?;
+                write_limits(f, limits.0.into(), limits.1.map(u64::from))?;
+                write!(f, " {}", ref_type)?;
+            }
+            ExternType::Memory {
+                limits,
+                is_memory64,
+                shared,
+            } => {
+                write!(f, "(memory")?;
+                if *is_memory64 {
+                    write!(f, " i64")?;
+                }
+                write_limits(f, limits.0, limits.1)?;
+                if *shared {
+                    write!(f, " shared")?;
+                }
+            }
+            ExternType::Tag { params } => {
+                write!(f, "(tag")?;
+                write_types(f, "param", params)?;
+            }
+        }
+        write!(f, ")")
+    }
+}
+
+fn same_types(a: &[ValueType], b: &[ValueType]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_type(a, b))
+}
+
+/// Whether two value types of different modules agree. Typed references
+/// index each module's own types, so only their nullability is compared.
+fn same_type(a: &ValueType, b: &ValueType) -> bool {
+    match (a, b) {
+        (ValueType::Ref(_), ValueType::Ref(_)) | (ValueType::RefNull(_), ValueType::RefNull(_)) => {
+            true
+        }
+        (ValueType::Unknown, _) | (_, ValueType::Unknown) => true,
+        _ => a == b,
+    }
+}
+
+/// Whether exported limits fit within imported ones
+fn limits_match<T: PartialOrd + Copy>(export: (T, Option<T>), import: (T, Option<T>)) -> bool {
+    let max_fits = match (export.1, import.1) {
+        (_, None) => true,
+        (Some(export_max), Some(import_max)) => export_max <= import_max,
+        (None, Some(_)) => false,
+    };
+    export.0 >= import.0 && max_fits
+}
+
+/// Why an import can't bind to the module configured to provide it
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkError {
+    /// The providing module has no export with the import's field name
+    MissingExport,
+    /// The export is a different kind of item
+    KindMismatch { export: ExternKind },
+    /// The export's type doesn't satisfy the import's
+    TypeMismatch {
+        import: ExternType,
+        export: ExternType,
+    },
+}
+
+impl LinkError {
+    /// Message for an import of `import`
+    pub fn message(&self, import: &Import) -> String {
+        match self {
+            LinkError::MissingExport => format!(
+                "Module '{}' has no export named '{}'",
+                import.module, import.field
+            ),
+            LinkError::KindMismatch { export } => format!(
+                "Import '{}' expects a {}, but '{}' exports a {}",
+                import.qualified_name(),
+                import.kind,
+                import.module,
+                export
+            ),
+            LinkError::TypeMismatch {
+                import: expected,
+                export,
+            } => format!(
+                "Import '{}' has type {}, but the export has type {}",
+                import.qualified_name(),
+                expected,
+                export
+            ),
+        }
+    }
+}
+
+/// Check an import of `symbols` against the module that provides it
+pub fn check_import(
+    symbols: &SymbolTable,
+    import: &Import,
+    provider: &SymbolTable,
+) -> Result<(), LinkError> {
+    let export = find_export(provider, import).ok_or(LinkError::MissingExport)?;
+    if export.kind != import.kind {
+        return Err(LinkError::KindMismatch {
+            export: export.kind,
+        });
+    }
+
+    let (Some(expected), Some(actual)) = (
+        ExternType::of(symbols, import.kind, import.index),
+        ExternType::of(provider, export.kind, export.index),
+    ) else {
+        return Ok(());
+    };
+    if actual.satisfies(&expected) {
+        Ok(())
+    } else {
+        Err(LinkError::TypeMismatch {
+            import: expected,
+            export: actual,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wast_parser::parse_document;
+
+    const CORE: &str = r#"(module
+  (memory (export "memory") 1 4)
+  (global (export "heap") (mut i32) (i32.const 0))
+  (func (export "alloc") (param i32) (result i32) (local.get 0)))"#;
+
+    fn check(source: &str) -> Vec<Result<(), LinkError>> {
+        let provider = parse_document(CORE).unwrap();
+        let symbols = parse_document(source).unwrap();
+        symbols
+            .imports
+            .iter()
+            .map(|import| check_import(&symbols, import, &provider))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = LinkConfig::parse(
+            "[modules]\ncore = \"runtime/core.wat\"\n",
+            Path::new("/workspace"),
+        )
+        .unwrap();
+        assert_eq!(
+            config.module_path("core"),
+            Some(Path::new("/workspace/runtime/core.wat"))
+        );
+        assert_eq!(config.module_path("env"), None);
+
+        let config =
+            LinkConfig::parse("host = [\"host/env.toml\"]\n", Path::new("/workspace")).unwrap();
+        assert!(config.is_host_file(Path::new("/workspace/host/env.toml")));
+
+        assert!(LinkConfig::parse("", Path::new("/")).unwrap().is_empty());
+        assert!(LinkConfig::parse("[modules]\ncore = 1\n", Path::new("/")).is_err());
+        assert!(LinkConfig::parse("[module]\n", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_matching_imports() {
+        let results = check(
+            r#"(module
+  (import "core" "alloc" (func (param i32) (result i32)))
+  (import "core" "heap" (global (mut i32)))
+  (import "core" "memory" (memory 1)))"#,
+        );
+        assert_eq!(results, vec![Ok(()), Ok(()), Ok(())]);
+    }
+
+    #[test]
+    fn test_type_use_imports() {
+        let results = check(
+            r#"(module
+  (type $alloc_t (func (param i32) (result i32)))
+  (import "core" "alloc" (func (type $alloc_t)))
+  (import "core" "alloc" (func (type 0))))"#,
+        );
+        assert_eq!(results, vec![Ok(()), Ok(())]);
+
+        let results = check(
+            r#"(module
+  (type $free_t (func (param i32)))
+  (import "core" "alloc" (func (type $free_t))))"#,
+        );
+        assert!(matches!(results[0], Err(LinkError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn test_mismatched_imports() {
+        let results = check(
+            r#"(module
+  (import "core" "alloc" (func (param i64) (result i32)))
+  (import "core" "heap" (global i32))
+  (import "core" "memory" (memory 1 2))
+  (import "core" "alloc" (global i32))
+  (import "core" "free" (func (param i32))))"#,
+        );
+
+        let Err(LinkError::TypeMismatch { import, export }) = &results[0] else {
+            panic!("expected a type mismatch, got {:?}", results[0]);
+        };
+        assert_eq!(import.to_string(), "(func (param i64) (result i32))");
+        assert_eq!(export.to_string(), "(func (param i32) (result i32))");
+
+        assert!(matches!(results[1], Err(LinkError::TypeMismatch { .. })));
+        // The export may grow to 4 pages, more than the import allows
+        assert!(matches!(results[2], Err(LinkError::TypeMismatch { .. })));
+        assert_eq!(
+            results[3],
+            Err(LinkError::KindMismatch {
+                export: ExternKind::Func
+            })
+        );
+        assert_eq!(results[4], Err(LinkError::MissingExport));
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let symbols = parse_document(r#"(module (import "core" "heap" (global i32)))"#).unwrap();
+        let provider = parse_document(CORE).unwrap();
+        let import = &symbols.imports[0];
+        let error = check_import(&symbols, import, &provider).unwrap_err();
+        assert_eq!(
+            error.message(import),
+            "Import 'core.heap' has type (global i32), but the export has type (global (mut i32))"
+        );
+        assert_eq!(
+            LinkError::MissingExport.message(import),
+            "Module 'core' has no export named 'heap'"
+        );
+    }
+}
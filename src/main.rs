@@ -1,93 +1,651 @@
 // Use modules from the library crate
+use wat_lsp_rust::component;
+use wat_lsp_rust::core::{CancellationGuard, CancellationToken, LineIndex, PositionEncoding};
+use wat_lsp_rust::disassembler::Disassembly;
+use wat_lsp_rust::interpreter::{self, Outcome};
+use wat_lsp_rust::linking::{self, LinkConfig, CONFIG_FILE};
+use wat_lsp_rust::native::adapters::{self, ConvertPositions, PositionConverter};
+use wat_lsp_rust::native::{
+    binary_offset, commands, disassembly, find_wat_files, import_definition, importable_items,
+    importing_locations, linked_documents, DisassembleParams, DisassembleResult, DocumentSnapshot,
+    GotoBinaryOffsetParams, LinkedDocument,
+};
+use wat_lsp_rust::settings::{DiagnosticLevel, Settings, CONFIGURATION_SECTION};
 use wat_lsp_rust::{
-    completion, definition, diagnostics, hover, parser, references, signature, symbols,
-    tree_sitter_bindings, utils,
+    call_hierarchy, code_lens, compile, completion, definition, diagnostics, document_symbols,
+    hover, linked_editing, parser, references, signature, symbols, tree_sitter_bindings,
+    type_definition, type_hierarchy, typescript, utils,
 };
 
 use dashmap::DashMap;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
 use tokio::sync::watch;
 use tokio::time::{sleep, Duration};
-use tower_lsp::jsonrpc::Result;
+use tower_lsp::jsonrpc::{Error, ErrorCode, Result};
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 use tree_sitter::Tree;
 
-const DEBOUNCE_DURATION_MS: u64 = 500;
-
-/// Type alias for document context references returned by `get_document_context`
-type DocumentContext<'a> = (
-    dashmap::mapref::one::Ref<'a, String, String>,
-    dashmap::mapref::one::Ref<'a, String, symbols::SymbolTable>,
-    dashmap::mapref::one::Ref<'a, String, Tree>,
-);
-
 #[derive(Debug)]
 struct Backend {
     client: Client,
-    document_map: DashMap<String, String>,
-    symbol_map: DashMap<String, symbols::SymbolTable>,
-    tree_map: DashMap<String, Tree>,
+    /// Latest snapshot of each open document, shared with background validation
+    documents: Arc<DashMap<String, Arc<DocumentSnapshot>>>,
     validation_cancellation: DashMap<String, watch::Sender<bool>>,
+    /// Pending reparse of each large component, cancelled by the next edit
+    reparse_cancellation: DashMap<String, watch::Sender<bool>>,
+    /// Encoding of position columns agreed on in `initialize`
+    position_encoding: OnceLock<PositionEncoding>,
+    /// Whether the client pulls diagnostics instead of having them pushed
+    pull_diagnostics: OnceLock<bool>,
+    /// Workspace folders searched for `workspace/diagnostic`
+    workspace_roots: OnceLock<Vec<PathBuf>>,
+    /// Whether the client answers `workspace/configuration` requests
+    configuration_support: OnceLock<bool>,
+    /// Whether the client accepts a dynamic registration for type hierarchy
+    type_hierarchy_registration: OnceLock<bool>,
+    settings: RwLock<Arc<Settings>>,
+    /// Why the `initializationOptions` were rejected, shown once initialized
+    initialization_error: OnceLock<String>,
+    /// Workspace files providing import modules, from `wat-lsp.toml`
+    links: RwLock<Arc<LinkConfig>>,
+    /// Disassembly of each `.wasm` file shown as a document, by document URI
+    disassemblies: DashMap<String, Arc<Disassembly>>,
 }
 
 impl Backend {
     fn new(client: Client) -> Self {
         Self {
             client,
-            document_map: DashMap::new(),
-            symbol_map: DashMap::new(),
-            tree_map: DashMap::new(),
+            documents: Arc::new(DashMap::new()),
             validation_cancellation: DashMap::new(),
+            reparse_cancellation: DashMap::new(),
+            position_encoding: OnceLock::new(),
+            pull_diagnostics: OnceLock::new(),
+            workspace_roots: OnceLock::new(),
+            configuration_support: OnceLock::new(),
+            type_hierarchy_registration: OnceLock::new(),
+            settings: RwLock::new(Arc::new(Settings::default())),
+            initialization_error: OnceLock::new(),
+            links: RwLock::new(Arc::new(LinkConfig::default())),
+            disassemblies: DashMap::new(),
         }
     }
 
-    /// Get document, symbols, and tree for a given URI.
-    /// Returns None if any of them are missing.
-    fn get_document_context(&self, uri: &str) -> Option<DocumentContext<'_>> {
-        let doc = self.document_map.get(uri)?;
-        let syms = self.symbol_map.get(uri)?;
-        let tree = self.tree_map.get(uri)?;
-        Some((doc, syms, tree))
+    /// Current settings; a change applies to requests made after it
+    fn settings(&self) -> Arc<Settings> {
+        match self.settings.read() {
+            Ok(settings) => Arc::clone(&settings),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
     }
 
-    async fn update_document(&self, uri: String, text: String) {
-        // Parse with tree-sitter and cache the tree
-        let mut parser = tree_sitter_bindings::create_parser();
-        if let Some(tree) = parser.parse(&text, None) {
-            // Generate IMMEDIATE syntax diagnostics only
-            let syntax_diagnostics = diagnostics::provide_tree_sitter_diagnostics(&tree, &text);
-
-            // Extract symbols from the document (needed for semantic diagnostics)
-            let semantic_diagnostics = if let Ok(symbol_table) = parser::parse_document(&text) {
-                // Generate diagnostics first, then move symbol_table into the map to avoid clone
-                let diags = diagnostics::provide_semantic_diagnostics(&tree, &text, &symbol_table);
-                self.symbol_map.insert(uri.clone(), symbol_table);
-                diags
-            } else {
-                vec![]
-            };
+    /// Replace the settings and recheck open documents with them
+    async fn apply_settings(&self, settings: Settings) {
+        if *self.settings() == settings {
+            return;
+        }
+        match self.settings.write() {
+            Ok(mut current) => *current = Arc::new(settings),
+            Err(poisoned) => *poisoned.into_inner() = Arc::new(settings),
+        }
+        self.recheck_documents(|_| true).await;
+    }
 
-            // Merge syntax and semantic diagnostics
-            let mut combined = syntax_diagnostics;
-            combined.extend(semantic_diagnostics);
+    /// Current link config
+    fn links(&self) -> Arc<LinkConfig> {
+        match self.links.read() {
+            Ok(links) => Arc::clone(&links),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
 
-            // Publish immediate diagnostics
-            if let Ok(lsp_uri) = uri.parse() {
+    /// Read `wat-lsp.toml` from the workspace roots and recheck open
+    /// documents against the modules it links
+    async fn load_links(&self) {
+        let roots = self.workspace_roots.get().cloned().unwrap_or_default();
+        let links = match tokio::task::spawn_blocking(move || LinkConfig::load(&roots)).await {
+            Ok(Ok(links)) => links,
+            Ok(Err(err)) => {
+                self.client.show_message(MessageType::ERROR, err).await;
+                return;
+            }
+            Err(_) => return,
+        };
+        if *self.links() == links {
+            return;
+        }
+        match self.links.write() {
+            Ok(mut current) => *current = Arc::new(links),
+            Err(poisoned) => *poisoned.into_inner() = Arc::new(links),
+        }
+        self.recheck_documents(|_| true).await;
+    }
+
+    /// Recheck the open documents that import from a module the document at
+    /// `uri` provides, after it changed
+    async fn recheck_importers(&self, uri: &str) {
+        let links = self.links();
+        let Some(path) = Url::parse(uri).ok().and_then(|uri| uri.to_file_path().ok()) else {
+            return;
+        };
+        let modules: Vec<&str> = links.modules_provided_by(&path).collect();
+        if modules.is_empty() {
+            return;
+        }
+        self.recheck_documents(|doc| {
+            doc.symbols
+                .imports
+                .iter()
+                .any(|import| modules.contains(&import.module.as_str()))
+        })
+        .await;
+    }
+
+    /// Recheck open documents after something they are checked with
+    /// changed. Clients that pull diagnostics are asked to pull them all
+    /// again; otherwise the documents matching `filter` are revalidated.
+    async fn recheck_documents(&self, filter: impl Fn(&DocumentSnapshot) -> bool) {
+        if self.pull_diagnostics() {
+            let _ = self.client.workspace_diagnostic_refresh().await;
+            return;
+        }
+        let open: Vec<(String, Arc<DocumentSnapshot>)> = self
+            .documents
+            .iter()
+            .filter(|doc| filter(doc.value()))
+            .map(|doc| (doc.key().clone(), Arc::clone(doc.value())))
+            .collect();
+        for (uri, snapshot) in open {
+            self.schedule_wast_validation(uri, snapshot).await;
+        }
+    }
+
+    /// Ask the client for the `wat` configuration section
+    async fn fetch_settings(&self) {
+        let item = ConfigurationItem {
+            scope_uri: None,
+            section: Some(CONFIGURATION_SECTION.to_string()),
+        };
+        let value = match self.client.configuration(vec![item]).await {
+            Ok(mut values) if !values.is_empty() => values.remove(0),
+            Ok(_) => return,
+            Err(err) => {
                 self.client
-                    .publish_diagnostics(lsp_uri, combined, None)
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Failed to fetch settings: {}", err),
+                    )
                     .await;
+                return;
+            }
+        };
+        self.update_settings(&value).await;
+    }
+
+    /// Apply settings sent by the client, keeping the current ones if invalid
+    async fn update_settings(&self, value: &serde_json::Value) {
+        match Settings::from_json(value) {
+            Ok(settings) => self.apply_settings(settings).await,
+            Err(err) => {
+                self.client.show_message(MessageType::ERROR, err).await;
             }
+        }
+    }
+
+    fn position_encoding(&self) -> PositionEncoding {
+        self.position_encoding.get().copied().unwrap_or_default()
+    }
+
+    fn pull_diagnostics(&self) -> bool {
+        self.pull_diagnostics.get().copied().unwrap_or(false)
+    }
+
+    /// Convert positions in client params to the internal UTF-8 byte columns
+    fn to_internal<T: ConvertPositions>(&self, snapshot: &DocumentSnapshot, value: T) -> T {
+        PositionConverter::new(
+            &snapshot.text,
+            &snapshot.line_index,
+            self.position_encoding(),
+            PositionEncoding::Utf8,
+        )
+        .convert(value)
+    }
+
+    /// Convert positions in a result to the client's encoding
+    fn to_client<T: ConvertPositions>(&self, snapshot: &DocumentSnapshot, value: T) -> T {
+        PositionConverter::new(
+            &snapshot.text,
+            &snapshot.line_index,
+            PositionEncoding::Utf8,
+            self.position_encoding(),
+        )
+        .convert(value)
+    }
 
-            // Cache the parsed tree
-            self.tree_map.insert(uri.clone(), tree);
+    /// Get the current snapshot of a document
+    fn snapshot(&self, uri: &str) -> Option<Arc<DocumentSnapshot>> {
+        self.documents.get(uri).map(|doc| Arc::clone(&doc))
+    }
+
+    /// Whether a snapshot is still the latest version of its document
+    fn is_current(&self, uri: &str, snapshot: &DocumentSnapshot) -> bool {
+        self.documents
+            .get(uri)
+            .is_some_and(|doc| doc.version == snapshot.version)
+    }
+
+    /// Run a feature on the blocking thread pool against a snapshot.
+    ///
+    /// The feature gets a token that is cancelled when the client sends
+    /// `$/cancelRequest`, so long walks can stop early; a cancelled request
+    /// fails with `RequestCancelled`. Its result is dropped with
+    /// `ContentModified` if the document changed while it ran. With a
+    /// `progress` token, begin and end notifications are reported under
+    /// `title`, and the end is sent even if the request is cancelled.
+    async fn run_blocking<R, F>(
+        &self,
+        uri: &str,
+        snapshot: &Arc<DocumentSnapshot>,
+        progress: Option<ProgressToken>,
+        title: &str,
+        feature: F,
+    ) -> Result<R>
+    where
+        F: FnOnce(&DocumentSnapshot, &CancellationToken) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        // Dropped with this future when the client sends `$/cancelRequest`
+        let guard = CancellationGuard::default();
+        let cancel = guard.token();
+        let progress = self.begin_progress(progress, title).await;
+
+        let doc = Arc::clone(snapshot);
+        let result = tokio::task::spawn_blocking(move || {
+            (!cancel.is_cancelled()).then(|| feature(&doc, &cancel))
+        })
+        .await;
+
+        if let Some(progress) = progress {
+            progress.end().await;
+        }
+
+        // A cancelled request never gets here: tower-lsp drops its future,
+        // answers `RequestCancelled` itself, and the dropped guard stops the
+        // feature that is still running on the blocking pool
+        match result {
+            Ok(Some(_)) if !self.is_current(uri, snapshot) => {
+                Err(Error::new(ErrorCode::ContentModified))
+            }
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(Error::new(ErrorCode::RequestCancelled)),
+            Err(_) => Err(Error::internal_error()),
         }
-        self.document_map.insert(uri.clone(), text.clone());
+    }
+
+    /// Report the start of work under `title`, if the client gave a progress
+    /// token, returning the guard that reports its end
+    async fn begin_progress(
+        &self,
+        progress: Option<ProgressToken>,
+        title: &str,
+    ) -> Option<ProgressGuard> {
+        let progress = progress?;
+        let begin = WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: title.to_string(),
+            ..Default::default()
+        });
+        send_progress(&self.client, progress.clone(), begin).await;
+        Some(ProgressGuard {
+            client: self.client.clone(),
+            token: Some(progress),
+        })
+    }
+
+    async fn update_document(&self, uri: String, version: i32, text: String) {
+        // Parse with tree-sitter
+        let tree = parser::parse_tree(&text);
+
+        // Extract symbols from the document (needed for semantic diagnostics)
+        let symbol_result = match &tree {
+            Some(tree) => parser::parse_document_from_tree(tree, &text),
+            None => Err("Failed to parse document".to_string()),
+        };
+
+        self.publish_snapshot(uri, version, text, tree, symbol_result)
+            .await;
+    }
+
+    /// Keep the new text of a large component and reparse it once edits
+    /// pause for the debounce period. Until then the document has no tree,
+    /// and the symbols of its last parse are kept.
+    async fn defer_reparse(
+        &self,
+        uri: String,
+        version: i32,
+        text: String,
+        previous: &DocumentSnapshot,
+    ) {
+        // Cancel the reparse waiting for the previous edit
+        if let Some(entry) = self.reparse_cancellation.get(&uri) {
+            let _ = entry.send(true);
+        }
+        let (tx, mut rx) = watch::channel(false);
+        self.reparse_cancellation.insert(uri.clone(), tx);
+
+        let snapshot = DocumentSnapshot::new(version, text, previous.symbols.clone(), None);
+        let text = snapshot.text.clone();
+        self.documents.insert(uri.clone(), Arc::new(snapshot));
+
+        tokio::select! {
+            _ = sleep(Duration::from_millis(self.settings().debounce_ms)) => {}
+            _ = rx.changed() => return,
+        }
+        self.update_document(uri, version, text).await;
+    }
+
+    /// Make a newly parsed version of a document current and publish its
+    /// immediate diagnostics. When symbol extraction failed, the previous
+    /// version's symbols are kept. A version older than the current one is
+    /// dropped.
+    async fn publish_snapshot(
+        &self,
+        uri: String,
+        version: i32,
+        text: String,
+        tree: Option<Tree>,
+        symbol_result: std::result::Result<symbols::SymbolTable, String>,
+    ) {
+        let previous = self.snapshot(&uri);
+        if previous.as_ref().is_some_and(|doc| doc.version > version) {
+            return;
+        }
+
+        // Clients that pull diagnostics ask for them after each change
+        let push = !self.pull_diagnostics();
+
+        let immediate = tree.as_ref().filter(|_| push).map(|tree| {
+            // Generate IMMEDIATE syntax diagnostics
+            let syntax_diagnostics = diagnostics::provide_tree_sitter_diagnostics(tree, &text);
+
+            // Generate semantic diagnostics
+            let settings = self.settings();
+            let semantic = settings.diagnostics.level >= DiagnosticLevel::Semantic;
+            let mut semantic_diagnostics = match &symbol_result {
+                Ok(symbol_table) if semantic => {
+                    diagnostics::provide_semantic_diagnostics(tree, &text, symbol_table)
+                }
+                _ => vec![],
+            };
+            if semantic {
+                semantic_diagnostics.extend(diagnostics::provide_proposal_diagnostics(
+                    tree,
+                    &text,
+                    &settings.target,
+                ));
+            }
+
+            // Merge syntax and semantic diagnostics
+            let mut combined = syntax_diagnostics;
+            combined.extend(semantic_diagnostics);
+            combined
+        });
+
+        let symbols = symbol_result.unwrap_or_else(|_| {
+            previous
+                .map(|previous| previous.symbols.clone())
+                .unwrap_or_default()
+        });
+        let snapshot = Arc::new(DocumentSnapshot::new(version, text, symbols, tree));
+        self.documents.insert(uri.clone(), Arc::clone(&snapshot));
+
+        // Publish immediate diagnostics
+        if let (Some(combined), Ok(lsp_uri)) = (immediate, uri.parse()) {
+            let combined = PositionConverter::new(
+                &snapshot.text,
+                &snapshot.line_index,
+                PositionEncoding::Utf8,
+                self.position_encoding(),
+            )
+            .convert(combined);
+            self.client
+                .publish_diagnostics(lsp_uri, combined, Some(version))
+                .await;
+        }
+
+        // Modules importing from this one are checked against its new exports
+        self.recheck_importers(&uri).await;
 
         // Schedule debounced wast validation
-        self.schedule_wast_validation(uri, text).await;
+        if push {
+            self.schedule_wast_validation(uri, snapshot).await;
+        }
+    }
+
+    /// Run a script's assertions and report them with its diagnostics
+    async fn run_assertions(
+        &self,
+        uri: String,
+        snapshot: Arc<DocumentSnapshot>,
+        progress: Option<ProgressToken>,
+    ) -> Result<Option<serde_json::Value>> {
+        let results = self
+            .run_blocking(&uri, &snapshot, progress, "Running assertions", |doc, _| {
+                interpreter::run_script(&doc.text)
+            })
+            .await?
+            .map_err(Error::invalid_params)?;
+
+        let count = |matches: fn(&Outcome) -> bool| {
+            results
+                .iter()
+                .filter(|result| result.is_assertion() && matches(&result.outcome))
+                .count()
+        };
+        let summary = serde_json::json!({
+            "passed": count(|outcome| *outcome == Outcome::Passed),
+            "failed": count(|outcome| matches!(outcome, Outcome::Failed { .. })),
+            "skipped": count(|outcome| matches!(outcome, Outcome::Skipped(_))),
+        });
+
+        // Results belong to this version; the next edit drops them
+        let _ = snapshot.assertions.set(results);
+        if self.pull_diagnostics() {
+            let _ = self.client.workspace_diagnostic_refresh().await;
+        } else {
+            self.schedule_wast_validation(uri, snapshot).await;
+        }
+        let _ = self.client.code_lens_refresh().await;
+
+        Ok(Some(summary))
+    }
+
+    /// Run an exported function and show its results on its lens
+    async fn run_export(
+        &self,
+        uri: String,
+        snapshot: Arc<DocumentSnapshot>,
+        progress: Option<ProgressToken>,
+        name: String,
+        args: Vec<String>,
+    ) -> Result<Option<serde_json::Value>> {
+        let stubs = self.settings().run.imports;
+        let export = name.clone();
+        let output = self
+            .run_blocking(
+                &uri,
+                &snapshot,
+                progress,
+                "Running export",
+                move |doc, _| interpreter::run_export(doc.text.as_bytes(), &export, &args, stubs),
+            )
+            .await?
+            .map_err(Error::invalid_params)?;
+
+        for line in &output.log {
+            self.client
+                .log_message(MessageType::INFO, format!("{}: {}", name, line))
+                .await;
+        }
+        let summary = output.summary();
+        if let Ok(mut runs) = snapshot.runs.lock() {
+            runs.insert(name, summary.clone());
+        }
+        let _ = self.client.code_lens_refresh().await;
+
+        let (results, trap) = match &output.results {
+            Ok(values) => (values.iter().map(|v| v.to_string()).collect(), None),
+            Err(trap) => (Vec::new(), Some(trap.to_string())),
+        };
+        Ok(Some(serde_json::json!({
+            "summary": summary,
+            "results": results,
+            "trap": trap,
+            "log": output.log,
+        })))
+    }
+
+    /// Encode a document to a binary, writing it to `path` and its source
+    /// map next to it if given, and report its size by section and by function
+    async fn compile(
+        &self,
+        uri: String,
+        snapshot: Arc<DocumentSnapshot>,
+        progress: Option<ProgressToken>,
+        path: Option<PathBuf>,
+    ) -> Result<Option<serde_json::Value>> {
+        // The map names the source relative to itself, next to the binary
+        let source = uri.rsplit('/').next().unwrap_or_default().to_string();
+        let (bytes, report, source_map, unmapped) = self
+            .run_blocking(&uri, &snapshot, progress, "Compiling", move |doc, _| {
+                let (bytes, map) = compile::compile_with_source_map(&doc.text)?;
+                let report = compile::size_report(&bytes)?;
+                let unmapped = map.unmapped().to_vec();
+                Ok::<_, String>((bytes, report, map.to_json(&doc.text, &source), unmapped))
+            })
+            .await?
+            .map_err(Error::invalid_params)?;
+
+        let write_error = |path: &Path, e: std::io::Error| Error {
+            code: ErrorCode::InternalError,
+            message: format!("Error writing {}: {}", path.display(), e).into(),
+            data: None,
+        };
+        let mut map_path = None;
+        if let Some(path) = &path {
+            fs::write(path, &bytes).map_err(|e| write_error(path, e))?;
+            let map = compile::source_map_path(path);
+            fs::write(&map, source_map.to_string()).map_err(|e| write_error(&map, e))?;
+            map_path = Some(map);
+        }
+        let _ = snapshot.sizes.set(Some(report.clone()));
+
+        Ok(Some(serde_json::json!({
+            "output": path,
+            "sourceMap": map_path,
+            // Functions the source map leads only to the start of
+            "unmappedFunctions": unmapped,
+            "size": report.size,
+            "sections": report.sections,
+            "functions": report.functions,
+        })))
+    }
+
+    /// TypeScript declarations for a document's exports and imports,
+    /// written to `path` if given
+    fn generate_types(
+        &self,
+        snapshot: Arc<DocumentSnapshot>,
+        path: Option<PathBuf>,
+    ) -> Result<Option<serde_json::Value>> {
+        let declarations = typescript::generate_declarations(&snapshot.symbols);
+        if let Some(path) = &path {
+            fs::write(path, &declarations).map_err(|e| Error {
+                code: ErrorCode::InternalError,
+                message: format!("Error writing {}: {}", path.display(), e).into(),
+                data: None,
+            })?;
+        }
+        Ok(Some(serde_json::json!({
+            "output": path,
+            "declarations": declarations,
+        })))
+    }
+
+    /// Disassemble a `.wasm` file for its document, reading it from disk
+    async fn load_disassembly(
+        &self,
+        binary: &Url,
+        document: &Url,
+    ) -> std::result::Result<Arc<Disassembly>, String> {
+        let path = binary.clone();
+        let loaded = tokio::task::spawn_blocking(move || disassembly::load(&path))
+            .await
+            .map_err(|e| e.to_string())??;
+        let loaded = Arc::new(loaded);
+        self.disassemblies
+            .insert(document.to_string(), Arc::clone(&loaded));
+        Ok(loaded)
+    }
+
+    /// `wat/disassemble`: the WAT text of a `.wasm` file and the URI of the
+    /// read-only document to show it in
+    async fn disassemble(&self, params: DisassembleParams) -> Result<DisassembleResult> {
+        let binary = if disassembly::is_binary_uri(&params.uri) {
+            Some(params.uri)
+        } else {
+            disassembly::binary_uri(&params.uri)
+        };
+        let Some((binary, document)) = binary.and_then(|binary| {
+            let document = disassembly::disassembly_uri(&binary)?;
+            Some((binary, document))
+        }) else {
+            return Err(Error::invalid_params("expected the URI of a .wasm file"));
+        };
+        let loaded = self
+            .load_disassembly(&binary, &document)
+            .await
+            .map_err(|err| Error {
+                code: ErrorCode::InternalError,
+                message: err.into(),
+                data: None,
+            })?;
+        Ok(DisassembleResult {
+            uri: document,
+            text: loaded.text.clone(),
+        })
+    }
+
+    /// `wat/gotoBinaryOffset`: the instruction of a document that a code
+    /// offset in its binary came from
+    async fn goto_binary_offset(&self, params: GotoBinaryOffsetParams) -> Result<Option<Location>> {
+        let Some((function, offset)) = params.location() else {
+            return Err(Error::invalid_params(
+                "expected an offset like 0x3a4 or wasm-function[12]:0x3a4",
+            ));
+        };
+        let uri = params.text_document.uri;
+        let Some(snapshot) = self.snapshot(uri.as_str()) else {
+            return Err(Error::invalid_params(format!("Document not open: {}", uri)));
+        };
+        let range = self
+            .run_blocking(
+                uri.as_str(),
+                &snapshot,
+                None,
+                "Resolving binary offset",
+                move |doc, _| binary_offset::resolve_binary_offset(&doc.text, function, offset),
+            )
+            .await?
+            .map_err(Error::invalid_params)?;
+        let location = range.map(|range| Location::new(uri, range.into()));
+        Ok(self.to_client(&snapshot, location))
     }
 
-    async fn schedule_wast_validation(&self, uri: String, text: String) {
+    async fn schedule_wast_validation(&self, uri: String, snapshot: Arc<DocumentSnapshot>) {
         // Cancel any existing validation task for this document
         if let Some(entry) = self.validation_cancellation.get(&uri) {
             let _ = entry.send(true); // Signal cancellation
@@ -99,14 +657,17 @@ impl Backend {
 
         // Clone what we need for the async task
         let client = self.client.clone();
-        let tree_map = self.tree_map.clone();
-        let symbol_map = self.symbol_map.clone();
+        let documents = Arc::clone(&self.documents);
+        let version = snapshot.version;
+        let encoding = self.position_encoding();
+        let settings = self.settings();
+        let links = self.links();
 
         // Spawn background task
         tokio::spawn(async move {
             // Wait for debounce period or cancellation
             tokio::select! {
-                _ = sleep(Duration::from_millis(DEBOUNCE_DURATION_MS)) => {
+                _ = sleep(Duration::from_millis(settings.debounce_ms)) => {
                     // Debounce period elapsed, run validation
                 }
                 _ = rx.changed() => {
@@ -115,32 +676,36 @@ impl Backend {
                 }
             }
 
-            // Get tree-sitter diagnostics (from cached tree)
-            let tree_diags = if let Some(tree) = tree_map.get(&uri) {
-                diagnostics::provide_tree_sitter_diagnostics(&tree, &text)
-            } else {
-                vec![]
-            };
-
-            // Get semantic diagnostics (undefined label references)
-            // Use cached symbols instead of re-parsing
-            let semantic_diags = match (tree_map.get(&uri), symbol_map.get(&uri)) {
-                (Some(tree), Some(symbols)) => {
-                    diagnostics::provide_semantic_diagnostics(&tree, &text, &symbols)
-                }
-                _ => vec![],
-            };
-
-            // Run wast validation
-            let wast_diags = diagnostics::validate_wat(&text);
+            // Validate on the blocking pool so large modules don't stall requests
+            let open = Arc::clone(&documents);
+            let combined = tokio::task::spawn_blocking(move || {
+                // Tree-sitter, semantic, wast and link diagnostics, merged
+                let linked = linked_documents(&links, &snapshot.symbols, &open);
+                let combined = document_diagnostics(&snapshot, &settings, &links, &linked);
+                PositionConverter::new(
+                    &snapshot.text,
+                    &snapshot.line_index,
+                    PositionEncoding::Utf8,
+                    encoding,
+                )
+                .convert(combined)
+            })
+            .await;
 
-            // Merge all diagnostics
-            let combined =
-                diagnostics::merge_all_diagnostics(tree_diags, semantic_diags, wast_diags);
+            // Drop results for a version that was edited or closed during validation
+            let Ok(combined) = combined else { return };
+            let is_current = documents
+                .get(&uri)
+                .is_some_and(|doc| doc.version == version);
+            if !is_current {
+                return;
+            }
 
             // Publish combined diagnostics
             if let Ok(lsp_uri) = uri.parse() {
-                client.publish_diagnostics(lsp_uri, combined, None).await;
+                client
+                    .publish_diagnostics(lsp_uri, combined, Some(version))
+                    .await;
             }
         });
     }
@@ -148,13 +713,74 @@ impl Backend {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        let supported = params
+            .capabilities
+            .general
+            .as_ref()
+            .and_then(|general| general.position_encodings.as_deref());
+        let encoding = *self
+            .position_encoding
+            .get_or_init(|| adapters::negotiate_position_encoding(supported));
+
+        let pull = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .is_some_and(|text_document| text_document.diagnostic.is_some());
+        self.pull_diagnostics.get_or_init(|| pull);
+
+        let type_hierarchy = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.type_hierarchy.as_ref())
+            .and_then(|type_hierarchy| type_hierarchy.dynamic_registration)
+            .unwrap_or(false);
+        self.type_hierarchy_registration
+            .get_or_init(|| type_hierarchy);
+
+        #[allow(deprecated)] // root_uri is the fallback for clients without workspace folders
+        let roots: Vec<PathBuf> = match &params.workspace_folders {
+            Some(folders) => folders
+                .iter()
+                .filter_map(|folder| folder.uri.to_file_path().ok())
+                .collect(),
+            None => params
+                .root_uri
+                .iter()
+                .filter_map(|uri| uri.to_file_path().ok())
+                .collect(),
+        };
+        self.workspace_roots.get_or_init(|| roots);
+
+        let configuration = params
+            .capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.configuration)
+            .unwrap_or(false);
+        self.configuration_support.get_or_init(|| configuration);
+
+        // Settings the client sent up front; invalid ones are reported once initialized
+        if let Some(options) = &params.initialization_options {
+            match Settings::from_json(options) {
+                Ok(settings) => {
+                    *self.settings.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(settings);
+                }
+                Err(err) => {
+                    self.initialization_error.get_or_init(|| err);
+                }
+            }
+        }
+
         Ok(InitializeResult {
             server_info: Some(ServerInfo {
                 name: "wat-lsp".to_string(),
                 version: Some("0.1.0".to_string()),
             }),
             capabilities: ServerCapabilities {
+                position_encoding: Some(adapters::position_encoding_kind(encoding)),
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
                     TextDocumentSyncKind::INCREMENTAL,
                 )),
@@ -166,6 +792,7 @@ impl LanguageServer for Backend {
                         "@".to_string(),
                         "2".to_string(),
                         "4".to_string(),
+                        "\"".to_string(),
                     ]),
                     ..Default::default()
                 }),
@@ -175,54 +802,204 @@ impl LanguageServer for Backend {
                     work_done_progress_options: WorkDoneProgressOptions::default(),
                 }),
                 definition_provider: Some(OneOf::Left(true)),
-                references_provider: Some(OneOf::Left(true)),
+                type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+                implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+                references_provider: Some(OneOf::Right(ReferencesOptions {
+                    work_done_progress_options: WorkDoneProgressOptions {
+                        work_done_progress: Some(true),
+                    },
+                })),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                call_hierarchy_provider: Some(CallHierarchyServerCapability::Options(
+                    CallHierarchyOptions {
+                        work_done_progress_options: WorkDoneProgressOptions {
+                            work_done_progress: Some(true),
+                        },
+                    },
+                )),
+                linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(
+                    true,
+                )),
+                code_lens_provider: Some(CodeLensOptions {
+                    resolve_provider: Some(true),
+                }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![
+                        commands::RUN_ASSERTIONS_COMMAND.to_string(),
+                        commands::RUN_EXPORT_COMMAND.to_string(),
+                        commands::COMPILE_COMMAND.to_string(),
+                        commands::GENERATE_TYPES_COMMAND.to_string(),
+                    ],
+                    work_done_progress_options: WorkDoneProgressOptions {
+                        work_done_progress: Some(true),
+                    },
+                }),
                 rename_provider: Some(OneOf::Right(RenameOptions {
                     prepare_provider: Some(true),
-                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                    work_done_progress_options: WorkDoneProgressOptions {
+                        work_done_progress: Some(true),
+                    },
                 })),
+                diagnostic_provider: pull.then(|| {
+                    DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                        identifier: Some("wat-lsp".to_string()),
+                        inter_file_dependencies: true,
+                        workspace_diagnostics: true,
+                        work_done_progress_options: WorkDoneProgressOptions {
+                            work_done_progress: Some(true),
+                        },
+                    })
+                }),
                 ..Default::default()
             },
         })
     }
 
     async fn initialized(&self, _: InitializedParams) {
+        if let Some(err) = self.initialization_error.get() {
+            self.client
+                .show_message(MessageType::ERROR, err.clone())
+                .await;
+        }
+
+        // lsp-types has no static capability for type hierarchy, so register it dynamically
+        if self
+            .type_hierarchy_registration
+            .get()
+            .copied()
+            .unwrap_or(false)
+        {
+            let registration = Registration {
+                id: "wat-type-hierarchy".to_string(),
+                method: "textDocument/prepareTypeHierarchy".to_string(),
+                register_options: Some(serde_json::json!({
+                    "documentSelector": [{ "language": "wat" }]
+                })),
+            };
+            if let Err(err) = self.client.register_capability(vec![registration]).await {
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Type hierarchy registration failed: {}", err),
+                    )
+                    .await;
+            }
+        }
+
+        if self.configuration_support.get().copied().unwrap_or(false) {
+            self.fetch_settings().await;
+        }
+
+        // Reload links when the config or a host description changes
+        let registration = Registration {
+            id: "wat-link-config".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(DidChangeWatchedFilesRegistrationOptions {
+                watchers: vec![FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/*.{toml,json}".to_string()),
+                    kind: None,
+                }],
+            })
+            .ok(),
+        };
+        let _ = self.client.register_capability(vec![registration]).await;
+        self.load_links().await;
+
         self.client
             .log_message(MessageType::INFO, "WAT LSP server initialized")
             .await;
     }
 
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let links = self.links();
+        let config_changed = params.changes.iter().any(|change| {
+            change.uri.path().ends_with(CONFIG_FILE)
+                || change
+                    .uri
+                    .to_file_path()
+                    .is_ok_and(|path| links.is_host_file(&path))
+        });
+        if config_changed {
+            self.load_links().await;
+        }
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        // Clients using the pull model send no settings and expect a request
+        let pushed = match &params.settings {
+            serde_json::Value::Object(map) => !map.is_empty(),
+            value => !value.is_null(),
+        };
+        if pushed {
+            self.update_settings(&params.settings).await;
+        } else if self.configuration_support.get().copied().unwrap_or(false) {
+            self.fetch_settings().await;
+        }
+    }
+
     async fn shutdown(&self) -> Result<()> {
         Ok(())
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        // The bytes of a binary aren't WAT; its disassembly is opened instead
+        if disassembly::is_binary_uri(&params.text_document.uri) {
+            return;
+        }
         let uri = params.text_document.uri.to_string();
-        let text = params.text_document.text;
-        self.update_document(uri, text).await;
+        let version = params.text_document.version;
+        let mut text = params.text_document.text;
+        if let Some(binary) = disassembly::binary_uri(&params.text_document.uri) {
+            // Disassembled documents are read-only, so the server's text wins
+            let cached = self
+                .disassemblies
+                .get(&uri)
+                .map(|loaded| Arc::clone(&loaded));
+            let loaded = match cached {
+                Some(loaded) => Ok(loaded),
+                None => {
+                    self.load_disassembly(&binary, &params.text_document.uri)
+                        .await
+                }
+            };
+            match loaded {
+                Ok(loaded) => text = loaded.text.clone(),
+                Err(err) => self.client.show_message(MessageType::ERROR, err).await,
+            }
+        }
+        self.update_document(uri, version, text).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri.to_string();
+        let version = params.text_document.version;
 
-        // Get the current document text
-        let mut text = match self.document_map.get(&uri) {
-            Some(doc) => doc.clone(),
-            None => {
-                // Document not found, fall back to full sync
-                if let Some(change) = params.content_changes.into_iter().next() {
-                    self.update_document(uri, change.text).await;
-                }
-                return;
+        // Get the current document snapshot
+        let Some(previous) = self.snapshot(&uri) else {
+            // Document not found, fall back to full sync
+            if let Some(change) = params.content_changes.into_iter().next() {
+                self.update_document(uri, version, change.text).await;
             }
+            return;
         };
+        let mut text = previous.text.clone();
 
-        // Get the current tree for incremental reparsing
-        let mut old_tree = self.tree_map.get(&uri).map(|t| t.clone());
+        // Copy the current tree for incremental reparsing
+        let mut old_tree = previous.tree.clone();
+        let mut edits = Vec::new();
 
         // Apply all incremental changes
         for change in params.content_changes {
             if let Some(range) = change.range {
-                // Incremental change
+                // Incremental change, positioned against the text before it
+                let index = LineIndex::new(&text);
+                let range = PositionConverter::new(
+                    &text,
+                    &index,
+                    self.position_encoding(),
+                    PositionEncoding::Utf8,
+                )
+                .convert(range);
                 let start_byte = utils::position_to_byte(&text, range.start.into());
                 let old_end_byte = utils::position_to_byte(&text, range.end.into());
 
@@ -257,50 +1034,42 @@ impl LanguageServer for Backend {
                     };
 
                     tree.edit(&edit);
+                    edits.push(edit);
                 }
             } else {
                 // Full document sync fallback
                 text = change.text;
                 old_tree = None; // Invalidate tree for full sync
+                edits.clear();
             }
         }
 
-        // Reparse with the edited tree for better performance
-        let mut parser = tree_sitter_bindings::create_parser();
-        if let Some(tree) = parser.parse(&text, old_tree.as_ref()) {
-            // Generate IMMEDIATE syntax diagnostics
-            let syntax_diagnostics = diagnostics::provide_tree_sitter_diagnostics(&tree, &text);
-
-            // Extract symbols and generate semantic diagnostics
-            let semantic_diagnostics = if let Ok(symbol_table) = parser::parse_document(&text) {
-                // Generate diagnostics first, then move symbol_table into the map to avoid clone
-                let diags = diagnostics::provide_semantic_diagnostics(&tree, &text, &symbol_table);
-                self.symbol_map.insert(uri.clone(), symbol_table);
-                diags
+        // A component's tree covers only its core modules, so it is parsed
+        // afresh when the document is or was a component
+        if previous.symbols.component.is_some() || component::is_component(&text) {
+            if text.len() > component::REPARSE_LIMIT {
+                self.defer_reparse(uri, version, text, &previous).await;
             } else {
-                vec![]
-            };
-
-            // Merge syntax and semantic diagnostics
-            let mut combined = syntax_diagnostics;
-            combined.extend(semantic_diagnostics);
-
-            // Publish immediate diagnostics
-            if let Ok(lsp_uri) = uri.parse() {
-                self.client
-                    .publish_diagnostics(lsp_uri, combined, None)
-                    .await;
+                self.update_document(uri, version, text).await;
             }
-
-            // Cache the new tree
-            self.tree_map.insert(uri.clone(), tree);
+            return;
         }
 
-        // Update document text
-        self.document_map.insert(uri.clone(), text.clone());
+        // Reparse with the edited tree for better performance
+        let mut parser = tree_sitter_bindings::create_parser();
+        let tree = parser.parse(&text, old_tree.as_ref());
 
-        // Schedule debounced wast validation
-        self.schedule_wast_validation(uri, text).await;
+        // Update symbols from the changed fields only, reusing the rest of the old table
+        let symbol_result = match (&tree, &old_tree) {
+            (Some(tree), Some(old_tree)) => {
+                parser::update_symbols(&previous.symbols, old_tree, tree, &text, &edits)
+            }
+            (Some(tree), None) => parser::parse_document_from_tree(tree, &text),
+            (None, _) => Err("Failed to parse document".to_string()),
+        };
+
+        self.publish_snapshot(uri, version, text, tree, symbol_result)
+            .await;
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
@@ -312,12 +1081,164 @@ impl LanguageServer for Backend {
             .await;
 
         // Remove cached data
-        self.document_map.remove(&uri);
-        self.symbol_map.remove(&uri);
-        self.tree_map.remove(&uri);
+        self.documents.remove(&uri);
+        self.disassemblies.remove(&uri);
 
         // Cancel any pending validation
-        self.validation_cancellation.remove(&uri);
+        if let Some((_, cancel)) = self.validation_cancellation.remove(&uri) {
+            let _ = cancel.send(true);
+        }
+        if let Some((_, cancel)) = self.reparse_cancellation.remove(&uri) {
+            let _ = cancel.send(true);
+        }
+    }
+
+    async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReportResult> {
+        let uri = params.text_document.uri.to_string();
+        let progress = params.work_done_progress_params.work_done_token;
+        let previous_result_id = params.previous_result_id;
+        let settings = self.settings();
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            // Documents that aren't open are read from disk
+            let text = params
+                .text_document
+                .uri
+                .to_file_path()
+                .ok()
+                .and_then(|path| fs::read_to_string(path).ok())
+                .unwrap_or_default();
+            let result_id = diagnostics::diagnostics_result_id(&text, &settings);
+            if previous_result_id.as_ref() == Some(&result_id) {
+                return Ok(unchanged_report(result_id));
+            }
+
+            let encoding = self.position_encoding();
+            let items = tokio::task::spawn_blocking(move || {
+                let items = diagnostics::diagnose_source(&text, &settings);
+                let index = LineIndex::new(&text);
+                PositionConverter::new(&text, &index, PositionEncoding::Utf8, encoding)
+                    .convert(items)
+            })
+            .await
+            .map_err(|_| Error::internal_error())?;
+            return Ok(full_report(result_id, items));
+        };
+
+        // Modules providing imports may be read from disk, so load them off the runtime
+        let links = self.links();
+        let (config, documents, doc) = (
+            Arc::clone(&links),
+            Arc::clone(&self.documents),
+            Arc::clone(&snapshot),
+        );
+        let linked = tokio::task::spawn_blocking(move || {
+            linked_documents(&config, &doc.symbols, &documents)
+        })
+        .await
+        .map_err(|_| Error::internal_error())?;
+        let result_id = document_result_id(&snapshot, &settings, &links, &linked);
+        if previous_result_id.as_ref() == Some(&result_id) {
+            return Ok(unchanged_report(result_id));
+        }
+
+        let items = self
+            .run_blocking(
+                &uri,
+                &snapshot,
+                progress,
+                "Checking document",
+                move |doc, _| document_diagnostics(doc, &settings, &links, &linked),
+            )
+            .await?;
+        Ok(full_report(result_id, self.to_client(&snapshot, items)))
+    }
+
+    async fn workspace_diagnostic(
+        &self,
+        params: WorkspaceDiagnosticParams,
+    ) -> Result<WorkspaceDiagnosticReportResult> {
+        let roots = self.workspace_roots.get().cloned().unwrap_or_default();
+        let previous: HashMap<Url, String> = params
+            .previous_result_ids
+            .into_iter()
+            .map(|previous| (previous.uri, previous.value))
+            .collect();
+        let open: HashMap<String, Arc<DocumentSnapshot>> = self
+            .documents
+            .iter()
+            .map(|doc| (doc.key().clone(), Arc::clone(doc.value())))
+            .collect();
+        let encoding = self.position_encoding();
+        let settings = self.settings();
+        let links = self.links();
+        let documents = Arc::clone(&self.documents);
+        let progress = params.work_done_progress_params.work_done_token;
+        let progress = self.begin_progress(progress, "Checking workspace").await;
+
+        // Dropped with this future when the client sends `$/cancelRequest`
+        let guard = CancellationGuard::default();
+        let token = guard.token();
+        let items = tokio::task::spawn_blocking(move || {
+            let mut items = Vec::new();
+            for path in find_wat_files(&roots, &token) {
+                if token.is_cancelled() {
+                    return None;
+                }
+                let Ok(uri) = Url::from_file_path(&path) else {
+                    continue;
+                };
+                let previous_result_id = previous.get(&uri);
+
+                // Open documents are checked at their current version
+                let report = match open.get(uri.as_str()) {
+                    Some(doc) => {
+                        let linked = linked_documents(&links, &doc.symbols, &documents);
+                        workspace_document_report(
+                            uri.clone(),
+                            Some(doc.version as i64),
+                            &doc.text,
+                            document_result_id(doc, &settings, &links, &linked),
+                            previous_result_id,
+                            encoding,
+                            || document_diagnostics(doc, &settings, &links, &linked),
+                        )
+                    }
+                    None => {
+                        let Ok(text) = fs::read_to_string(&path) else {
+                            continue;
+                        };
+                        workspace_document_report(
+                            uri.clone(),
+                            None,
+                            &text,
+                            diagnostics::diagnostics_result_id(&text, &settings),
+                            previous_result_id,
+                            encoding,
+                            || diagnostics::diagnose_source(&text, &settings),
+                        )
+                    }
+                };
+                items.push(report);
+            }
+            Some(items)
+        })
+        .await;
+
+        if let Some(progress) = progress {
+            progress.end().await;
+        }
+
+        match items {
+            Ok(Some(items)) => Ok(WorkspaceDiagnosticReportResult::Report(
+                WorkspaceDiagnosticReport { items },
+            )),
+            Ok(None) => Err(Error::new(ErrorCode::RequestCancelled)),
+            Err(_) => Err(Error::internal_error()),
+        }
     }
 
     async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -328,8 +1249,20 @@ impl LanguageServer for Backend {
             .to_string();
         let position = params.text_document_position_params.position;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
-            return Ok(hover::provide_hover(&doc, &syms, &tree, position));
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            let mut hover = hover::provide_hover(doc, syms, tree, position);
+            if let Some(offset) = self
+                .disassemblies
+                .get(&uri)
+                .and_then(|d| disassembly::offset_hover(&d, position.line))
+            {
+                hover = Some(with_offset(hover, offset));
+            }
+            return Ok(self.to_client(&snapshot, hover));
         }
 
         Ok(None)
@@ -339,9 +1272,39 @@ impl LanguageServer for Backend {
         let uri = params.text_document_position.text_document.uri.to_string();
         let position = params.text_document_position.position;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+
+            // Names inside `(import "` come from the host and linked modules
+            let links = self.links();
+            if !links.is_empty() {
+                let documents = Arc::clone(&self.documents);
+                let importable =
+                    tokio::task::spawn_blocking(move || importable_items(&links, &documents))
+                        .await
+                        .map_err(|_| Error::internal_error())?;
+                if let Some(items) =
+                    completion::provide_import_completions(doc, position, &importable)
+                {
+                    return Ok(Some(CompletionResponse::Array(
+                        self.to_client(&snapshot, items),
+                    )));
+                }
+            }
+
+            let settings = self.settings();
+            let items = completion::provide_completion_with_settings(
+                doc,
+                syms,
+                tree,
+                position,
+                &settings.completion,
+            );
             return Ok(Some(CompletionResponse::Array(
-                completion::provide_completion(&doc, &syms, &tree, position),
+                self.to_client(&snapshot, items),
             )));
         }
 
@@ -356,10 +1319,12 @@ impl LanguageServer for Backend {
             .to_string();
         let position = params.text_document_position_params.position;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
-            return Ok(signature::provide_signature_help(
-                &doc, &syms, &tree, position,
-            ));
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            return Ok(signature::provide_signature_help(doc, syms, tree, position));
         }
 
         Ok(None)
@@ -376,10 +1341,66 @@ impl LanguageServer for Backend {
             .to_string();
         let position = params.text_document_position_params.position;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            let location = definition::provide_definition(doc, syms, tree, position, &uri);
+
+            // An import from a linked module leads to the export it binds to,
+            // unless the cursor is on a name defined elsewhere in the import
+            if let Some(import) = linking::import_at(syms, position.into()) {
+                let in_import = location.as_ref().is_none_or(|location| {
+                    import
+                        .range
+                        .is_some_and(|range| range.contains(location.range.start.into()))
+                });
+                if in_import {
+                    let (links, documents) = (self.links(), Arc::clone(&self.documents));
+                    let encoding = self.position_encoding();
+                    let import = import.clone();
+                    let linked = tokio::task::spawn_blocking(move || {
+                        import_definition(&links, &import, &documents, encoding)
+                    })
+                    .await
+                    .ok()
+                    .flatten();
+                    if let Some(linked) = linked {
+                        return Ok(Some(GotoDefinitionResponse::Scalar(linked)));
+                    }
+                }
+            }
+
+            if let Some(location) = location {
+                let location = self.to_client(&snapshot, location);
+                return Ok(Some(GotoDefinitionResponse::Scalar(location)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    async fn goto_type_definition(
+        &self,
+        params: request::GotoTypeDefinitionParams,
+    ) -> Result<Option<request::GotoTypeDefinitionResponse>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .to_string();
+        let position = params.text_document_position_params.position;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
             if let Some(location) =
-                definition::provide_definition(&doc, &syms, &tree, position, &uri)
+                type_definition::provide_type_definition(doc, syms, tree, position, &uri)
             {
+                let location = self.to_client(&snapshot, location);
                 return Ok(Some(GotoDefinitionResponse::Scalar(location)));
             }
         }
@@ -387,6 +1408,343 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
+    async fn goto_implementation(
+        &self,
+        params: request::GotoImplementationParams,
+    ) -> Result<Option<request::GotoImplementationResponse>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .to_string();
+        let position = params.text_document_position_params.position;
+        let progress = params.work_done_progress_params.work_done_token;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        let position = self.to_internal(&snapshot, position);
+        let doc_uri = uri.clone();
+        let locations = self
+            .run_blocking(
+                &uri,
+                &snapshot,
+                progress,
+                "Finding implementations",
+                move |doc, _| {
+                    let (doc, syms, tree) = doc.context()?;
+                    Some(type_definition::provide_implementation(
+                        doc, syms, tree, position, &doc_uri,
+                    ))
+                },
+            )
+            .await?;
+
+        match locations {
+            Some(locations) if !locations.is_empty() => {
+                let locations = self.to_client(&snapshot, locations);
+                Ok(Some(GotoDefinitionResponse::Array(locations)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .to_string();
+        let position = params.text_document_position_params.position;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            let items = call_hierarchy::prepare_call_hierarchy(doc, syms, tree, position, &uri);
+            return Ok(self.to_client(&snapshot, items));
+        }
+
+        Ok(None)
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let uri = params.item.uri.to_string();
+        let progress = params.work_done_progress_params.work_done_token;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        let item = self.to_internal(&snapshot, params.item);
+        let calls = self
+            .run_blocking(
+                &uri,
+                &snapshot,
+                progress,
+                "Finding incoming calls",
+                move |doc, cancel| {
+                    let graph = doc.call_graph(cancel)?;
+                    let (_, syms, tree) = doc.context()?;
+                    Some(call_hierarchy::incoming_calls(syms, tree, graph, &item))
+                },
+            )
+            .await?;
+
+        Ok(calls.map(|calls| self.to_client(&snapshot, calls)))
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let uri = params.item.uri.to_string();
+        let progress = params.work_done_progress_params.work_done_token;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        let item = self.to_internal(&snapshot, params.item);
+        let calls = self
+            .run_blocking(
+                &uri,
+                &snapshot,
+                progress,
+                "Finding outgoing calls",
+                move |doc, cancel| {
+                    let graph = doc.call_graph(cancel)?;
+                    let (_, syms, tree) = doc.context()?;
+                    Some(call_hierarchy::outgoing_calls(syms, tree, graph, &item))
+                },
+            )
+            .await?;
+
+        Ok(calls.map(|calls| self.to_client(&snapshot, calls)))
+    }
+
+    async fn prepare_type_hierarchy(
+        &self,
+        params: TypeHierarchyPrepareParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .to_string();
+        let position = params.text_document_position_params.position;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            let items = type_hierarchy::prepare_type_hierarchy(doc, syms, tree, position, &uri);
+            return Ok(self.to_client(&snapshot, items));
+        }
+
+        Ok(None)
+    }
+
+    async fn supertypes(
+        &self,
+        params: TypeHierarchySupertypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let uri = params.item.uri.to_string();
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let item = self.to_internal(&snapshot, params.item);
+            let items = type_hierarchy::supertypes(doc, syms, tree, &item);
+            return Ok(Some(self.to_client(&snapshot, items)));
+        }
+
+        Ok(None)
+    }
+
+    async fn subtypes(
+        &self,
+        params: TypeHierarchySubtypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        let uri = params.item.uri.to_string();
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let item = self.to_internal(&snapshot, params.item);
+            let items = type_hierarchy::subtypes(doc, syms, tree, &item);
+            return Ok(Some(self.to_client(&snapshot, items)));
+        }
+
+        Ok(None)
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let uri = params.text_document.uri.to_string();
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((_, syms, tree)) = snapshot.context() {
+            let symbols = document_symbols::provide_document_symbols(syms, tree);
+            return Ok(Some(DocumentSymbolResponse::Nested(
+                self.to_client(&snapshot, symbols),
+            )));
+        }
+
+        Ok(None)
+    }
+
+    async fn linked_editing_range(
+        &self,
+        params: LinkedEditingRangeParams,
+    ) -> Result<Option<LinkedEditingRanges>> {
+        let uri = params
+            .text_document_position_params
+            .text_document
+            .uri
+            .to_string();
+        let position = params.text_document_position_params.position;
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, _syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            let ranges = linked_editing::provide_linked_editing_ranges(doc, tree, position);
+            return Ok(self.to_client(&snapshot, ranges));
+        }
+
+        Ok(None)
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let uri = params.text_document.uri.to_string();
+
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((_, syms, _tree)) = snapshot.context() {
+            let mut lenses = code_lens::provide_code_lenses(syms, &uri);
+            let results = snapshot.assertions.get();
+            lenses.extend(code_lens::provide_assertion_lenses(
+                syms,
+                &uri,
+                results.map(Vec::as_slice),
+            ));
+            if let Ok(runs) = snapshot.runs.lock() {
+                lenses.extend(code_lens::provide_run_lenses(syms, &uri, &runs));
+            }
+            // Encoding is measured once per version, off the async runtime
+            let measured = Arc::clone(&snapshot);
+            let report = tokio::task::spawn_blocking(move || measured.size_report().cloned())
+                .await
+                .ok()
+                .flatten();
+            if let Some(report) = report {
+                lenses.extend(code_lens::provide_size_lenses(syms, &report));
+            }
+            return Ok(Some(self.to_client(&snapshot, lenses)));
+        }
+
+        Ok(None)
+    }
+
+    async fn execute_command(
+        &self,
+        params: ExecuteCommandParams,
+    ) -> Result<Option<serde_json::Value>> {
+        let Some(uri) = params.arguments.first().and_then(|uri| uri.as_str()) else {
+            return Err(Error::invalid_params("Expected a document URI"));
+        };
+        let uri = uri.to_string();
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Err(Error::invalid_params(format!("Document not open: {}", uri)));
+        };
+        let progress = params.work_done_progress_params.work_done_token;
+
+        match params.command.as_str() {
+            commands::RUN_ASSERTIONS_COMMAND => self.run_assertions(uri, snapshot, progress).await,
+            commands::RUN_EXPORT_COMMAND => {
+                let name = params.arguments.get(1).and_then(|name| name.as_str());
+                let args = params
+                    .arguments
+                    .get(2)
+                    .and_then(|args| serde_json::from_value::<Vec<String>>(args.clone()).ok());
+                let (Some(name), Some(args)) = (name, args) else {
+                    return Err(Error::invalid_params(
+                        "Expected an export name and a list of arguments",
+                    ));
+                };
+                self.run_export(uri, snapshot, progress, name.to_string(), args)
+                    .await
+            }
+            commands::COMPILE_COMMAND => {
+                let path = params
+                    .arguments
+                    .get(1)
+                    .and_then(|path| path.as_str())
+                    .map(PathBuf::from);
+                self.compile(uri, snapshot, progress, path).await
+            }
+            commands::GENERATE_TYPES_COMMAND => {
+                let path = params
+                    .arguments
+                    .get(1)
+                    .and_then(|path| path.as_str())
+                    .map(PathBuf::from);
+                self.generate_types(snapshot, path)
+            }
+            command => Err(Error::invalid_params(format!(
+                "Unknown command: {}",
+                command
+            ))),
+        }
+    }
+
+    async fn code_lens_resolve(&self, lens: CodeLens) -> Result<CodeLens> {
+        let uri = lens
+            .data
+            .as_ref()
+            .and_then(|data| data.get("uri"))
+            .and_then(|uri| uri.as_str())
+            .map(str::to_string);
+
+        let Some((uri, snapshot)) = uri.and_then(|uri| Some((uri.clone(), self.snapshot(&uri)?)))
+        else {
+            return Ok(lens);
+        };
+
+        // Counting references can be slow, so resolve off the async runtime
+        let unresolved = lens.clone();
+        let lens = self.to_internal(&snapshot, lens);
+        let resolved = self
+            .run_blocking(
+                &uri,
+                &snapshot,
+                None,
+                "Resolving code lens",
+                move |doc, _| {
+                    let (doc, syms, tree) = doc.context()?;
+                    Some(code_lens::resolve_code_lens(doc, syms, tree, lens))
+                },
+            )
+            .await?;
+
+        Ok(resolved.map_or(unresolved, |lens| self.to_client(&snapshot, lens)))
+    }
+
     async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
         let uri = params.text_document_position.text_document.uri.to_string();
         let position = params.text_document_position.position;
@@ -402,31 +1760,80 @@ impl LanguageServer for Backend {
             )
             .await;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
-            let refs = references::provide_references(
-                &doc,
-                &syms,
-                &tree,
-                position,
+        let Some(snapshot) = self.snapshot(&uri) else {
+            self.client
+                .log_message(MessageType::WARNING, "No document/symbols/tree found")
+                .await;
+            return Ok(None);
+        };
+
+        let position = self.to_internal(&snapshot, position);
+        let progress = params.work_done_progress_params.work_done_token;
+        let doc_uri = uri.clone();
+        let links = self.links();
+        let documents = Arc::clone(&self.documents);
+        let roots = self.workspace_roots.get().cloned().unwrap_or_default();
+        let encoding = self.position_encoding();
+        let refs = self
+            .run_blocking(
                 &uri,
-                include_declaration,
-            );
+                &snapshot,
+                progress,
+                "Finding references",
+                move |doc, cancel| {
+                    let (doc, syms, tree) = doc.context()?;
+                    let refs = references::provide_references_cancellable(
+                        doc,
+                        syms,
+                        tree,
+                        position,
+                        &doc_uri,
+                        include_declaration,
+                        cancel,
+                    );
+
+                    // Imports of other workspace modules bound to the item's exports
+                    let path = Url::parse(&doc_uri)
+                        .ok()
+                        .and_then(|uri| uri.to_file_path().ok());
+                    let item = references::identify_symbol_at_position(doc, syms, tree, position)
+                        .and_then(|target| target.extern_item());
+                    let importers = match (path, item) {
+                        (Some(path), Some((kind, index))) if !links.is_empty() => {
+                            let exports: Vec<_> = syms.exports_of(kind, index).collect();
+                            importing_locations(
+                                &links,
+                                &path,
+                                &exports,
+                                &find_wat_files(&roots, cancel),
+                                &documents,
+                                encoding,
+                            )
+                        }
+                        _ => Vec::new(),
+                    };
+                    Some((refs, importers))
+                },
+            )
+            .await?;
 
+        let Some((refs, importers)) = refs else {
             self.client
-                .log_message(
-                    MessageType::INFO,
-                    format!("Found {} references", refs.len()),
-                )
+                .log_message(MessageType::WARNING, "No document/symbols/tree found")
                 .await;
-
-            return Ok(Some(refs));
-        }
+            return Ok(None);
+        };
 
         self.client
-            .log_message(MessageType::WARNING, "No document/symbols/tree found")
+            .log_message(
+                MessageType::INFO,
+                format!("Found {} references", refs.len() + importers.len()),
+            )
             .await;
 
-        Ok(None)
+        let mut refs = self.to_client(&snapshot, refs);
+        refs.extend(importers);
+        Ok(Some(refs))
     }
 
     async fn prepare_rename(
@@ -436,11 +1843,15 @@ impl LanguageServer for Backend {
         let uri = params.text_document.uri.to_string();
         let position = params.position;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
-            if references::identify_symbol_at_position(&doc, &syms, &tree, position).is_some() {
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+        if let Some((doc, syms, tree)) = snapshot.context() {
+            let position = self.to_internal(&snapshot, position);
+            if references::identify_symbol_at_position(doc, syms, tree, position).is_some() {
                 // The symbol logic deems this a valid symbol.
                 // Now find the range to select.
-                if let Some(node) = utils::node_at_position(&tree, &doc, position.into()) {
+                if let Some(node) = utils::node_at_position(tree, doc, position.into()) {
                     // If it's an identifier (e.g. $foo), return its full range.
                     if node.kind() == "identifier" {
                         let range = Range {
@@ -453,7 +1864,9 @@ impl LanguageServer for Backend {
                                 character: node.end_position().column as u32,
                             },
                         };
-                        return Ok(Some(PrepareRenameResponse::Range(range)));
+                        return Ok(Some(PrepareRenameResponse::Range(
+                            self.to_client(&snapshot, range),
+                        )));
                     } else if node.kind() == "nat" || node.kind() == "index" {
                         // Even for indices, return the range so client knows what to replace
                         let range = Range {
@@ -466,7 +1879,9 @@ impl LanguageServer for Backend {
                                 character: node.end_position().column as u32,
                             },
                         };
-                        return Ok(Some(PrepareRenameResponse::Range(range)));
+                        return Ok(Some(PrepareRenameResponse::Range(
+                            self.to_client(&snapshot, range),
+                        )));
                     }
                 }
             }
@@ -500,11 +1915,24 @@ impl LanguageServer for Backend {
             )
             .await;
 
-        if let Some((doc, syms, tree)) = self.get_document_context(&uri) {
-            // Identify the symbol we are renaming
-            if let Some(target) =
-                references::identify_symbol_at_position(&doc, &syms, &tree, position)
-            {
+        let Some(snapshot) = self.snapshot(&uri) else {
+            return Ok(None);
+        };
+
+        let position = self.to_internal(&snapshot, position);
+        let progress = params.work_done_progress_params.work_done_token;
+        let doc_uri = uri.clone();
+        let refs = self
+            .run_blocking(&uri, &snapshot, progress, "Renaming", move |doc, cancel| {
+                let (doc, syms, tree) = doc.context()?;
+
+                // Identify the symbol we are renaming
+                let Some(target) =
+                    references::identify_symbol_at_position(doc, syms, tree, position)
+                else {
+                    return Some(Err((MessageType::WARNING, "No symbol found at position")));
+                };
+
                 // Check if the symbol has a name - we don't support renaming unnamed symbols yet
                 let has_name = match &target {
                     references::ReferenceTarget::Function { name, .. } => name.is_some(),
@@ -521,56 +1949,230 @@ impl LanguageServer for Backend {
                 };
 
                 if !has_name {
-                    self.client
-                        .show_message(MessageType::ERROR, "Cannot rename unnamed symbol")
-                        .await;
-                    return Ok(None);
+                    return Some(Err((MessageType::ERROR, "Cannot rename unnamed symbol")));
                 }
 
                 // Find all references
-                let refs = references::provide_references(
-                    &doc, &syms, &tree, position, &uri, true, // include declaration
-                );
+                Some(Ok(references::provide_references_cancellable(
+                    doc, syms, tree, position, &doc_uri, true, // include declaration
+                    cancel,
+                )))
+            })
+            .await?;
+
+        let refs = match refs {
+            Some(Ok(refs)) => self.to_client(&snapshot, refs),
+            Some(Err((kind, message))) => {
+                self.client.show_message(kind, message).await;
+                return Ok(None);
+            }
+            None => return Ok(None),
+        };
 
-                if refs.is_empty() {
-                    return Ok(None);
-                }
+        if refs.is_empty() {
+            return Ok(None);
+        }
 
-                // Create WorkspaceEdit
-                let mut changes = std::collections::HashMap::new();
-                let mut text_edits = Vec::new();
+        // Create WorkspaceEdit
+        let mut changes = HashMap::new();
+        let mut text_edits = Vec::new();
 
-                for location in refs {
-                    text_edits.push(TextEdit {
-                        range: location.range,
-                        new_text: new_name.clone(),
-                    });
-                }
+        for location in refs {
+            text_edits.push(TextEdit {
+                range: location.range,
+                new_text: new_name.clone(),
+            });
+        }
 
-                if let Ok(url) = Url::parse(&uri) {
-                    changes.insert(url, text_edits);
-                    return Ok(Some(WorkspaceEdit {
-                        changes: Some(changes),
-                        document_changes: None,
-                        change_annotations: None,
-                    }));
-                }
-            } else {
-                self.client
-                    .show_message(MessageType::WARNING, "No symbol found at position")
-                    .await;
-            }
+        if let Ok(url) = Url::parse(&uri) {
+            changes.insert(url, text_edits);
+            return Ok(Some(WorkspaceEdit {
+                changes: Some(changes),
+                document_changes: None,
+                change_annotations: None,
+            }));
         }
 
         Ok(None)
     }
 }
 
+/// Reports the end of a work done progress. Dropping it without calling
+/// [`ProgressGuard::end`], as when tower-lsp drops a cancelled request's
+/// future, still sends the end so the client's indicator goes away.
+struct ProgressGuard {
+    client: Client,
+    token: Option<ProgressToken>,
+}
+
+impl ProgressGuard {
+    async fn end(mut self) {
+        if let Some(token) = self.token.take() {
+            let end = WorkDoneProgress::End(WorkDoneProgressEnd::default());
+            send_progress(&self.client, token, end).await;
+        }
+    }
+}
+
+impl Drop for ProgressGuard {
+    fn drop(&mut self) {
+        let Some(token) = self.token.take() else {
+            return;
+        };
+        let client = self.client.clone();
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            runtime.spawn(async move {
+                let end = WorkDoneProgress::End(WorkDoneProgressEnd::default());
+                send_progress(&client, token, end).await;
+            });
+        }
+    }
+}
+
+async fn send_progress(client: &Client, token: ProgressToken, progress: WorkDoneProgress) {
+    client
+        .send_notification::<notification::Progress>(ProgressParams {
+            token,
+            value: ProgressParamsValue::WorkDone(progress),
+        })
+        .await;
+}
+
+/// A hover with the byte offset of a disassembled line appended
+fn with_offset(hover: Option<Hover>, offset: String) -> Hover {
+    let value = match hover.as_ref().map(|hover| &hover.contents) {
+        Some(HoverContents::Markup(markup)) => format!("{}\n\n---\n\n{}", markup.value, offset),
+        _ => offset,
+    };
+    Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: hover.and_then(|hover| hover.range),
+    }
+}
+
+/// A full pull diagnostics report
+fn full_report(result_id: String, items: Vec<Diagnostic>) -> DocumentDiagnosticReportResult {
+    DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(
+        RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items,
+            },
+        },
+    ))
+}
+
+/// A report telling the client its diagnostics for `result_id` still hold
+fn unchanged_report(result_id: String) -> DocumentDiagnosticReportResult {
+    DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Unchanged(
+        RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        },
+    ))
+}
+
+/// Diagnostics for an open document, with the results of its last
+/// assertion run if it had one, and its imports checked against the
+/// `linked` modules providing them or the host `links` describes
+fn document_diagnostics(
+    doc: &DocumentSnapshot,
+    settings: &Settings,
+    links: &LinkConfig,
+    linked: &HashMap<String, LinkedDocument>,
+) -> Vec<Diagnostic> {
+    let mut items =
+        diagnostics::provide_all_diagnostics(doc.tree.as_ref(), &doc.text, &doc.symbols, settings);
+    items.extend(diagnostics::provide_link_diagnostics(
+        &doc.symbols,
+        |module| linked.get(module).map(|provider| &provider.symbols),
+    ));
+    items.extend(diagnostics::provide_host_diagnostics(
+        &doc.symbols,
+        links.host(),
+        |module| links.module_path(module).is_some(),
+    ));
+    if let Some(results) = doc.assertions.get() {
+        items.extend(diagnostics::provide_assertion_diagnostics(results));
+    }
+    items
+}
+
+/// Result id for an open document; running its assertions adds diagnostics
+/// without changing the text, and editing a module it imports from or the
+/// host description changes its link diagnostics
+fn document_result_id(
+    doc: &DocumentSnapshot,
+    settings: &Settings,
+    links: &LinkConfig,
+    linked: &HashMap<String, LinkedDocument>,
+) -> String {
+    let mut providers: Vec<&LinkedDocument> = linked.values().collect();
+    providers.sort_by(|a, b| a.uri.cmp(&b.uri));
+    let mut source = doc.text.clone();
+    for provider in providers {
+        source.push('\0');
+        source.push_str(&provider.text);
+    }
+    for import in links.host().imports() {
+        source.push('\0');
+        source.push_str(&format!("{}.{}{}", import.module, import.field, import.ty));
+    }
+    let result_id = diagnostics::diagnostics_result_id(&source, settings);
+    match doc.assertions.get() {
+        Some(_) => format!("{}-run", result_id),
+        None => result_id,
+    }
+}
+
+/// Report for one workspace document; `diagnose` only runs when the client's
+/// previous result id no longer matches `result_id`
+fn workspace_document_report(
+    uri: Url,
+    version: Option<i64>,
+    text: &str,
+    result_id: String,
+    previous_result_id: Option<&String>,
+    encoding: PositionEncoding,
+    diagnose: impl FnOnce() -> Vec<Diagnostic>,
+) -> WorkspaceDocumentDiagnosticReport {
+    if previous_result_id == Some(&result_id) {
+        return WorkspaceDocumentDiagnosticReport::Unchanged(
+            WorkspaceUnchangedDocumentDiagnosticReport {
+                uri,
+                version,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+                    result_id,
+                },
+            },
+        );
+    }
+
+    let index = LineIndex::new(text);
+    let items =
+        PositionConverter::new(text, &index, PositionEncoding::Utf8, encoding).convert(diagnose());
+    WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+        uri,
+        version,
+        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+            result_id: Some(result_id),
+            items,
+        },
+    })
+}
+
 #[tokio::main]
 async fn main() {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(Backend::new);
+    let (service, socket) = LspService::build(Backend::new)
+        .custom_method("wat/disassemble", Backend::disassemble)
+        .custom_method("wat/gotoBinaryOffset", Backend::goto_binary_offset)
+        .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
 }
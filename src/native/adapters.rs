@@ -4,6 +4,11 @@
 //! provides additional helper functions for common conversions.
 
 use crate::core::types as core;
+use crate::core::{LineIndex, PositionEncoding};
+use crate::features::code_lens::SHOW_REFERENCES_COMMAND;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
 use tower_lsp::lsp_types as lsp;
 
 /// Convert a core HoverResult to an LSP Hover
@@ -35,3 +40,306 @@ pub fn ranges_to_locations(ranges: Vec<core::Range>, uri: lsp::Url) -> Vec<lsp::
         })
         .collect()
 }
+
+/// Pick the position encoding for a session from the client's supported list.
+///
+/// UTF-8 matches the internal byte columns, so it is preferred; UTF-16 is the
+/// fallback every client must support.
+pub fn negotiate_position_encoding(
+    supported: Option<&[lsp::PositionEncodingKind]>,
+) -> PositionEncoding {
+    let supported = supported.unwrap_or_default();
+    if supported.contains(&lsp::PositionEncodingKind::UTF8) {
+        PositionEncoding::Utf8
+    } else if supported.contains(&lsp::PositionEncodingKind::UTF32) {
+        PositionEncoding::Utf32
+    } else {
+        PositionEncoding::Utf16
+    }
+}
+
+/// Convert a PositionEncoding to its LSP kind
+pub fn position_encoding_kind(encoding: PositionEncoding) -> lsp::PositionEncodingKind {
+    match encoding {
+        PositionEncoding::Utf8 => lsp::PositionEncodingKind::UTF8,
+        PositionEncoding::Utf16 => lsp::PositionEncodingKind::UTF16,
+        PositionEncoding::Utf32 => lsp::PositionEncodingKind::UTF32,
+    }
+}
+
+/// Converts the positions of one document between encodings, using the
+/// document's line index
+pub struct PositionConverter<'a> {
+    text: &'a str,
+    index: &'a LineIndex,
+    from: PositionEncoding,
+    to: PositionEncoding,
+}
+
+impl<'a> PositionConverter<'a> {
+    pub fn new(
+        text: &'a str,
+        index: &'a LineIndex,
+        from: PositionEncoding,
+        to: PositionEncoding,
+    ) -> Self {
+        Self {
+            text,
+            index,
+            from,
+            to,
+        }
+    }
+
+    /// Convert every position of `value`
+    pub fn convert<T: ConvertPositions>(&self, mut value: T) -> T {
+        // Columns only differ between encodings for non-ASCII text
+        if self.from != self.to && !self.text.is_ascii() {
+            value.convert_positions(self);
+        }
+        value
+    }
+
+    fn position(&self, position: lsp::Position) -> lsp::Position {
+        self.index
+            .convert(self.text, position.into(), self.from, self.to)
+            .into()
+    }
+}
+
+/// LSP params and results holding positions in a document
+pub trait ConvertPositions {
+    fn convert_positions(&mut self, converter: &PositionConverter);
+}
+
+impl ConvertPositions for lsp::Position {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        *self = converter.position(*self);
+    }
+}
+
+impl ConvertPositions for lsp::Range {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.start.convert_positions(converter);
+        self.end.convert_positions(converter);
+    }
+}
+
+impl<T: ConvertPositions> ConvertPositions for Vec<T> {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.iter_mut()
+            .for_each(|value| value.convert_positions(converter));
+    }
+}
+
+impl<T: ConvertPositions> ConvertPositions for Option<T> {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        if let Some(value) = self {
+            value.convert_positions(converter);
+        }
+    }
+}
+
+impl ConvertPositions for lsp::Location {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::TextEdit {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::Diagnostic {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+        for related in self.related_information.iter_mut().flatten() {
+            related.location.convert_positions(converter);
+        }
+    }
+}
+
+impl ConvertPositions for lsp::Hover {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::CompletionItem {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        match &mut self.text_edit {
+            Some(lsp::CompletionTextEdit::Edit(edit)) => edit.convert_positions(converter),
+            Some(lsp::CompletionTextEdit::InsertAndReplace(edit)) => {
+                edit.insert.convert_positions(converter);
+                edit.replace.convert_positions(converter);
+            }
+            None => {}
+        }
+        self.additional_text_edits.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::DocumentSymbol {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+        self.selection_range.convert_positions(converter);
+        self.children.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::CallHierarchyItem {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+        self.selection_range.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::CallHierarchyIncomingCall {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.from.convert_positions(converter);
+        self.from_ranges.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::CallHierarchyOutgoingCall {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.to.convert_positions(converter);
+        self.from_ranges.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::TypeHierarchyItem {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+        self.selection_range.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::LinkedEditingRanges {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.ranges.convert_positions(converter);
+    }
+}
+
+impl ConvertPositions for lsp::CodeLens {
+    fn convert_positions(&mut self, converter: &PositionConverter) {
+        self.range.convert_positions(converter);
+
+        // The references command is given the lens position and the locations
+        let Some(command) = &mut self.command else {
+            return;
+        };
+        if command.command != SHOW_REFERENCES_COMMAND {
+            return;
+        }
+        if let Some(arguments) = &mut command.arguments {
+            convert_argument::<lsp::Position>(arguments.get_mut(1), converter);
+            convert_argument::<Vec<lsp::Location>>(arguments.get_mut(2), converter);
+        }
+    }
+}
+
+/// Convert the positions of a command argument holding a `T`
+fn convert_argument<T>(argument: Option<&mut Value>, converter: &PositionConverter)
+where
+    T: ConvertPositions + Serialize + DeserializeOwned,
+{
+    let Some(argument) = argument else {
+        return;
+    };
+    if let Ok(mut value) = serde_json::from_value::<T>(argument.clone()) {
+        value.convert_positions(converter);
+        *argument = serde_json::to_value(value).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_position_encoding() {
+        assert_eq!(negotiate_position_encoding(None), PositionEncoding::Utf16);
+        assert_eq!(
+            negotiate_position_encoding(Some(&[
+                lsp::PositionEncodingKind::UTF16,
+                lsp::PositionEncodingKind::UTF8,
+            ])),
+            PositionEncoding::Utf8
+        );
+        assert_eq!(
+            negotiate_position_encoding(Some(&[lsp::PositionEncodingKind::UTF32])),
+            PositionEncoding::Utf32
+        );
+    }
+
+    #[test]
+    fn test_convert_diagnostic_positions() {
+        let text = "(data \"𝄞\" $d)";
+        let dollar = text.find('$').unwrap() as u32;
+        let diagnostic = lsp::Diagnostic {
+            range: lsp::Range::new(
+                lsp::Position::new(0, dollar),
+                lsp::Position::new(0, dollar + 2),
+            ),
+            message: "unused".to_string(),
+            ..Default::default()
+        };
+
+        let index = LineIndex::new(text);
+        let converter = PositionConverter::new(
+            text,
+            &index,
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+        );
+        let converted = converter.convert(vec![diagnostic]);
+        // "𝄞" is 4 bytes but 2 UTF-16 code units
+        assert_eq!(
+            converted[0].range,
+            lsp::Range::new(
+                lsp::Position::new(0, dollar - 2),
+                lsp::Position::new(0, dollar),
+            )
+        );
+    }
+
+    #[test]
+    fn test_convert_references_lens_arguments() {
+        let text = "(func $𝄞)\n(call $𝄞)";
+        let index = LineIndex::new(text);
+        let converter = PositionConverter::new(
+            text,
+            &index,
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+        );
+        let uri = lsp::Url::parse("file:///test.wat").unwrap();
+        let call = lsp::Location::new(
+            uri.clone(),
+            lsp::Range::new(lsp::Position::new(1, 6), lsp::Position::new(1, 11)),
+        );
+        let lens = lsp::CodeLens {
+            range: lsp::Range::new(lsp::Position::new(0, 6), lsp::Position::new(0, 11)),
+            command: Some(lsp::Command {
+                title: "1 reference".to_string(),
+                command: SHOW_REFERENCES_COMMAND.to_string(),
+                arguments: Some(vec![
+                    serde_json::json!(uri),
+                    serde_json::json!(lsp::Position::new(0, 11)),
+                    serde_json::json!([call]),
+                ]),
+            }),
+            data: None,
+        };
+
+        let lens = converter.convert(lens);
+        assert_eq!(lens.range.end, lsp::Position::new(0, 9));
+        let arguments = lens.command.unwrap().arguments.unwrap();
+        assert_eq!(arguments[1], serde_json::json!(lsp::Position::new(0, 9)));
+        let locations: Vec<lsp::Location> = serde_json::from_value(arguments[2].clone()).unwrap();
+        assert_eq!(locations[0].range.end, lsp::Position::new(1, 9));
+    }
+}
@@ -0,0 +1,52 @@
+//! The `wat/gotoBinaryOffset` request: from a code offset in a document's
+//! binary, as a trap or profiler reports it, back to the instruction.
+
+use crate::compile::{compile_with_source_map, instruction_range, parse_binary_location};
+use crate::core::Range;
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types as lsp;
+
+/// Params of the `wat/gotoBinaryOffset` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GotoBinaryOffsetParams {
+    pub text_document: lsp::TextDocumentIdentifier,
+    pub offset: BinaryOffset,
+    /// Index of the function the offset was reported in, if known
+    pub function: Option<u32>,
+}
+
+/// An offset as a number, or as text like `0x3a4` or `wasm-function[12]:0x3a4`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BinaryOffset {
+    Number(usize),
+    Text(String),
+}
+
+impl GotoBinaryOffsetParams {
+    /// Function index and offset to resolve, or `None` if the text isn't an
+    /// offset
+    pub fn location(&self) -> Option<(Option<u32>, usize)> {
+        match &self.offset {
+            BinaryOffset::Number(offset) => Some((self.function, *offset)),
+            BinaryOffset::Text(text) => {
+                let (function, offset) = parse_binary_location(text)?;
+                Some((function.or(self.function), offset))
+            }
+        }
+    }
+}
+
+/// Range of the instruction a binary offset came from, or `None` when it
+/// isn't in a function body. Fails when the text doesn't compile.
+pub fn resolve_binary_offset(
+    text: &str,
+    function: Option<u32>,
+    offset: usize,
+) -> Result<Option<Range>, String> {
+    let (_, map) = compile_with_source_map(text)?;
+    Ok(map
+        .resolve(function, offset)
+        .map(|offset| instruction_range(text, offset)))
+}
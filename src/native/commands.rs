@@ -0,0 +1,23 @@
+//! Commands the server executes through `workspace/executeCommand`.
+//!
+//! Their IDs are in the `watLsp.` namespace shared with the editor
+//! commands of the clients, which must use other names, since clients
+//! register the server's commands as editor commands too. Custom requests
+//! such as `wat/disassemble` are methods rather than commands, under `wat/`.
+
+/// Runs a script's assertions and reports the results as diagnostics.
+/// Arguments are `[uri]`.
+pub const RUN_ASSERTIONS_COMMAND: &str = "watLsp.runAssertions";
+
+/// Runs an exported function and remembers its results for the lens.
+/// Arguments are `[uri, export name, [argument text...]]`.
+pub const RUN_EXPORT_COMMAND: &str = "watLsp.runExport";
+
+/// Encodes a document to a binary and reports its size by section and by
+/// function. Arguments are `[uri]`, or `[uri, path]` to also write the binary
+/// to `path` and its source map to `path.map`.
+pub const COMPILE_COMMAND: &str = "watLsp.compileBinary";
+
+/// Generates TypeScript declarations for a document's exports and imports.
+/// Arguments are `[uri]`, or `[uri, path]` to also write them to `path`.
+pub const GENERATE_TYPES_COMMAND: &str = "watLsp.generateDeclarations";
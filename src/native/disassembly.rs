@@ -0,0 +1,87 @@
+//! Virtual WAT documents for binary `.wasm` files.
+//!
+//! A binary at `file:///dir/app.wasm` is shown as the read-only document
+//! `wat-disassembly:/dir/app.wasm.wat`, whose text is its disassembly. The
+//! client fetches the text with `wat/disassemble` and then opens it like any
+//! other document, so every feature works on it.
+
+use crate::disassembler::{disassemble, Disassembly};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tower_lsp::lsp_types as lsp;
+
+/// URI scheme of disassembled documents
+pub const DISASSEMBLY_SCHEME: &str = "wat-disassembly";
+
+/// Params of the `wat/disassemble` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisassembleParams {
+    /// A `.wasm` file, or the disassembled document of one
+    pub uri: lsp::Url,
+}
+
+/// Result of the `wat/disassemble` request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisassembleResult {
+    /// The disassembled document to open
+    pub uri: lsp::Url,
+    pub text: String,
+}
+
+/// Whether a URI is a binary module on disk
+pub fn is_binary_uri(uri: &lsp::Url) -> bool {
+    uri.scheme() == "file" && uri.path().ends_with(".wasm")
+}
+
+/// The disassembled document of a `.wasm` file
+pub fn disassembly_uri(binary: &lsp::Url) -> Option<lsp::Url> {
+    if !is_binary_uri(binary) {
+        return None;
+    }
+    lsp::Url::parse(&format!("{}:{}.wat", DISASSEMBLY_SCHEME, binary.path())).ok()
+}
+
+/// The `.wasm` file a disassembled document shows
+pub fn binary_uri(disassembly: &lsp::Url) -> Option<lsp::Url> {
+    if disassembly.scheme() != DISASSEMBLY_SCHEME {
+        return None;
+    }
+    let path = disassembly.path().strip_suffix(".wat")?;
+    let binary = lsp::Url::parse(&format!("file://{}", path)).ok()?;
+    is_binary_uri(&binary).then_some(binary)
+}
+
+/// Read and disassemble a `.wasm` file
+pub fn load(binary: &lsp::Url) -> Result<Disassembly, String> {
+    let path = binary
+        .to_file_path()
+        .map_err(|_| format!("{} is not a file path", binary))?;
+    let bytes = fs::read(&path).map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+    disassemble(&bytes).map_err(|e| format!("Error disassembling {}: {}", path.display(), e))
+}
+
+/// Hover text for the byte offset a disassembly line came from
+pub fn offset_hover(disassembly: &Disassembly, line: u32) -> Option<String> {
+    let offset = disassembly.offset_at_line(line)?;
+    Some(format!("Offset: `0x{:x}`", offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disassembly_uris_round_trip() {
+        let binary = lsp::Url::parse("file:///work/build/app%20v2.wasm").unwrap();
+        let disassembly = disassembly_uri(&binary).unwrap();
+        assert_eq!(
+            disassembly.as_str(),
+            "wat-disassembly:/work/build/app%20v2.wasm.wat"
+        );
+        assert_eq!(binary_uri(&disassembly), Some(binary));
+
+        let text = lsp::Url::parse("file:///work/app.wat").unwrap();
+        assert_eq!(disassembly_uri(&text), None);
+        assert_eq!(binary_uri(&text), None);
+    }
+}
@@ -0,0 +1,76 @@
+//! Immutable per-version document state for the native server.
+//!
+//! Every edit produces a new [`DocumentSnapshot`]. Requests take an `Arc` to
+//! the snapshot current when they arrive, so features can run off the async
+//! runtime without holding locks on the document store.
+
+use crate::compile::{compile, size_report, SizeReport};
+use crate::core::{CancellationToken, LineIndex};
+use crate::features::call_hierarchy::CallGraph;
+use crate::interpreter::AssertionResult;
+use crate::symbols::SymbolTable;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tree_sitter::Tree;
+
+/// A document's text and analysis at one version
+#[derive(Debug)]
+pub struct DocumentSnapshot {
+    /// Version from the client's last `didOpen` or `didChange`
+    pub version: i32,
+    pub text: String,
+    /// Line starts of `text`, for converting positions between encodings
+    pub line_index: LineIndex,
+    /// Symbols from the last successful extraction
+    pub symbols: SymbolTable,
+    pub tree: Option<Tree>,
+    /// Results of running this version's assertions, once asked to
+    pub assertions: OnceLock<Vec<AssertionResult>>,
+    /// Summary of the last run of each export at this version, by export name
+    pub runs: Mutex<HashMap<String, String>>,
+    /// Sizes of this version's binary once measured, or `None` if it
+    /// doesn't compile
+    pub sizes: OnceLock<Option<SizeReport>>,
+    /// Call edges of this version, once a call hierarchy request has
+    /// collected them all
+    pub calls: OnceLock<CallGraph>,
+}
+
+impl DocumentSnapshot {
+    pub fn new(version: i32, text: String, symbols: SymbolTable, tree: Option<Tree>) -> Self {
+        Self {
+            version,
+            line_index: LineIndex::new(&text),
+            text,
+            symbols,
+            tree,
+            assertions: OnceLock::new(),
+            runs: Mutex::default(),
+            sizes: OnceLock::new(),
+            calls: OnceLock::new(),
+        }
+    }
+
+    /// Text, symbols and tree, if the document was parsed
+    pub fn context(&self) -> Option<(&str, &SymbolTable, &Tree)> {
+        Some((&self.text, &self.symbols, self.tree.as_ref()?))
+    }
+
+    /// Sizes of this version's binary, measured on first use
+    pub fn size_report(&self) -> Option<&SizeReport> {
+        self.sizes
+            .get_or_init(|| size_report(&compile(&self.text).ok()?).ok())
+            .as_ref()
+    }
+
+    /// Call graph of this version, built on first use. A build cut short
+    /// by `cancel` returns `None` and isn't kept.
+    pub fn call_graph(&self, cancel: &CancellationToken) -> Option<&CallGraph> {
+        if let Some(graph) = self.calls.get() {
+            return Some(graph);
+        }
+        let (text, symbols, tree) = self.context()?;
+        let graph = CallGraph::build(text, symbols, tree, cancel)?;
+        Some(self.calls.get_or_init(|| graph))
+    }
+}
@@ -0,0 +1,149 @@
+//! Resolving imports to the workspace files configured to provide them.
+//!
+//! A providing module is read from its open document when the client has
+//! one, so edits show up in the modules importing from it before they are
+//! saved, and from disk otherwise.
+
+use super::adapters::{self, PositionConverter};
+use super::DocumentSnapshot;
+use crate::core::{LineIndex, PositionEncoding};
+use crate::host::ImportDescription;
+use crate::linking::{find_export, imports_of, ExternType, LinkConfig};
+use crate::parser;
+use crate::symbols::{Export, Import, SymbolTable};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tower_lsp::lsp_types::{Location, Url};
+
+/// Open documents by URI
+pub type OpenDocuments = DashMap<String, Arc<DocumentSnapshot>>;
+
+/// A workspace module at its current text
+#[derive(Debug, Clone)]
+pub struct LinkedDocument {
+    pub uri: Url,
+    pub text: String,
+    pub line_index: LineIndex,
+    pub symbols: SymbolTable,
+}
+
+impl LinkedDocument {
+    /// Read the module at `path`
+    pub fn load(path: &Path, documents: &OpenDocuments) -> Option<Self> {
+        let uri = Url::from_file_path(path).ok()?;
+        if let Some(doc) = documents.get(uri.as_str()) {
+            return Some(Self {
+                uri,
+                text: doc.text.clone(),
+                line_index: doc.line_index.clone(),
+                symbols: doc.symbols.clone(),
+            });
+        }
+        let text = fs::read_to_string(path).ok()?;
+        let symbols = parser::parse_document(&text).ok()?;
+        Some(Self {
+            uri,
+            line_index: LineIndex::new(&text),
+            text,
+            symbols,
+        })
+    }
+
+    /// Location of a range of this document, in the client's encoding
+    fn location(&self, range: crate::core::Range, encoding: PositionEncoding) -> Location {
+        let location = adapters::range_to_location(range, self.uri.clone());
+        PositionConverter::new(
+            &self.text,
+            &self.line_index,
+            PositionEncoding::Utf8,
+            encoding,
+        )
+        .convert(location)
+    }
+}
+
+/// The modules providing the imports of `symbols`, by module name. Modules
+/// the config doesn't name, or whose file can't be read, are left out.
+pub fn linked_documents(
+    config: &LinkConfig,
+    symbols: &SymbolTable,
+    documents: &OpenDocuments,
+) -> HashMap<String, LinkedDocument> {
+    let mut linked = HashMap::new();
+    for import in &symbols.imports {
+        if linked.contains_key(&import.module) {
+            continue;
+        }
+        let Some(path) = config.module_path(&import.module) else {
+            continue;
+        };
+        if let Some(doc) = LinkedDocument::load(path, documents) {
+            linked.insert(import.module.clone(), doc);
+        }
+    }
+    linked
+}
+
+/// The items a module can import: those the host provides, then the
+/// exports of every linked module
+pub fn importable_items(config: &LinkConfig, documents: &OpenDocuments) -> Vec<ImportDescription> {
+    let mut items = config.host().imports().to_vec();
+    for (module, path) in config.modules() {
+        let Some(doc) = LinkedDocument::load(path, documents) else {
+            continue;
+        };
+        items.extend(doc.symbols.exports.iter().filter_map(|export| {
+            Some(ImportDescription {
+                module: module.to_string(),
+                field: export.name.clone(),
+                ty: ExternType::of(&doc.symbols, export.kind, export.index)?,
+            })
+        }));
+    }
+    items
+}
+
+/// Location of the export an import binds to, in the client's encoding
+pub fn import_definition(
+    config: &LinkConfig,
+    import: &Import,
+    documents: &OpenDocuments,
+    encoding: PositionEncoding,
+) -> Option<Location> {
+    let provider = LinkedDocument::load(config.module_path(&import.module)?, documents)?;
+    let export = find_export(&provider.symbols, import)?;
+    Some(provider.location(export.range?, encoding))
+}
+
+/// Locations of the imports in `files` bound to the given exports of the
+/// module at `path`, in the client's encoding
+pub fn importing_locations(
+    config: &LinkConfig,
+    path: &Path,
+    exports: &[&Export],
+    files: &[PathBuf],
+    documents: &OpenDocuments,
+    encoding: PositionEncoding,
+) -> Vec<Location> {
+    let modules: Vec<&str> = config.modules_provided_by(path).collect();
+    if modules.is_empty() || exports.is_empty() {
+        return Vec::new();
+    }
+
+    let mut locations = Vec::new();
+    for file in files.iter().filter(|file| file.as_path() != path) {
+        let Some(doc) = LinkedDocument::load(file, documents) else {
+            continue;
+        };
+        for module in &modules {
+            locations.extend(
+                imports_of(&doc.symbols, module, exports)
+                    .filter_map(|import| Some(doc.location(import.range?, encoding))),
+            );
+        }
+    }
+    locations
+}
@@ -1,8 +1,23 @@
 //! Native LSP adapters and utilities.
 //!
 //! This module contains code that's only needed for the native LSP server,
-//! including type conversions between core types and tower-lsp types.
+//! including type conversions between core types and tower-lsp types, the
+//! per-version document snapshots requests run against, workspace file
+//! discovery, imports resolved to the workspace modules providing them,
+//! disassembled `.wasm` documents, binary offset lookup and the commands the
+//! server executes.
 
 pub mod adapters;
+pub mod binary_offset;
+pub mod commands;
+pub mod disassembly;
+pub mod documents;
+pub mod linking;
+pub mod workspace;
 
 pub use adapters::*;
+pub use binary_offset::*;
+pub use disassembly::*;
+pub use documents::*;
+pub use linking::*;
+pub use workspace::*;
@@ -0,0 +1,53 @@
+//! Workspace file discovery for diagnostics of documents that aren't open.
+
+use crate::core::CancellationToken;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories that hold build output or dependencies rather than sources
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+/// Every `.wat` and `.wast` file under the given roots, sorted by path.
+///
+/// Hidden directories and [`SKIPPED_DIRS`] are not searched. Once `cancel`
+/// is cancelled the scan stops, returning the files found so far.
+pub fn find_wat_files(roots: &[PathBuf], cancel: &CancellationToken) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for root in roots {
+        collect_wat_files(root, &mut files, cancel);
+    }
+    files.sort();
+    files.dedup();
+    files
+}
+
+fn collect_wat_files(dir: &Path, files: &mut Vec<PathBuf>, cancel: &CancellationToken) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        if cancel.is_cancelled() {
+            return;
+        }
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            let skipped = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.') || SKIPPED_DIRS.contains(&name));
+            if !skipped {
+                collect_wat_files(&path, files, cancel);
+            }
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "wat" || ext == "wast")
+        {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+//! Differential comparison of the tree-sitter parser and the wast parser.
+//!
+//! Native builds extract symbols with tree-sitter while WASM builds use the
+//! wast crate, so both must produce the same [`SymbolTable`] for a valid
+//! module. Any field where they disagree is a bug in one of them.
+//!
+//! Names, indices, types, limits, flags and identifier ranges of module-level
+//! items are compared, along with imports, exports and the start function and
+//! the ranges of their declarations. Line numbers, block labels and data/elem
+//! segments are not: the wast parser doesn't track them the same way.
+
+use crate::core::types::Range;
+use crate::symbols::*;
+use std::path::Path;
+
+/// Files using syntax the wast crate rejects, e.g. the legacy `try`/`do`/`catch`
+/// exception blocks, so the parsers can't be compared on them
+pub const WAST_UNSUPPORTED: &[&str] = &["exceptions.wat"];
+
+/// Whether `path` is one of the [`WAST_UNSUPPORTED`] files
+pub fn is_wast_unsupported(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| WAST_UNSUPPORTED.iter().any(|skip| name == *skip))
+}
+
+/// A single field on which the two parsers disagree
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolDiff {
+    /// Path of the field, e.g. `functions[2].params[0].type`
+    pub path: String,
+    /// Value from the tree-sitter parser, None if it has no such item
+    pub tree_sitter: Option<String>,
+    /// Value from the wast parser, None if it has no such item
+    pub wast: Option<String>,
+}
+
+impl std::fmt::Display for SymbolDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "<missing>".to_string());
+        write!(
+            f,
+            "{}: tree-sitter {}, wast {}",
+            self.path,
+            value(&self.tree_sitter),
+            value(&self.wast)
+        )
+    }
+}
+
+/// Parse a document with both parsers and report where their symbol tables differ
+#[cfg(feature = "native")]
+pub fn compare_parsers(source: &str) -> Result<Vec<SymbolDiff>, String> {
+    let tree_sitter =
+        crate::parser::parse_document(source).map_err(|e| format!("tree-sitter parser: {}", e))?;
+    let wast =
+        crate::wast_parser::parse_document(source).map_err(|e| format!("wast parser: {}", e))?;
+    Ok(diff_symbol_tables(&tree_sitter, &wast))
+}
+
+/// Compare two symbol tables field by field
+pub fn diff_symbol_tables(tree_sitter: &SymbolTable, wast: &SymbolTable) -> Vec<SymbolDiff> {
+    let mut diffs = Vec::new();
+
+    diff_items(
+        &mut diffs,
+        &function_items(tree_sitter),
+        &function_items(wast),
+    );
+    diff_items(&mut diffs, &global_items(tree_sitter), &global_items(wast));
+    diff_items(&mut diffs, &table_items(tree_sitter), &table_items(wast));
+    diff_items(&mut diffs, &memory_items(tree_sitter), &memory_items(wast));
+    diff_items(&mut diffs, &type_items(tree_sitter), &type_items(wast));
+    diff_items(&mut diffs, &tag_items(tree_sitter), &tag_items(wast));
+    diff_items(&mut diffs, &import_items(tree_sitter), &import_items(wast));
+    diff_items(&mut diffs, &export_items(tree_sitter), &export_items(wast));
+    diff_items(&mut diffs, &start_items(tree_sitter), &start_items(wast));
+
+    diffs
+}
+
+/// An item identified by its path, with its compared fields in display order
+type Item = (String, Vec<(String, String)>);
+
+/// Compare items matched up by path, reporting missing items once rather than per field
+fn diff_items(diffs: &mut Vec<SymbolDiff>, tree_sitter: &[Item], wast: &[Item]) {
+    for (path, fields) in tree_sitter {
+        let Some((_, other_fields)) = wast.iter().find(|(other, _)| other == path) else {
+            diffs.push(SymbolDiff {
+                path: path.clone(),
+                tree_sitter: Some(summary(fields)),
+                wast: None,
+            });
+            continue;
+        };
+
+        for (field, value) in fields {
+            let other = other_fields
+                .iter()
+                .find(|(other, _)| other == field)
+                .map(|(_, v)| v);
+            if other != Some(value) {
+                diffs.push(SymbolDiff {
+                    path: format!("{}.{}", path, field),
+                    tree_sitter: Some(value.clone()),
+                    wast: other.cloned(),
+                });
+            }
+        }
+        for (field, value) in other_fields {
+            if !fields.iter().any(|(other, _)| other == field) {
+                diffs.push(SymbolDiff {
+                    path: format!("{}.{}", path, field),
+                    tree_sitter: None,
+                    wast: Some(value.clone()),
+                });
+            }
+        }
+    }
+
+    for (path, fields) in wast {
+        if !tree_sitter.iter().any(|(other, _)| other == path) {
+            diffs.push(SymbolDiff {
+                path: path.clone(),
+                tree_sitter: None,
+                wast: Some(summary(fields)),
+            });
+        }
+    }
+}
+
+/// Short description of an item that only one parser found
+fn summary(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .find(|(field, _)| field == "name")
+        .map(|(_, name)| name.clone())
+        .unwrap_or_else(|| "item".to_string())
+}
+
+fn function_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .functions
+        .iter()
+        .map(|func| {
+            let mut fields = vec![
+                field("name", name_text(&func.name)),
+                field("index", func.index),
+            ];
+            for param in &func.parameters {
+                let path = format!("params[{}]", param.index);
+                fields.push((format!("{}.name", path), name_text(&param.name)));
+                fields.push((format!("{}.type", path), type_text(&param.param_type)));
+                fields.push((format!("{}.range", path), range_text(param.range)));
+            }
+            fields.push(field("results", types_text(&func.results)));
+            for local in &func.locals {
+                let path = format!("locals[{}]", local.index);
+                fields.push((format!("{}.name", path), name_text(&local.name)));
+                fields.push((format!("{}.type", path), type_text(&local.var_type)));
+                fields.push((format!("{}.range", path), range_text(local.range)));
+            }
+            fields.push(field("range", range_text(func.range)));
+            (format!("functions[{}]", func.index), fields)
+        })
+        .collect()
+}
+
+fn global_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .globals
+        .iter()
+        .map(|global| {
+            let fields = vec![
+                field("name", name_text(&global.name)),
+                field("index", global.index),
+                field("type", type_text(&global.var_type)),
+                field("mutable", global.is_mutable),
+                field("range", range_text(global.range)),
+            ];
+            (format!("globals[{}]", global.index), fields)
+        })
+        .collect()
+}
+
+fn table_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .tables
+        .iter()
+        .map(|table| {
+            let fields = vec![
+                field("name", name_text(&table.name)),
+                field("index", table.index),
+                field("type", type_text(&table.ref_type)),
+                field("limits", limits_text(table.limits.0, table.limits.1)),
+                field("range", range_text(table.range)),
+            ];
+            (format!("tables[{}]", table.index), fields)
+        })
+        .collect()
+}
+
+fn memory_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .memories
+        .iter()
+        .map(|memory| {
+            let fields = vec![
+                field("name", name_text(&memory.name)),
+                field("index", memory.index),
+                field("limits", limits_text(memory.limits.0, memory.limits.1)),
+                field("memory64", memory.is_memory64),
+                field("shared", memory.shared),
+                field("range", range_text(memory.range)),
+            ];
+            (format!("memories[{}]", memory.index), fields)
+        })
+        .collect()
+}
+
+fn type_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .types
+        .iter()
+        .map(|type_def| {
+            let mut fields = vec![
+                field("name", name_text(&type_def.name)),
+                field("index", type_def.index),
+            ];
+            match &type_def.kind {
+                TypeKind::Func { params, results } => {
+                    fields.push(field("kind", "func"));
+                    fields.push(field("params", types_text(params)));
+                    fields.push(field("results", types_text(results)));
+                }
+                TypeKind::Struct {
+                    fields: struct_fields,
+                } => {
+                    fields.push(field("kind", "struct"));
+                    for (i, (name, ty, mutable)) in struct_fields.iter().enumerate() {
+                        let path = format!("fields[{}]", i);
+                        fields.push((format!("{}.name", path), name_text(name)));
+                        fields.push((format!("{}.type", path), type_text(ty)));
+                        fields.push((format!("{}.mutable", path), mutable.to_string()));
+                    }
+                }
+                TypeKind::Array {
+                    element_type,
+                    mutable,
+                } => {
+                    fields.push(field("kind", "array"));
+                    fields.push(field("element_type", type_text(element_type)));
+                    fields.push(field("mutable", mutable));
+                }
+            }
+            fields.push(field("supertype", option_text(type_def.supertype)));
+            fields.push(field("final", type_def.is_final));
+            fields.push(field("rec_group", option_text(type_def.rec_group_id)));
+            fields.push(field("range", range_text(type_def.range)));
+            (format!("types[{}]", type_def.index), fields)
+        })
+        .collect()
+}
+
+fn tag_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .tags
+        .iter()
+        .map(|tag| {
+            let fields = vec![
+                field("name", name_text(&tag.name)),
+                field("index", tag.index),
+                field("params", types_text(&tag.params)),
+                field("range", range_text(tag.range)),
+            ];
+            (format!("tags[{}]", tag.index), fields)
+        })
+        .collect()
+}
+
+/// Imports are keyed by the item they declare, since the parsers collect
+/// `(import ...)` fields and inline imports in different passes
+fn import_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .imports
+        .iter()
+        .map(|import| {
+            let fields = vec![
+                field("module", &import.module),
+                field("field", &import.field),
+                field("range", range_text(import.range)),
+            ];
+            (format!("imports[{} {}]", import.kind, import.index), fields)
+        })
+        .collect()
+}
+
+/// Exports are keyed by their name, which is unique within a module
+fn export_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .exports
+        .iter()
+        .map(|export| {
+            let fields = vec![
+                field("kind", export.kind),
+                field("index", export.index),
+                field("range", range_text(export.range)),
+            ];
+            (format!("exports[\"{}\"]", export.name), fields)
+        })
+        .collect()
+}
+
+fn start_items(symbols: &SymbolTable) -> Vec<Item> {
+    symbols
+        .start
+        .iter()
+        .map(|start| {
+            let fields = vec![
+                field("index", start.index),
+                field("range", range_text(start.range)),
+            ];
+            ("start".to_string(), fields)
+        })
+        .collect()
+}
+
+fn field(name: &str, value: impl ToString) -> (String, String) {
+    (name.to_string(), value.to_string())
+}
+
+fn name_text(name: &Option<String>) -> String {
+    name.clone().unwrap_or_else(|| "-".to_string())
+}
+
+fn option_text<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.to_string())
+}
+
+fn limits_text<T: ToString>(min: T, max: Option<T>) -> String {
+    match max {
+        Some(max) => format!("{} {}", min.to_string(), max.to_string()),
+        None => min.to_string(),
+    }
+}
+
+fn range_text(range: Option<Range>) -> String {
+    match range {
+        Some(range) => format!(
+            "{}:{}-{}:{}",
+            range.start.line, range.start.character, range.end.line, range.end.character
+        ),
+        None => "-".to_string(),
+    }
+}
+
+/// Render a type, with typed references by the index they resolve to.
+/// The `$name` a reference was written with doesn't matter for equality.
+fn type_text(value_type: &ValueType) -> String {
+    match value_type {
+        ValueType::Ref(TypeRef {
+            index: Some(index), ..
+        }) => format!("(ref {})", index),
+        ValueType::RefNull(TypeRef {
+            index: Some(index), ..
+        }) => format!("(ref null {})", index),
+        other => other.to_string(),
+    }
+}
+
+fn types_text(types: &[ValueType]) -> String {
+    let types: Vec<String> = types.iter().map(type_text).collect();
+    format!("[{}]", types.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_tables_have_no_diffs() {
+        let source = r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (table $t 2 10 externref)
+  (func $run (export "run") (param $x i32) (result i32)
+    local.get $x
+  )
+)"#;
+        let symbols = crate::wast_parser::parse_document(source).unwrap();
+        assert!(diff_symbol_tables(&symbols, &symbols).is_empty());
+    }
+
+    #[test]
+    fn test_field_differences_are_reported() {
+        let source = r#"(module
+  (memory $mem 1 2)
+  (func $f (param i32))
+)"#;
+        let left = crate::wast_parser::parse_document(source).unwrap();
+        let mut right = left.clone();
+        right.memories[0].limits = (1, None);
+        right.functions[0].parameters[0].param_type = ValueType::I64;
+
+        let diffs = diff_symbol_tables(&left, &right);
+        let paths: Vec<&str> = diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec!["functions[0].params[0].type", "memories[0].limits"]
+        );
+        assert_eq!(
+            diffs[1].to_string(),
+            "memories[0].limits: tree-sitter 1 2, wast 1"
+        );
+    }
+
+    #[test]
+    fn test_missing_items_are_reported_once() {
+        let source = r#"(module
+  (global $a i32 (i32.const 0))
+  (global $b i32 (i32.const 1))
+)"#;
+        let left = crate::wast_parser::parse_document(source).unwrap();
+        let mut right = left.clone();
+        right.globals.pop();
+
+        let diffs = diff_symbol_tables(&left, &right);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].path, "globals[1]");
+        assert_eq!(diffs[0].tree_sitter.as_deref(), Some("$b"));
+        assert_eq!(diffs[0].wast, None);
+    }
+}
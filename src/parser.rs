@@ -1,3 +1,4 @@
+use crate::component::{self, ComponentSymbols};
 use crate::core::types::{Position, Range};
 use crate::symbols::*;
 use crate::utils::{block_type_from_kind, BLOCK_KINDS_EXPR, BLOCK_KINDS_STATEMENT};
@@ -16,17 +17,32 @@ use crate::ts_facade::{Node, Tree};
 #[cfg(test)]
 mod tests;
 
+// Incremental updates after edits (native only - needs Tree::changed_ranges)
+#[cfg(feature = "native")]
+mod incremental;
+#[cfg(feature = "native")]
+pub use incremental::update_symbols;
+
 /// Parse a WAT document and extract symbols (PUBLIC API - unchanged)
 #[cfg(feature = "native")]
 pub fn parse_document(text: &str) -> Result<SymbolTable, String> {
-    let mut parser = create_parser();
-    let tree = parser
-        .parse(text, None)
-        .ok_or_else(|| "Failed to parse document".to_string())?;
+    let tree = parse_tree(text).ok_or_else(|| "Failed to parse document".to_string())?;
 
     extract_symbols(&tree, text)
 }
 
+/// Parse a WAT document with tree-sitter. The grammar has no components,
+/// so a component's tree only covers its core modules; see
+/// [`component::core_module_text`].
+#[cfg(feature = "native")]
+pub fn parse_tree(text: &str) -> Option<Tree> {
+    let mut parser = create_parser();
+    match component::core_module_text(text) {
+        Some(modules) => parser.parse(&modules, None),
+        None => parser.parse(text, None),
+    }
+}
+
 /// Parse a WAT document from a pre-parsed tree (works for both native and WASM)
 pub fn parse_document_from_tree(tree: &Tree, text: &str) -> Result<SymbolTable, String> {
     extract_symbols(tree, text)
@@ -34,28 +50,168 @@ pub fn parse_document_from_tree(tree: &Tree, text: &str) -> Result<SymbolTable,
 
 /// Extract all symbols from the parse tree
 fn extract_symbols(tree: &Tree, source: &str) -> Result<SymbolTable, String> {
-    let mut symbol_table = SymbolTable::new();
     let root = tree.root_node();
+    // Each core module of a component gets its own table, as in a script.
+    // The keyword check spares plain modules a second read of the source.
+    if component::is_component(source) {
+        if let Some(component) = ComponentSymbols::parse(source) {
+            let mut symbol_table = extract_script(&root, source);
+            symbol_table.component = Some(component);
+            return Ok(symbol_table);
+        }
+    }
+    if is_script(&root) {
+        return Ok(extract_script(&root, source));
+    }
+    Ok(extract_module_symbols(&root, source))
+}
+
+/// Extract the items of one module; `root` is the document root or a `module` node
+fn extract_module_symbols(root: &Node, source: &str) -> SymbolTable {
+    let mut symbol_table = SymbolTable::new();
 
     // Extract imports FIRST - imports get indices before regular declarations
     // Returns counters for each kind of import
-    let import_counts = extract_imports(&root, source, &mut symbol_table);
+    let import_counts = extract_imports(root, source, &mut symbol_table);
 
     // Extract in order: globals, types, tables, memories, functions
     // (Order matters for index assignment)
     // Pass import counts to offset the indices
-    extract_globals_with_offset(&root, source, &mut symbol_table, import_counts.globals);
-    extract_types(&root, source, &mut symbol_table);
-    extract_tables_with_offset(&root, source, &mut symbol_table, import_counts.tables);
-    extract_memories_with_offset(&root, source, &mut symbol_table, import_counts.memories);
-    extract_tags_with_offset(&root, source, &mut symbol_table, import_counts.tags);
-    extract_functions_with_offset(&root, source, &mut symbol_table, import_counts.functions);
+    extract_globals_with_offset(root, source, &mut symbol_table, import_counts.globals);
+    extract_types(root, source, &mut symbol_table);
+    extract_tables_with_offset(root, source, &mut symbol_table, import_counts.tables);
+    extract_memories_with_offset(root, source, &mut symbol_table, import_counts.memories);
+    extract_tags_with_offset(root, source, &mut symbol_table, import_counts.tags);
+    extract_functions_with_offset(root, source, &mut symbol_table, import_counts.functions);
 
     // Extract data and elem segments
-    extract_data_segments(&root, source, &mut symbol_table);
-    extract_elem_segments(&root, source, &mut symbol_table);
+    extract_data_segments(root, source, &mut symbol_table);
+    extract_elem_segments(root, source, &mut symbol_table);
+
+    // Exports, inline imports and start refer to items by name, so extract them last
+    extract_linkage(root, source, &mut symbol_table);
+    symbol_table.resolve_type_refs();
 
-    Ok(symbol_table)
+    symbol_table
+}
+
+/// Whether the document is a `.wast` script: several modules or any command
+fn is_script(root: &Node) -> bool {
+    let mut cursor = root.walk();
+    let mut modules = 0;
+    for child in root.children(&mut cursor) {
+        if child.kind() == "module" {
+            modules += 1;
+        } else if child.kind().starts_with("script_") {
+            return true;
+        }
+    }
+    modules > 1
+}
+
+/// Extract each module of a script into its own table, and the commands
+fn extract_script(root: &Node, source: &str) -> SymbolTable {
+    let mut symbol_table = SymbolTable::new();
+    let mut cursor = root.walk();
+
+    for child in root.children(&mut cursor) {
+        if child.kind() == "module" || child.kind() == "script_module" {
+            let module = extract_script_module(&child, source, false);
+            symbol_table.modules.push(module);
+        } else if let Some(command) = extract_script_command(&child, source, &symbol_table) {
+            symbol_table.commands.push(command);
+            // The module of assert_invalid, assert_trap and the like
+            let mut cursor = child.walk();
+            let asserted = child
+                .children(&mut cursor)
+                .find(|c| c.kind() == "module" || c.kind() == "script_module");
+            if let Some(node) = asserted {
+                let module = extract_script_module(&node, source, true);
+                symbol_table.modules.push(module);
+            }
+        }
+    }
+
+    symbol_table
+}
+
+/// Extract a `module` or quoted `script_module` node of a script
+fn extract_script_module(node: &Node, source: &str, in_assertion: bool) -> ScriptModule {
+    let quoted = node.kind() == "script_module";
+    let symbols = if quoted {
+        SymbolTable::new()
+    } else {
+        extract_module_symbols(node, source)
+    };
+    ScriptModule {
+        name: find_identifier_node(node).map(|id| node_text(&id, source)),
+        range: node_to_range(node),
+        symbols,
+        quoted,
+        in_assertion,
+    }
+}
+
+/// Extract a register, action or assertion command
+fn extract_script_command(
+    node: &Node,
+    source: &str,
+    symbol_table: &SymbolTable,
+) -> Option<ScriptCommand> {
+    // The keyword follows the opening paren, except for actions, which wrap
+    // an invoke or get
+    let action = if node.kind() == "script_action" {
+        node.child(0)
+    } else {
+        None
+    };
+    let form = action.as_ref().unwrap_or(node);
+    let kind = ScriptCommandKind::from_keyword(&node_text(&form.child(1)?, source))?;
+
+    // Assertions about actions name their module and export in the action
+    let mut cursor = form.walk();
+    let asserted_action = form
+        .children(&mut cursor)
+        .find(|child| child.kind() == "script_action")
+        .and_then(|child| child.child(0));
+    let target = asserted_action.as_ref().unwrap_or(form);
+
+    let mut module_name = None;
+    let mut name = None;
+    let mut name_range = None;
+    if target.kind() != "script_assertion" {
+        let mut cursor = target.walk();
+        for child in target.children(&mut cursor) {
+            if child.kind() == "identifier" {
+                module_name = Some(node_text(&child, source));
+            } else if child.kind() == "name" {
+                name = Some(unquote(&node_text(&child, source)));
+                name_range = Some(node_to_range(&child));
+            }
+        }
+    }
+
+    // Commands about a module they define themselves use no earlier module
+    let module = if target.kind() == "script_assertion" {
+        None
+    } else {
+        match &module_name {
+            Some(id) => symbol_table
+                .modules
+                .iter()
+                .rposition(|m| !m.in_assertion && m.name.as_ref() == Some(id)),
+            None => symbol_table.modules.iter().rposition(|m| !m.in_assertion),
+        }
+    };
+
+    Some(ScriptCommand {
+        kind,
+        range: node_to_range(node),
+        module,
+        module_name,
+        name,
+        name_range,
+    })
 }
 
 /// Counters for imported items
@@ -111,6 +267,20 @@ fn extract_single_import(
     symbol_table: &mut SymbolTable,
     counts: &mut ImportCounts,
 ) {
+    let (module, field) = import_names(import_node, source);
+    let record = |kind: ExternKind, index: usize, symbol_table: &mut SymbolTable| {
+        if let (Some(module), Some(field)) = (&module, &field) {
+            symbol_table.imports.push(Import {
+                module: module.clone(),
+                field: field.clone(),
+                kind,
+                index,
+                line: import_node.range().start_point.row as u32,
+                range: Some(node_to_range(import_node)),
+            });
+        }
+    };
+
     let mut cursor = import_node.walk();
 
     // Find the import_desc wrapper which contains the actual import descriptor
@@ -128,6 +298,7 @@ fn extract_single_import(
                         if let Some(func) =
                             extract_imported_function(&desc_child, source, counts.functions)
                         {
+                            record(ExternKind::Func, counts.functions, symbol_table);
                             symbol_table.add_function(func);
                             counts.functions += 1;
                         }
@@ -137,6 +308,7 @@ fn extract_single_import(
                         if let Some(global) =
                             extract_imported_global(&desc_child, source, counts.globals)
                         {
+                            record(ExternKind::Global, counts.globals, symbol_table);
                             symbol_table.add_global(global);
                             counts.globals += 1;
                         }
@@ -146,6 +318,7 @@ fn extract_single_import(
                         if let Some(table) =
                             extract_imported_table(&desc_child, source, counts.tables)
                         {
+                            record(ExternKind::Table, counts.tables, symbol_table);
                             symbol_table.add_table(table);
                             counts.tables += 1;
                         }
@@ -155,6 +328,7 @@ fn extract_single_import(
                         if let Some(memory) =
                             extract_imported_memory(&desc_child, source, counts.memories)
                         {
+                            record(ExternKind::Memory, counts.memories, symbol_table);
                             symbol_table.add_memory(memory);
                             counts.memories += 1;
                         }
@@ -162,6 +336,7 @@ fn extract_single_import(
                     "import_desc_tag_type" => {
                         // Imported tag: (tag $name? (param ...)?)
                         if let Some(tag) = extract_imported_tag(&desc_child, source, counts.tags) {
+                            record(ExternKind::Tag, counts.tags, symbol_table);
                             symbol_table.add_tag(tag);
                             counts.tags += 1;
                         }
@@ -236,6 +411,7 @@ fn extract_imported_function(desc_node: &Node, source: &str, index: usize) -> Op
         index,
         parameters,
         results,
+        type_use: extract_type_use(desc_node, source),
         locals: Vec::new(), // Imported functions have no locals
         blocks: Vec::new(), // Imported functions have no blocks
         line: desc_node.range().start_point.row as u32,
@@ -287,6 +463,7 @@ fn extract_imported_global(desc_node: &Node, source: &str, index: usize) -> Opti
         is_mutable,
         initial_value: None,
         line: desc_node.range().start_point.row as u32,
+        start_byte: desc_node.start_byte(),
         range: name_range,
     })
 }
@@ -340,6 +517,7 @@ fn extract_imported_table(desc_node: &Node, source: &str, index: usize) -> Optio
         ref_type,
         limits: (min_limit, max_limit),
         line: desc_node.range().start_point.row as u32,
+        start_byte: desc_node.start_byte(),
         range: name_range,
     })
 }
@@ -366,7 +544,7 @@ fn extract_imported_memory(desc_node: &Node, source: &str, index: usize) -> Opti
             let mut type_cursor = child.walk();
             for type_child in child.children(&mut type_cursor) {
                 // Check for i64 keyword indicating memory64
-                if type_child.kind() == "value_type" {
+                if type_child.kind() == "address_type" || type_child.kind() == "value_type" {
                     let type_text = node_text(&type_child, source);
                     if type_text == "i64" {
                         is_memory64 = true;
@@ -405,6 +583,7 @@ fn extract_imported_memory(desc_node: &Node, source: &str, index: usize) -> Opti
         is_memory64,
         shared,
         line: desc_node.range().start_point.row as u32,
+        start_byte: desc_node.start_byte(),
         range: name_range,
     })
 }
@@ -450,6 +629,7 @@ fn extract_imported_tag(desc_node: &Node, source: &str, index: usize) -> Option<
         index,
         params,
         line: desc_node.range().start_point.row as u32,
+        start_byte: desc_node.start_byte(),
         range: name_range,
     })
 }
@@ -599,6 +779,7 @@ fn extract_function(func_node: &Node, source: &str, index: usize) -> Option<Func
         index,
         parameters,
         results,
+        type_use: extract_type_use(func_node, source),
         locals,
         blocks,
         line: range.start_point.row as u32,
@@ -609,6 +790,24 @@ fn extract_function(func_node: &Node, source: &str, index: usize) -> Option<Func
     })
 }
 
+/// The `(type $t)` a function or imported function is declared with.
+/// Named targets are resolved by SymbolTable::resolve_type_refs
+fn extract_type_use(node: &Node, source: &str) -> Option<TypeRef> {
+    let mut cursor = node.walk();
+    let type_use = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "type_use")?;
+    let mut cursor = type_use.walk();
+    let index_node = type_use
+        .children(&mut cursor)
+        .find(|child| child.kind() == "index")?;
+    let text = node_text(&index_node, source);
+    Some(match text.parse::<u32>() {
+        Ok(index) => TypeRef::numeric(index),
+        Err(_) => TypeRef::named(text),
+    })
+}
+
 /// Extract parameters from a function node
 fn extract_parameters(func_node: &Node, source: &str) -> Vec<Parameter> {
     let mut parameters = Vec::new();
@@ -875,6 +1074,7 @@ fn extract_global(global_node: &Node, source: &str, index: usize) -> Option<Glob
         is_mutable,
         initial_value: None,
         line: global_node.range().start_point.row as u32,
+        start_byte: global_node.start_byte(),
         range: name_range,
     })
 }
@@ -892,7 +1092,9 @@ fn extract_types(root: &Node, source: &str, symbol_table: &mut SymbolTable) {
                     let mut field_cursor = module_child.walk();
                     for field_child in module_child.children(&mut field_cursor) {
                         if field_child.kind() == "module_field_type" {
-                            if let Some(type_def) = extract_type(&field_child, source, type_index) {
+                            if let Some(type_def) =
+                                extract_type(&field_child, source, type_index, symbol_table)
+                            {
                                 symbol_table.add_type(type_def);
                                 type_index += 1;
                             }
@@ -908,7 +1110,9 @@ fn extract_types(root: &Node, source: &str, symbol_table: &mut SymbolTable) {
             let mut field_cursor = child.walk();
             for field_child in child.children(&mut field_cursor) {
                 if field_child.kind() == "module_field_type" {
-                    if let Some(type_def) = extract_type(&field_child, source, type_index) {
+                    if let Some(type_def) =
+                        extract_type(&field_child, source, type_index, symbol_table)
+                    {
                         symbol_table.add_type(type_def);
                         type_index += 1;
                     }
@@ -934,6 +1138,9 @@ fn extract_rec_types(
     let mut cursor = rec_node.walk();
     let children_vec: Vec<_> = rec_node.children(&mut cursor).collect();
 
+    // Types in the same rec group share the index of the group's first type
+    let rec_group_id = type_index;
+
     let mut i = 0;
     while i < children_vec.len() {
         let child = &children_vec[i];
@@ -965,9 +1172,15 @@ fn extract_rec_types(
                     || type_node.kind() == "type_field"
                 {
                     // Extract the type
-                    if let Some(type_def) = extract_type_from_single_node(
-                        type_node, source, type_index, name, name_range,
+                    if let Some(mut type_def) = extract_type_from_single_node(
+                        type_node,
+                        source,
+                        type_index,
+                        name,
+                        name_range,
+                        symbol_table,
                     ) {
+                        type_def.rec_group_id = Some(rec_group_id);
                         symbol_table.add_type(type_def);
                         type_index += 1;
                     }
@@ -997,47 +1210,27 @@ fn extract_type_from_single_node(
     index: usize,
     name: Option<String>,
     name_range: Option<Range>,
+    symbol_table: &SymbolTable,
 ) -> Option<TypeDef> {
     let mut kind = TypeKind::Func {
         params: Vec::new(),
         results: Vec::new(),
     };
+    let mut supertype = None;
+    let mut is_final = true; // Default to final
 
     let type_kind = type_node.kind();
     #[cfg(all(feature = "wasm", not(feature = "native")))]
     let type_kind = type_kind.as_str();
     match type_kind {
         "struct_type" => {
-            // Extract struct fields
-            let mut fields = Vec::new();
-            let mut cursor = type_node.walk();
-            for child in type_node.children(&mut cursor) {
-                if child.kind() == "field_type" {
-                    let (field_name, field_type, mutable) = extract_field_type(&child, source);
-                    fields.push((field_name, field_type, mutable));
-                }
-            }
-            kind = TypeKind::Struct { fields };
+            kind = extract_struct_kind(type_node, source);
         }
         "array_type" => {
-            // Extract array field
-            let mut element_type = ValueType::Unknown;
-            let mut mutable = false;
-            let mut cursor = type_node.walk();
-            for child in type_node.children(&mut cursor) {
-                if child.kind() == "field_type" {
-                    let (_, field_type, mut_flag) = extract_field_type(&child, source);
-                    element_type = field_type;
-                    mutable = mut_flag;
-                }
-            }
-            kind = TypeKind::Array {
-                element_type,
-                mutable,
-            };
+            kind = extract_array_kind(type_node, source);
         }
         "type_field" => {
-            // This is a func type inside a type_field wrapper
+            // A func, struct, array or sub type inside a type_field wrapper
             let mut parameters = Vec::new();
             let mut results = Vec::new();
             let mut cursor = type_node.walk();
@@ -1048,6 +1241,16 @@ fn extract_type_from_single_node(
                         parameters.push(param.param_type);
                     }
                     results.extend(extract_results(&child, source));
+                } else if child.kind() == "struct_type" {
+                    kind = extract_struct_kind(&child, source);
+                } else if child.kind() == "array_type" {
+                    kind = extract_array_kind(&child, source);
+                } else if child.kind() == "sub_type" {
+                    let (sub_kind, sub_supertype, sub_final) =
+                        extract_sub_type(&child, source, symbol_table);
+                    kind = sub_kind;
+                    supertype = sub_supertype;
+                    is_final = sub_final;
                 }
             }
             if !parameters.is_empty() || !results.is_empty() {
@@ -1064,8 +1267,8 @@ fn extract_type_from_single_node(
         name,
         index,
         kind,
-        supertype: None,
-        is_final: true, // Default to final
+        supertype,
+        is_final,
         rec_group_id: None,
         line: type_node.range().start_point.row as u32,
         range: name_range,
@@ -1084,6 +1287,8 @@ fn extract_field_type(field_node: &Node, source: &str) -> (Option<String>, Value
             field_name = Some(node_text(&child, source));
         } else if child.kind() == "value_type" {
             field_type = extract_value_type(&child, source);
+        } else if child.kind() == "storage_type" {
+            field_type = extract_storage_type(&child, source);
         }
     }
 
@@ -1095,8 +1300,31 @@ fn extract_field_type(field_node: &Node, source: &str) -> (Option<String>, Value
     (field_name, field_type, mutable)
 }
 
+/// Extract the value type from a storage_type node, unwrapping `(mut ...)`
+fn extract_storage_type(storage_node: &Node, source: &str) -> ValueType {
+    let mut cursor = storage_node.walk();
+    for child in storage_node.children(&mut cursor) {
+        if child.kind() == "value_type" {
+            return extract_value_type(&child, source);
+        } else if child.kind() == "mut_storage_type" {
+            let mut mut_cursor = child.walk();
+            for c in child.children(&mut mut_cursor) {
+                if c.kind() == "value_type" {
+                    return extract_value_type(&c, source);
+                }
+            }
+        }
+    }
+    ValueType::Unknown
+}
+
 /// Extract a single type definition from a type node
-fn extract_type(type_node: &Node, source: &str, index: usize) -> Option<TypeDef> {
+fn extract_type(
+    type_node: &Node,
+    source: &str,
+    index: usize,
+    symbol_table: &SymbolTable,
+) -> Option<TypeDef> {
     let (name, name_range) = if let Some(id_node) = find_identifier_node(type_node) {
         (
             Some(node_text(&id_node, source)),
@@ -1119,60 +1347,11 @@ fn extract_type(type_node: &Node, source: &str, index: usize) -> Option<TypeDef>
     for child in type_node.children(&mut cursor) {
         // Handle sub_type: (sub final? supertype_index? def_type)
         if child.kind() == "sub_type" {
-            let mut sub_cursor = child.walk();
-            for sub_child in child.children(&mut sub_cursor) {
-                let sub_kind = sub_child.kind();
-                #[cfg(all(feature = "wasm", not(feature = "native")))]
-                let sub_kind = sub_kind.as_str();
-
-                match sub_kind {
-                    "final" => {
-                        is_final = true;
-                    }
-                    "index" => {
-                        // Parse supertype index
-                        let idx_text = node_text(&sub_child, source);
-                        // Remove $ prefix if present and parse as number
-                        let idx_str = idx_text.trim_start_matches('$');
-                        if let Ok(idx) = idx_str.parse::<u32>() {
-                            supertype = Some(idx);
-                        }
-                    }
-                    "def_type" => {
-                        // Process the inner type definition
-                        let mut def_cursor = sub_child.walk();
-                        for def_child in sub_child.children(&mut def_cursor) {
-                            if def_child.kind() == "struct_type" {
-                                kind = extract_struct_kind(&def_child, source);
-                            } else if def_child.kind() == "array_type" {
-                                kind = extract_array_kind(&def_child, source);
-                            } else if def_child.kind() == "func_type" {
-                                let params = extract_parameters(&def_child, source);
-                                for param in params {
-                                    parameters.push(param.param_type);
-                                }
-                                results.extend(extract_results(&def_child, source));
-                            }
-                        }
-                    }
-                    // If no "final" keyword, type is not final (can be subtyped)
-                    _ => {
-                        // Check if this is a type definition directly
-                        if sub_child.kind() == "struct_type" {
-                            kind = extract_struct_kind(&sub_child, source);
-                            is_final = false; // sub without final means not final
-                        } else if sub_child.kind() == "array_type" {
-                            kind = extract_array_kind(&sub_child, source);
-                            is_final = false;
-                        }
-                    }
-                }
-            }
-            // If we saw "sub" but no "final", the type is not final
-            let text = node_text(&child, source);
-            if !text.contains("final") {
-                is_final = false;
-            }
+            let (sub_kind, sub_supertype, sub_final) =
+                extract_sub_type(&child, source, symbol_table);
+            kind = sub_kind;
+            supertype = sub_supertype;
+            is_final = sub_final;
             continue;
         }
         // Check if this child is directly a struct_type or array_type
@@ -1303,6 +1482,12 @@ fn extract_type(type_node: &Node, source: &str, index: usize) -> Option<TypeDef>
                         element_type,
                         mutable,
                     };
+                } else if field_child.kind() == "sub_type" {
+                    let (sub_kind, sub_supertype, sub_final) =
+                        extract_sub_type(&field_child, source, symbol_table);
+                    kind = sub_kind;
+                    supertype = sub_supertype;
+                    is_final = sub_final;
                 }
             }
         }
@@ -1328,6 +1513,68 @@ fn extract_type(type_node: &Node, source: &str, index: usize) -> Option<TypeDef>
     })
 }
 
+/// Extract a sub_type node: (sub final? supertype? def_type)
+/// Returns (TypeKind, supertype, is_final). Named supertypes are resolved against
+/// the types extracted so far, since a supertype must be declared before its subtypes.
+fn extract_sub_type(
+    sub_node: &Node,
+    source: &str,
+    symbol_table: &SymbolTable,
+) -> (TypeKind, Option<u32>, bool) {
+    let mut kind = TypeKind::Func {
+        params: Vec::new(),
+        results: Vec::new(),
+    };
+    let mut parameters = Vec::new();
+    let mut results = Vec::new();
+    let mut supertype = None;
+    // "sub" without "final" means the type can be subtyped further
+    let mut is_final = false;
+
+    let mut cursor = sub_node.walk();
+    for sub_child in sub_node.children(&mut cursor) {
+        let sub_kind = sub_child.kind();
+        #[cfg(all(feature = "wasm", not(feature = "native")))]
+        let sub_kind = sub_kind.as_str();
+
+        match sub_kind {
+            "final" => is_final = true,
+            "index" => {
+                let idx_text = node_text(&sub_child, source);
+                supertype = symbol_table
+                    .resolve_type_index(&idx_text)
+                    .map(|index| index as u32);
+            }
+            "def_type" => {
+                let mut def_cursor = sub_child.walk();
+                for def_child in sub_child.children(&mut def_cursor) {
+                    if def_child.kind() == "struct_type" {
+                        kind = extract_struct_kind(&def_child, source);
+                    } else if def_child.kind() == "array_type" {
+                        kind = extract_array_kind(&def_child, source);
+                    } else if def_child.kind() == "func_type" {
+                        let params = extract_parameters(&def_child, source);
+                        for param in params {
+                            parameters.push(param.param_type);
+                        }
+                        results.extend(extract_results(&def_child, source));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !parameters.is_empty() || !results.is_empty() {
+        kind = TypeKind::Func {
+            params: parameters,
+            results,
+        };
+    }
+
+    (kind, supertype, is_final)
+}
+
 /// Helper to extract struct kind from a struct_type node
 fn extract_struct_kind(struct_node: &Node, source: &str) -> TypeKind {
     let mut fields = Vec::new();
@@ -1349,7 +1596,12 @@ fn extract_array_kind(array_node: &Node, source: &str) -> TypeKind {
 
     let mut array_cursor = array_node.walk();
     for array_child in array_node.children(&mut array_cursor) {
-        if array_child.kind() == "field_type" {
+        if array_child.kind() == "storage_type" {
+            element_type = extract_storage_type(&array_child, source);
+            mutable = array_child
+                .child(0)
+                .is_some_and(|c| c.kind() == "mut_storage_type");
+        } else if array_child.kind() == "field_type" {
             let mut fc = array_child.walk();
             for c in array_child.children(&mut fc) {
                 if c.kind() == "value_type" {
@@ -1488,6 +1740,7 @@ fn extract_table(table_node: &Node, source: &str, index: usize) -> Option<Table>
         ref_type,
         limits: (min_limit, max_limit),
         line: table_node.range().start_point.row as u32,
+        start_byte: table_node.start_byte(),
         range: name_range,
     })
 }
@@ -1597,7 +1850,8 @@ fn extract_memory(memory_node: &Node, source: &str, index: usize) -> Option<Memo
                     let mut type_cursor = fields_child.walk();
                     for type_child in fields_child.children(&mut type_cursor) {
                         // Check for i64 keyword in memory_type
-                        if type_child.kind() == "value_type" {
+                        if type_child.kind() == "address_type" || type_child.kind() == "value_type"
+                        {
                             let type_text = node_text(&type_child, source);
                             if type_text == "i64" {
                                 is_memory64 = true;
@@ -1619,7 +1873,7 @@ fn extract_memory(memory_node: &Node, source: &str, index: usize) -> Option<Memo
             let mut type_cursor = child.walk();
             for type_child in child.children(&mut type_cursor) {
                 // Check for i64 keyword in memory_type
-                if type_child.kind() == "value_type" {
+                if type_child.kind() == "address_type" || type_child.kind() == "value_type" {
                     let type_text = node_text(&type_child, source);
                     if type_text == "i64" {
                         is_memory64 = true;
@@ -1639,6 +1893,7 @@ fn extract_memory(memory_node: &Node, source: &str, index: usize) -> Option<Memo
         is_memory64,
         shared,
         line: memory_node.range().start_point.row as u32,
+        start_byte: memory_node.start_byte(),
         range: name_range,
     })
 }
@@ -1721,10 +1976,203 @@ fn extract_tag(tag_node: &Node, source: &str, index: usize) -> Option<Tag> {
         index,
         params,
         line: tag_node.range().start_point.row as u32,
+        start_byte: tag_node.start_byte(),
         range: name_range,
     })
 }
 
+/// Read the `"module" "field"` name pair of an import, without quotes
+fn import_names(import_node: &Node, source: &str) -> (Option<String>, Option<String>) {
+    let mut cursor = import_node.walk();
+    let mut names = import_node
+        .children(&mut cursor)
+        .filter(|c| c.kind() == "name")
+        .map(|c| unquote(&node_text(&c, source)));
+    (names.next(), names.next())
+}
+
+/// Strip the surrounding quotes of a string literal
+fn unquote(text: &str) -> String {
+    text.trim_matches('"').to_string()
+}
+
+/// Extract exports, inline imports and the start function
+fn extract_linkage(root: &Node, source: &str, symbol_table: &mut SymbolTable) {
+    let mut cursor = root.walk();
+
+    for child in root.children(&mut cursor) {
+        if child.kind() == "module" {
+            let mut module_cursor = child.walk();
+            for module_child in child.children(&mut module_cursor) {
+                if module_child.kind() == "module_field" {
+                    let mut field_cursor = module_child.walk();
+                    for field_child in module_child.children(&mut field_cursor) {
+                        extract_field_linkage(&field_child, source, symbol_table);
+                    }
+                }
+            }
+        } else if child.kind() == "module_field" {
+            let mut field_cursor = child.walk();
+            for field_child in child.children(&mut field_cursor) {
+                extract_field_linkage(&field_child, source, symbol_table);
+            }
+        }
+    }
+}
+
+/// Extract the exports, inline import or start declaration of a single module field
+fn extract_field_linkage(field_node: &Node, source: &str, symbol_table: &mut SymbolTable) {
+    let kind = field_node.kind();
+    #[cfg(all(feature = "wasm", not(feature = "native")))]
+    let kind = kind.as_str();
+    let item_kind = match kind {
+        "module_field_export" => {
+            extract_export(field_node, source, symbol_table);
+            return;
+        }
+        "module_field_start" => {
+            let mut cursor = field_node.walk();
+            for child in field_node.children(&mut cursor) {
+                if child.kind() == "index" {
+                    if let Some(index) =
+                        symbol_table.resolve_index(ExternKind::Func, &node_text(&child, source))
+                    {
+                        symbol_table.start = Some(StartFunction {
+                            index,
+                            line: field_node.range().start_point.row as u32,
+                            range: Some(node_to_range(field_node)),
+                        });
+                    }
+                }
+            }
+            return;
+        }
+        "module_field_func" => ExternKind::Func,
+        "module_field_global" => ExternKind::Global,
+        "module_field_table" => ExternKind::Table,
+        "module_field_memory" => ExternKind::Memory,
+        "module_field_tag" => ExternKind::Tag,
+        _ => return,
+    };
+
+    // Inline (export "name") and (import "module" "field") abbreviations
+    let Some(index) = declared_item_index(field_node, item_kind, symbol_table) else {
+        return;
+    };
+    let line = field_node.range().start_point.row as u32;
+
+    let mut cursor = field_node.walk();
+    for child in field_node.children(&mut cursor) {
+        if child.kind() == "export" {
+            let mut name_cursor = child.walk();
+            for name_node in child.children(&mut name_cursor) {
+                if name_node.kind() == "name" {
+                    symbol_table.exports.push(Export {
+                        name: unquote(&node_text(&name_node, source)),
+                        kind: item_kind,
+                        index,
+                        line,
+                        range: Some(node_to_range(&child)),
+                    });
+                }
+            }
+        } else if child.kind() == "import" {
+            if let (Some(module), Some(field)) = import_names(&child, source) {
+                symbol_table.imports.push(Import {
+                    module,
+                    field,
+                    kind: item_kind,
+                    index,
+                    line,
+                    range: Some(node_to_range(&child)),
+                });
+            }
+        }
+    }
+}
+
+/// Extract an `(export "name" (func $f))` field
+fn extract_export(export_node: &Node, source: &str, symbol_table: &mut SymbolTable) {
+    let mut name = None;
+    let mut target = None;
+
+    let mut cursor = export_node.walk();
+    for child in export_node.children(&mut cursor) {
+        if child.kind() == "name" {
+            name = Some(unquote(&node_text(&child, source)));
+        } else if child.kind() == "export_desc" {
+            let mut desc_cursor = child.walk();
+            for desc in child.children(&mut desc_cursor) {
+                let desc_kind = desc.kind();
+                #[cfg(all(feature = "wasm", not(feature = "native")))]
+                let desc_kind = desc_kind.as_str();
+                let kind = match desc_kind {
+                    "export_desc_func" => ExternKind::Func,
+                    "export_desc_global" => ExternKind::Global,
+                    "export_desc_table" => ExternKind::Table,
+                    "export_desc_memory" => ExternKind::Memory,
+                    "export_desc_tag" => ExternKind::Tag,
+                    _ => continue,
+                };
+                let mut index_cursor = desc.walk();
+                for index_node in desc.children(&mut index_cursor) {
+                    if index_node.kind() == "index" {
+                        target = symbol_table
+                            .resolve_index(kind, &node_text(&index_node, source))
+                            .map(|index| (kind, index));
+                    }
+                }
+            }
+        }
+    }
+
+    if let (Some(name), Some((kind, index))) = (name, target) {
+        symbol_table.exports.push(Export {
+            name,
+            kind,
+            index,
+            line: export_node.range().start_point.row as u32,
+            range: Some(node_to_range(export_node)),
+        });
+    }
+}
+
+/// Find the index the symbol table assigned to the item declared by a module field
+fn declared_item_index(
+    field_node: &Node,
+    kind: ExternKind,
+    symbol_table: &SymbolTable,
+) -> Option<usize> {
+    let start_byte = field_node.start_byte();
+    match kind {
+        ExternKind::Func => symbol_table
+            .functions
+            .iter()
+            .find(|f| f.start_byte == start_byte)
+            .map(|f| f.index),
+        ExternKind::Global => symbol_table
+            .globals
+            .iter()
+            .find(|g| g.start_byte == start_byte)
+            .map(|g| g.index),
+        ExternKind::Table => symbol_table
+            .tables
+            .iter()
+            .find(|t| t.start_byte == start_byte)
+            .map(|t| t.index),
+        ExternKind::Memory => symbol_table
+            .memories
+            .iter()
+            .find(|m| m.start_byte == start_byte)
+            .map(|m| m.index),
+        ExternKind::Tag => symbol_table
+            .tags
+            .iter()
+            .find(|t| t.start_byte == start_byte)
+            .map(|t| t.index),
+    }
+}
+
 /// Helper: Extract text from a node
 fn node_text(node: &Node, source: &str) -> String {
     source[node.byte_range()].to_string()
@@ -1817,56 +2265,34 @@ fn extract_ref_type(ref_type_node: &Node, source: &str) -> ValueType {
         match child_kind {
             "ref_type_funcref" => return ValueType::Funcref,
             "ref_type_externref" => return ValueType::Externref,
-            "ref_type_concrete" => {
-                // (ref null? $index)
-                // Find index child
+            "ref_type_concrete" | "ref_type_ref" => {
+                // (ref null? $index) or (ref null? kind)
                 let mut concrete_cursor = child.walk();
-                let mut index_val = None;
+                let mut target = None;
                 let mut nullable = false;
 
                 for c in child.children(&mut concrete_cursor) {
                     if c.kind() == "index" {
-                        // Parse index (could be numeric or identifier)
-                        // For now we just store 0 or some indicator, or we need to resolve it?
-                        // ValueType::Ref(u32) stores a numeric index.
-                        // But if it's an identifier, we can't resolve it yet without symbol table lookup.
-                        // However, Symbols extractor runs in passes. Globals using Ref types might refer to types not yet seen?
-                        // No, Types are extracted before globals.
-                        // But we don't have access to the SymbolTable here easily.
-                        // We will rely on later resolution or just store 0 for now if identifier.
-                        // Improving this requires major refactor to pass SymbolTable.
-                        // For now, let's just return Ref(0) if we can't parse, or Ref(index).
+                        // Named targets are resolved by SymbolTable::resolve_type_refs
+                        // once every type has been extracted
                         let idx_text = node_text(&c, source);
-                        if let Ok(idx) = idx_text.parse::<u32>() {
-                            index_val = Some(idx);
-                        } else {
-                            // It's a named index ($type).
-                            // We arguably should store the name in ValueType, but ValueType::Ref takes u32.
-                            // Let's stick to parsing what we can.
-                        }
-                    } else if c.kind() == "null" {
-                        // "null" keyword
-                        nullable = true;
-                    } else if node_text(&c, source) == "null" {
+                        target = Some(match idx_text.parse::<u32>() {
+                            Ok(idx) => TypeRef::numeric(idx),
+                            Err(_) => TypeRef::named(idx_text),
+                        });
+                    } else if c.kind() == "null" || node_text(&c, source) == "null" {
                         nullable = true;
                     }
                 }
 
-                if let Some(idx) = index_val {
+                if let Some(target) = target {
                     return if nullable {
-                        ValueType::RefNull(idx)
+                        ValueType::RefNull(target)
                     } else {
-                        ValueType::Ref(idx)
+                        ValueType::Ref(target)
                     };
                 }
-                // Fallback for named types: map to generic StructRef or similar?
-                // Or maybe we treat Ref(0) as "some typed ref".
-                return ValueType::Structref; // Safe fallback?
-            }
-            "ref_type_ref" => {
-                // (ref null? kind) or (ref null? $type)
-                // Similar logic
-                return ValueType::Structref; // Fallback
+                return ValueType::Structref;
             }
             _ => {
                 let text = node_text(&child, source);
@@ -2007,43 +2433,43 @@ fn extract_elem_segments(root: &Node, source: &str, symbol_table: &mut SymbolTab
 
 /// Extract a single elem segment
 fn extract_elem_segment(elem_node: &Node, source: &str, index: usize) -> Option<ElemSegment> {
-    // Elem segment identifiers are wrapped in an "index" node
-    let (name, name_range) = find_identifier_in_data_or_elem(elem_node, source);
-
-    // Extract function names from elem segment
+    let mut name = None;
+    let mut name_range = None;
     let mut func_names = Vec::new();
     let mut table_name = None;
+    let mut seen_body = false;
 
-    // Look for index nodes which could be function references
-    let text = node_text(elem_node, source);
-
-    // Find table name if present (table $name)
-    if let Some(table_pos) = text.find("(table ") {
-        let after_table = &text[table_pos + 7..];
-        if let Some(dollar_pos) = after_table.find('$') {
-            let name_start = dollar_pos;
-            let name_end = after_table[name_start + 1..]
-                .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
-                .map(|i| name_start + i + 1)
-                .unwrap_or(after_table.len());
-            table_name = Some(after_table[name_start..name_end].to_string());
-        }
-    }
-
-    // Find function references after "func" keyword
-    if let Some(func_pos) = text.find(" func ") {
-        let after_func = &text[func_pos + 6..];
-        // Extract all $identifiers
-        let mut remaining = after_func;
-        while let Some(dollar_pos) = remaining.find('$') {
-            let name_start = dollar_pos;
-            let name_end = remaining[name_start + 1..]
-                .find(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
-                .map(|i| name_start + i + 1)
-                .unwrap_or(remaining.len());
-            let func_name = &remaining[name_start..name_end];
-            func_names.push(func_name.to_string());
-            remaining = &remaining[name_end..];
+    let mut cursor = elem_node.walk();
+    for child in elem_node.children(&mut cursor) {
+        let child_kind = child.kind();
+        #[cfg(all(feature = "wasm", not(feature = "native")))]
+        let child_kind = child_kind.as_str();
+        match child_kind {
+            // An index directly after "elem" is the segment's own name;
+            // indices after the offset are function references
+            "index" if !seen_body => {
+                let text = node_text(&child, source);
+                if text.starts_with('$') {
+                    name_range = Some(node_to_range(&child));
+                    name = Some(text);
+                }
+            }
+            "index" => func_names.push(node_text(&child, source)),
+            "table_use" => {
+                seen_body = true;
+                let mut table_cursor = child.walk();
+                for table_child in child.children(&mut table_cursor) {
+                    if table_child.kind() == "index" {
+                        table_name = Some(node_text(&table_child, source));
+                    }
+                }
+            }
+            "elem_list" => {
+                seen_body = true;
+                collect_elem_list_funcs(&child, source, &mut func_names);
+            }
+            "offset" | "declare" => seen_body = true,
+            _ => {}
         }
     }
 
@@ -2057,6 +2483,27 @@ fn extract_elem_segment(elem_node: &Node, source: &str, index: usize) -> Option<
     })
 }
 
+/// Collect function references from an elem_list: either `func $a $b ...`
+/// or a ref-typed list of `(ref.func $a)` / `(item ref.func $a)` expressions
+fn collect_elem_list_funcs(list_node: &Node, source: &str, func_names: &mut Vec<String>) {
+    let mut cursor = list_node.walk();
+    for child in list_node.children(&mut cursor) {
+        if child.kind() == "index" {
+            func_names.push(node_text(&child, source));
+        } else if child.kind() == "elem_expr" {
+            let text = node_text(&child, source);
+            if let Some(pos) = text.find("ref.func") {
+                if let Some(func_ref) = text[pos + "ref.func".len()..]
+                    .split(|c: char| c.is_whitespace() || c == ')')
+                    .find(|t| !t.is_empty())
+                {
+                    func_names.push(func_ref.to_string());
+                }
+            }
+        }
+    }
+}
+
 /// Find identifier in data or elem segment nodes.
 /// These have identifiers wrapped in an "index" node, unlike other constructs.
 fn find_identifier_in_data_or_elem(node: &Node, source: &str) -> (Option<String>, Option<Range>) {
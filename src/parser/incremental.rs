@@ -0,0 +1,351 @@
+//! Incremental symbol table updates.
+//!
+//! After an edit, most of a module is unchanged. Instead of re-extracting
+//! every field, only the functions whose fields changed are extracted again
+//! and the rest of the previous table is shifted to its new position.
+
+use super::{extract_function, extract_symbols, is_script};
+use crate::core::types::{Position, Range};
+use crate::symbols::*;
+use std::collections::HashMap;
+use tree_sitter::{InputEdit, Node, Tree};
+
+/// Update a symbol table after `edits`, reusing everything outside the changed module fields.
+///
+/// `old_tree` is the previous tree with `edits` already applied through
+/// `Tree::edit`, and `new_tree` the tree reparsed from it. Changed fields are
+/// found with `Tree::changed_ranges` and the edited byte ranges. When only
+/// function bodies changed, those functions are re-extracted and everything
+/// else is shifted. Any change that could move indices, rename an item or
+/// touch imports and exports falls back to a full extraction from `new_tree`.
+pub fn update_symbols(
+    old_symbols: &SymbolTable,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    source: &str,
+    edits: &[InputEdit],
+) -> Result<SymbolTable, String> {
+    match update_changed_functions(old_symbols, old_tree, new_tree, source, edits) {
+        Some(symbols) => Ok(symbols),
+        None => extract_symbols(new_tree, source),
+    }
+}
+
+fn update_changed_functions(
+    old_symbols: &SymbolTable,
+    old_tree: &Tree,
+    new_tree: &Tree,
+    source: &str,
+    edits: &[InputEdit],
+) -> Option<SymbolTable> {
+    // Scripts keep a table per module; re-extract them whole
+    if old_symbols.is_script() || is_script(&new_tree.root_node()) {
+        return None;
+    }
+
+    // Adding, removing or reordering fields changes indices
+    let old_fields = module_fields(old_tree);
+    let new_fields = module_fields(new_tree);
+    if old_fields.len() != new_fields.len()
+        || old_fields
+            .iter()
+            .zip(&new_fields)
+            .any(|(old, new)| old.kind() != new.kind())
+    {
+        return None;
+    }
+
+    let changed = changed_byte_ranges(old_tree, new_tree, edits);
+    let changed_fields: Vec<usize> = (0..new_fields.len())
+        .filter(|&i| {
+            let field = new_fields[i].byte_range();
+            changed.iter().any(|range| overlaps(&field, range))
+        })
+        .collect();
+    if changed_fields
+        .iter()
+        .any(|&i| new_fields[i].kind() != "module_field_func" || has_linkage(&new_fields[i]))
+    {
+        return None;
+    }
+
+    // Match each changed field to its function by where the function now starts
+    let mut function_starts: HashMap<usize, usize> = HashMap::new();
+    for func in &old_symbols.functions {
+        let start = edits
+            .iter()
+            .fold(func.start_byte, |byte, edit| shift_byte(edit, byte));
+        function_starts.insert(start, func.index);
+    }
+
+    let mut replacements = HashMap::new();
+    for &i in &changed_fields {
+        let index = *function_starts.get(&old_fields[i].start_byte())?;
+        let old_func = old_symbols.functions.get(index)?;
+        if old_symbols.import_of(ExternKind::Func, index).is_some()
+            || old_symbols
+                .exports_of(ExternKind::Func, index)
+                .next()
+                .is_some()
+        {
+            return None;
+        }
+
+        // Other fields may refer to the function by name
+        let new_func = extract_function(&new_fields[i], source, index)?;
+        if new_func.name != old_func.name {
+            return None;
+        }
+        replacements.insert(index, new_func);
+    }
+
+    let mut symbols = old_symbols.clone();
+    for edit in edits {
+        Shift::new(edit).symbols(&mut symbols, &replacements)?;
+    }
+    for (index, func) in replacements {
+        symbols.functions[index] = func;
+    }
+    symbols.resolve_type_refs();
+
+    Some(symbols)
+}
+
+/// Module-level fields in source order, e.g. `module_field_func` nodes
+fn module_fields(tree: &Tree) -> Vec<Node<'_>> {
+    let mut fields = Vec::new();
+    collect_module_fields(tree.root_node(), &mut fields);
+    fields
+}
+
+fn collect_module_fields<'a>(node: Node<'a>, fields: &mut Vec<Node<'a>>) {
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if matches!(child.kind(), "module" | "module_field") {
+            collect_module_fields(child, fields);
+        } else if child.kind().starts_with("module_field_") {
+            fields.push(child);
+        }
+    }
+}
+
+/// Byte ranges in the new text whose structure or content changed
+fn changed_byte_ranges(
+    old_tree: &Tree,
+    new_tree: &Tree,
+    edits: &[InputEdit],
+) -> Vec<std::ops::Range<usize>> {
+    // Edits that keep the structure, e.g. renaming a local, aren't reported by
+    // `changed_ranges`, so add the edited text itself, moved past later edits
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for edit in edits {
+        for range in &mut ranges {
+            *range = shift_byte(edit, range.start)..shift_byte(edit, range.end);
+        }
+        ranges.push(edit.start_byte..edit.new_end_byte);
+    }
+
+    ranges.extend(
+        old_tree
+            .changed_ranges(new_tree)
+            .map(|range| range.start_byte..range.end_byte),
+    );
+    ranges
+}
+
+/// Whether a changed range touches a field; an empty range is a deletion point
+fn overlaps(field: &std::ops::Range<usize>, changed: &std::ops::Range<usize>) -> bool {
+    changed.start < field.end && field.start < changed.end.max(changed.start + 1)
+}
+
+/// Whether a field has an inline `(export ...)` or `(import ...)`
+fn has_linkage(field: &Node) -> bool {
+    let mut cursor = field.walk();
+    let found = field
+        .children(&mut cursor)
+        .any(|child| matches!(child.kind(), "export" | "import"));
+    found
+}
+
+/// Move a byte offset past an edit the same way `Tree::edit` moves node starts
+fn shift_byte(edit: &InputEdit, byte: usize) -> usize {
+    if byte >= edit.old_end_byte {
+        byte - edit.old_end_byte + edit.new_end_byte
+    } else if byte > edit.start_byte {
+        edit.new_end_byte
+    } else {
+        byte
+    }
+}
+
+/// Moves positions of unchanged items past a single edit.
+///
+/// Each method returns None for a position inside the edited text, which
+/// means the item changed and the table has to be extracted again.
+struct Shift<'a> {
+    edit: &'a InputEdit,
+    start: Position,
+    old_end: Position,
+    new_end: Position,
+}
+
+impl<'a> Shift<'a> {
+    fn new(edit: &'a InputEdit) -> Self {
+        let point = |p: tree_sitter::Point| Position::new(p.row as u32, p.column as u32);
+        Self {
+            edit,
+            start: point(edit.start_position),
+            old_end: point(edit.old_end_position),
+            new_end: point(edit.new_end_position),
+        }
+    }
+
+    fn line_delta(&self) -> i64 {
+        self.new_end.line as i64 - self.old_end.line as i64
+    }
+
+    /// Whether a byte offset lies after the edit. An insertion right at the
+    /// end of an item, `is_end`, doesn't move it; one at its start does.
+    fn is_after(&self, byte: usize, is_end: bool) -> Option<bool> {
+        let edit = self.edit;
+        if is_end && byte <= edit.start_byte {
+            Some(false)
+        } else if byte >= edit.old_end_byte {
+            Some(true)
+        } else if byte <= edit.start_byte {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn byte(&self, byte: usize, is_end: bool) -> Option<usize> {
+        Some(if self.is_after(byte, is_end)? {
+            byte - self.edit.old_end_byte + self.edit.new_end_byte
+        } else {
+            byte
+        })
+    }
+
+    fn position(&self, position: Position, is_end: bool) -> Option<Position> {
+        if is_end && position <= self.start {
+            Some(position)
+        } else if position >= self.old_end {
+            Some(if position.line == self.old_end.line {
+                Position::new(
+                    self.new_end.line,
+                    self.new_end.character + (position.character - self.old_end.character),
+                )
+            } else {
+                Position::new(
+                    (position.line as i64 + self.line_delta()) as u32,
+                    position.character,
+                )
+            })
+        } else if position <= self.start {
+            Some(position)
+        } else {
+            None
+        }
+    }
+
+    fn range(&self, range: &mut Option<Range>) -> Option<()> {
+        if let Some(r) = range {
+            *r = Range::new(self.position(r.start, false)?, self.position(r.end, true)?);
+        }
+        Some(())
+    }
+
+    /// Shift the start line of an item, using its range to tell whether an
+    /// item starting on an edited line comes before or after the edit
+    fn line(&self, line: &mut u32, anchor: Option<Range>) -> Option<()> {
+        if *line > self.old_end.line {
+            *line = (*line as i64 + self.line_delta()) as u32;
+        } else if *line >= self.start.line && self.line_delta() != 0 {
+            let anchor = anchor?.start;
+            if anchor >= self.old_end {
+                *line = (*line as i64 + self.line_delta()) as u32;
+            } else if anchor > self.start {
+                return None;
+            }
+        }
+        Some(())
+    }
+
+    fn item(&self, line: &mut u32, range: &mut Option<Range>) -> Option<()> {
+        self.line(line, *range)?;
+        self.range(range)
+    }
+
+    fn function(&self, func: &mut Function) -> Option<()> {
+        let delta = self.line_delta();
+        if self.is_after(func.start_byte, false)? {
+            func.line = (func.line as i64 + delta) as u32;
+        }
+        if self.is_after(func.end_byte, true)? {
+            func.end_line = (func.end_line as i64 + delta) as u32;
+        }
+        func.start_byte = self.byte(func.start_byte, false)?;
+        func.end_byte = self.byte(func.end_byte, true)?;
+        self.range(&mut func.range)?;
+
+        for param in &mut func.parameters {
+            self.range(&mut param.range)?;
+        }
+        for local in &mut func.locals {
+            self.range(&mut local.range)?;
+        }
+        for block in &mut func.blocks {
+            self.item(&mut block.line, &mut block.range)?;
+        }
+        Some(())
+    }
+
+    /// Shift every item of the table, except functions about to be replaced
+    fn symbols(
+        &self,
+        symbols: &mut SymbolTable,
+        replaced: &HashMap<usize, Function>,
+    ) -> Option<()> {
+        for func in &mut symbols.functions {
+            if !replaced.contains_key(&func.index) {
+                self.function(func)?;
+            }
+        }
+        for global in &mut symbols.globals {
+            self.item(&mut global.line, &mut global.range)?;
+            global.start_byte = self.byte(global.start_byte, false)?;
+        }
+        for table in &mut symbols.tables {
+            self.item(&mut table.line, &mut table.range)?;
+            table.start_byte = self.byte(table.start_byte, false)?;
+        }
+        for memory in &mut symbols.memories {
+            self.item(&mut memory.line, &mut memory.range)?;
+            memory.start_byte = self.byte(memory.start_byte, false)?;
+        }
+        for type_def in &mut symbols.types {
+            self.item(&mut type_def.line, &mut type_def.range)?;
+        }
+        for tag in &mut symbols.tags {
+            self.item(&mut tag.line, &mut tag.range)?;
+            tag.start_byte = self.byte(tag.start_byte, false)?;
+        }
+        for data in &mut symbols.data_segments {
+            self.item(&mut data.line, &mut data.range)?;
+        }
+        for elem in &mut symbols.elem_segments {
+            self.item(&mut elem.line, &mut elem.range)?;
+        }
+        for export in &mut symbols.exports {
+            self.item(&mut export.line, &mut export.range)?;
+        }
+        for import in &mut symbols.imports {
+            self.item(&mut import.line, &mut import.range)?;
+        }
+        if let Some(start) = &mut symbols.start {
+            self.item(&mut start.line, &mut start.range)?;
+        }
+        Some(())
+    }
+}
@@ -472,3 +472,186 @@ fn test_parse_011_host_watlings() {
         "$log_some_numbers should be found"
     );
 }
+
+#[test]
+fn test_parse_exports_imports_and_start() {
+    let wat = r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (global $g (import "env" "g") i32)
+  (func $init (export "init") (export "setup")
+    i32.const 1
+    call $log
+  )
+  (memory $mem 1)
+  (export "memory" (memory $mem))
+  (export "first" (func 0))
+  (start $init)
+)"#;
+
+    let symbols = parse_document(wat).unwrap();
+
+    let imports: Vec<(String, ExternKind, usize)> = symbols
+        .imports
+        .iter()
+        .map(|i| (i.qualified_name(), i.kind, i.index))
+        .collect();
+    assert_eq!(
+        imports,
+        vec![
+            ("env.log".to_string(), ExternKind::Func, 0),
+            ("env.g".to_string(), ExternKind::Global, 0),
+        ]
+    );
+
+    let exports: Vec<(&str, ExternKind, usize)> = symbols
+        .exports
+        .iter()
+        .map(|e| (e.name.as_str(), e.kind, e.index))
+        .collect();
+    assert_eq!(
+        exports,
+        vec![
+            ("init", ExternKind::Func, 1),
+            ("setup", ExternKind::Func, 1),
+            ("memory", ExternKind::Memory, 0),
+            ("first", ExternKind::Func, 0),
+        ]
+    );
+    assert_eq!(symbols.exports_of(ExternKind::Func, 1).count(), 2);
+
+    let start = symbols.start.as_ref().expect("Expected start function");
+    assert_eq!(start.index, 1);
+    assert_eq!(start.line, 10);
+}
+
+#[test]
+fn test_parse_inline_exports_on_one_line() {
+    let wat = r#"(module (global $a (export "a") i32 (i32.const 0)) (global $b (export "b") i32 (i32.const 1)) (memory (export "m0") 1) (memory (export "m1") 1))"#;
+
+    let symbols = parse_document(wat).unwrap();
+    let exports: Vec<(&str, ExternKind, usize)> = symbols
+        .exports
+        .iter()
+        .map(|e| (e.name.as_str(), e.kind, e.index))
+        .collect();
+    assert_eq!(
+        exports,
+        vec![
+            ("a", ExternKind::Global, 0),
+            ("b", ExternKind::Global, 1),
+            ("m0", ExternKind::Memory, 0),
+            ("m1", ExternKind::Memory, 1),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_named_type_refs() {
+    let wat = r#"(module
+  (rec
+    (type $node (struct (field $next (ref null $list))))
+    (type $list (struct (field $head (ref null $node)))))
+  (global $empty (ref null $list) (ref.null $list))
+  (func $first (param $l (ref $list)) (result (ref null $node))
+    ref.null $node
+  )
+)"#;
+
+    let symbols = parse_document(wat).unwrap();
+
+    // $list is declared after the field that refers to it
+    match &symbols.types[0].kind {
+        TypeKind::Struct { fields } => {
+            assert_eq!(fields[0].1, ValueType::RefNull(TypeRef::numeric(1)));
+            assert_eq!(fields[0].1.to_string(), "(ref null $list)");
+        }
+        other => panic!("Expected struct, got {:?}", other),
+    }
+
+    let global = symbols.get_global_by_name("$empty").unwrap();
+    assert_eq!(global.var_type, ValueType::RefNull(TypeRef::numeric(1)));
+
+    let func = symbols.get_function_by_name("$first").unwrap();
+    assert_eq!(func.parameters[0].param_type.to_string(), "(ref $list)");
+    assert_eq!(func.results[0], ValueType::RefNull(TypeRef::numeric(0)));
+}
+
+#[test]
+fn test_parse_wast_script() {
+    let wast = r#"
+(module $a
+  (func $f (export "run") (result i32) (i32.const 1)))
+(module $b
+  (func $f (param i32))
+  (func $g (export "go")))
+(register "a" $a)
+(assert_return (invoke $a "run") (i32.const 1))
+(invoke "go")
+(assert_invalid (module (func (result i32))) "type mismatch")
+"#;
+
+    let symbols = parse_document(wast).unwrap();
+    assert!(symbols.is_script());
+    assert!(symbols.functions.is_empty());
+
+    assert_eq!(symbols.modules.len(), 3);
+    assert_eq!(symbols.modules[0].name.as_deref(), Some("$a"));
+    assert_eq!(symbols.modules[0].symbols.functions.len(), 1);
+    assert_eq!(symbols.modules[1].symbols.functions.len(), 2);
+    assert_eq!(symbols.modules[1].symbols.functions[0].parameters.len(), 1);
+
+    let kinds: Vec<ScriptCommandKind> = symbols.commands.iter().map(|c| c.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ScriptCommandKind::Register,
+            ScriptCommandKind::AssertReturn,
+            ScriptCommandKind::Invoke,
+            ScriptCommandKind::AssertInvalid,
+        ]
+    );
+
+    // Named module, latest module, and none for a module the assertion defines
+    assert_eq!(symbols.commands[1].module, Some(0));
+    assert_eq!(symbols.commands[1].name.as_deref(), Some("run"));
+    assert_eq!(symbols.commands[2].module, Some(1));
+    assert_eq!(symbols.commands[3].module, None);
+
+    let (module, export) = symbols.command_export(&symbols.commands[1]).unwrap();
+    assert_eq!(module.name.as_deref(), Some("$a"));
+    assert_eq!(export.kind, ExternKind::Func);
+}
+
+#[test]
+fn test_parse_modules_in_assertions() {
+    let wast = r#"
+(module $a (func (export "run")))
+(assert_invalid (module $a (func $bad (result i32))) "type mismatch")
+(assert_trap (module (func $boom unreachable) (start $boom)) "unreachable")
+(invoke "run")
+(invoke $a "run")
+"#;
+
+    let symbols = parse_document(wast).unwrap();
+    assert_eq!(symbols.modules.len(), 3);
+    assert!(!symbols.modules[0].in_assertion);
+    assert!(symbols.modules[1].in_assertion);
+    assert!(symbols.modules[2].in_assertion);
+
+    // Their items can be looked up at positions inside them
+    let bad = symbols.scope_at(Position::new(2, 30));
+    assert!(bad.get_function_by_name("$bad").is_some());
+    let boom = symbols.scope_at(Position::new(3, 24));
+    assert!(boom.get_function_by_name("$boom").is_some());
+
+    // Later commands still refer to the last module outside an assertion
+    assert_eq!(symbols.commands[2].module, Some(0));
+    assert_eq!(symbols.commands[3].module, Some(0));
+}
+
+#[test]
+fn test_single_module_is_not_a_script() {
+    let symbols = parse_document("(module (func $f))").unwrap();
+    assert!(!symbols.is_script());
+    assert_eq!(symbols.functions.len(), 1);
+}
@@ -0,0 +1,367 @@
+//! Server settings.
+//!
+//! Settings arrive as JSON, first in `initializationOptions` and later through
+//! `workspace/didChangeConfiguration` or a `workspace/configuration` request
+//! for the `wat` section. Every field has a default, so clients only send the
+//! values they change:
+//!
+//! ```json
+//! { "debounceMs": 250, "diagnostics": { "level": "semantic" } }
+//! ```
+
+use crate::interpreter::ImportStubs;
+use serde::{Deserialize, Serialize};
+
+/// Configuration section requested from the client
+pub const CONFIGURATION_SECTION: &str = "wat";
+
+/// All configurable server behavior
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    /// Delay after the last edit before full validation runs
+    pub debounce_ms: u64,
+    pub diagnostics: DiagnosticSettings,
+    /// WebAssembly proposals the code may use
+    pub target: Target,
+    /// Reserved: the server doesn't provide inlay hints yet
+    pub inlay_hints: InlayHintSettings,
+    pub completion: CompletionSettings,
+    /// Reserved: the server doesn't format documents yet
+    pub format: FormatSettings,
+    pub run: RunSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 500,
+            diagnostics: DiagnosticSettings::default(),
+            target: Target::default(),
+            inlay_hints: InlayHintSettings::default(),
+            completion: CompletionSettings::default(),
+            format: FormatSettings::default(),
+            run: RunSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Read settings from a JSON value, either the settings object itself or
+    /// one nested under the `wat` section
+    pub fn from_json(value: &serde_json::Value) -> Result<Self, String> {
+        let value = value.get(CONFIGURATION_SECTION).unwrap_or(value);
+        if value.is_null() {
+            return Ok(Self::default());
+        }
+        serde_json::from_value(value.clone()).map_err(|e| format!("Invalid settings: {}", e))
+    }
+}
+
+/// Which diagnostic layers run, from fastest to most thorough
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(feature = "native", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+    /// Only syntax errors (fastest)
+    Syntax,
+    /// Syntax + semantic validation (default for wat-check)
+    #[default]
+    Semantic,
+    /// Full validation including wast deep checks (slowest but most thorough;
+    /// default in the server)
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiagnosticSettings {
+    pub level: DiagnosticLevel,
+}
+
+impl Default for DiagnosticSettings {
+    fn default() -> Self {
+        // Validation is debounced in the server, so it can afford every layer
+        Self {
+            level: DiagnosticLevel::Full,
+        }
+    }
+}
+
+/// Proposals code may use: a spec release or an explicit list
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Target {
+    Profile(TargetProfile),
+    Proposals(Vec<Proposal>),
+}
+
+impl Default for Target {
+    /// Everything the grammar accepts
+    fn default() -> Self {
+        Target::Proposals(Proposal::ALL.to_vec())
+    }
+}
+
+impl Target {
+    pub fn allows(&self, proposal: Proposal) -> bool {
+        match self {
+            Target::Profile(profile) => profile.proposals().contains(&proposal),
+            Target::Proposals(proposals) => proposals.contains(&proposal),
+        }
+    }
+
+    /// How the target is named in messages
+    pub fn describe(&self) -> String {
+        match self {
+            Target::Profile(profile) => format!("target '{}'", profile.name()),
+            Target::Proposals(_) => "the enabled proposals".to_string(),
+        }
+    }
+}
+
+/// WebAssembly spec releases, by the proposals they standardized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "native", derive(clap::ValueEnum))]
+pub enum TargetProfile {
+    /// WebAssembly 1.0
+    #[serde(rename = "mvp")]
+    #[cfg_attr(feature = "native", value(name = "mvp"))]
+    Mvp,
+    /// WebAssembly 2.0: SIMD, bulk memory, reference types, sign extension,
+    /// non-trapping float-to-int and multi-value
+    #[serde(rename = "wasm2.0")]
+    #[cfg_attr(feature = "native", value(name = "wasm2.0"))]
+    Wasm2,
+    /// WebAssembly 3.0: adds relaxed SIMD, GC, exception handling, tail calls,
+    /// memory64, multiple memories and extended constant expressions.
+    /// Threads are in no release yet, so only a proposal list enables them.
+    #[serde(rename = "wasm3.0")]
+    #[cfg_attr(feature = "native", value(name = "wasm3.0"))]
+    Wasm3,
+}
+
+impl TargetProfile {
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetProfile::Mvp => "mvp",
+            TargetProfile::Wasm2 => "wasm2.0",
+            TargetProfile::Wasm3 => "wasm3.0",
+        }
+    }
+
+    pub fn proposals(self) -> &'static [Proposal] {
+        match self {
+            TargetProfile::Mvp => &[],
+            TargetProfile::Wasm2 => &[
+                Proposal::Simd,
+                Proposal::BulkMemory,
+                Proposal::ReferenceTypes,
+                Proposal::SignExtension,
+                Proposal::NontrappingFloatToInt,
+                Proposal::MultiValue,
+            ],
+            TargetProfile::Wasm3 => &[
+                Proposal::Simd,
+                Proposal::BulkMemory,
+                Proposal::ReferenceTypes,
+                Proposal::SignExtension,
+                Proposal::NontrappingFloatToInt,
+                Proposal::MultiValue,
+                Proposal::RelaxedSimd,
+                Proposal::Gc,
+                Proposal::ExceptionHandling,
+                Proposal::TailCall,
+                Proposal::Memory64,
+                Proposal::MultiMemory,
+                Proposal::ExtendedConst,
+            ],
+        }
+    }
+}
+
+/// A WebAssembly proposal beyond the MVP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Proposal {
+    Simd,
+    RelaxedSimd,
+    BulkMemory,
+    ReferenceTypes,
+    Threads,
+    Gc,
+    ExceptionHandling,
+    SignExtension,
+    NontrappingFloatToInt,
+    MultiValue,
+    TailCall,
+    Memory64,
+    MultiMemory,
+    ExtendedConst,
+}
+
+impl Proposal {
+    pub const ALL: [Proposal; 14] = [
+        Proposal::Simd,
+        Proposal::RelaxedSimd,
+        Proposal::BulkMemory,
+        Proposal::ReferenceTypes,
+        Proposal::Threads,
+        Proposal::Gc,
+        Proposal::ExceptionHandling,
+        Proposal::SignExtension,
+        Proposal::NontrappingFloatToInt,
+        Proposal::MultiValue,
+        Proposal::TailCall,
+        Proposal::Memory64,
+        Proposal::MultiMemory,
+        Proposal::ExtendedConst,
+    ];
+
+    /// Human-readable name for diagnostics
+    pub fn name(self) -> &'static str {
+        match self {
+            Proposal::Simd => "SIMD",
+            Proposal::RelaxedSimd => "relaxed SIMD",
+            Proposal::BulkMemory => "bulk memory",
+            Proposal::ReferenceTypes => "reference types",
+            Proposal::Threads => "threads",
+            Proposal::Gc => "GC",
+            Proposal::ExceptionHandling => "exception handling",
+            Proposal::SignExtension => "sign extension",
+            Proposal::NontrappingFloatToInt => "non-trapping float-to-int",
+            Proposal::MultiValue => "multi-value",
+            Proposal::TailCall => "tail call",
+            Proposal::Memory64 => "memory64",
+            Proposal::MultiMemory => "multiple memories",
+            Proposal::ExtendedConst => "extended constant expressions",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct InlayHintSettings {
+    /// Show parameter names at call arguments
+    pub parameter_names: bool,
+    /// Show the value type of locals, globals and folded expressions
+    pub types: bool,
+}
+
+impl Default for InlayHintSettings {
+    fn default() -> Self {
+        Self {
+            parameter_names: true,
+            types: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct CompletionSettings {
+    /// Emmet-like expansions such as `5i32` and `l$var`
+    pub emmet: bool,
+    /// Insert snippets with tab stops; when off, plain text is inserted
+    pub snippets: bool,
+}
+
+impl Default for CompletionSettings {
+    fn default() -> Self {
+        Self {
+            emmet: true,
+            snippets: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct FormatSettings {
+    /// Spaces per nesting level
+    pub indent_width: u32,
+    /// Line width folded expressions are wrapped at
+    pub max_line_width: u32,
+}
+
+impl Default for FormatSettings {
+    fn default() -> Self {
+        Self {
+            indent_width: 2,
+            max_line_width: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RunSettings {
+    /// What imports do when an export is run from its code lens
+    pub imports: ImportStubs,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_partial_settings_keep_defaults() {
+        let settings = Settings::from_json(&json!({
+            "debounceMs": 250,
+            "diagnostics": { "level": "syntax" },
+            "completion": { "emmet": false },
+            "run": { "imports": "log" }
+        }))
+        .unwrap();
+
+        assert_eq!(settings.debounce_ms, 250);
+        assert_eq!(settings.diagnostics.level, DiagnosticLevel::Syntax);
+        assert!(!settings.completion.emmet);
+        assert!(settings.completion.snippets);
+        assert_eq!(settings.run.imports, ImportStubs::Log);
+        assert!(Proposal::ALL.iter().all(|&p| settings.target.allows(p)));
+    }
+
+    #[test]
+    fn test_settings_under_section() {
+        let settings = Settings::from_json(&json!({
+            "wat": { "target": ["simd", "bulk-memory"] }
+        }))
+        .unwrap();
+        assert_eq!(
+            settings.target,
+            Target::Proposals(vec![Proposal::Simd, Proposal::BulkMemory])
+        );
+
+        assert_eq!(
+            Settings::from_json(&serde_json::Value::Null).unwrap(),
+            Settings::default()
+        );
+        assert!(Settings::from_json(&json!({ "debounceMs": "soon" })).is_err());
+    }
+
+    #[test]
+    fn test_target_profiles() {
+        let settings = Settings::from_json(&json!({ "target": "wasm2.0" })).unwrap();
+        assert_eq!(settings.target, Target::Profile(TargetProfile::Wasm2));
+        assert!(settings.target.allows(Proposal::Simd));
+        assert!(!settings.target.allows(Proposal::Gc));
+        assert!(settings.target.allows(Proposal::MultiValue));
+        assert!(!settings.target.allows(Proposal::TailCall));
+
+        let mvp = Target::Profile(TargetProfile::Mvp);
+        assert!(Proposal::ALL.iter().all(|&p| !mvp.allows(p)));
+
+        let wasm3 = Target::Profile(TargetProfile::Wasm3);
+        assert!(wasm3.allows(Proposal::ExceptionHandling));
+        assert!(wasm3.allows(Proposal::TailCall));
+        assert!(wasm3.allows(Proposal::Memory64));
+        assert!(!settings.target.allows(Proposal::ExtendedConst));
+        assert!(!wasm3.allows(Proposal::Threads));
+
+        assert!(Settings::from_json(&json!({ "target": "wasm4.0" })).is_err());
+    }
+}
@@ -222,6 +222,7 @@ mod tests {
                 },
             ],
             results: vec![ValueType::I32],
+            type_use: None,
             locals: vec![],
             blocks: vec![],
             line: 1,
@@ -237,6 +238,7 @@ mod tests {
             is_mutable: true,
             initial_value: None,
             line: 0,
+            start_byte: 0,
             range: Some(Range::from_coords(0, 8, 0, 16)),
         });
         symbols
@@ -0,0 +1,277 @@
+//! TypeScript declarations for a module's exports and imports.
+//!
+//! Values cross the JS boundary as the JS API converts them: `i32`, `f32`
+//! and `f64` are numbers, `i64` is a `bigint`, function references are
+//! functions, and other references are opaque. Globals, memories, tables
+//! and tags are the `WebAssembly` objects wrapping them, each with a doc
+//! comment giving its WAT type.
+
+use crate::linking::ExternType;
+use crate::symbols::{ExternKind, SymbolTable, TypeKind, TypeRef, ValueType};
+
+/// Declarations of the module's `Exports`, of the `Imports` object to pass
+/// to `WebAssembly.instantiate`, and of an `Instance` with typed exports
+pub fn generate_declarations(symbols: &SymbolTable) -> String {
+    let mut out = String::from("// Generated by wat-lsp. Do not edit.\n\n");
+
+    out.push_str("/** The exports of an instance of the module */\n");
+    out.push_str("export interface Exports {\n");
+    for export in &symbols.exports {
+        if let Some(member) = member(symbols, &export.name, export.kind, export.index) {
+            push_member(&mut out, "  ", &member);
+        }
+    }
+    out.push_str("}\n\n");
+
+    // Imports grouped by module, in the order modules first appear
+    let mut modules: Vec<(&str, Vec<Member>)> = Vec::new();
+    for import in &symbols.imports {
+        let Some(member) = member(symbols, &import.field, import.kind, import.index) else {
+            continue;
+        };
+        match modules.iter_mut().find(|(name, _)| *name == import.module) {
+            Some((_, members)) => members.push(member),
+            None => modules.push((&import.module, vec![member])),
+        }
+    }
+    out.push_str("/** The import object to pass to `WebAssembly.instantiate` */\n");
+    out.push_str("export interface Imports {\n");
+    for (module, members) in &modules {
+        out.push_str(&format!("  {}: {{\n", property_name(module)));
+        for member in members {
+            push_member(&mut out, "    ", member);
+        }
+        out.push_str("  };\n");
+    }
+    out.push_str("}\n\n");
+
+    out.push_str("/** An instance of the module */\n");
+    out.push_str("export interface Instance extends WebAssembly.Instance {\n");
+    out.push_str("  readonly exports: Exports;\n");
+    out.push_str("}\n");
+    out
+}
+
+/// A property of `Exports` or of a module in `Imports`
+struct Member {
+    /// The item's WAT type, for its doc comment
+    doc: String,
+    declaration: String,
+}
+
+fn push_member(out: &mut String, indent: &str, member: &Member) {
+    out.push_str(&format!("{}/** `{}` */\n", indent, member.doc));
+    out.push_str(&format!("{}{};\n", indent, member.declaration));
+}
+
+/// The member for the item of the given kind and index, named `name`
+fn member(symbols: &SymbolTable, name: &str, kind: ExternKind, index: usize) -> Option<Member> {
+    let ty = ExternType::of(symbols, kind, index)?;
+    let name = property_name(name);
+    let declaration = match &ty {
+        ExternType::Func { params, results } => {
+            let param_names: Vec<Option<&str>> = symbols
+                .get_function_by_index(index)
+                .map(|func| func.parameters.iter().map(|p| p.name.as_deref()).collect())
+                .unwrap_or_default();
+            let params: Vec<String> = params
+                .iter()
+                .enumerate()
+                .map(|(i, param)| {
+                    let param_name = param_names.get(i).copied().flatten();
+                    format!(
+                        "{}: {}",
+                        parameter_name(param_name, i),
+                        ts_type(symbols, param)
+                    )
+                })
+                .collect();
+            format!(
+                "{}({}): {}",
+                name,
+                params.join(", "),
+                result_type(symbols, results)
+            )
+        }
+        ExternType::Global { .. } => format!("{}: WebAssembly.Global", name),
+        ExternType::Table { .. } => format!("{}: WebAssembly.Table", name),
+        ExternType::Memory { .. } => format!("{}: WebAssembly.Memory", name),
+        ExternType::Tag { .. } => format!("{}: WebAssembly.Tag", name),
+    };
+    Some(Member {
+        doc: ty.to_string(),
+        declaration,
+    })
+}
+
+/// The JS type of a value of a WAT type
+pub fn ts_type(symbols: &SymbolTable, value_type: &ValueType) -> String {
+    match value_type {
+        ValueType::I32 | ValueType::F32 | ValueType::F64 | ValueType::I8 | ValueType::I16 => {
+            "number".to_string()
+        }
+        ValueType::I64 => "bigint".to_string(),
+        ValueType::Funcref => "Function | null".to_string(),
+        ValueType::NullFuncref | ValueType::NullExternref | ValueType::Nullref => {
+            "null".to_string()
+        }
+        ValueType::Ref(target) if is_func_type(symbols, target) => "Function".to_string(),
+        ValueType::RefNull(target) if is_func_type(symbols, target) => {
+            "Function | null".to_string()
+        }
+        // Vectors can't cross the boundary, and other references are opaque
+        _ => "unknown".to_string(),
+    }
+}
+
+/// `void`, a single type, or a tuple for multiple results
+fn result_type(symbols: &SymbolTable, results: &[ValueType]) -> String {
+    match results {
+        [] => "void".to_string(),
+        [result] => ts_type(symbols, result),
+        _ => {
+            let types: Vec<String> = results.iter().map(|r| ts_type(symbols, r)).collect();
+            format!("[{}]", types.join(", "))
+        }
+    }
+}
+
+fn is_func_type(symbols: &SymbolTable, target: &TypeRef) -> bool {
+    let Some(index) = target.index else {
+        return false;
+    };
+    symbols
+        .types
+        .iter()
+        .find(|ty| ty.index == index as usize)
+        .is_some_and(|ty| matches!(ty.kind, TypeKind::Func { .. }))
+}
+
+/// A parameter's `$name` without the `$` when it is a usable identifier,
+/// `p0`, `p1`, ... otherwise
+fn parameter_name(name: Option<&str>, index: usize) -> String {
+    match name.map(|name| name.trim_start_matches('$')) {
+        Some(name) if is_identifier(name) && !RESERVED_WORDS.contains(&name) => name.to_string(),
+        _ => format!("p{}", index),
+    }
+}
+
+/// An export or module name as a property name, quoted unless it is an
+/// identifier
+fn property_name(name: &str) -> String {
+    if is_identifier(name) {
+        name.to_string()
+    } else {
+        serde_json::to_string(name).unwrap_or_else(|_| format!("\"{}\"", name))
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Words that can't name a parameter
+const RESERVED_WORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wast_parser::parse_document;
+
+    #[test]
+    fn test_generate_declarations() {
+        let symbols = parse_document(
+            r#"(module
+  (type $callback (func (param i32)))
+  (import "env" "log" (func $log (param i32 i64)))
+  (import "env" "memory" (memory 1))
+  (import "js-host" "now" (func (result f64)))
+  (global $counter (export "counter") (mut i32) (i32.const 0))
+  (table (export "table") 1 funcref)
+  (func (export "add") (param $a i32) (param $this f32) (result f64 i64)
+    (f64.const 0) (i64.const 0))
+  (func (export "get-callback") (result (ref null $callback))
+    (ref.null $callback)))"#,
+        )
+        .unwrap();
+
+        let declarations = generate_declarations(&symbols);
+        assert_eq!(
+            declarations,
+            r#"// Generated by wat-lsp. Do not edit.
+
+/** The exports of an instance of the module */
+export interface Exports {
+  /** `(global (mut i32))` */
+  counter: WebAssembly.Global;
+  /** `(table 1 funcref)` */
+  table: WebAssembly.Table;
+  /** `(func (param i32 f32) (result f64 i64))` */
+  add(a: number, p1: number): [number, bigint];
+  /** `(func (result (ref null $callback)))` */
+  "get-callback"(): Function | null;
+}
+
+/** The import object to pass to `WebAssembly.instantiate` */
+export interface Imports {
+  env: {
+    /** `(func (param i32 i64))` */
+    log(p0: number, p1: bigint): void;
+    /** `(memory 1)` */
+    memory: WebAssembly.Memory;
+  };
+  "js-host": {
+    /** `(func (result f64))` */
+    now(): number;
+  };
+}
+
+/** An instance of the module */
+export interface Instance extends WebAssembly.Instance {
+  readonly exports: Exports;
+}
+"#
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core::types::Position;
+use crate::core::{LineIndex, PositionEncoding};
 use crate::symbols::{Function, SymbolTable};
 
 // Use the appropriate tree-sitter types based on feature
@@ -433,32 +434,29 @@ pub fn get_line_at_position(document: &str, line_num: usize) -> Option<&str> {
 
 /// Get the word at the specified position in a document.
 /// A word includes alphanumerics, underscores, dollar signs, dots, and hyphens.
+/// `position.character` is a UTF-8 byte column.
 pub fn get_word_at_position(document: &str, position: Position) -> Option<String> {
     let line = get_line_at_position(document, position.line as usize)?;
     let col = position.character as usize;
 
-    if col > line.len() {
+    if col > line.len() || !line.is_char_boundary(col) {
         return None;
     }
 
     // Find word boundaries
-    let mut start = col;
-    let mut end = col;
-
-    let chars: Vec<char> = line.chars().collect();
-
-    // Move back to start of word
-    while start > 0 && is_word_char(chars.get(start - 1).copied()?) {
-        start -= 1;
-    }
-
-    // Move forward to end of word
-    while end < chars.len() && is_word_char(chars.get(end).copied()?) {
-        end += 1;
-    }
+    let start = line[..col]
+        .char_indices()
+        .rev()
+        .take_while(|&(_, c)| is_word_char(c))
+        .last()
+        .map_or(col, |(i, _)| i);
+    let end = line[col..]
+        .char_indices()
+        .find(|&(_, c)| !is_word_char(c))
+        .map_or(line.len(), |(i, _)| col + i);
 
     if start < end {
-        Some(chars[start..end].iter().collect())
+        Some(line[start..end].to_string())
     } else {
         None
     }
@@ -469,25 +467,9 @@ pub fn is_word_char(c: char) -> bool {
     c.is_alphanumeric() || c == '_' || c == '$' || c == '.' || c == '-'
 }
 
-/// Convert an LSP Position to a byte offset in the source text
+/// Convert a position with a UTF-8 byte column to a byte offset in the source text
 pub fn position_to_byte(source: &str, position: Position) -> usize {
-    let mut byte_offset = 0;
-
-    for (current_line, line) in source.lines().enumerate() {
-        if current_line == position.line as usize {
-            // Add character offset within this line
-            let char_offset = position.character as usize;
-            let line_bytes: Vec<_> = line.char_indices().collect();
-            if char_offset < line_bytes.len() {
-                return byte_offset + line_bytes[char_offset].0;
-            } else {
-                return byte_offset + line.len();
-            }
-        }
-        byte_offset += line.len() + 1; // +1 for newline
-    }
-
-    byte_offset
+    LineIndex::new(source).offset(source, position, PositionEncoding::Utf8)
 }
 
 /// Find the AST node at the given position (native version - returns borrowed node)
@@ -570,7 +552,7 @@ fn find_deepest_node(node: Node, byte_offset: usize) -> Option<Node> {
     best_child.or(Some(node))
 }
 
-/// Apply a text edit to a string in-place
+/// Apply a text edit to a string in-place, with positions in UTF-8 byte columns
 /// Returns the new end position after the edit
 pub fn apply_text_edit(
     text: &mut String,
@@ -578,8 +560,9 @@ pub fn apply_text_edit(
     end: Position,
     new_text: &str,
 ) -> Position {
-    let start_byte = position_to_byte(text, start);
-    let end_byte = position_to_byte(text, end);
+    let index = LineIndex::new(text);
+    let start_byte = index.offset(text, start, PositionEncoding::Utf8);
+    let end_byte = index.offset(text, end, PositionEncoding::Utf8);
 
     // Remove the old text and insert the new text
     text.replace_range(start_byte..end_byte, new_text);
@@ -604,7 +587,8 @@ fn calculate_position_after_edit(_text: &str, start: Position, inserted_text: &s
         }
     } else {
         // Multi-line insert
-        let last_line = inserted_text.lines().last().unwrap_or("");
+        // Text after the last newline, empty when the insert ends with one
+        let last_line = inserted_text.rsplit('\n').next().unwrap_or("");
         Position {
             line: start.line + newline_count as u32,
             character: last_line.len() as u32,
@@ -758,6 +742,44 @@ mod tests {
         assert_eq!(new_end.character, 4);
     }
 
+    #[test]
+    fn test_apply_text_edit_trailing_newline() {
+        let mut text = String::from("hello world");
+        let start = Position::new(0, 5);
+        let end = Position::new(0, 5);
+
+        let new_end = apply_text_edit(&mut text, start, end, ",\n");
+        assert_eq!(text, "hello,\n world");
+        assert_eq!(new_end, Position::new(1, 0));
+    }
+
+    #[test]
+    fn test_apply_text_edit_crlf_and_multibyte() {
+        // Columns are bytes and "\r\n" line breaks are kept intact
+        let mut text = String::from(";; é\r\n(func $a)\r\n");
+        let new_end = apply_text_edit(
+            &mut text,
+            Position::new(0, 5),
+            Position::new(1, 8),
+            "!\r\n(func $b",
+        );
+        assert_eq!(text, ";; é!\r\n(func $b)\r\n");
+        assert_eq!(new_end, Position::new(1, 8));
+        assert_eq!(position_to_byte(&text, Position::new(2, 0)), text.len());
+    }
+
+    #[test]
+    fn test_get_word_at_byte_column() {
+        let document = "(data \"𝄞\" $name)";
+        let dollar = document.find('$').unwrap() as u32;
+        assert_eq!(
+            get_word_at_position(document, Position::new(0, dollar + 2)),
+            Some("$name".to_string())
+        );
+        // A column inside a multi-byte character has no word
+        assert_eq!(get_word_at_position(document, Position::new(0, 8)), None);
+    }
+
     #[test]
     fn test_node_at_position_identifier() {
         // We need tree-sitter-wasm to test this
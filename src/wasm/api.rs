@@ -4,7 +4,9 @@
 
 use wasm_bindgen::prelude::*;
 
+use crate::component;
 use crate::core::types::{HoverResult, Position, Range};
+use crate::core::{LineIndex, PositionEncoding};
 use crate::hover::provide_hover_core;
 use crate::parser::parse_document_from_tree;
 use crate::symbol_lookup::{find_symbol_definition_range, IndexContext};
@@ -26,6 +28,8 @@ pub fn init() {
 #[wasm_bindgen]
 pub struct WatLSP {
     document: String,
+    /// Line starts of `document`, for converting JavaScript's UTF-16 columns
+    line_index: LineIndex,
     symbols: Option<SymbolTable>,
     tree: Option<Tree>,
     parser: Option<Parser>,
@@ -41,6 +45,7 @@ impl WatLSP {
     pub fn new() -> Self {
         Self {
             document: String::new(),
+            line_index: LineIndex::default(),
             symbols: None,
             tree: None,
             parser: None,
@@ -94,10 +99,13 @@ impl WatLSP {
     /// Parse a WAT document and build symbol table using tree-sitter
     pub fn parse(&mut self, document: &str) {
         self.document = document.to_string();
+        self.line_index = LineIndex::new(document);
 
         // Parse with tree-sitter if parser is available
         if let Some(parser) = &mut self.parser {
-            if let Some(tree) = parser.parse(document, None) {
+            // A component's tree covers its core modules only
+            let source = component::core_module_text(document);
+            if let Some(tree) = parser.parse(source.as_deref().unwrap_or(document), None) {
                 // Extract symbols from tree
                 match parse_document_from_tree(&tree, document) {
                     Ok(symbols) => {
@@ -127,7 +135,15 @@ impl WatLSP {
     pub fn provide_diagnostics(&self) -> JsValue {
         let diagnostics = validate_wat(&self.document);
         let js_array = js_sys::Array::new();
-        for diag in diagnostics {
+        for mut diag in diagnostics {
+            let range = self.range_to_utf16(&Range::from_coords(
+                diag.line,
+                diag.character,
+                diag.line,
+                diag.end_character,
+            ));
+            diag.character = range.start.character;
+            diag.end_character = range.end.character;
             js_array.push(&diagnostic_to_js(&diag));
         }
         js_array.into()
@@ -146,11 +162,14 @@ impl WatLSP {
             None => return JsValue::NULL,
         };
 
-        let position = Position::new(line, col);
+        let position = self.position_from_utf16(line, col);
 
         // Use the shared tree-sitter based hover implementation
         match provide_hover_core(&self.document, symbols, tree, position) {
-            Some(hover) => hover_to_js(&hover),
+            Some(mut hover) => {
+                hover.range = hover.range.map(|range| self.range_to_utf16(&range));
+                hover_to_js(&hover)
+            }
             None => JsValue::NULL,
         }
     }
@@ -163,7 +182,7 @@ impl WatLSP {
             None => return JsValue::NULL,
         };
 
-        let position = Position::new(line, col);
+        let position = self.position_from_utf16(line, col);
 
         // Get word at position
         let word = match get_word_at_position(&self.document, position) {
@@ -174,7 +193,7 @@ impl WatLSP {
         // Find definition for symbols
         if word.starts_with('$') {
             if let Some(range) = find_symbol_definition_range(&word, symbols, position) {
-                return definition_to_js(&range);
+                return definition_to_js(&self.range_to_utf16(&range));
             }
             // Fallback: if no range but symbol exists, use line number
             if let Some(func) = symbols.get_function_by_name(&word) {
@@ -188,7 +207,7 @@ impl WatLSP {
         // Find definition for numeric indices
         if let Ok(index) = word.parse::<usize>() {
             if let Some(range) = find_index_definition(index, symbols, &self.document, position) {
-                return definition_to_js(&range);
+                return definition_to_js(&self.range_to_utf16(&range));
             }
         }
 
@@ -198,7 +217,7 @@ impl WatLSP {
     /// Debug: get info about a word at position
     #[wasm_bindgen(js_name = debugWordAt)]
     pub fn debug_word_at(&self, line: u32, col: u32) -> JsValue {
-        let position = Position::new(line, col);
+        let position = self.position_from_utf16(line, col);
         let word = get_word_at_position(&self.document, position);
 
         let obj = js_sys::Object::new();
@@ -241,7 +260,7 @@ impl WatLSP {
             None => return js_sys::Array::new().into(),
         };
 
-        let position = Position::new(line, col);
+        let position = self.position_from_utf16(line, col);
 
         // Get word at position
         let word = match get_word_at_position(&self.document, position) {
@@ -259,7 +278,7 @@ impl WatLSP {
         // Convert to JS array
         let js_array = js_sys::Array::new();
         for range in refs {
-            js_array.push(&reference_to_js(&range));
+            js_array.push(&reference_to_js(&self.range_to_utf16(&range)));
         }
         js_array.into()
     }
@@ -310,6 +329,32 @@ impl WatLSP {
             html.push_str("</ul>");
         }
 
+        // Imports
+        if !symbols.imports.is_empty() {
+            html.push_str("<h4>Imports</h4><ul>");
+            for import in &symbols.imports {
+                html.push_str(&format!(
+                    "<li>{}: {} {}</li>",
+                    import.qualified_name(),
+                    import.kind,
+                    import.index
+                ));
+            }
+            html.push_str("</ul>");
+        }
+
+        // Exports
+        if !symbols.exports.is_empty() {
+            html.push_str("<h4>Exports</h4><ul>");
+            for export in &symbols.exports {
+                html.push_str(&format!(
+                    "<li>\"{}\": {} {}</li>",
+                    export.name, export.kind, export.index
+                ));
+            }
+            html.push_str("</ul>");
+        }
+
         if html.is_empty() {
             "<p>No symbols found</p>".to_string()
         } else {
@@ -345,24 +390,32 @@ impl WatLSP {
             let name = capture.name();
             let node = capture.node();
 
-            let start_pos = node.start_position();
-            let end_pos = node.end_position();
+            // Monaco counts columns and lengths in UTF-16 code units
+            let utf16 = PositionEncoding::Utf16;
+            let start_pos = self
+                .line_index
+                .position(&self.document, node.start_byte(), utf16);
+            let end_pos = self
+                .line_index
+                .position(&self.document, node.end_byte(), utf16);
 
             // Calculate length (handle multi-line tokens)
-            let length = if start_pos.row == end_pos.row {
-                (end_pos.column - start_pos.column) as u32
+            let length = if start_pos.line == end_pos.line {
+                end_pos.character - start_pos.character
             } else {
                 // For multi-line tokens, just use the first line length
                 // This is a simplification; proper handling would split tokens
-                (node.end_byte() - node.start_byte()) as u32
+                self.document[node.start_byte()..node.end_byte()]
+                    .encode_utf16()
+                    .count() as u32
             };
 
             // Map capture name to token type index
             let (token_type, token_modifiers) = capture_name_to_token(&name);
 
             tokens.push((
-                start_pos.row as u32,
-                start_pos.column as u32,
+                start_pos.line,
+                start_pos.character,
                 length,
                 token_type,
                 token_modifiers,
@@ -474,6 +527,28 @@ fn capture_name_to_token(name: &str) -> (u32, u32) {
     }
 }
 
+impl WatLSP {
+    /// Convert a JavaScript position (UTF-16 columns) to internal byte columns
+    fn position_from_utf16(&self, line: u32, col: u32) -> Position {
+        self.line_index.convert(
+            &self.document,
+            Position::new(line, col),
+            PositionEncoding::Utf16,
+            PositionEncoding::Utf8,
+        )
+    }
+
+    /// Convert an internal range to JavaScript's UTF-16 columns
+    fn range_to_utf16(&self, range: &Range) -> Range {
+        self.line_index.convert_range(
+            &self.document,
+            *range,
+            PositionEncoding::Utf8,
+            PositionEncoding::Utf16,
+        )
+    }
+}
+
 impl Default for WatLSP {
     fn default() -> Self {
         Self::new()
@@ -573,10 +648,11 @@ fn find_symbol_references(
             let end_pos = abs_pos + word.len();
 
             // Check that it's a complete word (not part of a longer identifier)
-            let before_ok =
-                abs_pos == 0 || !is_word_char(line.chars().nth(abs_pos - 1).unwrap_or(' '));
-            let after_ok =
-                end_pos >= line.len() || !is_word_char(line.chars().nth(end_pos).unwrap_or(' '));
+            let before_ok = !line[..abs_pos]
+                .chars()
+                .next_back()
+                .is_some_and(is_word_char);
+            let after_ok = !line[end_pos..].chars().next().is_some_and(is_word_char);
 
             if before_ok && after_ok {
                 refs.push(Range::from_coords(
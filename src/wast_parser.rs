@@ -3,8 +3,10 @@
 //! This parser is used for WASM builds since tree-sitter has C dependencies
 //! that don't compile to WASM. The wast crate is pure Rust and works in WASM.
 
-use crate::core::types::Range;
+use crate::component::{self, ComponentSymbols};
+use crate::core::types::{Position, Range};
 use crate::symbols::*;
+use std::collections::HashMap;
 
 /// Parse a WAT document and extract symbols using the wast crate.
 pub fn parse_document(source: &str) -> Result<SymbolTable, String> {
@@ -16,14 +18,38 @@ pub fn parse_document(source: &str) -> Result<SymbolTable, String> {
     extract_symbols(&wat, source)
 }
 
+/// Component-level items of a component, and the symbols of each of its
+/// core modules, parsed on their own
+fn extract_component(source: &str) -> SymbolTable {
+    let mut symbols = SymbolTable::new();
+    let component = ComponentSymbols::parse(source);
+    for (range, text) in component::core_module_texts(source) {
+        let Ok(module) = parse_document(&text) else {
+            continue;
+        };
+        let name = component
+            .as_ref()
+            .and_then(|component| component.core_module_at(range.start))
+            .and_then(|item| item.name.clone());
+        symbols.modules.push(ScriptModule {
+            name,
+            range,
+            symbols: module,
+            quoted: false,
+            in_assertion: false,
+        });
+    }
+    symbols.component = component;
+    symbols
+}
+
 /// Extract symbols from parsed WAT AST
 fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String> {
     let mut symbols = SymbolTable::new();
 
-    // Get the module (ignore components for now)
     let module = match wat {
         wast::Wat::Module(m) => m,
-        wast::Wat::Component(_) => return Ok(symbols),
+        wast::Wat::Component(_) => return Ok(extract_component(source)),
     };
 
     // Get fields based on module kind
@@ -40,6 +66,13 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
     let mut type_index = 0usize;
     let mut tag_index = 0usize;
 
+    // Supertypes may name types declared later, so collect type names up front
+    let type_names = collect_type_names(fields);
+
+    // Exports and start may name items declared later, so resolve them after the loop
+    let mut pending_exports: Vec<(String, ExternKind, String, wast::token::Span)> = Vec::new();
+    let mut pending_start: Option<(String, wast::token::Span)> = None;
+
     // Process module fields
     for field in fields.iter() {
         match field {
@@ -47,14 +80,29 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                 let name = import.item.id.map(|id| format!("${}", id.name()));
                 let range = import.item.id.map(|id| id_to_range(id, source));
                 let line = span_to_line(import.span, source);
+                let import_range = enclosing_form_range(source, import.span.offset());
+                let mut record = |kind: ExternKind, index: usize| {
+                    symbols.imports.push(Import {
+                        module: import.module.to_string(),
+                        field: import.field.to_string(),
+                        kind,
+                        index,
+                        line,
+                        range: import_range,
+                    });
+                };
 
                 match &import.item.kind {
-                    wast::core::ItemKind::Func(_) | wast::core::ItemKind::FuncExact(_) => {
+                    wast::core::ItemKind::Func(type_use)
+                    | wast::core::ItemKind::FuncExact(type_use) => {
+                        let (parameters, results) = extract_type_use_info(type_use, source);
+                        record(ExternKind::Func, func_index);
                         symbols.add_function(Function {
                             name,
                             index: func_index,
-                            parameters: Vec::new(),
-                            results: Vec::new(),
+                            parameters,
+                            results,
+                            type_use: type_use_ref(type_use),
                             locals: Vec::new(),
                             blocks: Vec::new(),
                             line,
@@ -66,6 +114,7 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                         func_index += 1;
                     }
                     wast::core::ItemKind::Global(g) => {
+                        record(ExternKind::Global, global_index);
                         symbols.add_global(Global {
                             name,
                             index: global_index,
@@ -73,23 +122,28 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                             is_mutable: g.mutable,
                             initial_value: None,
                             line,
+                            start_byte: import.span.offset(),
                             range,
                         });
                         global_index += 1;
                     }
-                    wast::core::ItemKind::Table(_) => {
+                    wast::core::ItemKind::Table(table_type) => {
+                        let (ref_type, limits) = extract_table_type(table_type);
+                        record(ExternKind::Table, table_index);
                         symbols.add_table(Table {
                             name,
                             index: table_index,
-                            ref_type: ValueType::Funcref,
-                            limits: (0, None),
+                            ref_type,
+                            limits,
                             line,
+                            start_byte: import.span.offset(),
                             range,
                         });
                         table_index += 1;
                     }
                     wast::core::ItemKind::Memory(mem_type) => {
                         let (min, max, is_memory64, shared) = extract_memory_type(mem_type);
+                        record(ExternKind::Memory, memory_index);
                         symbols.add_memory(Memory {
                             name,
                             index: memory_index,
@@ -97,16 +151,19 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                             is_memory64,
                             shared,
                             line,
+                            start_byte: import.span.offset(),
                             range,
                         });
                         memory_index += 1;
                     }
-                    wast::core::ItemKind::Tag(_) => {
+                    wast::core::ItemKind::Tag(tag_type) => {
+                        record(ExternKind::Tag, tag_index);
                         symbols.add_tag(Tag {
                             name,
                             index: tag_index,
-                            params: Vec::new(),
+                            params: extract_tag_params(tag_type),
                             line,
+                            start_byte: import.span.offset(),
                             range,
                         });
                         tag_index += 1;
@@ -129,6 +186,7 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                     index: func_index,
                     parameters,
                     results,
+                    type_use: type_use_ref(&func.ty),
                     locals,
                     blocks: Vec::new(),
                     line,
@@ -137,9 +195,35 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                     end_byte: func.span.offset(),
                     range: func.id.map(|id| id_to_range(id, source)),
                 });
+                let import = match &func.kind {
+                    wast::core::FuncKind::Import(import, _) => Some(import),
+                    _ => None,
+                };
+                record_inline_linkage(
+                    &mut symbols,
+                    ExternKind::Func,
+                    func_index,
+                    func.span,
+                    source,
+                    &func.exports,
+                    import,
+                );
                 func_index += 1;
             }
             wast::core::ModuleField::Global(global) => {
+                let import = match &global.kind {
+                    wast::core::GlobalKind::Import(import) => Some(import),
+                    _ => None,
+                };
+                record_inline_linkage(
+                    &mut symbols,
+                    ExternKind::Global,
+                    global_index,
+                    global.span,
+                    source,
+                    &global.exports,
+                    import,
+                );
                 symbols.add_global(Global {
                     name: global.id.map(|id| format!("${}", id.name())),
                     index: global_index,
@@ -147,23 +231,52 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                     is_mutable: global.ty.mutable,
                     initial_value: None,
                     line: span_to_line(global.span, source),
+                    start_byte: global.span.offset(),
                     range: global.id.map(|id| id_to_range(id, source)),
                 });
                 global_index += 1;
             }
             wast::core::ModuleField::Table(table) => {
+                let import = match &table.kind {
+                    wast::core::TableKind::Import { import, .. } => Some(import),
+                    _ => None,
+                };
+                record_inline_linkage(
+                    &mut symbols,
+                    ExternKind::Table,
+                    table_index,
+                    table.span,
+                    source,
+                    &table.exports,
+                    import,
+                );
+                let (ref_type, limits) = extract_table_kind(&table.kind);
                 symbols.add_table(Table {
                     name: table.id.map(|id| format!("${}", id.name())),
                     index: table_index,
-                    ref_type: ValueType::Funcref,
-                    limits: (0, None),
+                    ref_type,
+                    limits,
                     line: span_to_line(table.span, source),
+                    start_byte: table.span.offset(),
                     range: table.id.map(|id| id_to_range(id, source)),
                 });
                 table_index += 1;
             }
             wast::core::ModuleField::Memory(memory) => {
                 let (min, max, is_memory64, shared) = extract_memory_kind(&memory.kind);
+                let import = match &memory.kind {
+                    wast::core::MemoryKind::Import { import, .. } => Some(import),
+                    _ => None,
+                };
+                record_inline_linkage(
+                    &mut symbols,
+                    ExternKind::Memory,
+                    memory_index,
+                    memory.span,
+                    source,
+                    &memory.exports,
+                    import,
+                );
                 symbols.add_memory(Memory {
                     name: memory.id.map(|id| format!("${}", id.name())),
                     index: memory_index,
@@ -171,12 +284,13 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                     is_memory64,
                     shared,
                     line: span_to_line(memory.span, source),
+                    start_byte: memory.span.offset(),
                     range: memory.id.map(|id| id_to_range(id, source)),
                 });
                 memory_index += 1;
             }
             wast::core::ModuleField::Type(type_def) => {
-                let (kind, supertype, is_final) = extract_type_def_info(&type_def.def);
+                let (kind, supertype, is_final) = extract_type_def_info(&type_def.def, &type_names);
                 symbols.add_type(TypeDef {
                     name: type_def.id.map(|id| format!("${}", id.name())),
                     index: type_index,
@@ -192,7 +306,8 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
             wast::core::ModuleField::Rec(rec_group) => {
                 let rec_id = Some(type_index);
                 for rec_type in rec_group.types.iter() {
-                    let (kind, supertype, is_final) = extract_type_def_info(&rec_type.def);
+                    let (kind, supertype, is_final) =
+                        extract_type_def_info(&rec_type.def, &type_names);
                     symbols.add_type(TypeDef {
                         name: rec_type.id.map(|id| format!("${}", id.name())),
                         index: type_index,
@@ -207,22 +322,226 @@ fn extract_symbols(wat: &wast::Wat, source: &str) -> Result<SymbolTable, String>
                 }
             }
             wast::core::ModuleField::Tag(tag) => {
+                let import = match &tag.kind {
+                    wast::core::TagKind::Import(import) => Some(import),
+                    _ => None,
+                };
+                record_inline_linkage(
+                    &mut symbols,
+                    ExternKind::Tag,
+                    tag_index,
+                    tag.span,
+                    source,
+                    &tag.exports,
+                    import,
+                );
                 symbols.add_tag(Tag {
                     name: tag.id.map(|id| format!("${}", id.name())),
                     index: tag_index,
-                    params: Vec::new(),
+                    params: extract_tag_params(&tag.ty),
                     line: span_to_line(tag.span, source),
+                    start_byte: tag.span.offset(),
                     range: tag.id.map(|id| id_to_range(id, source)),
                 });
                 tag_index += 1;
             }
+            wast::core::ModuleField::Export(export) => {
+                let kind = match export.kind {
+                    wast::core::ExportKind::Func => ExternKind::Func,
+                    wast::core::ExportKind::Table => ExternKind::Table,
+                    wast::core::ExportKind::Memory => ExternKind::Memory,
+                    wast::core::ExportKind::Global => ExternKind::Global,
+                    wast::core::ExportKind::Tag => ExternKind::Tag,
+                };
+                pending_exports.push((
+                    export.name.to_string(),
+                    kind,
+                    index_to_text(&export.item),
+                    export.span,
+                ));
+            }
+            wast::core::ModuleField::Start(index) => {
+                pending_start = Some((index_to_text(index), index.span()));
+            }
             _ => {}
         }
     }
 
+    for (name, kind, item, span) in pending_exports {
+        if let Some(index) = symbols.resolve_index(kind, &item) {
+            symbols.exports.push(Export {
+                name,
+                kind,
+                index,
+                line: span_to_line(span, source),
+                range: enclosing_form_range(source, span.offset()),
+            });
+        }
+    }
+    if let Some((item, span)) = pending_start {
+        if let Some(index) = symbols.resolve_index(ExternKind::Func, &item) {
+            symbols.start = Some(StartFunction {
+                index,
+                line: span_to_line(span, source),
+                range: enclosing_form_range(source, span.offset()),
+            });
+        }
+    }
+
+    symbols.resolve_type_refs();
+
     Ok(symbols)
 }
 
+/// Map each named type to its index, across `(type)` and `(rec)` fields
+fn collect_type_names(fields: &[wast::core::ModuleField]) -> HashMap<String, u32> {
+    let mut type_names = HashMap::new();
+    let mut index = 0u32;
+    let mut add = |id: Option<wast::token::Id>| {
+        if let Some(id) = id {
+            type_names.insert(format!("${}", id.name()), index);
+        }
+        index += 1;
+    };
+
+    for field in fields {
+        match field {
+            wast::core::ModuleField::Type(type_def) => add(type_def.id),
+            wast::core::ModuleField::Rec(rec_group) => {
+                rec_group.types.iter().for_each(|t| add(t.id));
+            }
+            _ => {}
+        }
+    }
+
+    type_names
+}
+
+/// Record the inline `(export "name")` and `(import "module" "field")` abbreviations of an item
+fn record_inline_linkage(
+    symbols: &mut SymbolTable,
+    kind: ExternKind,
+    index: usize,
+    span: wast::token::Span,
+    source: &str,
+    exports: &wast::core::InlineExport,
+    import: Option<&wast::core::InlineImport>,
+) {
+    let line = span_to_line(span, source);
+    let export_ranges = child_form_ranges(source, span.offset(), "export");
+    for (i, name) in exports.names.iter().enumerate() {
+        symbols.exports.push(Export {
+            name: name.to_string(),
+            kind,
+            index,
+            line,
+            range: export_ranges.get(i).copied(),
+        });
+    }
+    if let Some(import) = import {
+        symbols.imports.push(Import {
+            module: import.module.to_string(),
+            field: import.field.to_string(),
+            kind,
+            index,
+            line,
+            range: child_form_ranges(source, span.offset(), "import")
+                .first()
+                .copied(),
+        });
+    }
+}
+
+/// Range of the parenthesized form whose keyword or first operand starts
+/// at `offset`, e.g. all of `(export "a" (func 0))` for the `export` keyword
+fn enclosing_form_range(source: &str, offset: usize) -> Option<Range> {
+    let start = source[..offset].rfind('(')?;
+    let mut depth = 0usize;
+    for token in form_tokens(source, start) {
+        match token.kind {
+            wast::lexer::TokenKind::LParen => depth += 1,
+            wast::lexer::TokenKind::RParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(offsets_to_range(source, start, token.offset + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Ranges of the `(keyword ...)` forms directly inside the form whose keyword
+/// is at `offset`, e.g. the `(export "a")` of `(global (export "a") i32 ...)`.
+/// Inline exports and imports have no spans of their own.
+fn child_form_ranges(source: &str, offset: usize, keyword: &str) -> Vec<Range> {
+    let mut ranges = Vec::new();
+    let mut depth = 0usize;
+    let mut open: Option<(usize, bool)> = None;
+    let mut tokens = form_tokens(source, offset).peekable();
+    while let Some(token) = tokens.next() {
+        match token.kind {
+            wast::lexer::TokenKind::LParen => {
+                depth += 1;
+                if depth == 1 {
+                    let matches = tokens.peek().is_some_and(|next| {
+                        next.kind == wast::lexer::TokenKind::Keyword && next.src(source) == keyword
+                    });
+                    open = Some((token.offset, matches));
+                }
+            }
+            wast::lexer::TokenKind::RParen => {
+                // The end of the enclosing form
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+                if depth == 0 {
+                    if let Some((start, true)) = open.take() {
+                        ranges.push(offsets_to_range(source, start, token.offset + 1));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// Tokens from `offset` on, without whitespace and comments
+fn form_tokens(source: &str, offset: usize) -> impl Iterator<Item = wast::lexer::Token> + '_ {
+    let lexer = wast::lexer::Lexer::new(source);
+    let mut position = offset;
+    std::iter::from_fn(move || lexer.parse(&mut position).ok().flatten()).filter(|token| {
+        !matches!(
+            token.kind,
+            wast::lexer::TokenKind::Whitespace
+                | wast::lexer::TokenKind::LineComment
+                | wast::lexer::TokenKind::BlockComment
+        )
+    })
+}
+
+/// Convert a byte range of the source to a Range
+fn offsets_to_range(source: &str, start: usize, end: usize) -> Range {
+    let position = |offset: usize| {
+        let before = &source[..offset];
+        let line = before.matches('\n').count() as u32;
+        let column = offset - before.rfind('\n').map_or(0, |i| i + 1);
+        Position::new(line, column as u32)
+    };
+    Range::new(position(start), position(end))
+}
+
+/// Render an index reference as written in the source, e.g. `$name` or `3`
+fn index_to_text(index: &wast::token::Index) -> String {
+    match index {
+        wast::token::Index::Num(n, _) => n.to_string(),
+        wast::token::Index::Id(id) => format!("${}", id.name()),
+    }
+}
+
 /// Convert wast Span to line number
 fn span_to_line(span: wast::token::Span, source: &str) -> u32 {
     let (line, _col) = span.linecol_in(source);
@@ -274,36 +593,94 @@ fn id_to_range(id: wast::token::Id, source: &str) -> Range {
 fn extract_func_type_info(
     func: &wast::core::Func,
     source: &str,
+) -> (Vec<Parameter>, Vec<ValueType>) {
+    // Inline imports keep their signature too, e.g. `(func (import "a" "b") (param i32))`
+    extract_type_use_info(&func.ty, source)
+}
+
+/// The `(type $t)` of a type use. Named targets are resolved by
+/// SymbolTable::resolve_type_refs
+fn type_use_ref(type_use: &wast::core::TypeUse<wast::core::FunctionType>) -> Option<TypeRef> {
+    Some(match type_use.index.as_ref()? {
+        wast::token::Index::Num(n, _) => TypeRef::numeric(*n),
+        wast::token::Index::Id(id) => TypeRef::named(format!("${}", id.name())),
+    })
+}
+
+/// Extract parameters and results from the inline part of a type use.
+/// A bare `(type $sig)` reference yields no parameters or results.
+fn extract_type_use_info(
+    type_use: &wast::core::TypeUse<wast::core::FunctionType>,
+    source: &str,
 ) -> (Vec<Parameter>, Vec<ValueType>) {
     let mut params = Vec::new();
     let mut results = Vec::new();
 
-    if let wast::core::FuncKind::Inline { .. } = &func.kind {
-        // Get inline type if present
-        if let Some(inline) = &func.ty.inline {
-            for (index, param) in inline.params.iter().enumerate() {
-                // param is a tuple: (Option<Id>, Option<NameAnnotation>, ValType)
-                let name = param.0.map(|id| format!("${}", id.name()));
-                let range = param.0.map(|id| id_to_range(id, source));
-                let val_type = extract_value_type(&param.2);
+    if let Some(inline) = &type_use.inline {
+        for (index, param) in inline.params.iter().enumerate() {
+            // param is a tuple: (Option<Id>, Option<NameAnnotation>, ValType)
+            let name = param.0.map(|id| format!("${}", id.name()));
+            let range = param.0.map(|id| id_to_range(id, source));
+            let val_type = extract_value_type(&param.2);
 
-                params.push(Parameter {
-                    name,
-                    param_type: val_type,
-                    index,
-                    range,
-                });
-            }
+            params.push(Parameter {
+                name,
+                param_type: val_type,
+                index,
+                range,
+            });
+        }
 
-            for result in &inline.results {
-                results.push(extract_value_type(result));
-            }
+        for result in &inline.results {
+            results.push(extract_value_type(result));
         }
     }
 
     (params, results)
 }
 
+/// Extract the parameter types of a tag
+fn extract_tag_params(tag_type: &wast::core::TagType) -> Vec<ValueType> {
+    let wast::core::TagType::Exception(type_use) = tag_type;
+    type_use
+        .inline
+        .as_ref()
+        .map(|inline| {
+            inline
+                .params
+                .iter()
+                .map(|(_, _, ty)| extract_value_type(ty))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Extract the element type and limits of a table type
+fn extract_table_type(table_type: &wast::core::TableType) -> (ValueType, (u32, Option<u32>)) {
+    let limits = &table_type.limits;
+    (
+        ValueType::from(&table_type.elem),
+        (limits.min as u32, limits.max.map(|max| max as u32)),
+    )
+}
+
+/// Extract the element type and limits of a table from wast TableKind
+fn extract_table_kind(kind: &wast::core::TableKind) -> (ValueType, (u32, Option<u32>)) {
+    match kind {
+        wast::core::TableKind::Normal { ty, .. } | wast::core::TableKind::Import { ty, .. } => {
+            extract_table_type(ty)
+        }
+        wast::core::TableKind::Inline { elem, payload, .. } => {
+            // Inline elements fix the table size to the number of elements
+            let len = match payload {
+                wast::core::ElemPayload::Indices(indices) => indices.len(),
+                wast::core::ElemPayload::Exprs { exprs, .. } => exprs.len(),
+            } as u32;
+            (ValueType::from(elem), (len, Some(len)))
+        }
+    }
+}
+
 /// Extract function locals
 fn extract_func_locals(func: &wast::core::Func, source: &str) -> Vec<Variable> {
     let mut locals = Vec::new();
@@ -340,11 +717,14 @@ fn extract_value_type(val_type: &wast::core::ValType) -> ValueType {
 
 /// Extract type definition info from wast TypeDef
 /// Returns (TypeKind, supertype, is_final)
-fn extract_type_def_info(def: &wast::core::TypeDef) -> (TypeKind, Option<u32>, bool) {
+fn extract_type_def_info(
+    def: &wast::core::TypeDef,
+    type_names: &HashMap<String, u32>,
+) -> (TypeKind, Option<u32>, bool) {
     // Extract supertype index if present
     let supertype = def.parent.as_ref().and_then(|idx| match idx {
         wast::token::Index::Num(n, _) => Some(*n),
-        wast::token::Index::Id(_) => None, // Can't resolve named index here
+        wast::token::Index::Id(id) => type_names.get(&format!("${}", id.name())).copied(),
     });
 
     // is_final defaults to true if not specified
@@ -471,4 +851,136 @@ mod tests {
         let symbols = result.unwrap();
         assert_eq!(symbols.globals.len(), 2);
     }
+
+    #[test]
+    fn test_parse_exports_imports_and_start() {
+        let source = r#"
+            (module
+                (import "env" "log" (func $log (param i32)))
+                (func $init (export "init")
+                    i32.const 1
+                    call $log
+                )
+                (export "log" (func $log))
+                (start $init)
+            )
+        "#;
+
+        let symbols = parse_document(source).unwrap();
+
+        assert_eq!(symbols.imports.len(), 1);
+        assert_eq!(symbols.imports[0].qualified_name(), "env.log");
+        assert_eq!(symbols.imports[0].index, 0);
+
+        let exports: Vec<(&str, usize)> = symbols
+            .exports
+            .iter()
+            .map(|e| (e.name.as_str(), e.index))
+            .collect();
+        assert_eq!(exports, vec![("init", 1), ("log", 0)]);
+
+        assert_eq!(symbols.start.as_ref().map(|s| s.index), Some(1));
+    }
+
+    #[test]
+    fn test_linkage_ranges() {
+        let source = r#"(module
+  (import "env" "log" (func $log))
+  (global $a (export "a") (export "b") i32 (i32.const 0)) (global $c (import "env" "c") i32)
+  (export "log" (func $log)) ;; (export "x")
+  (start $log))"#;
+
+        let symbols = parse_document(source).unwrap();
+        let text = |range: Option<Range>| {
+            let range = range.expect("Expected a range");
+            let line = source.lines().nth(range.start.line as usize).unwrap();
+            line[range.start.character as usize..range.end.character as usize].to_string()
+        };
+
+        let imports: Vec<String> = symbols.imports.iter().map(|i| text(i.range)).collect();
+        assert_eq!(
+            imports,
+            vec![
+                r#"(import "env" "log" (func $log))"#,
+                r#"(import "env" "c")"#
+            ]
+        );
+        let exports: Vec<String> = symbols.exports.iter().map(|e| text(e.range)).collect();
+        assert_eq!(
+            exports,
+            vec![
+                r#"(export "a")"#,
+                r#"(export "b")"#,
+                r#"(export "log" (func $log))"#
+            ]
+        );
+        assert_eq!(text(symbols.start.as_ref().unwrap().range), "(start $log)");
+    }
+
+    #[test]
+    fn test_resolve_named_type_refs() {
+        let source = r#"
+            (module
+                (type $point (struct (field $x i32) (field $y i32)))
+                (type $line (struct (field (ref $point)) (field (ref null $point))))
+                (func $len (param $p (ref $point)) (result i32)
+                    i32.const 0
+                )
+            )
+        "#;
+
+        let symbols = parse_document(source).unwrap();
+
+        let param = &symbols.functions[0].parameters[0].param_type;
+        assert_eq!(*param, ValueType::Ref(TypeRef::numeric(0)));
+        assert_eq!(param.to_string(), "(ref $point)");
+
+        match &symbols.types[1].kind {
+            TypeKind::Struct { fields } => {
+                assert_eq!(fields[0].1, ValueType::Ref(TypeRef::numeric(0)));
+                assert_eq!(fields[1].1.to_string(), "(ref null $point)");
+            }
+            other => panic!("Expected struct, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_resolve_named_supertype() {
+        let source = r#"
+            (module
+                (type $shape (sub (struct (field f64))))
+                (type $circle (sub $shape (struct (field f64) (field f64))))
+            )
+        "#;
+
+        let symbols = parse_document(source).unwrap();
+
+        assert_eq!(symbols.types[1].supertype, Some(0));
+        assert!(!symbols.types[0].is_final);
+    }
+
+    #[test]
+    fn test_parse_imported_signatures_and_table_types() {
+        let source = r#"
+            (module
+                (import "env" "log" (func $log (param $msg i32) (result i32)))
+                (import "env" "error" (tag $error (param i32 i64)))
+                (table $refs 2 8 externref)
+                (tag $local (param f32))
+            )
+        "#;
+
+        let symbols = parse_document(source).unwrap();
+
+        let log = &symbols.functions[0];
+        assert_eq!(log.parameters[0].name.as_deref(), Some("$msg"));
+        assert_eq!(log.parameters[0].param_type, ValueType::I32);
+        assert_eq!(log.results, vec![ValueType::I32]);
+
+        assert_eq!(symbols.tables[0].ref_type, ValueType::Externref);
+        assert_eq!(symbols.tables[0].limits, (2, Some(8)));
+
+        assert_eq!(symbols.tags[0].params, vec![ValueType::I32, ValueType::I64]);
+        assert_eq!(symbols.tags[1].params, vec![ValueType::F32]);
+    }
 }
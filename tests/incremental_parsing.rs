@@ -1,5 +1,6 @@
 use std::time::Instant;
 use tower_lsp::lsp_types::Position;
+use wat_lsp_rust::parser::{parse_document, update_symbols};
 use wat_lsp_rust::tree_sitter_bindings::create_parser;
 use wat_lsp_rust::utils::apply_text_edit;
 
@@ -233,3 +234,157 @@ fn test_multiple_incremental_edits() {
     let root = tree.root_node();
     assert!(!root.has_error(), "Final tree should not have errors");
 }
+
+/// Apply an edit to a document and its tree, returning the tree-sitter edit
+fn edit_document(
+    document: &mut String,
+    tree: &mut tree_sitter::Tree,
+    start: Position,
+    end: Position,
+    text: &str,
+) -> tree_sitter::InputEdit {
+    let start_byte = wat_lsp_rust::utils::position_to_byte(document, start.into());
+    let old_end_byte = wat_lsp_rust::utils::position_to_byte(document, end.into());
+    let new_end = apply_text_edit(document, start.into(), end.into(), text);
+
+    let point = |line: u32, character: u32| tree_sitter::Point {
+        row: line as usize,
+        column: character as usize,
+    };
+    let edit = tree_sitter::InputEdit {
+        start_byte,
+        old_end_byte,
+        new_end_byte: start_byte + text.len(),
+        start_position: point(start.line, start.character),
+        old_end_position: point(end.line, end.character),
+        new_end_position: point(new_end.line, new_end.character),
+    };
+    tree.edit(&edit);
+    edit
+}
+
+/// Apply edits one after another, then update the symbols incrementally
+/// and check they match a full extraction of the new document
+fn assert_incremental_symbols(document: &str, edits: &[(Position, Position, &str)]) {
+    let mut parser = create_parser();
+    let mut tree = parser.parse(document, None).expect("Initial parse failed");
+    let old_symbols = parse_document(document).expect("Initial extraction failed");
+
+    let mut text = document.to_string();
+    let input_edits: Vec<_> = edits
+        .iter()
+        .map(|(start, end, new_text)| edit_document(&mut text, &mut tree, *start, *end, new_text))
+        .collect();
+    let new_tree = parser
+        .parse(&text, Some(&tree))
+        .expect("Incremental reparse failed");
+
+    let incremental = update_symbols(&old_symbols, &tree, &new_tree, &text, &input_edits)
+        .expect("Incremental update failed");
+    let full = parse_document(&text).expect("Full extraction failed");
+
+    assert_eq!(
+        format!("{:?}", incremental.functions),
+        format!("{:?}", full.functions)
+    );
+    assert_eq!(
+        format!("{:?}", incremental.globals),
+        format!("{:?}", full.globals)
+    );
+    assert_eq!(
+        format!("{:?}", incremental.types),
+        format!("{:?}", full.types)
+    );
+    assert_eq!(
+        format!("{:?}", incremental.exports),
+        format!("{:?}", full.exports)
+    );
+    assert_eq!(
+        format!("{:?}", incremental.imports),
+        format!("{:?}", full.imports)
+    );
+    assert_eq!(
+        format!("{:?}", incremental.data_segments),
+        format!("{:?}", full.data_segments)
+    );
+    assert_eq!(incremental.function_map, full.function_map);
+}
+
+const SYMBOLS_MODULE: &str = r#"(module
+  (import "env" "log" (func $log (param i32)))
+  (type $pair (struct (field i32) (field i32)))
+  (global $counter (mut i32) (i32.const 0))
+  (func $first (param $x i32) (result i32)
+    local.get $x
+  )
+  (func $second (param $y i32)
+    (block $done
+      br $done
+    )
+  )
+  (data $greeting "hi")
+  (export "second" (func $second))
+)"#;
+
+#[test]
+fn test_incremental_symbols_function_body_edit() {
+    // Add a local inside $first, pushing everything after it down a line
+    assert_incremental_symbols(
+        SYMBOLS_MODULE,
+        &[(
+            Position::new(5, 4),
+            Position::new(5, 4),
+            "(local $tmp i64)\n    ",
+        )],
+    );
+}
+
+#[test]
+fn test_incremental_symbols_edit_on_same_line() {
+    // Rename a parameter without changing line count
+    assert_incremental_symbols(
+        SYMBOLS_MODULE,
+        &[
+            (Position::new(4, 22), Position::new(4, 24), "$value"),
+            (Position::new(5, 14), Position::new(5, 16), "$value"),
+        ],
+    );
+}
+
+#[test]
+fn test_incremental_symbols_whitespace_between_fields() {
+    assert_incremental_symbols(
+        SYMBOLS_MODULE,
+        &[(Position::new(3, 43), Position::new(3, 43), "\n\n")],
+    );
+}
+
+#[test]
+fn test_incremental_symbols_fall_back_on_rename() {
+    // Renaming a function changes what other fields refer to
+    assert_incremental_symbols(
+        SYMBOLS_MODULE,
+        &[(Position::new(7, 8), Position::new(7, 15), "$renamed")],
+    );
+}
+
+#[test]
+fn test_incremental_symbols_fall_back_on_new_field() {
+    assert_incremental_symbols(
+        SYMBOLS_MODULE,
+        &[(
+            Position::new(3, 43),
+            Position::new(3, 43),
+            "\n  (global $extra i64 (i64.const 1))",
+        )],
+    );
+}
+
+#[test]
+fn test_incremental_symbols_non_function_edit() {
+    // Change the struct field types
+    assert_incremental_symbols(
+        SYMBOLS_MODULE,
+        &[(Position::new(2, 29), Position::new(2, 32), "i64")],
+    );
+}
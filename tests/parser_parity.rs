@@ -0,0 +1,75 @@
+//! Differential tests: the tree-sitter and wast parsers must extract the same
+//! symbols from every example and fixture file.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use wat_lsp_rust::parity::{compare_parsers, is_wast_unsupported};
+
+fn wat_files(dir: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.expect("Failed to read directory entry").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "wat"))
+        .filter(|path| !is_wast_unsupported(path))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Compare both parsers on each file, failing with every difference found
+fn assert_parsers_agree(dir: &str) {
+    let files = wat_files(dir);
+    assert!(!files.is_empty(), "No .wat files in {}", dir);
+
+    let mut report = Vec::new();
+    for path in &files {
+        let source = fs::read_to_string(path).expect("Failed to read file");
+        match compare_parsers(&source) {
+            Ok(diffs) => {
+                for diff in diffs {
+                    report.push(format!("{}: {}", path.display(), diff));
+                }
+            }
+            Err(e) => report.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    assert!(
+        report.is_empty(),
+        "Parsers disagree:\n{}",
+        report.join("\n")
+    );
+}
+
+#[test]
+fn test_parsers_agree_on_examples() {
+    assert_parsers_agree("docs/examples");
+}
+
+#[test]
+fn test_parsers_agree_on_fixtures() {
+    assert_parsers_agree("tests/fixtures");
+}
+
+#[test]
+fn test_parsers_agree_on_imports_and_tables() {
+    let source = r#"(module
+  (import "env" "log" (func $log (param $msg i32) (result i32)))
+  (import "env" "table" (table $imported 2 8 externref))
+  (import "env" "error" (tag $error (param i32 i64)))
+  (table $local 4 funcref)
+  (tag $local_error (param f32))
+  (global $g (export "g") (mut i64) (i64.const 0))
+  (func $main (export "main") (local $tmp f64))
+  (start $main)
+)"#;
+    let diffs = compare_parsers(source).expect("Both parsers should succeed");
+    let report: Vec<String> = diffs.iter().map(|d| d.to_string()).collect();
+    assert!(
+        report.is_empty(),
+        "Parsers disagree:\n{}",
+        report.join("\n")
+    );
+}
@@ -268,6 +268,16 @@ fn test_rec_group_assigns_rec_group_id() {
     // Both types should be found
     assert!(symbols.get_type_by_name("$a").is_some());
     assert!(symbols.get_type_by_name("$b").is_some());
+
+    // Types in the same rec group share the index of the group's first type
+    assert_eq!(
+        symbols.get_type_by_name("$a").unwrap().rec_group_id,
+        Some(0)
+    );
+    assert_eq!(
+        symbols.get_type_by_name("$b").unwrap().rec_group_id,
+        Some(0)
+    );
 }
 
 #[test]
@@ -291,8 +301,9 @@ fn test_subtype_parsing() {
     // Child should not be final (has sub keyword without final)
     // Note: Parsing depends on grammar structure
     // The child type should exist
-    let _child = symbols.get_type_by_name("$child").unwrap();
-    assert!(symbols.get_type_by_name("$child").is_some());
+    let child = symbols.get_type_by_name("$child").unwrap();
+    assert_eq!(child.supertype, Some(0), "Child should extend $parent");
+    assert!(!child.is_final, "Child should not be final");
 }
 
 #[test]
@@ -312,3 +323,32 @@ fn test_subtype_final_parsing() {
     assert!(symbols.get_type_by_name("$base").is_some());
     assert!(symbols.get_type_by_name("$derived").is_some());
 }
+
+#[test]
+fn test_elem_segment_func_names() {
+    let wat = r#"
+(module
+  (table $t 4 funcref)
+  (func $a)
+  (func $b)
+  (elem (i32.const 0) $a $b)
+  (elem $named (table $t) (i32.const 2) func $b 0)
+  (elem declare funcref (ref.func $a))
+)
+"#;
+
+    let symbols = parse_document(wat).unwrap();
+    assert_eq!(symbols.elem_segments.len(), 3);
+
+    // MVP shorthand: function indices directly after the offset
+    let first = &symbols.elem_segments[0];
+    assert_eq!(first.name, None);
+    assert_eq!(first.func_names, vec!["$a", "$b"]);
+
+    let named = symbols.get_elem_by_name("$named").unwrap();
+    assert_eq!(named.table_name.as_deref(), Some("$t"));
+    assert_eq!(named.func_names, vec!["$b", "0"]);
+
+    // Expression form
+    assert_eq!(symbols.elem_segments[2].func_names, vec!["$a"]);
+}
@@ -0,0 +1,70 @@
+//! Tests for the pieces behind pull diagnostics: diagnosing files that
+//! aren't open, result ids, and finding `.wat` files in the workspace.
+
+use std::fs;
+use std::path::PathBuf;
+
+use wat_lsp_rust::core::CancellationToken;
+use wat_lsp_rust::diagnostics::{diagnose_source, diagnostics_result_id};
+use wat_lsp_rust::native::find_wat_files;
+use wat_lsp_rust::settings::{DiagnosticLevel, Settings};
+
+#[test]
+fn test_diagnose_source_reports_all_sources() {
+    let valid = "(module (func $f (result i32) i32.const 1))";
+    assert!(diagnose_source(valid, &Settings::default()).is_empty());
+
+    let invalid = "(module (func $f (result i32) i32.const))";
+    assert!(!diagnose_source(invalid, &Settings::default()).is_empty());
+}
+
+#[test]
+fn test_result_id_tracks_source_and_settings() {
+    let source = "(module (func $f))";
+    let settings = Settings::default();
+    assert_eq!(
+        diagnostics_result_id(source, &settings),
+        diagnostics_result_id(source, &settings)
+    );
+    assert_ne!(
+        diagnostics_result_id(source, &settings),
+        diagnostics_result_id("(module (func $g))", &settings)
+    );
+
+    let mut syntax_only = Settings::default();
+    syntax_only.diagnostics.level = DiagnosticLevel::Syntax;
+    assert_ne!(
+        diagnostics_result_id(source, &settings),
+        diagnostics_result_id(source, &syntax_only)
+    );
+}
+
+#[test]
+fn test_find_wat_files_skips_hidden_and_build_dirs() {
+    let root = std::env::temp_dir().join(format!("wat-lsp-workspace-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for dir in ["src/nested", ".git", "target", "node_modules"] {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    for file in [
+        "main.wat",
+        "src/nested/lib.wat",
+        "src/nested/spec.wast",
+        "src/notes.txt",
+        ".git/ignored.wat",
+        "target/out.wat",
+        "node_modules/dep.wat",
+    ] {
+        fs::write(root.join(file), "(module)").unwrap();
+    }
+
+    let files = find_wat_files(std::slice::from_ref(&root), &CancellationToken::default());
+    let _ = fs::remove_dir_all(&root);
+
+    let expected: Vec<PathBuf> = vec![
+        root.join("main.wat"),
+        root.join("src/nested/lib.wat"),
+        root.join("src/nested/spec.wast"),
+    ];
+    assert_eq!(files, expected);
+}